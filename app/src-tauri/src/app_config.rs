@@ -1,23 +1,31 @@
 //! Application configuration management
 
+use crate::versioned_file::load_versioned;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
 use tauri::{AppHandle, Manager};
 
+/// Current schema version of config.json
+pub const APP_CONFIG_VERSION: u32 = 1;
+
 /// Application configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
+    /// Schema version (files written before versioning load as 0)
+    #[serde(default)]
+    pub schema_version: u32,
     /// Whether the user has seen the donation prompt
     pub has_seen_donation_prompt: bool,
     /// Timestamp of first launch (Unix timestamp in milliseconds)
     pub first_launch_timestamp: Option<i64>,
 }
 
-impl AppConfig {
+impl Default for AppConfig {
     /// Create default application configuration
-    pub fn default() -> Self {
+    fn default() -> Self {
         Self {
+            schema_version: APP_CONFIG_VERSION,
             has_seen_donation_prompt: false,
             first_launch_timestamp: Some(chrono::Utc::now().timestamp_millis()),
         }
@@ -43,11 +51,12 @@ pub fn load_app_config(app_handle: &AppHandle) -> Result<AppConfig, String> {
     let path = get_app_config_path(app_handle)?;
 
     if path.exists() {
-        let content = fs::read_to_string(&path)
-            .map_err(|e| format!("Failed to read config file: {}", e))?;
+        let mut config: AppConfig = load_versioned(&path, APP_CONFIG_VERSION)
+            .map_err(|e| format!("Failed to load config file: {}", e))?;
 
-        serde_json::from_str(&content)
-            .map_err(|e| format!("Failed to parse config file: {}", e))
+        // v0 -> v1 added only the version field
+        config.schema_version = APP_CONFIG_VERSION;
+        Ok(config)
     } else {
         // Return default config if file doesn't exist
         Ok(AppConfig::default())
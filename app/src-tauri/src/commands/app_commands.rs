@@ -1,6 +1,11 @@
 //! Application-level commands
 
+use crate::config::ConnectedClientInfo;
+use crate::state::{AppState, ProtocolErrorRecord};
+use kanpe_core::types::VirtualMonitor;
+use kanpe_server::{MonitorSyncStatus, ServerMetrics};
 use serde::Serialize;
+use tauri::State;
 
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -18,3 +23,115 @@ pub fn get_app_version() -> AppVersionInfo {
         build_timestamp: option_env!("BUILD_TIMESTAMP").map(|s| s.to_string()),
     }
 }
+
+/// Get the current application mode ("not_selected", "server" or "client")
+#[tauri::command]
+pub async fn get_app_mode(state: State<'_, AppState>) -> Result<String, String> {
+    Ok(state.current_mode().await.as_str().to_string())
+}
+
+/// Snapshot of the running server for diagnostics
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerDiagnostics {
+    pub addresses: Vec<String>,
+    pub ping_enabled: bool,
+    pub connected_clients: Vec<ConnectedClientInfo>,
+    pub monitors: Vec<VirtualMonitor>,
+    pub monitor_sync: Vec<MonitorSyncStatus>,
+    pub metrics: ServerMetrics,
+}
+
+/// Snapshot of the client connection for diagnostics
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientDiagnostics {
+    pub connected: bool,
+    pub client_name: Option<String>,
+    pub monitors: Vec<VirtualMonitor>,
+}
+
+/// Full state capture to attach to bug reports
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostics {
+    pub app: AppVersionInfo,
+    pub mode: String,
+    pub server: Option<ServerDiagnostics>,
+    pub client: Option<ClientDiagnostics>,
+    pub streamdeck_port: Option<u16>,
+    /// Frames the client recently failed to parse or handle
+    pub protocol_errors: Vec<ProtocolErrorRecord>,
+}
+
+/// Collect diagnostics from the current application state
+pub async fn collect_diagnostics(state: &AppState) -> Diagnostics {
+    let server = match state.server.read().await.as_ref() {
+        Some(server) => Some(ServerDiagnostics {
+            addresses: server.listening_addresses(),
+            ping_enabled: server.is_ping_enabled(),
+            connected_clients: server
+                .get_connected_clients()
+                .await
+                .into_iter()
+                .map(|c| ConnectedClientInfo {
+                    client_id: c.client_id,
+                    name: c.client_name,
+                    monitor_ids: c.display_monitor_ids,
+                    connected_at: c.connected_at,
+                    last_pong_at: c.last_pong_at,
+                })
+                .collect(),
+            monitors: server.get_monitors().await,
+            monitor_sync: server.get_monitor_sync_status().await,
+            metrics: server.metrics().await,
+        }),
+        None => None,
+    };
+
+    let client = match state.client.read().await.as_ref() {
+        Some(client) => Some(ClientDiagnostics {
+            connected: client.is_connected().await,
+            client_name: client.client_name().await,
+            monitors: client.monitors().await,
+        }),
+        None => None,
+    };
+
+    let streamdeck_port = state.streamdeck_server.read().await.as_ref().map(|s| s.port());
+
+    Diagnostics {
+        app: get_app_version(),
+        mode: state.current_mode().await.as_str().to_string(),
+        server,
+        client,
+        streamdeck_port,
+        protocol_errors: state.protocol_errors.recent(),
+    }
+}
+
+/// Get a single diagnostics dump for bug reports
+#[tauri::command]
+pub async fn get_diagnostics(state: State<'_, AppState>) -> Result<Diagnostics, String> {
+    Ok(collect_diagnostics(&state).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_diagnostics_include_version_and_mode() {
+        let state = AppState::new();
+        state.enter_client().await.unwrap();
+
+        let diagnostics = collect_diagnostics(&state).await;
+        assert_eq!(diagnostics.app.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(diagnostics.mode, "client");
+
+        let json = serde_json::to_value(&diagnostics).unwrap();
+        assert_eq!(json["app"]["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(json["mode"], "client");
+        assert!(json["server"].is_null());
+    }
+}
@@ -1,9 +1,10 @@
 //! Client-mode Tauri commands
 
-use crate::state::{AppMode, AppState};
+use crate::commands::streamdeck_commands::push_streamdeck_state;
+use crate::state::AppState;
 use kanpe_client::events::ClientEvent;
-use kanpe_client::KanpeClient;
-use kanpe_core::{FeedbackType, Message};
+use kanpe_client::{ConnectionState, KanpeClient, ReconnectPolicy};
+use kanpe_core::FeedbackType;
 use tauri::{AppHandle, Emitter, State, Manager, WebviewUrl, WebviewWindowBuilder};
 use tokio::sync::mpsc;
 
@@ -13,35 +14,35 @@ pub async fn connect_to_server(
     server_address: String,
     client_name: String,
     display_monitor_ids: Vec<String>,
+    auth_token: Option<String>,
     app_handle: AppHandle,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
-    // Check mode
-    let mode = state.mode.read().await;
-    if *mode == AppMode::Server {
-        return Err("Cannot connect to server while in server mode".to_string());
-    }
-    drop(mode);
-
-    // Set mode to Client
-    *state.mode.write().await = AppMode::Client;
+    // Switch to client mode (rejected while in server mode)
+    state.enter_client().await?;
 
     // Create event channel
     let (event_tx, mut event_rx) = mpsc::unbounded_channel::<ClientEvent>();
 
     // Create and connect client
     let mut client = KanpeClient::new(event_tx);
-    client
+    client.set_auth_token(auth_token.filter(|token| !token.is_empty()));
+    client.set_reconnect_policy(Some(ReconnectPolicy::default()));
+    if let Err(e) = client
         .connect(&server_address, client_name, display_monitor_ids)
         .await
-        .map_err(|e| format!("Failed to connect to server: {}", e))?;
+    {
+        state.abandon_transition().await;
+        return Err(format!("Failed to connect to server: {}", e));
+    }
 
     // Store client in state
     *state.client.write().await = Some(client);
 
-    // Spawn task to handle client events
+    // Spawn task to handle client events, keeping any StreamDeck up to date
     let client_arc = state.client.clone();
-    let mode_arc = state.mode.clone();
+    let streamdeck_arc = state.streamdeck_server.clone();
+    let protocol_errors = state.protocol_errors.clone();
     tokio::spawn(async move {
         while let Some(event) = event_rx.recv().await {
             match event {
@@ -52,6 +53,7 @@ pub async fn connect_to_server(
                             "server_address": server_address,
                         }),
                     );
+                    push_streamdeck_state(&streamdeck_arc, &client_arc, true).await;
                 }
                 ClientEvent::ConnectionLost { reason } => {
                     let _ = app_handle.emit(
@@ -61,12 +63,34 @@ pub async fn connect_to_server(
                         }),
                     );
 
-                    // Clean up AppState
-                    *client_arc.write().await = None;
-                    *mode_arc.write().await = AppMode::NotSelected;
+                    // Tell the deck while the client is still in AppState; the deck keeps running
+                    push_streamdeck_state(&streamdeck_arc, &client_arc, false).await;
+                    let _ = app_handle.state::<AppState>().reset().await;
+                }
+                ClientEvent::ServerShuttingDown { reason } => {
+                    let _ = app_handle.emit(
+                        "server_shutting_down",
+                        serde_json::json!({ "reason": reason }),
+                    );
+
+                    push_streamdeck_state(&streamdeck_arc, &client_arc, false).await;
+                    let _ = app_handle.state::<AppState>().reset().await;
                 }
                 ClientEvent::MessageReceived { message } => {
                     let _ = app_handle.emit("kanpe_message_received", message);
+                    push_streamdeck_state(&streamdeck_arc, &client_arc, true).await;
+                }
+                ClientEvent::MessageRecalled {
+                    message_id,
+                    target_monitor_ids,
+                } => {
+                    let _ = app_handle.emit(
+                        "message_recalled",
+                        serde_json::json!({
+                            "message_id": message_id,
+                            "target_monitor_ids": target_monitor_ids,
+                        }),
+                    );
                 }
                 ClientEvent::ServerWelcomeReceived { server_name } => {
                     let _ = app_handle.emit(
@@ -79,6 +103,9 @@ pub async fn connect_to_server(
                 ClientEvent::MonitorListReceived { monitors } => {
                     let _ = app_handle.emit("monitor_list_received", monitors);
                 }
+                ClientEvent::MonitorGroupsReceived { groups } => {
+                    let _ = app_handle.emit("monitor_groups_received", groups);
+                }
                 ClientEvent::MonitorAdded { monitor } => {
                     let _ = app_handle.emit("monitor_added", monitor);
                 }
@@ -91,6 +118,9 @@ pub async fn connect_to_server(
                 ClientEvent::MonitorUpdated { monitor } => {
                     let _ = app_handle.emit("monitor_updated", monitor);
                 }
+                ClientEvent::MonitorsChanged { monitors } => {
+                    let _ = app_handle.emit("monitors_changed", monitors);
+                }
                 ClientEvent::FlashReceived { target_monitor_ids } => {
                     let _ = app_handle.emit(
                         "flash_received",
@@ -103,6 +133,73 @@ pub async fn connect_to_server(
                         serde_json::json!({ "target_monitor_ids": target_monitor_ids }),
                     );
                 }
+                ClientEvent::PresenterNoteReceived {
+                    note_id,
+                    content,
+                    target_monitor_ids,
+                } => {
+                    let _ = app_handle.emit(
+                        "presenter_note_received",
+                        serde_json::json!({
+                            "note_id": note_id,
+                            "content": content,
+                            "target_monitor_ids": target_monitor_ids,
+                        }),
+                    );
+                }
+                ClientEvent::PresenterNoteIncomplete {
+                    note_id,
+                    missing_seqs,
+                } => {
+                    let _ = app_handle.emit(
+                        "presenter_note_incomplete",
+                        serde_json::json!({
+                            "note_id": note_id,
+                            "missing_seqs": missing_seqs,
+                        }),
+                    );
+                }
+                ClientEvent::AirStatusChanged { status, since } => {
+                    let _ = app_handle.emit(
+                        "air_status_changed",
+                        serde_json::json!({ "status": status, "since": since }),
+                    );
+                }
+                ClientEvent::LatencyMeasured { millis } => {
+                    let _ = app_handle.emit(
+                        "latency_measured",
+                        serde_json::json!({ "millis": millis }),
+                    );
+                }
+                ClientEvent::ProtocolError { raw, reason } => {
+                    protocol_errors.record(reason.clone(), Some(raw.clone()));
+                    let _ = app_handle.emit(
+                        "protocol_error",
+                        serde_json::json!({ "raw": raw, "reason": reason }),
+                    );
+                }
+                ClientEvent::SequenceGap { expected, got } => {
+                    let _ = app_handle.emit(
+                        "sequence_gap",
+                        serde_json::json!({ "expected": expected, "got": got }),
+                    );
+                }
+                ClientEvent::Stale { idle_ms } => {
+                    let _ = app_handle.emit(
+                        "connection_stale",
+                        serde_json::json!({ "idle_ms": idle_ms }),
+                    );
+                }
+                ClientEvent::ActivityResumed => {
+                    let _ = app_handle.emit("connection_active", ());
+                }
+                ClientEvent::UnhandledMessage { message_type } => {
+                    protocol_errors.record(format!("unhandled message type: {}", message_type), None);
+                    let _ = app_handle.emit(
+                        "unhandled_message",
+                        serde_json::json!({ "message_type": message_type }),
+                    );
+                }
             }
         }
     });
@@ -113,17 +210,9 @@ pub async fn connect_to_server(
 /// Disconnect from the Kanpe server
 #[tauri::command]
 pub async fn disconnect_from_server(state: State<'_, AppState>) -> Result<(), String> {
-    let mut client = state.client.write().await;
-    if let Some(c) = client.take() {
-        let mut c = c;
-        c.disconnect()
-            .await
-            .map_err(|e| format!("Failed to disconnect: {}", e))?;
-    }
-
-    // Reset mode
-    *state.mode.write().await = AppMode::NotSelected;
-
+    // Disconnect the client and reset mode, leaving any StreamDeck showing the disconnect
+    state.reset().await?;
+    push_streamdeck_state(&state.streamdeck_server, &state.client, false).await;
     Ok(())
 }
 
@@ -135,35 +224,63 @@ pub async fn send_feedback(
     reply_to_message_id: String,
     feedback_type: String,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<String, String> {
     let client = state.client.read().await;
     if let Some(client) = client.as_ref() {
-        // Parse feedback type
-        let feedback_type = match feedback_type.to_lowercase().as_str() {
-            "question" => FeedbackType::Question,
-            "issue" => FeedbackType::Issue,
-            "info" => FeedbackType::Info,
-            _ => FeedbackType::Ack,
-        };
-
-        // Create and send feedback message
-        let message = Message::feedback_message(
-            content,
-            client_name,
-            reply_to_message_id,
-            feedback_type,
-        );
+        // Parse feedback type; unknown names are custom categories, blank means ack
+        let feedback_type = FeedbackType::from_name(&feedback_type).unwrap_or_default();
+
+        // Send feedback and hand its ID back so the UI can track it
         client
-            .send_message(&message)
+            .send_feedback(content, client_name, reply_to_message_id, feedback_type)
             .await
-            .map_err(|e| format!("Failed to send feedback: {}", e))?;
-
-        Ok(())
+            .map_err(|e| format!("Failed to send feedback: {}", e))
     } else {
         Err("Not connected to server".to_string())
     }
 }
 
+/// Send a one-tap reaction to a cue, returning its ID
+#[tauri::command]
+pub async fn send_reaction(
+    reply_to_message_id: String,
+    reaction: String,
+    client_name: String,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let client = state.client.read().await;
+    let client = client.as_ref().ok_or("Not connected to server")?;
+
+    client
+        .send_reaction(reply_to_message_id, reaction, client_name)
+        .await
+        .map_err(|e| format!("Failed to send reaction: {}", e))
+}
+
+/// Acknowledge a received cue, stopping any repeated urgent flash
+#[tauri::command]
+pub async fn acknowledge_message(message_id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let client = state.client.read().await;
+    let client = client.as_ref().ok_or("Not connected to server")?;
+
+    client
+        .acknowledge_message(message_id)
+        .await
+        .map_err(|e| format!("Failed to acknowledge message: {}", e))
+}
+
+/// Tell the director whether the operator is composing feedback
+#[tauri::command]
+pub async fn send_typing(is_typing: bool, state: State<'_, AppState>) -> Result<(), String> {
+    let client = state.client.read().await;
+    let client = client.as_ref().ok_or("Not connected to server")?;
+
+    client
+        .send_typing(is_typing)
+        .await
+        .map_err(|e| format!("Failed to send typing status: {}", e))
+}
+
 /// Create a popout window for a specific monitor
 #[tauri::command]
 pub async fn create_popout_window(
@@ -208,11 +325,14 @@ pub async fn close_popout_window(
     }
 }
 
-/// Get client connection status
+/// Get client connection status, including any reconnect attempt in progress
 #[tauri::command]
 pub async fn get_client_connection_status(
     state: State<'_, AppState>,
-) -> Result<bool, String> {
+) -> Result<ConnectionState, String> {
     let client = state.client.read().await;
-    Ok(client.is_some())
+    match client.as_ref() {
+        Some(client) => Ok(client.connection_state().await),
+        None => Ok(ConnectionState::Disconnected),
+    }
 }
@@ -1,40 +1,67 @@
 //! Server-mode Tauri commands
 
 use crate::config::ConnectedClientInfo;
-use crate::state::{AppMode, AppState};
-use kanpe_core::{Message, Priority};
-use kanpe_core::types::VirtualMonitor;
+use crate::state::{AppState, PortOwner};
+use crate::templates::{load_templates, render_template};
+use kanpe_core::{AirStatus, Message, Priority};
+use kanpe_core::types::{MonitorGroup, VirtualMonitor};
 use kanpe_server::events::ServerEvent;
-use kanpe_server::KanpeServer;
-use tauri::{AppHandle, Emitter, State};
+use kanpe_server::{ConnectionLogEntry, KanpeServer, MonitorSyncStatus, ServerConfig, ServerMetrics, ServerSnapshot};
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
 use tokio::sync::mpsc;
 
+/// How often an unacknowledged urgent cue re-flashes its monitors, unless the UI says otherwise
+const URGENT_REPEAT_INTERVAL: Duration = Duration::from_secs(3);
+
+/// How long an urgent cue keeps re-flashing without an ack, unless the UI says otherwise
+const URGENT_REPEAT_TIMEOUT: Duration = Duration::from_secs(60);
+
 /// Start the Kanpe server
 #[tauri::command]
 pub async fn start_server(
     port: u16,
+    auth_token: Option<String>,
+    server_name: Option<String>,
     app_handle: AppHandle,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
-    // Check mode
-    let mode = state.mode.read().await;
-    if *mode == AppMode::Client {
-        return Err("Cannot start server while in client mode".to_string());
-    }
-    drop(mode);
+    state.check_port_available(PortOwner::Kanpe, port).await?;
 
-    // Set mode to Server
-    *state.mode.write().await = AppMode::Server;
+    // Switch to server mode (rejected while in client mode)
+    state.enter_server().await?;
 
     // Create event channel
     let (event_tx, mut event_rx) = mpsc::unbounded_channel::<ServerEvent>();
 
+    // Persist scenes and the monitor layout next to the other app data files
+    let data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .ok()
+        .filter(|dir| std::fs::create_dir_all(dir).is_ok());
+    let config = ServerConfig {
+        scenes_path: data_dir.as_ref().map(|dir| dir.join("scenes.json")),
+        monitors_path: data_dir.as_ref().map(|dir| dir.join("monitors.json")),
+        auth_token: auth_token.filter(|token| !token.is_empty()),
+        ..Default::default()
+    };
+
     // Create and start server
-    let mut server = KanpeServer::new(event_tx);
-    server
-        .start(port)
-        .await
-        .map_err(|e| format!("Failed to start server: {}", e))?;
+    let mut server = KanpeServer::with_config(event_tx, config);
+    if let Some(name) = server_name.filter(|name| !name.trim().is_empty()) {
+        server.set_name(name.trim());
+    }
+    let addr = match server.start(port).await {
+        Ok(addr) => addr,
+        Err(e) => {
+            state.abandon_transition().await;
+            return Err(format!("Failed to start server: {}", e));
+        }
+    };
+
+    state.record_port(PortOwner::Kanpe, addr.port()).await;
 
     // Get initial monitors
     let monitors = server.get_monitors().await;
@@ -44,7 +71,7 @@ pub async fn start_server(
 
     // Emit server_started event with monitors
     app_handle
-        .emit("server_started", serde_json::json!({ "port": port, "monitors": monitors }))
+        .emit("server_started", serde_json::json!({ "port": addr.port(), "monitors": monitors }))
         .map_err(|e| format!("Failed to emit event: {}", e))?;
 
     // Spawn task to handle server events
@@ -55,27 +82,71 @@ pub async fn start_server(
                     client_id,
                     name,
                     monitor_ids,
+                    connected_at,
                 } => {
                     let _ = app_handle.emit(
                         "client_connected",
+                        ConnectedClientInfo {
+                            client_id,
+                            name,
+                            monitor_ids,
+                            connected_at,
+                            last_pong_at: None,
+                        },
+                    );
+                }
+                ServerEvent::ClientReconnected {
+                    client_id,
+                    name,
+                    monitor_ids,
+                    connected_at,
+                } => {
+                    let _ = app_handle.emit(
+                        "client_reconnected",
+                        ConnectedClientInfo {
+                            client_id,
+                            name,
+                            monitor_ids,
+                            connected_at,
+                            last_pong_at: None,
+                        },
+                    );
+                }
+                ServerEvent::ClientDisconnected { client_id, reason } => {
+                    let _ = app_handle.emit(
+                        "client_disconnected",
                         serde_json::json!({
                             "client_id": client_id,
-                            "name": name,
-                            "monitor_ids": monitor_ids,
+                            "reason": reason,
                         }),
                     );
                 }
-                ServerEvent::ClientDisconnected { client_id } => {
+                ServerEvent::ClientSubscriptionUpdated {
+                    client_id,
+                    monitor_ids,
+                } => {
                     let _ = app_handle.emit(
-                        "client_disconnected",
+                        "client_subscription_updated",
                         serde_json::json!({
                             "client_id": client_id,
+                            "monitor_ids": monitor_ids,
+                        }),
+                    );
+                }
+                ServerEvent::ClientRejected { reason } => {
+                    let _ = app_handle.emit(
+                        "client_rejected",
+                        serde_json::json!({
+                            "reason": reason,
                         }),
                     );
                 }
                 ServerEvent::FeedbackReceived { message } => {
                     let _ = app_handle.emit("feedback_received", message);
                 }
+                ServerEvent::ReactionReceived { message } => {
+                    let _ = app_handle.emit("reaction_received", message);
+                }
                 ServerEvent::MonitorAdded { monitor } => {
                     let _ = app_handle.emit("monitor_added", monitor);
                 }
@@ -88,6 +159,58 @@ pub async fn start_server(
                 ServerEvent::MonitorUpdated { monitor } => {
                     let _ = app_handle.emit("monitor_updated", monitor);
                 }
+                ServerEvent::MessageAcknowledged {
+                    client_id,
+                    message_id,
+                } => {
+                    let _ = app_handle.emit(
+                        "message_acknowledged",
+                        serde_json::json!({
+                            "client_id": client_id,
+                            "message_id": message_id,
+                        }),
+                    );
+                }
+                ServerEvent::ClientTyping {
+                    client_id,
+                    client_name,
+                    is_typing,
+                } => {
+                    let _ = app_handle.emit(
+                        "client_typing",
+                        serde_json::json!({
+                            "client_id": client_id,
+                            "client_name": client_name,
+                            "is_typing": is_typing,
+                        }),
+                    );
+                }
+                ServerEvent::UnknownTargets {
+                    message_id,
+                    monitor_ids,
+                } => {
+                    let _ = app_handle.emit(
+                        "unknown_targets",
+                        serde_json::json!({
+                            "message_id": message_id,
+                            "monitor_ids": monitor_ids,
+                        }),
+                    );
+                }
+                ServerEvent::MessageBroadcast {
+                    message_id,
+                    recipient_count,
+                    targets,
+                } => {
+                    let _ = app_handle.emit(
+                        "message_broadcast",
+                        serde_json::json!({
+                            "message_id": message_id,
+                            "recipient_count": recipient_count,
+                            "targets": targets,
+                        }),
+                    );
+                }
             }
         }
     });
@@ -101,16 +224,8 @@ pub async fn stop_server(
     app_handle: AppHandle,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
-    let mut server = state.server.write().await;
-    if let Some(s) = server.take() {
-        let mut s = s;
-        s.stop()
-            .await
-            .map_err(|e| format!("Failed to stop server: {}", e))?;
-    }
-
-    // Reset mode
-    *state.mode.write().await = AppMode::NotSelected;
+    // Stop the server and reset mode
+    state.reset().await?;
 
     // Emit server_stopped event
     app_handle
@@ -132,14 +247,8 @@ pub async fn send_kanpe_message(
     let server = state.server.read().await;
     let server = server.as_ref().ok_or("Server not running")?;
 
-    // Parse priority
-    let priority = match priority.to_lowercase().as_str() {
-        "high" => Priority::High,
-        "urgent" => Priority::Urgent,
-        _ => Priority::Normal,
-    };
-
     // Create and send message
+    let priority = parse_priority(&priority);
     let message = Message::kanpe_message(content, target_monitor_ids, priority);
     server
         .broadcast_message(message.clone())
@@ -154,6 +263,99 @@ pub async fn send_kanpe_message(
     Ok(())
 }
 
+/// Send an urgent cue that re-flashes its monitors until a caster on one of them acknowledges it
+///
+/// Returns the cue's message ID.
+#[tauri::command]
+pub async fn send_urgent_message(
+    target_monitor_ids: Vec<String>,
+    content: String,
+    repeat_interval_ms: Option<u64>,
+    timeout_ms: Option<u64>,
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let server = state.server.read().await;
+    let server = server.as_ref().ok_or("Server not running")?;
+
+    let message_id = server
+        .broadcast_urgent(
+            content,
+            target_monitor_ids,
+            repeat_interval_ms.map_or(URGENT_REPEAT_INTERVAL, Duration::from_millis),
+            timeout_ms.map_or(URGENT_REPEAT_TIMEOUT, Duration::from_millis),
+        )
+        .await
+        .map_err(|e| format!("Failed to send message: {}", e))?;
+
+    // Emit event for sent message, as recorded in the history
+    if let Some(message) = server
+        .message_history()
+        .await
+        .into_iter()
+        .rev()
+        .find(|message| message.id() == message_id)
+    {
+        app_handle
+            .emit("kanpe_message_sent", &message)
+            .map_err(|e| format!("Failed to emit event: {}", e))?;
+    }
+
+    Ok(message_id)
+}
+
+/// Send a server template as a cue, filling its `{name}` placeholders from `vars`
+#[tauri::command]
+pub async fn send_template_message(
+    template_id: String,
+    vars: HashMap<String, String>,
+    target_monitor_ids: Vec<String>,
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let config = load_templates(&app_handle)?;
+    let template = config
+        .server_templates
+        .into_iter()
+        .find(|t| t.id == template_id)
+        .ok_or_else(|| "Template not found".to_string())?;
+
+    let content = render_template(&template.content, &vars);
+    send_kanpe_message(target_monitor_ids, content, template.priority, app_handle, state).await
+}
+
+/// Map a priority name from the UI, treating anything unknown as normal
+fn parse_priority(priority: &str) -> Priority {
+    match priority.to_lowercase().as_str() {
+        "low" => Priority::Low,
+        "high" => Priority::High,
+        "urgent" => Priority::Urgent,
+        _ => Priority::Normal,
+    }
+}
+
+/// Get the cues sent and feedback received this session, oldest first
+#[tauri::command]
+pub async fn get_message_history(state: State<'_, AppState>) -> Result<serde_json::Value, String> {
+    let server = state.server.read().await;
+    let server = server.as_ref().ok_or("Server not running")?;
+
+    Ok(serde_json::json!({
+        "messages": server.message_history().await,
+        "feedback": server.feedback_history().await,
+    }))
+}
+
+/// Clear the session's cue and feedback history
+#[tauri::command]
+pub async fn clear_message_history(state: State<'_, AppState>) -> Result<(), String> {
+    let server = state.server.read().await;
+    let server = server.as_ref().ok_or("Server not running")?;
+
+    server.clear_history().await;
+    Ok(())
+}
+
 /// Get list of connected clients
 #[tauri::command]
 pub async fn get_connected_clients(
@@ -169,23 +371,110 @@ pub async fn get_connected_clients(
             client_id: c.client_id,
             name: c.client_name,
             monitor_ids: c.display_monitor_ids,
+            connected_at: c.connected_at,
+            last_pong_at: c.last_pong_at,
         })
         .collect())
 }
 
+/// Get counters for the current server session
+#[tauri::command]
+pub async fn get_server_metrics(state: State<'_, AppState>) -> Result<ServerMetrics, String> {
+    let server = state.server.read().await;
+    let server = server.as_ref().ok_or("Server not running")?;
+
+    Ok(server.metrics().await)
+}
+
+/// Get the whole server state in one call, for a window that is (re)loading
+#[tauri::command]
+pub async fn get_server_snapshot(state: State<'_, AppState>) -> Result<ServerSnapshot, String> {
+    let server = state.server.read().await;
+    let server = server.as_ref().ok_or("Server not running")?;
+
+    Ok(server.snapshot().await)
+}
+
+/// Get the timeline of client connections and disconnections
+#[tauri::command]
+pub async fn get_connection_log(state: State<'_, AppState>) -> Result<Vec<ConnectionLogEntry>, String> {
+    let server = state.server.read().await;
+    let server = server.as_ref().ok_or("Server not running")?;
+
+    Ok(server.connection_log().await)
+}
+
+/// Set the on-air banner shown on every caster screen
+#[tauri::command]
+pub async fn set_air_status(status: AirStatus, state: State<'_, AppState>) -> Result<(), String> {
+    let server = state.server.read().await;
+    let server = server.as_ref().ok_or("Server not running")?;
+
+    server
+        .set_air_status(status)
+        .await
+        .map_err(|e| format!("Failed to set air status: {}", e))
+}
+
+/// Check whether a client is connected, with why it last dropped if it is not
+#[tauri::command]
+pub async fn is_client_connected(
+    client_id: String,
+    state: State<'_, AppState>,
+) -> Result<serde_json::Value, String> {
+    let server = state.server.read().await;
+    let server = server.as_ref().ok_or("Server not running")?;
+
+    let connected = server.is_client_connected(&client_id).await;
+    let last_disconnect_reason = if connected {
+        None
+    } else {
+        server.last_disconnect_reason(&client_id).await
+    };
+    Ok(serde_json::json!({
+        "connected": connected,
+        "last_disconnect_reason": last_disconnect_reason,
+    }))
+}
+
+/// Forcibly disconnect a connected client
+#[tauri::command]
+pub async fn disconnect_client(client_id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let server = state.server.read().await;
+    let server = server.as_ref().ok_or("Server not running")?;
+
+    server
+        .disconnect_client(&client_id)
+        .await
+        .map_err(|e| format!("Failed to disconnect client: {}", e))
+}
+
+/// Ask a connected client to drop its connection and reconnect
+#[tauri::command]
+pub async fn request_client_reconnect(client_id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let server = state.server.read().await;
+    let server = server.as_ref().ok_or("Server not running")?;
+
+    server
+        .request_client_reconnect(&client_id)
+        .await
+        .map_err(|e| format!("Failed to request reconnect: {}", e))
+}
+
 /// Add a new virtual monitor
 #[tauri::command]
 pub async fn add_virtual_monitor(
     name: String,
     description: Option<String>,
     color: Option<String>,
+    icon: Option<String>,
     state: State<'_, AppState>,
 ) -> Result<VirtualMonitor, String> {
     let server = state.server.read().await;
     let server = server.as_ref().ok_or("Server not running")?;
 
     server
-        .add_monitor(name, description, color)
+        .add_monitor(name, description, color, icon)
         .await
         .map_err(|e| format!("Failed to add monitor: {}", e))
 }
@@ -220,6 +509,91 @@ pub async fn update_virtual_monitor(
         .map_err(|e| format!("Failed to update monitor: {}", e))
 }
 
+/// Create a named group of monitors that cues can target as "group:<id>"
+#[tauri::command]
+pub async fn create_monitor_group(
+    group_id: String,
+    name: String,
+    monitor_ids: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<MonitorGroup, String> {
+    let server = state.server.read().await;
+    let server = server.as_ref().ok_or("Server not running")?;
+
+    server
+        .create_monitor_group(group_id, name, monitor_ids)
+        .await
+        .map_err(|e| format!("Failed to create group: {}", e))
+}
+
+/// Delete a monitor group
+#[tauri::command]
+pub async fn delete_monitor_group(group_id: String, state: State<'_, AppState>) -> Result<bool, String> {
+    let server = state.server.read().await;
+    let server = server.as_ref().ok_or("Server not running")?;
+
+    server
+        .delete_monitor_group(&group_id)
+        .await
+        .map_err(|e| format!("Failed to delete group: {}", e))
+}
+
+/// Get all monitor groups
+#[tauri::command]
+pub async fn get_monitor_groups(state: State<'_, AppState>) -> Result<Vec<MonitorGroup>, String> {
+    let server = state.server.read().await;
+    let server = server.as_ref().ok_or("Server not running")?;
+    Ok(server.get_monitor_groups().await)
+}
+
+/// Enable or disable a virtual monitor, keeping its config
+#[tauri::command]
+pub async fn set_monitor_enabled(
+    monitor_id: String,
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let server = state.server.read().await;
+    let server = server.as_ref().ok_or("Server not running")?;
+
+    server
+        .set_monitor_enabled(&monitor_id, enabled)
+        .await
+        .map_err(|e| format!("Failed to update monitor: {}", e))
+}
+
+/// Change the display order of virtual monitors, returning the reordered list
+#[tauri::command]
+pub async fn reorder_virtual_monitors(
+    ordered_ids: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<VirtualMonitor>, String> {
+    let server = state.server.read().await;
+    let server = server.as_ref().ok_or("Server not running")?;
+
+    server
+        .reorder_monitors(ordered_ids)
+        .await
+        .map_err(|e| format!("Failed to reorder monitors: {}", e))?;
+    Ok(server.get_monitors().await)
+}
+
+/// Replace the whole set of virtual monitors, returning the new list
+#[tauri::command]
+pub async fn replace_virtual_monitors(
+    monitors: Vec<VirtualMonitor>,
+    state: State<'_, AppState>,
+) -> Result<Vec<VirtualMonitor>, String> {
+    let server = state.server.read().await;
+    let server = server.as_ref().ok_or("Server not running")?;
+
+    server
+        .replace_monitors(monitors)
+        .await
+        .map_err(|e| format!("Failed to replace monitors: {}", e))?;
+    Ok(server.get_monitors().await)
+}
+
 /// Get all virtual monitors
 #[tauri::command]
 pub async fn get_virtual_monitors(state: State<'_, AppState>) -> Result<Vec<VirtualMonitor>, String> {
@@ -229,6 +603,17 @@ pub async fn get_virtual_monitors(state: State<'_, AppState>) -> Result<Vec<Virt
     Ok(server.get_monitors().await)
 }
 
+/// Get which clients have applied the current monitor list
+#[tauri::command]
+pub async fn get_monitor_sync_status(
+    state: State<'_, AppState>,
+) -> Result<Vec<MonitorSyncStatus>, String> {
+    let server = state.server.read().await;
+    let server = server.as_ref().ok_or("Server not running")?;
+
+    Ok(server.get_monitor_sync_status().await)
+}
+
 /// Send a flash command to clients
 #[tauri::command]
 pub async fn send_flash_command(
@@ -238,11 +623,12 @@ pub async fn send_flash_command(
     let server = state.server.read().await;
     let server = server.as_ref().ok_or("Server not running")?;
 
-    let message = Message::flash_command(target_monitor_ids);
     server
-        .broadcast_message(message)
+        .broadcast_flash_command(target_monitor_ids)
         .await
-        .map_err(|e| format!("Failed to send flash command: {}", e))
+        .map_err(|e| format!("Failed to send flash command: {}", e))?;
+
+    Ok(())
 }
 
 /// Send a clear command to clients
@@ -254,44 +640,120 @@ pub async fn send_clear_command(
     let server = state.server.read().await;
     let server = server.as_ref().ok_or("Server not running")?;
 
-    let message = Message::clear_command(target_monitor_ids);
     server
-        .broadcast_message(message)
+        .broadcast_clear_command(target_monitor_ids)
+        .await
+        .map_err(|e| format!("Failed to send clear command: {}", e))?;
+
+    Ok(())
+}
+
+/// Send a presenter note to clients (split into chunks when long)
+#[tauri::command]
+pub async fn send_presenter_note(
+    content: String,
+    target_monitor_ids: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let server = state.server.read().await;
+    let server = server.as_ref().ok_or("Server not running")?;
+
+    server
+        .send_presenter_note(&content, target_monitor_ids)
+        .await
+        .map_err(|e| format!("Failed to send presenter note: {}", e))
+}
+
+/// Save the current monitors and cues as a named scene
+#[tauri::command]
+pub async fn save_scene(name: String, state: State<'_, AppState>) -> Result<(), String> {
+    let server = state.server.read().await;
+    let server = server.as_ref().ok_or("Server not running")?;
+
+    server
+        .save_scene(name)
         .await
-        .map_err(|e| format!("Failed to send clear command: {}", e))
+        .map_err(|e| format!("Failed to save scene: {}", e))
+}
+
+/// Recall a saved scene, restoring its monitors and cues on all clients
+#[tauri::command]
+pub async fn recall_scene(
+    name: String,
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let server = state.server.read().await;
+    let server = server.as_ref().ok_or("Server not running")?;
+
+    server
+        .recall_scene(&name)
+        .await
+        .map_err(|e| format!("Failed to recall scene: {}", e))?;
+
+    // Let the UI refresh its monitor list
+    app_handle
+        .emit("scene_recalled", serde_json::json!({ "name": name, "monitors": server.get_monitors().await }))
+        .map_err(|e| format!("Failed to emit event: {}", e))?;
+
+    Ok(())
+}
+
+/// Get the names of all saved scenes
+#[tauri::command]
+pub async fn list_scenes(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    let server = state.server.read().await;
+    let server = server.as_ref().ok_or("Server not running")?;
+
+    Ok(server.list_scenes().await)
+}
+
+/// Delete a saved scene
+#[tauri::command]
+pub async fn delete_scene(name: String, state: State<'_, AppState>) -> Result<bool, String> {
+    let server = state.server.read().await;
+    let server = server.as_ref().ok_or("Server not running")?;
+
+    server
+        .delete_scene(&name)
+        .await
+        .map_err(|e| format!("Failed to delete scene: {}", e))
+}
+
+/// Pause or resume keepalive pings (for idle battery measurements)
+#[tauri::command]
+pub async fn set_server_ping_enabled(enabled: bool, state: State<'_, AppState>) -> Result<(), String> {
+    let server = state.server.read().await;
+    let server = server.as_ref().ok_or("Server not running")?;
+
+    server.set_ping_enabled(enabled);
+    Ok(())
 }
 
 /// Get server addresses for Web client connection
 #[tauri::command]
 pub async fn get_server_addresses(state: State<'_, AppState>) -> Result<Vec<String>, String> {
     let server = state.server.read().await;
-    if server.is_none() {
-        return Err("Server not running".to_string());
-    }
-    
-    // Get local IP addresses
-    let mut addresses = Vec::new();
-    
-    // Try to get network interfaces
-    if let Ok(interfaces) = local_ip_address::list_afinet_netifas() {
-        for (_name, ip) in interfaces {
-            // Only include IPv4 addresses that are accessible from other local PCs
-            if let std::net::IpAddr::V4(ipv4) = ip {
-                // Skip loopback (127.x.x.x) and link-local (169.254.x.x) addresses
-                if !ipv4.is_loopback() && !ipv4.to_string().starts_with("169.254") {
-                    // Get port from config or use default
-                    let port = 9876; // Default port, could be from state
-                    addresses.push(format!("http://{}:{}", ipv4, port));
-                }
-            }
-            // Skip IPv6 addresses (IpAddr::V6)
-        }
-    }
-    
-    // If no addresses found, add localhost
-    if addresses.is_empty() {
-        addresses.push("http://localhost:9876".to_string());
+    let server = server.as_ref().ok_or("Server not running")?;
+
+    // Web casters open the HTTP page served on the same host and port
+    Ok(server
+        .listening_addresses()
+        .into_iter()
+        .map(|address| address.replacen("ws://", "http://", 1))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_priority_maps_names() {
+        assert_eq!(parse_priority("low"), Priority::Low);
+        assert_eq!(parse_priority("Normal"), Priority::Normal);
+        assert_eq!(parse_priority("high"), Priority::High);
+        assert_eq!(parse_priority("URGENT"), Priority::Urgent);
+        assert_eq!(parse_priority("unknown"), Priority::Normal);
     }
-    
-    Ok(addresses)
 }
@@ -1,17 +1,21 @@
 //! StreamDeck integration Tauri commands
 
-use crate::state::AppState;
+use crate::state::{AppState, PortOwner};
 use kanpe_client::KanpeClient;
-use kanpe_core::{FeedbackType, Message};
+use kanpe_core::FeedbackType;
 use kanpe_streamdeck_server::{StreamDeckEvent, StreamDeckResponse, StreamDeckServer, protocol::LatestMessageInfo};
 use tauri::{AppHandle, Emitter, State};
 use tokio::sync::{mpsc, RwLock};
+use std::net::{IpAddr, Ipv4Addr};
 use std::sync::Arc;
 
-/// Start the StreamDeck WebSocket server
+/// Start the StreamDeck WebSocket server, returning the port it bound
+///
+/// Binds to loopback unless `bind_address` is given (e.g. "0.0.0.0" for a deck on another machine).
 #[tauri::command]
 pub async fn start_streamdeck_server(
     port: u16,
+    bind_address: Option<String>,
     app_handle: AppHandle,
     state: State<'_, AppState>,
 ) -> Result<u16, String> {
@@ -22,34 +26,53 @@ pub async fn start_streamdeck_server(
             return Err("StreamDeck server is already running".to_string());
         }
     }
+    state.check_port_available(PortOwner::StreamDeck, port).await?;
 
     // Create event channel
     let (event_tx, mut event_rx) = mpsc::unbounded_channel::<StreamDeckEvent>();
 
     // Create server
-    let server = StreamDeckServer::new(port, event_tx)
+    let ip = match bind_address.as_deref().filter(|address| !address.is_empty()) {
+        Some(address) => address
+            .parse::<IpAddr>()
+            .map_err(|e| format!("Invalid bind address {}: {}", address, e))?,
+        None => IpAddr::V4(Ipv4Addr::LOCALHOST),
+    };
+    let server = StreamDeckServer::new_on(ip, port, event_tx)
         .await
         .map_err(|e| format!("Failed to start StreamDeck server: {}", e))?;
 
     let actual_port = server.port();
+    state.record_port(PortOwner::StreamDeck, actual_port).await;
 
     // Store server in state
     *state.streamdeck_server.write().await = Some(server);
 
     // Spawn task to handle StreamDeck events
     let client_arc = state.client.clone();
+    let server_arc = state.server.clone();
     let streamdeck_arc = state.streamdeck_server.clone();
     let app_handle_clone = app_handle.clone();
     tokio::spawn(async move {
         while let Some(event) = event_rx.recv().await {
             match event {
-                StreamDeckEvent::Connected => {
-                    let _ = app_handle_clone.emit("streamdeck_connected", ());
+                StreamDeckEvent::Connected { connection_id } => {
+                    let _ = app_handle_clone.emit(
+                        "streamdeck_connected",
+                        serde_json::json!({ "connection_id": connection_id }),
+                    );
+                    // A deck that (re)connects gets the current state without asking
+                    let connected = client_arc.read().await.is_some();
+                    push_streamdeck_state(&streamdeck_arc, &client_arc, connected).await;
                 }
-                StreamDeckEvent::Disconnected => {
-                    let _ = app_handle_clone.emit("streamdeck_disconnected", ());
+                StreamDeckEvent::Disconnected { connection_id } => {
+                    let _ = app_handle_clone.emit(
+                        "streamdeck_disconnected",
+                        serde_json::json!({ "connection_id": connection_id }),
+                    );
                 }
                 StreamDeckEvent::SendFeedback {
+                    connection_id,
                     content,
                     feedback_type,
                 } => {
@@ -68,10 +91,13 @@ pub async fn start_streamdeck_server(
                     };
 
                     if let Some(server) = streamdeck_arc.read().await.as_ref() {
-                        let _ = server.send_response(response).await;
+                        let _ = server.send_response(connection_id, response).await;
                     }
                 }
-                StreamDeckEvent::ReactToLatest { feedback_type } => {
+                StreamDeckEvent::ReactToLatest {
+                    connection_id,
+                    feedback_type,
+                } => {
                     // Get latest message and send feedback
                     let result = react_to_latest_internal(
                         &client_arc,
@@ -86,39 +112,90 @@ pub async fn start_streamdeck_server(
                     };
 
                     if let Some(server) = streamdeck_arc.read().await.as_ref() {
-                        let _ = server.send_response(response).await;
+                        let _ = server.send_response(connection_id, response).await;
+                    }
+                }
+                StreamDeckEvent::SendFlash {
+                    connection_id,
+                    target_monitor_ids,
+                } => {
+                    let result = match server_arc.read().await.as_ref() {
+                        Some(server) => server
+                            .broadcast_flash_command(target_monitor_ids)
+                            .await
+                            .map(|_| ())
+                            .map_err(|e| format!("Failed to send flash command: {}", e)),
+                        None => Err("Server not running".to_string()),
+                    };
+
+                    let response = match result {
+                        Ok(_) => StreamDeckResponse::success(),
+                        Err(e) => StreamDeckResponse::error(e),
+                    };
+
+                    if let Some(server) = streamdeck_arc.read().await.as_ref() {
+                        let _ = server.send_response(connection_id, response).await;
                     }
                 }
-                StreamDeckEvent::GetState => {
-                    // Get current state and send to StreamDeck
-                    let (connected, latest_message, monitors) = {
-                        let client_lock = client_arc.read().await;
-                        if let Some(client) = client_lock.as_ref() {
-                            let latest = client.get_latest_message().map(|(id, payload)| {
-                                LatestMessageInfo {
-                                    id,
-                                    content: payload.content,
-                                    priority: format!("{:?}", payload.priority).to_lowercase(),
-                                    target_monitor_ids: payload.target_monitor_ids,
-                                }
-                            });
-                            let monitors = client.get_monitors();
-                            (true, latest, monitors)
-                        } else {
-                            (false, None, vec![])
-                        }
+                StreamDeckEvent::SendClear {
+                    connection_id,
+                    target_monitor_ids,
+                } => {
+                    let result = match server_arc.read().await.as_ref() {
+                        Some(server) => server
+                            .broadcast_clear_command(target_monitor_ids)
+                            .await
+                            .map(|_| ())
+                            .map_err(|e| format!("Failed to send clear command: {}", e)),
+                        None => Err("Server not running".to_string()),
+                    };
+
+                    let response = match result {
+                        Ok(_) => StreamDeckResponse::success(),
+                        Err(e) => StreamDeckResponse::error(e),
+                    };
+
+                    if let Some(server) = streamdeck_arc.read().await.as_ref() {
+                        let _ = server.send_response(connection_id, response).await;
+                    }
+                }
+                StreamDeckEvent::GetMonitors { connection_id } => {
+                    let monitors = match client_arc.read().await.as_ref() {
+                        Some(client) => client.monitors().await,
+                        None => vec![],
                     };
 
-                    let response = StreamDeckResponse::StateUpdate {
-                        connected,
-                        latest_message,
-                        monitors,
+                    if let Some(server) = streamdeck_arc.read().await.as_ref() {
+                        let _ = server
+                            .send_response(connection_id, StreamDeckResponse::Monitors { monitors })
+                            .await;
+                    }
+                }
+                StreamDeckEvent::GetHistory { connection_id, limit } => {
+                    let mut history = match client_arc.read().await.as_ref() {
+                        Some(client) => client.message_history().await,
+                        None => vec![],
                     };
+                    // Keep the most recent `limit` entries, still oldest first
+                    if let Some(limit) = limit {
+                        history.drain(..history.len().saturating_sub(limit));
+                    }
+                    let messages = history
+                        .into_iter()
+                        .map(|(id, payload)| LatestMessageInfo::from_payload(id, payload))
+                        .collect();
 
                     if let Some(server) = streamdeck_arc.read().await.as_ref() {
-                        let _ = server.send_response(response).await;
+                        let _ = server
+                            .send_response(connection_id, StreamDeckResponse::History { messages })
+                            .await;
                     }
                 }
+                StreamDeckEvent::GetState { .. } => {
+                    // Send current state to every StreamDeck so they stay in sync
+                    let connected = client_arc.read().await.is_some();
+                    push_streamdeck_state(&streamdeck_arc, &client_arc, connected).await;
+                }
             }
         }
     });
@@ -131,6 +208,7 @@ pub async fn start_streamdeck_server(
 pub async fn stop_streamdeck_server(state: State<'_, AppState>) -> Result<(), String> {
     let mut server_lock = state.streamdeck_server.write().await;
     if let Some(server) = server_lock.take() {
+        state.release_port(PortOwner::StreamDeck).await;
         server
             .shutdown()
             .await
@@ -148,6 +226,40 @@ pub async fn get_streamdeck_status(state: State<'_, AppState>) -> Result<bool, S
     Ok(server_lock.is_some())
 }
 
+/// Send the client's current state to every connected StreamDeck
+///
+/// `connected` is passed in because connection events can arrive before the
+/// client is stored in (or after it is removed from) `AppState`.
+pub(crate) async fn push_streamdeck_state(
+    streamdeck_arc: &Arc<RwLock<Option<StreamDeckServer>>>,
+    client_arc: &Arc<RwLock<Option<KanpeClient>>>,
+    connected: bool,
+) {
+    let streamdeck = streamdeck_arc.read().await;
+    let Some(server) = streamdeck.as_ref() else {
+        return;
+    };
+
+    let (latest_message, monitors) = match client_arc.read().await.as_ref() {
+        Some(client) => (
+            client
+                .latest_message()
+                .await
+                .map(|(id, payload)| LatestMessageInfo::from_payload(id, payload)),
+            client.monitors().await,
+        ),
+        None => (None, vec![]),
+    };
+
+    let _ = server
+        .broadcast(StreamDeckResponse::StateUpdate {
+            connected,
+            latest_message,
+            monitors,
+        })
+        .await;
+}
+
 // Internal helper functions
 
 async fn send_feedback_internal(
@@ -156,13 +268,8 @@ async fn send_feedback_internal(
     feedback_type_str: String,
 ) -> Result<(), String> {
     // Parse feedback type
-    let feedback_type = match feedback_type_str.as_str() {
-        "Ack" => FeedbackType::Ack,
-        "Question" => FeedbackType::Question,
-        "Issue" => FeedbackType::Issue,
-        "Info" => FeedbackType::Info,
-        _ => return Err(format!("Invalid feedback type: {}", feedback_type_str)),
-    };
+    let feedback_type = FeedbackType::from_name(&feedback_type_str)
+        .ok_or_else(|| format!("Invalid feedback type: {}", feedback_type_str))?;
 
     // Get client
     let client_lock = client_arc.read().await;
@@ -172,13 +279,13 @@ async fn send_feedback_internal(
 
     // Get client name
     let client_name = client
-        .get_client_name()
+        .client_name()
+        .await
         .unwrap_or("StreamDeck".to_string());
 
-    // Create and send feedback message
-    let message = Message::feedback_message(content, client_name, String::new(), feedback_type);
+    // Send feedback message
     client
-        .send_message(&message)
+        .send_feedback(content, client_name, String::new(), feedback_type)
         .await
         .map_err(|e| format!("Failed to send feedback: {}", e))?;
 
@@ -190,13 +297,8 @@ async fn react_to_latest_internal(
     feedback_type_str: String,
 ) -> Result<(), String> {
     // Parse feedback type
-    let feedback_type = match feedback_type_str.as_str() {
-        "Ack" => FeedbackType::Ack,
-        "Question" => FeedbackType::Question,
-        "Issue" => FeedbackType::Issue,
-        "Info" => FeedbackType::Info,
-        _ => return Err(format!("Invalid feedback type: {}", feedback_type_str)),
-    };
+    let feedback_type = FeedbackType::from_name(&feedback_type_str)
+        .ok_or_else(|| format!("Invalid feedback type: {}", feedback_type_str))?;
 
     // Get client and latest message
     let client_lock = client_arc.read().await;
@@ -205,27 +307,93 @@ async fn react_to_latest_internal(
         .ok_or("Not connected to a server")?;
 
     let (message_id, _payload) = client
-        .get_latest_message()
+        .latest_message()
+        .await
         .ok_or("No messages received yet")?;
 
     let client_name = client
-        .get_client_name()
+        .client_name()
+        .await
         .unwrap_or("StreamDeck".to_string());
 
     // Create acknowledgment content
-    let content = match feedback_type {
+    let content = match &feedback_type {
         FeedbackType::Ack => "了解しました".to_string(),
         FeedbackType::Question => "質問があります".to_string(),
         FeedbackType::Issue => "問題が発生しました".to_string(),
         FeedbackType::Info => "情報を共有します".to_string(),
+        FeedbackType::Custom(label) => label.clone(),
     };
 
-    // Create and send feedback message
-    let message = Message::feedback_message(content, client_name, message_id, feedback_type);
+    // Send feedback message
     client
-        .send_message(&message)
+        .send_feedback(content, client_name, message_id, feedback_type)
         .await
         .map_err(|e| format!("Failed to send feedback: {}", e))?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+    use kanpe_client::events::ClientEvent;
+    use kanpe_core::{Message, Priority};
+    use kanpe_server::KanpeServer;
+    use tokio_tungstenite::tungstenite::Message as TungsteniteMessage;
+
+    #[tokio::test]
+    async fn test_received_cue_is_pushed_without_get_state() {
+        let (server_tx, _server_rx) = mpsc::unbounded_channel();
+        let mut kanpe = KanpeServer::new(server_tx);
+        let addr = kanpe.start(0).await.unwrap();
+
+        let (client_tx, mut client_rx) = mpsc::unbounded_channel();
+        let mut client = KanpeClient::new(client_tx);
+        client
+            .connect(&format!("127.0.0.1:{}", addr.port()), "caster".to_string(), vec!["A".to_string()])
+            .await
+            .unwrap();
+        let client_arc = Arc::new(RwLock::new(Some(client)));
+
+        let (deck_tx, mut deck_rx) = mpsc::unbounded_channel();
+        let deck_server = StreamDeckServer::new(0, deck_tx).await.unwrap();
+        let url = format!("ws://127.0.0.1:{}/ws", deck_server.port());
+        let (mut deck, _) = tokio_tungstenite::connect_async(url.as_str()).await.unwrap();
+        assert!(matches!(deck_rx.recv().await, Some(StreamDeckEvent::Connected { .. })));
+        let streamdeck_arc = Arc::new(RwLock::new(Some(deck_server)));
+
+        let cue = Message::kanpe_message("巻いてください".to_string(), vec!["A".to_string()], Priority::High);
+        let cue_id = cue.id().to_string();
+        kanpe.broadcast_message(cue).await.unwrap();
+        loop {
+            if let Some(ClientEvent::MessageReceived { .. }) = client_rx.recv().await {
+                break;
+            }
+        }
+
+        // What the client event loop does on MessageReceived
+        push_streamdeck_state(&streamdeck_arc, &client_arc, true).await;
+
+        let frame = tokio::time::timeout(std::time::Duration::from_secs(2), deck.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        let TungsteniteMessage::Text(text) = frame else {
+            panic!("expected a text frame, got {:?}", frame);
+        };
+        let StreamDeckResponse::StateUpdate { connected, latest_message: Some(latest), .. } =
+            serde_json::from_str(&text).unwrap()
+        else {
+            panic!("expected a StateUpdate with the new cue");
+        };
+        assert!(connected);
+        assert_eq!(latest.id, cue_id);
+        assert_eq!(latest.content, "巻いてください");
+        assert_eq!(latest.priority, "high");
+
+        kanpe.stop().await.unwrap();
+    }
+}
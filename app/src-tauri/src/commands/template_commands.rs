@@ -1,9 +1,10 @@
 //! Template management Tauri commands
 
-use crate::templates::{load_templates, save_templates, ServerTemplate, ClientTemplate, TemplateConfig};
+use crate::templates::{self, load_templates, save_templates, ServerTemplate, ClientTemplate, TemplateConfig};
+use std::path::PathBuf;
 use tauri::AppHandle;
 
-/// Get all templates (both server and client)
+/// Get all templates (both server and client), sorted by their order
 #[tauri::command]
 pub async fn get_templates(app_handle: AppHandle) -> Result<TemplateConfig, String> {
     load_templates(&app_handle)
@@ -14,6 +15,7 @@ pub async fn get_templates(app_handle: AppHandle) -> Result<TemplateConfig, Stri
 pub async fn add_server_template(
     content: String,
     priority: String,
+    category: Option<String>,
     app_handle: AppHandle,
 ) -> Result<ServerTemplate, String> {
     let mut config = load_templates(&app_handle)?;
@@ -22,6 +24,8 @@ pub async fn add_server_template(
         id: uuid::Uuid::new_v4().to_string(),
         content,
         priority,
+        order: config.next_server_order(),
+        category,
     };
 
     config.server_templates.push(template.clone());
@@ -36,6 +40,7 @@ pub async fn update_server_template(
     id: String,
     content: String,
     priority: String,
+    category: Option<String>,
     app_handle: AppHandle,
 ) -> Result<(), String> {
     let mut config = load_templates(&app_handle)?;
@@ -48,6 +53,7 @@ pub async fn update_server_template(
 
     template.content = content;
     template.priority = priority;
+    template.category = category;
 
     save_templates(&app_handle, &config)?;
 
@@ -70,6 +76,7 @@ pub async fn delete_server_template(id: String, app_handle: AppHandle) -> Result
 pub async fn add_client_template(
     content: String,
     feedback_type: String,
+    category: Option<String>,
     app_handle: AppHandle,
 ) -> Result<ClientTemplate, String> {
     let mut config = load_templates(&app_handle)?;
@@ -78,6 +85,8 @@ pub async fn add_client_template(
         id: uuid::Uuid::new_v4().to_string(),
         content,
         feedback_type,
+        order: config.next_client_order(),
+        category,
     };
 
     config.client_templates.push(template.clone());
@@ -92,6 +101,7 @@ pub async fn update_client_template(
     id: String,
     content: String,
     feedback_type: String,
+    category: Option<String>,
     app_handle: AppHandle,
 ) -> Result<(), String> {
     let mut config = load_templates(&app_handle)?;
@@ -104,6 +114,7 @@ pub async fn update_client_template(
 
     template.content = content;
     template.feedback_type = feedback_type;
+    template.category = category;
 
     save_templates(&app_handle, &config)?;
 
@@ -120,3 +131,39 @@ pub async fn delete_client_template(id: String, app_handle: AppHandle) -> Result
 
     Ok(())
 }
+
+/// Reorder server templates; the listed IDs come first, in the given order
+#[tauri::command]
+pub async fn reorder_server_templates(ids: Vec<String>, app_handle: AppHandle) -> Result<TemplateConfig, String> {
+    let mut config = load_templates(&app_handle)?;
+
+    config.reorder_server_templates(&ids)?;
+    config.sort_by_order();
+    save_templates(&app_handle, &config)?;
+
+    Ok(config)
+}
+
+/// Reorder client templates; the listed IDs come first, in the given order
+#[tauri::command]
+pub async fn reorder_client_templates(ids: Vec<String>, app_handle: AppHandle) -> Result<TemplateConfig, String> {
+    let mut config = load_templates(&app_handle)?;
+
+    config.reorder_client_templates(&ids)?;
+    config.sort_by_order();
+    save_templates(&app_handle, &config)?;
+
+    Ok(config)
+}
+
+/// Export all templates to a JSON file
+#[tauri::command]
+pub async fn export_templates(path: String, app_handle: AppHandle) -> Result<(), String> {
+    templates::export_templates(&app_handle, &PathBuf::from(path))
+}
+
+/// Import templates from a JSON file, merging with or replacing the current ones
+#[tauri::command]
+pub async fn import_templates(path: String, merge: bool, app_handle: AppHandle) -> Result<TemplateConfig, String> {
+    templates::import_templates(&app_handle, &PathBuf::from(path), merge)
+}
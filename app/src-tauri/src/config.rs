@@ -8,4 +8,8 @@ pub struct ConnectedClientInfo {
     pub client_id: String,
     pub name: String,
     pub monitor_ids: Vec<String>,
+    /// When the client registered, in milliseconds since the epoch
+    pub connected_at: i64,
+    /// When the client last answered a ping, in milliseconds since the epoch
+    pub last_pong_at: Option<i64>,
 }
@@ -2,9 +2,10 @@ mod commands;
 mod config;
 mod state;
 mod templates;
+mod versioned_file;
 pub mod app_config;
 
-use state::{AppMode, AppState};
+use state::AppState;
 use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -32,6 +33,8 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             // App commands
             commands::get_app_version,
+            commands::get_app_mode,
+            commands::get_diagnostics,
             // Config commands
             commands::check_first_launch,
             commands::mark_donation_prompt_seen,
@@ -40,18 +43,45 @@ pub fn run() {
             commands::start_server,
             commands::stop_server,
             commands::send_kanpe_message,
+            commands::send_urgent_message,
+            commands::send_template_message,
+            commands::get_message_history,
+            commands::clear_message_history,
             commands::get_connected_clients,
+            commands::disconnect_client,
+            commands::request_client_reconnect,
+            commands::is_client_connected,
+            commands::set_air_status,
+            commands::get_connection_log,
+            commands::get_server_metrics,
+            commands::get_server_snapshot,
             commands::get_server_addresses,
             commands::add_virtual_monitor,
             commands::remove_virtual_monitor,
             commands::update_virtual_monitor,
+            commands::set_monitor_enabled,
+            commands::create_monitor_group,
+            commands::delete_monitor_group,
+            commands::get_monitor_groups,
             commands::get_virtual_monitors,
+            commands::reorder_virtual_monitors,
+            commands::replace_virtual_monitors,
+            commands::get_monitor_sync_status,
             commands::send_flash_command,
             commands::send_clear_command,
+            commands::send_presenter_note,
+            commands::save_scene,
+            commands::recall_scene,
+            commands::list_scenes,
+            commands::delete_scene,
+            commands::set_server_ping_enabled,
             // Client commands
             commands::connect_to_server,
             commands::disconnect_from_server,
             commands::send_feedback,
+            commands::send_reaction,
+            commands::acknowledge_message,
+            commands::send_typing,
             commands::create_popout_window,
             commands::close_popout_window,
             commands::get_client_connection_status,
@@ -63,6 +93,10 @@ pub fn run() {
             commands::add_client_template,
             commands::update_client_template,
             commands::delete_client_template,
+            commands::reorder_server_templates,
+            commands::reorder_client_templates,
+            commands::export_templates,
+            commands::import_templates,
             // StreamDeck commands
             commands::start_streamdeck_server,
             commands::stop_streamdeck_server,
@@ -70,38 +104,21 @@ pub fn run() {
         ])
         .on_window_event(|window, event| {
             if let tauri::WindowEvent::CloseRequested { .. } = event {
-                // Get app state and clone Arc references
-                let state = window.app_handle().state::<AppState>();
-                let client_arc = state.client.clone();
-                let server_arc = state.server.clone();
-                let streamdeck_arc = state.streamdeck_server.clone();
-                let mode_arc = state.mode.clone();
+                let app_handle = window.app_handle().clone();
 
                 // Cleanup client or server on window close
                 tauri::async_runtime::spawn(async move {
-                    // Check and cleanup client
-                    let mut client = client_arc.write().await;
-                    if let Some(mut c) = client.take() {
-                        let _ = c.disconnect().await;
-                    }
-                    drop(client);
-
-                    // Check and cleanup server
-                    let mut server = server_arc.write().await;
-                    if let Some(mut s) = server.take() {
-                        let _ = s.stop().await;
-                    }
-                    drop(server);
+                    let state = app_handle.state::<AppState>();
 
                     // Check and cleanup StreamDeck server
-                    let mut streamdeck = streamdeck_arc.write().await;
+                    let mut streamdeck = state.streamdeck_server.write().await;
                     if let Some(s) = streamdeck.take() {
                         let _ = s.shutdown().await;
                     }
                     drop(streamdeck);
 
-                    // Reset mode
-                    *mode_arc.write().await = AppMode::NotSelected;
+                    // Cleanup client or server and reset mode
+                    let _ = state.reset().await;
                 });
             }
         })
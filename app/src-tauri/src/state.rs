@@ -3,9 +3,18 @@
 use kanpe_client::KanpeClient;
 use kanpe_server::KanpeServer;
 use kanpe_streamdeck_server::StreamDeckServer;
-use std::sync::Arc;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex};
 use tokio::sync::RwLock;
 
+/// Number of protocol errors kept for diagnostics
+const MAX_PROTOCOL_ERRORS: usize = 50;
+
+/// Longest raw frame excerpt kept with a protocol error, in chars
+const MAX_RAW_EXCERPT_CHARS: usize = 256;
+
 /// Application mode
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AppMode {
@@ -14,12 +23,79 @@ pub enum AppMode {
     Client,
 }
 
+impl AppMode {
+    /// String form of the mode as exposed to the frontend
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AppMode::NotSelected => "not_selected",
+            AppMode::Server => "server",
+            AppMode::Client => "client",
+        }
+    }
+}
+
+/// A server the app runs that listens on a local port
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PortOwner {
+    Kanpe,
+    StreamDeck,
+}
+
+impl fmt::Display for PortOwner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PortOwner::Kanpe => write!(f, "Kanpe server"),
+            PortOwner::StreamDeck => write!(f, "StreamDeck server"),
+        }
+    }
+}
+
+/// A frame the client could not parse or handle, kept for bug reports
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtocolErrorRecord {
+    pub at: i64,
+    pub reason: String,
+    pub raw: Option<String>,
+}
+
+/// Most recent protocol errors seen by the client, oldest first
+#[derive(Clone, Default)]
+pub struct ProtocolErrorLog {
+    records: Arc<Mutex<VecDeque<ProtocolErrorRecord>>>,
+}
+
+impl ProtocolErrorLog {
+    /// Remember an error, dropping the oldest once the log is full
+    ///
+    /// Only the start of `raw` is kept so a huge bad frame can't bloat the log.
+    pub fn record(&self, reason: String, raw: Option<String>) {
+        let record = ProtocolErrorRecord {
+            at: kanpe_core::types::timestamp(),
+            reason,
+            raw: raw.map(|raw| raw.chars().take(MAX_RAW_EXCERPT_CHARS).collect()),
+        };
+        let mut records = self.records.lock().unwrap();
+        if records.len() >= MAX_PROTOCOL_ERRORS {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+
+    /// Get the recorded errors, oldest first
+    pub fn recent(&self) -> Vec<ProtocolErrorRecord> {
+        self.records.lock().unwrap().iter().cloned().collect()
+    }
+}
+
 /// Global application state
 pub struct AppState {
     pub mode: Arc<RwLock<AppMode>>,
     pub server: Arc<RwLock<Option<KanpeServer>>>,
     pub client: Arc<RwLock<Option<KanpeClient>>>,
     pub streamdeck_server: Arc<RwLock<Option<StreamDeckServer>>>,
+    pub bound_ports: Arc<RwLock<HashMap<PortOwner, u16>>>,
+    pub protocol_errors: ProtocolErrorLog,
 }
 
 impl AppState {
@@ -29,8 +105,114 @@ impl AppState {
             server: Arc::new(RwLock::new(None)),
             client: Arc::new(RwLock::new(None)),
             streamdeck_server: Arc::new(RwLock::new(None)),
+            bound_ports: Arc::new(RwLock::new(HashMap::new())),
+            protocol_errors: ProtocolErrorLog::default(),
+        }
+    }
+
+    /// Reject starting `owner` on a port the app's other server is already bound to
+    ///
+    /// Port 0 asks the OS for a free port, so it never conflicts.
+    pub async fn check_port_available(&self, owner: PortOwner, port: u16) -> Result<(), String> {
+        if port == 0 {
+            return Ok(());
+        }
+        let ports = self.bound_ports.read().await;
+        match ports
+            .iter()
+            .find(|(other, bound)| **other != owner && **bound == port)
+        {
+            Some((other, _)) => Err(format!(
+                "Port {} is already in use by the {}; choose a different port",
+                port, other
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Remember the port a server actually bound to
+    pub async fn record_port(&self, owner: PortOwner, port: u16) {
+        self.bound_ports.write().await.insert(owner, port);
+    }
+
+    /// Forget a stopped server's port
+    pub async fn release_port(&self, owner: PortOwner) {
+        self.bound_ports.write().await.remove(&owner);
+    }
+
+    /// Get the current application mode
+    pub async fn current_mode(&self) -> AppMode {
+        self.mode.read().await.clone()
+    }
+
+    /// Switch to server mode, rejecting the transition while in client mode
+    /// or while a server is already running
+    pub async fn enter_server(&self) -> Result<(), String> {
+        let mut mode = self.mode.write().await;
+        if *mode == AppMode::Client {
+            return Err("Cannot start server while in client mode".to_string());
+        }
+        if self.server.read().await.is_some() {
+            return Err("Server is already running".to_string());
+        }
+        *mode = AppMode::Server;
+        Ok(())
+    }
+
+    /// Switch to client mode, rejecting the transition while in server mode
+    /// or while a client is already connected
+    pub async fn enter_client(&self) -> Result<(), String> {
+        let mut mode = self.mode.write().await;
+        if *mode == AppMode::Server {
+            return Err("Cannot connect to server while in server mode".to_string());
+        }
+        if self.client.read().await.is_some() {
+            return Err("Already connected to a server".to_string());
+        }
+        *mode = AppMode::Client;
+        Ok(())
+    }
+
+    /// Return to mode selection after a server start or client connect failed
+    ///
+    /// Unlike `reset`, nothing is torn down: the mode is only reverted when no
+    /// server or client has been stored, so a running one is left untouched.
+    pub async fn abandon_transition(&self) {
+        let mut mode = self.mode.write().await;
+        if self.server.read().await.is_none() && self.client.read().await.is_none() {
+            *mode = AppMode::NotSelected;
         }
     }
+
+    /// Disconnect the client and stop the server, then return to mode selection
+    ///
+    /// Both are detached while the mode lock is held, so a concurrent
+    /// transition never observes a half-reset state. The StreamDeck server is
+    /// left running; it is only stopped on request or when the window closes.
+    pub async fn reset(&self) -> Result<(), String> {
+        let mut mode = self.mode.write().await;
+
+        let client = self.client.write().await.take();
+        let server = self.server.write().await.take();
+        *mode = AppMode::NotSelected;
+        drop(mode);
+        if server.is_some() {
+            self.release_port(PortOwner::Kanpe).await;
+        }
+
+        if let Some(mut c) = client {
+            c.disconnect()
+                .await
+                .map_err(|e| format!("Failed to disconnect: {}", e))?;
+        }
+        if let Some(mut s) = server {
+            s.stop()
+                .await
+                .map_err(|e| format!("Failed to stop server: {}", e))?;
+        }
+
+        Ok(())
+    }
 }
 
 impl Default for AppState {
@@ -38,3 +220,100 @@ impl Default for AppState {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protocol_error_log_keeps_the_latest() {
+        let log = ProtocolErrorLog::default();
+        for n in 0..MAX_PROTOCOL_ERRORS + 1 {
+            log.record(format!("error {}", n), Some("x".repeat(MAX_RAW_EXCERPT_CHARS * 2)));
+        }
+
+        let recent = log.recent();
+        assert_eq!(recent.len(), MAX_PROTOCOL_ERRORS);
+        assert_eq!(recent[0].reason, "error 1");
+        assert_eq!(recent[0].raw.as_ref().unwrap().len(), MAX_RAW_EXCERPT_CHARS);
+    }
+
+    #[tokio::test]
+    async fn test_enter_server_rejected_in_client_mode() {
+        let state = AppState::new();
+        state.enter_client().await.unwrap();
+
+        assert!(state.enter_server().await.is_err());
+        assert_eq!(state.current_mode().await, AppMode::Client);
+    }
+
+    #[tokio::test]
+    async fn test_reset_returns_to_not_selected() {
+        let state = AppState::new();
+        state.enter_server().await.unwrap();
+
+        state.reset().await.unwrap();
+        assert_eq!(state.current_mode().await, AppMode::NotSelected);
+        assert!(state.enter_client().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_reset_leaves_streamdeck_running() {
+        let state = AppState::new();
+        let (event_tx, _event_rx) = tokio::sync::mpsc::unbounded_channel();
+        let streamdeck = StreamDeckServer::new(0, event_tx).await.unwrap();
+        state.record_port(PortOwner::StreamDeck, streamdeck.port()).await;
+        *state.streamdeck_server.write().await = Some(streamdeck);
+        state.enter_client().await.unwrap();
+
+        state.reset().await.unwrap();
+        assert_eq!(state.current_mode().await, AppMode::NotSelected);
+        assert!(state.streamdeck_server.read().await.is_some());
+        assert!(state.bound_ports.read().await.contains_key(&PortOwner::StreamDeck));
+    }
+
+    #[tokio::test]
+    async fn test_failed_connect_keeps_existing_client() {
+        let state = AppState::new();
+        state.enter_client().await.unwrap();
+        let (event_tx, _event_rx) = tokio::sync::mpsc::unbounded_channel();
+        *state.client.write().await = Some(KanpeClient::new(event_tx));
+
+        // A second connect is refused, and its cleanup leaves the first alone
+        assert!(state.enter_client().await.is_err());
+        state.abandon_transition().await;
+        assert_eq!(state.current_mode().await, AppMode::Client);
+        assert!(state.client.read().await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_transition_returns_to_not_selected() {
+        let state = AppState::new();
+        state.enter_server().await.unwrap();
+
+        state.abandon_transition().await;
+        assert_eq!(state.current_mode().await, AppMode::NotSelected);
+    }
+
+    #[tokio::test]
+    async fn test_streamdeck_on_main_server_port_is_rejected() {
+        let state = AppState::new();
+        state.record_port(PortOwner::Kanpe, 9876).await;
+
+        let err = state
+            .check_port_available(PortOwner::StreamDeck, 9876)
+            .await
+            .unwrap_err();
+        assert!(err.contains("9876") && err.contains("Kanpe server"), "{}", err);
+        assert!(state
+            .check_port_available(PortOwner::StreamDeck, 9877)
+            .await
+            .is_ok());
+
+        state.release_port(PortOwner::Kanpe).await;
+        assert!(state
+            .check_port_available(PortOwner::StreamDeck, 9876)
+            .await
+            .is_ok());
+    }
+}
@@ -1,16 +1,27 @@
 //! Template management for message templates
 
+use crate::versioned_file::load_versioned;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tauri::{AppHandle, Manager};
 
+/// Current schema version of templates.json
+pub const TEMPLATES_VERSION: u32 = 2;
+
 /// Server-side message template
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerTemplate {
     pub id: String,
     pub content: String,
     pub priority: String,
+    /// Position in the list (lower first)
+    #[serde(default)]
+    pub order: u32,
+    /// Optional group the template is shown under
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
 }
 
 /// Client-side feedback template
@@ -19,34 +30,50 @@ pub struct ClientTemplate {
     pub id: String,
     pub content: String,
     pub feedback_type: String,
+    /// Position in the list (lower first)
+    #[serde(default)]
+    pub order: u32,
+    /// Optional group the template is shown under
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
 }
 
 /// Template configuration containing both server and client templates
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TemplateConfig {
+    /// Schema version (files written before versioning load as 0)
+    #[serde(default, alias = "version")]
+    pub schema_version: u32,
     pub server_templates: Vec<ServerTemplate>,
     pub client_templates: Vec<ClientTemplate>,
 }
 
-impl TemplateConfig {
+impl Default for TemplateConfig {
     /// Create default template configuration
-    pub fn default() -> Self {
+    fn default() -> Self {
         Self {
+            schema_version: TEMPLATES_VERSION,
             server_templates: vec![
                 ServerTemplate {
                     id: uuid::Uuid::new_v4().to_string(),
                     content: "巻いてください".to_string(),
                     priority: "high".to_string(),
+                    order: 0,
+                    category: None,
                 },
                 ServerTemplate {
                     id: uuid::Uuid::new_v4().to_string(),
                     content: "押してます".to_string(),
                     priority: "normal".to_string(),
+                    order: 1,
+                    category: None,
                 },
                 ServerTemplate {
                     id: uuid::Uuid::new_v4().to_string(),
                     content: "お水下さい".to_string(),
                     priority: "normal".to_string(),
+                    order: 2,
+                    category: None,
                 },
             ],
             client_templates: vec![
@@ -54,27 +81,124 @@ impl TemplateConfig {
                     id: uuid::Uuid::new_v4().to_string(),
                     content: "了解しました".to_string(),
                     feedback_type: "ack".to_string(),
+                    order: 0,
+                    category: None,
                 },
                 ClientTemplate {
                     id: uuid::Uuid::new_v4().to_string(),
                     content: "質問があります".to_string(),
                     feedback_type: "question".to_string(),
+                    order: 1,
+                    category: None,
                 },
                 ClientTemplate {
                     id: uuid::Uuid::new_v4().to_string(),
                     content: "問題が発生しています".to_string(),
                     feedback_type: "issue".to_string(),
+                    order: 2,
+                    category: None,
                 },
                 ClientTemplate {
                     id: uuid::Uuid::new_v4().to_string(),
                     content: "情報を共有します".to_string(),
                     feedback_type: "info".to_string(),
+                    order: 3,
+                    category: None,
                 },
             ],
         }
     }
 }
 
+impl TemplateConfig {
+    /// Sort both template lists by their `order` values
+    pub fn sort_by_order(&mut self) {
+        self.server_templates.sort_by_key(|template| template.order);
+        self.client_templates.sort_by_key(|template| template.order);
+    }
+
+    /// Order value for a server template added after the existing ones
+    pub fn next_server_order(&self) -> u32 {
+        self.server_templates.iter().map(|t| t.order + 1).max().unwrap_or(0)
+    }
+
+    /// Order value for a client template added after the existing ones
+    pub fn next_client_order(&self) -> u32 {
+        self.client_templates.iter().map(|t| t.order + 1).max().unwrap_or(0)
+    }
+
+    /// Put the listed server templates first, in the given order
+    pub fn reorder_server_templates(&mut self, ids: &[String]) -> Result<(), String> {
+        reorder(&mut self.server_templates, ids, |t| &t.id, |t, order| t.order = order)
+    }
+
+    /// Put the listed client templates first, in the given order
+    pub fn reorder_client_templates(&mut self, ids: &[String]) -> Result<(), String> {
+        reorder(&mut self.client_templates, ids, |t| &t.id, |t, order| t.order = order)
+    }
+}
+
+/// Rewrite order values so `ids` come first in that order, followed by any
+/// templates not listed in their existing order
+fn reorder<T>(
+    templates: &mut [T],
+    ids: &[String],
+    id_of: impl Fn(&T) -> &str,
+    set_order: impl Fn(&mut T, u32),
+) -> Result<(), String> {
+    if let Some(unknown) = ids.iter().find(|id| !templates.iter().any(|t| id_of(t) == id.as_str())) {
+        return Err(format!("Template not found: {}", unknown));
+    }
+
+    let mut unlisted = ids.len() as u32;
+    for template in templates.iter_mut() {
+        let order = match ids.iter().position(|id| id.as_str() == id_of(template)) {
+            Some(position) => position as u32,
+            None => {
+                unlisted += 1;
+                unlisted - 1
+            }
+        };
+        set_order(template, order);
+    }
+    Ok(())
+}
+
+/// Fill `{name}` placeholders in a template from `vars`
+///
+/// Placeholders with no matching variable are left as written, and `{{` / `}}`
+/// produce literal braces.
+pub fn render_template(content: &str, vars: &HashMap<String, String>) -> String {
+    let mut rendered = String::with_capacity(content.len());
+    let mut rest = content;
+
+    while let Some(start) = rest.find(['{', '}']) {
+        rendered.push_str(&rest[..start]);
+        let tail = &rest[start..];
+
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            rendered.push_str(&tail[..1]);
+            rest = &tail[2..];
+        } else if let Some(end) = tail.strip_prefix('{').and_then(|t| t.find(['{', '}']))
+            && tail[1 + end..].starts_with('}')
+        {
+            let name = &tail[1..1 + end];
+            match vars.get(name) {
+                Some(value) => rendered.push_str(value),
+                None => rendered.push_str(&tail[..end + 2]),
+            }
+            rest = &tail[end + 2..];
+        } else {
+            // A lone brace that opens or closes nothing
+            rendered.push_str(&tail[..1]);
+            rest = &tail[1..];
+        }
+    }
+
+    rendered.push_str(rest);
+    rendered
+}
+
 /// Get the path to the templates configuration file
 fn get_templates_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
     let app_data_dir = app_handle
@@ -89,16 +213,49 @@ fn get_templates_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
     Ok(app_data_dir.join("templates.json"))
 }
 
+/// Upgrade a template configuration from an older schema version to the current one
+pub fn migrate(mut config: TemplateConfig) -> TemplateConfig {
+    if config.schema_version < 1 {
+        // v0 -> v1: files predating the version field; no shape changes
+        config.schema_version = 1;
+    }
+    if config.schema_version < 2 {
+        // v1 -> v2: templates gained an explicit order; keep the order they were stored in
+        for (order, template) in config.server_templates.iter_mut().enumerate() {
+            template.order = order as u32;
+        }
+        for (order, template) in config.client_templates.iter_mut().enumerate() {
+            template.order = order as u32;
+        }
+        config.schema_version = 2;
+    }
+
+    config
+}
+
 /// Load templates from file, or return defaults if file doesn't exist
 pub fn load_templates(app_handle: &AppHandle) -> Result<TemplateConfig, String> {
     let path = get_templates_path(app_handle)?;
+    load_templates_from(&path)
+}
 
+/// Load templates from a specific path, migrating and rewriting older files
+///
+/// Files from a newer app version are rejected (and backed up) rather than overwritten.
+/// Templates come back sorted by their order values.
+fn load_templates_from(path: &Path) -> Result<TemplateConfig, String> {
     if path.exists() {
-        let content = fs::read_to_string(&path)
-            .map_err(|e| format!("Failed to read templates file: {}", e))?;
+        let mut config: TemplateConfig = load_versioned(path, TEMPLATES_VERSION)
+            .map_err(|e| format!("Failed to load templates file: {}", e))?;
+
+        // Upgrade older files in place so they are only migrated once
+        if config.schema_version < TEMPLATES_VERSION {
+            config = migrate(config);
+            save_templates_to(path, &config)?;
+        }
 
-        serde_json::from_str(&content)
-            .map_err(|e| format!("Failed to parse templates file: {}", e))
+        config.sort_by_order();
+        Ok(config)
     } else {
         // Return default templates if file doesn't exist
         Ok(TemplateConfig::default())
@@ -108,11 +265,286 @@ pub fn load_templates(app_handle: &AppHandle) -> Result<TemplateConfig, String>
 /// Save templates to file
 pub fn save_templates(app_handle: &AppHandle, config: &TemplateConfig) -> Result<(), String> {
     let path = get_templates_path(app_handle)?;
+    save_templates_to(&path, config)
+}
 
+/// Save templates to a specific path
+fn save_templates_to(path: &Path, config: &TemplateConfig) -> Result<(), String> {
     let content = serde_json::to_string_pretty(config)
         .map_err(|e| format!("Failed to serialize templates: {}", e))?;
 
-    fs::write(&path, content).map_err(|e| format!("Failed to write templates file: {}", e))?;
+    fs::write(path, content).map_err(|e| format!("Failed to write templates file: {}", e))?;
 
     Ok(())
 }
+
+/// Write the saved templates to a file the user chose
+pub fn export_templates(app_handle: &AppHandle, path: &Path) -> Result<(), String> {
+    let templates_path = get_templates_path(app_handle)?;
+    export_templates_to(&templates_path, path)
+}
+
+/// Copy the templates stored at `templates_path` to `dest`
+fn export_templates_to(templates_path: &Path, dest: &Path) -> Result<(), String> {
+    let config = load_templates_from(templates_path)?;
+    save_templates_to(dest, &config)
+}
+
+/// Import templates from a file the user chose, returning the resulting set
+///
+/// With `merge`, the incoming templates are added alongside the existing ones
+/// under fresh IDs; otherwise they replace them.
+pub fn import_templates(app_handle: &AppHandle, path: &Path, merge: bool) -> Result<TemplateConfig, String> {
+    let templates_path = get_templates_path(app_handle)?;
+    import_templates_into(&templates_path, path, merge)
+}
+
+/// Import templates from `source` into the file at `templates_path`
+fn import_templates_into(templates_path: &Path, source: &Path, merge: bool) -> Result<TemplateConfig, String> {
+    let incoming = read_import_file(source)?;
+
+    let mut config = if merge {
+        // Incoming templates go after the existing ones, keeping their relative order
+        let mut config = load_templates_from(templates_path)?;
+        let (server_base, client_base) = (config.next_server_order(), config.next_client_order());
+        config.server_templates.extend(incoming.server_templates.into_iter().map(|template| ServerTemplate {
+            id: uuid::Uuid::new_v4().to_string(),
+            order: server_base + template.order,
+            ..template
+        }));
+        config.client_templates.extend(incoming.client_templates.into_iter().map(|template| ClientTemplate {
+            id: uuid::Uuid::new_v4().to_string(),
+            order: client_base + template.order,
+            ..template
+        }));
+        config
+    } else {
+        incoming
+    };
+    config.sort_by_order();
+
+    save_templates_to(templates_path, &config)?;
+    Ok(config)
+}
+
+/// Read and validate a templates file for import, leaving the file untouched
+fn read_import_file(path: &Path) -> Result<TemplateConfig, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let config: TemplateConfig = serde_json::from_str(&content)
+        .map_err(|e| format!("{} is not a valid templates file: {}", path.display(), e))?;
+    if config.schema_version > TEMPLATES_VERSION {
+        return Err(format!(
+            "{} was written by a newer version of the app (templates version {}, this app supports {})",
+            path.display(),
+            config.schema_version,
+            TEMPLATES_VERSION
+        ));
+    }
+
+    Ok(migrate(config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path() -> PathBuf {
+        std::env::temp_dir().join(format!("kanpe-templates-{}.json", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn test_v0_file_migrates_to_current_version() {
+        let path = temp_path();
+        fs::write(
+            &path,
+            r#"{
+                "server_templates": [{ "id": "s1", "content": "巻いてください", "priority": "high" }],
+                "client_templates": [{ "id": "c1", "content": "了解しました", "feedback_type": "ack" }]
+            }"#,
+        )
+        .unwrap();
+
+        let config = load_templates_from(&path).unwrap();
+        assert_eq!(config.schema_version, TEMPLATES_VERSION);
+        assert_eq!(config.server_templates.len(), 1);
+        assert_eq!(config.server_templates[0].content, "巻いてください");
+        assert_eq!(config.client_templates[0].feedback_type, "ack");
+
+        // The migrated file is rewritten with the current version
+        let rewritten: TemplateConfig =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(rewritten.schema_version, TEMPLATES_VERSION);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    fn sample_config(content: &str) -> TemplateConfig {
+        TemplateConfig {
+            schema_version: TEMPLATES_VERSION,
+            server_templates: vec![ServerTemplate {
+                id: "s1".to_string(),
+                content: content.to_string(),
+                priority: "high".to_string(),
+                order: 0,
+                category: None,
+            }],
+            client_templates: vec![ClientTemplate {
+                id: "c1".to_string(),
+                content: "了解しました".to_string(),
+                feedback_type: "ack".to_string(),
+                order: 0,
+                category: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_import_replace_round_trips_exported_file() {
+        let (source, export, target) = (temp_path(), temp_path(), temp_path());
+        save_templates_to(&source, &sample_config("巻いてください")).unwrap();
+        export_templates_to(&source, &export).unwrap();
+
+        save_templates_to(&target, &sample_config("押してます")).unwrap();
+        let config = import_templates_into(&target, &export, false).unwrap();
+        assert_eq!(config.server_templates.len(), 1);
+        assert_eq!(config.server_templates[0].content, "巻いてください");
+        assert_eq!(config.server_templates[0].id, "s1");
+
+        let saved = load_templates_from(&target).unwrap();
+        assert_eq!(saved.server_templates[0].content, "巻いてください");
+
+        for path in [source, export, target] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_import_merge_keeps_existing_and_gives_incoming_fresh_ids() {
+        let (export, target) = (temp_path(), temp_path());
+        save_templates_to(&export, &sample_config("巻いてください")).unwrap();
+        save_templates_to(&target, &sample_config("押してます")).unwrap();
+
+        let config = import_templates_into(&target, &export, true).unwrap();
+        let contents: Vec<&str> = config.server_templates.iter().map(|t| t.content.as_str()).collect();
+        assert_eq!(contents, vec!["押してます", "巻いてください"]);
+        assert_eq!(config.client_templates.len(), 2);
+        assert_eq!(config.server_templates[0].id, "s1");
+        assert_ne!(config.server_templates[1].id, "s1");
+        assert_ne!(config.client_templates[1].id, "c1");
+
+        let saved = load_templates_from(&target).unwrap();
+        assert_eq!(saved.server_templates.len(), 2);
+
+        for path in [export, target] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_import_rejects_malformed_file() {
+        let (source, target) = (temp_path(), temp_path());
+        fs::write(&source, r#"{ "server_templates": [{ "id": "s1" }] }"#).unwrap();
+
+        let err = import_templates_into(&target, &source, false).unwrap_err();
+        assert!(err.contains("is not a valid templates file"), "{}", err);
+        assert!(!target.exists());
+
+        fs::remove_file(source).unwrap();
+    }
+
+    fn server_template(id: &str, order: u32) -> ServerTemplate {
+        ServerTemplate {
+            id: id.to_string(),
+            content: id.to_string(),
+            priority: "normal".to_string(),
+            order,
+            category: None,
+        }
+    }
+
+    fn server_ids(config: &TemplateConfig) -> Vec<&str> {
+        config.server_templates.iter().map(|t| t.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_load_returns_templates_sorted_by_order() {
+        let path = temp_path();
+        let mut config = sample_config("unused");
+        config.server_templates = vec![server_template("c", 2), server_template("a", 0), server_template("b", 1)];
+        save_templates_to(&path, &config).unwrap();
+
+        let loaded = load_templates_from(&path).unwrap();
+        assert_eq!(server_ids(&loaded), vec!["a", "b", "c"]);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_v1_file_gets_sequential_order() {
+        let path = temp_path();
+        fs::write(
+            &path,
+            r#"{
+                "schema_version": 1,
+                "server_templates": [
+                    { "id": "s1", "content": "巻いてください", "priority": "high" },
+                    { "id": "s2", "content": "押してます", "priority": "normal" }
+                ],
+                "client_templates": []
+            }"#,
+        )
+        .unwrap();
+
+        let config = load_templates_from(&path).unwrap();
+        let orders: Vec<u32> = config.server_templates.iter().map(|t| t.order).collect();
+        assert_eq!(orders, vec![0, 1]);
+        assert_eq!(server_ids(&config), vec!["s1", "s2"]);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_reorder_puts_listed_templates_first() {
+        let mut config = sample_config("unused");
+        config.server_templates = vec![server_template("a", 0), server_template("b", 1), server_template("c", 2)];
+
+        config.reorder_server_templates(&["c".to_string(), "a".to_string()]).unwrap();
+        config.sort_by_order();
+        assert_eq!(server_ids(&config), vec!["c", "a", "b"]);
+
+        let err = config.reorder_server_templates(&["missing".to_string()]).unwrap_err();
+        assert!(err.contains("missing"));
+        assert_eq!(server_ids(&config), vec!["c", "a", "b"]);
+    }
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_render_template_substitutes_variables() {
+        let rendered = render_template("巻いてください — あと {minutes}分 (cue {cue})", &vars(&[("minutes", "3"), ("cue", "12")]));
+        assert_eq!(rendered, "巻いてください — あと 3分 (cue 12)");
+    }
+
+    #[test]
+    fn test_render_template_leaves_missing_variables() {
+        let rendered = render_template("あと {minutes}分 {unknown}", &vars(&[("minutes", "5")]));
+        assert_eq!(rendered, "あと 5分 {unknown}");
+        assert_eq!(render_template("open { brace", &vars(&[])), "open { brace");
+    }
+
+    #[test]
+    fn test_render_template_unescapes_double_braces() {
+        let rendered = render_template("{{minutes}} = {minutes}, }} {{", &vars(&[("minutes", "2")]));
+        assert_eq!(rendered, "{minutes} = 2, } {");
+    }
+
+    #[test]
+    fn test_missing_file_returns_current_defaults() {
+        let config = load_templates_from(&temp_path()).unwrap();
+        assert_eq!(config.schema_version, TEMPLATES_VERSION);
+        assert!(!config.server_templates.is_empty());
+    }
+}
@@ -0,0 +1,35 @@
+//! Reading of schema-versioned JSON files, backing up ones from a newer version
+
+use kanpe_core::versioned::{versioned_parse, VersionParseError};
+use serde::de::DeserializeOwned;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Read a JSON file written with schema version `supported` or older
+///
+/// A file from a newer app version is copied to `<file>.v<found>.bak` and rejected,
+/// so it is never misread or overwritten without a copy.
+pub fn load_versioned<T: DeserializeOwned>(path: &Path, supported: u32) -> Result<T, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    match versioned_parse(&content, supported) {
+        Ok(value) => Ok(value),
+        Err(VersionParseError::Parse(e)) => Err(format!("Failed to parse {}: {}", path.display(), e)),
+        Err(VersionParseError::TooNew { found, supported }) => {
+            let mut backup = OsString::from(path.as_os_str());
+            backup.push(format!(".v{}.bak", found));
+            let backup = PathBuf::from(backup);
+            fs::copy(path, &backup)
+                .map_err(|e| format!("Failed to back up {}: {}", path.display(), e))?;
+            Err(format!(
+                "{} uses schema version {}, but this version of Bi-Kanpe only supports up to {}; \
+                 the file was left untouched and backed up to {}",
+                path.display(),
+                found,
+                supported,
+                backup.display()
+            ))
+        }
+    }
+}
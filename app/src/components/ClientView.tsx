@@ -17,6 +17,7 @@ export function ClientView({ onBackToMenu }: ClientViewProps) {
   const [showDisconnectWarning, setShowDisconnectWarning] = useState<boolean>(true);
   const [streamDeckPort, setStreamDeckPort] = useState<number>(9877);
   const [streamDeckStatus, setStreamDeckStatus] = useState<boolean>(false);
+  const [acknowledgedIds, setAcknowledgedIds] = useState<Set<string>>(new Set());
   const [confirmDialog, setConfirmDialog] = useState<{
     isOpen: boolean;
     message: string;
@@ -56,7 +57,8 @@ export function ClientView({ onBackToMenu }: ClientViewProps) {
         .filter((msg): msg is Message & { type: "kanpe_message" } => {
           if (msg.type !== "kanpe_message") return false;
           const targetIds = msg.payload.target_monitor_ids;
-          return targetIds.includes("ALL") || targetIds.includes(monitor.id);
+          const listed = targetIds.includes("ALL") || targetIds.includes(monitor.id);
+          return msg.payload.invert ? !listed : listed;
         })
         .slice(-1)[0];
       if (last) map.set(monitor.id, last);
@@ -182,6 +184,16 @@ export function ClientView({ onBackToMenu }: ClientViewProps) {
     };
   }, [clientState.isConnected]);
 
+  // Stop an urgent cue's repeated flash once the caster has seen it
+  const handleAcknowledge = async (messageId: string) => {
+    try {
+      await invoke("acknowledge_message", { messageId });
+      setAcknowledgedIds((prev) => new Set(prev).add(messageId));
+    } catch (err) {
+      setError(String(err));
+    }
+  };
+
   const handlePopoutMonitor = async (monitorId: string, monitorName: string) => {
     try {
       await invoke("create_popout_window", {
@@ -415,6 +427,26 @@ export function ClientView({ onBackToMenu }: ClientViewProps) {
                           >
                             {msg?.type === "kanpe_message" ? msg.payload.content : "—"}
                           </div>
+                          {msg?.type === "kanpe_message" &&
+                            msg.payload.repeat_flash_interval_ms != null &&
+                            !acknowledgedIds.has(msg.id) && (
+                              <button
+                                onClick={() => handleAcknowledge(msg.id)}
+                                style={{
+                                  padding: "0.4rem 0.75rem",
+                                  fontSize: "0.85rem",
+                                  backgroundColor: "#22c55e",
+                                  color: "white",
+                                  border: "none",
+                                  borderRadius: "4px",
+                                  cursor: "pointer",
+                                  fontWeight: "600",
+                                  alignSelf: "flex-start",
+                                }}
+                              >
+                                ✓ 確認しました
+                              </button>
+                            )}
                           <button
                             onClick={() => handlePopoutMonitor(monitor.id, monitor.name)}
                             style={{
@@ -520,6 +552,21 @@ export function ClientView({ onBackToMenu }: ClientViewProps) {
               </div>
             )}
 
+            {clientState.isConnected && clientState.isStale && (
+              <div
+                style={{
+                  padding: "0.75rem",
+                  backgroundColor: "rgba(245, 158, 11, 0.1)",
+                  color: "#f59e0b",
+                  border: "2px solid #f59e0b",
+                  borderRadius: "6px",
+                  fontWeight: "600",
+                }}
+              >
+                ⏳ ディレクターからの応答がありません
+              </div>
+            )}
+
             {clientState.disconnectReason && !clientState.isConnected && showDisconnectWarning && (
               <div
                 style={{
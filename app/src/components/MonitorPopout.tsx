@@ -31,6 +31,7 @@ export default function MonitorPopout({
   const [feedbackSent, setFeedbackSent] = useState<boolean>(false);
   const [showFeedbackPanel, setShowFeedbackPanel] = useState<boolean>(false);
   const [activeTab, setActiveTab] = useState<"reply" | "new" | "template">("new");
+  const [acknowledgedIds, setAcknowledgedIds] = useState<Set<string>>(new Set());
   // Load font size from localStorage
   useEffect(() => {
     const saved = localStorage.getItem("clientFontSize");
@@ -59,13 +60,29 @@ export default function MonitorPopout({
       .filter((msg) => {
         if (msg.type === "kanpe_message") {
           const targetIds = msg.payload.target_monitor_ids;
-          return targetIds.includes("ALL") || targetIds.includes(monitorId);
+          const listed = targetIds.includes("ALL") || targetIds.includes(monitorId);
+          return msg.payload.invert ? !listed : listed;
         }
         return false;
       })
       .slice(-1)[0];
   }, [clientState.messages, monitorId]);
 
+  // Urgent cues keep re-flashing until a caster acknowledges them
+  const needsAck =
+    currentMessage?.type === "kanpe_message" &&
+    currentMessage.payload.repeat_flash_interval_ms != null &&
+    !acknowledgedIds.has(currentMessage.id);
+
+  const handleAcknowledge = async (messageId: string) => {
+    try {
+      await invoke("acknowledge_message", { messageId });
+      setAcknowledgedIds((prev) => new Set(prev).add(messageId));
+    } catch (err) {
+      console.error("Failed to acknowledge message:", err);
+    }
+  };
+
   // Auto-switch tabs based on message availability
   useEffect(() => {
     setActiveTab((prevTab) => {
@@ -100,14 +117,18 @@ export default function MonitorPopout({
         hasCurrentMessage: !!currentMessage,
       });
 
-      await invoke("send_feedback", {
+      const feedbackId = await invoke<string>("send_feedback", {
         content: template.content,
         clientName,
         replyToMessageId: replyTo,
         feedbackType: template.feedback_type,
       });
 
-      console.log("Feedback sent successfully");
+      console.log("Feedback sent successfully:", feedbackId);
+      // Replying to an urgent cue also acknowledges it
+      if (needsAck && currentMessage && replyTo === currentMessage.id) {
+        await handleAcknowledge(currentMessage.id);
+      }
       setFeedbackSent(true);
       setTimeout(() => setFeedbackSent(false), 1500);
     } catch (err) {
@@ -211,6 +232,25 @@ export default function MonitorPopout({
                 ? "⚠ 重要"
                 : "📝 通常"}
             </div>
+            {needsAck && (
+              <div style={{ marginTop: "1.5rem" }}>
+                <button
+                  onClick={() => handleAcknowledge(currentMessage.id)}
+                  style={{
+                    padding: "0.75rem 2rem",
+                    fontSize: "1.2rem",
+                    fontWeight: "600",
+                    backgroundColor: "#22c55e",
+                    color: "white",
+                    border: "none",
+                    borderRadius: "6px",
+                    cursor: "pointer",
+                  }}
+                >
+                  ✓ 確認しました
+                </button>
+              </div>
+            )}
           </div>
         ) : (
           <div style={{ textAlign: "center", color: "var(--muted-text)", padding: "2rem" }}>
@@ -7,15 +7,24 @@ import { TemplateManager } from "./TemplateManager";
 import { ThemeToggle } from "./ThemeToggle";
 import { ConfirmDialog } from "./ConfirmDialog";
 import { QRCodeSVG } from "qrcode.react";
-import type { Message, Priority, ServerTemplate } from "../types/messages";
+import type { DisconnectReason, Message, Priority, ServerTemplate } from "../types/messages";
 
 // Hoist static priority options to avoid recreation on every render
 const PRIORITY_OPTIONS = [
+  { value: "low", label: "参考", emoji: "💬", color: "#888", bg: "#f7f7f7", desc: "急ぎではない連絡" },
   { value: "normal", label: "通常", emoji: "📝", color: "#333", bg: "#f0f0f0", desc: "通常のメッセージ" },
   { value: "high", label: "重要", emoji: "⚠", color: "#ff8800", bg: "#ffeecc", desc: "注意が必要" },
   { value: "urgent", label: "緊急", emoji: "🚨", color: "#ff0000", bg: "#ffcccc", desc: "即座の対応が必要" },
 ] as const;
 
+const DISCONNECT_REASON_LABELS: Record<DisconnectReason, string> = {
+  client_closed: "キャスターが終了",
+  network_error: "通信エラー",
+  kicked: "サーバーから切断",
+  timeout: "応答なし",
+  server_shutdown: "サーバー停止",
+};
+
 interface ServerViewProps {
   onBackToMenu: () => void;
 }
@@ -80,7 +89,8 @@ export function ServerView({ onBackToMenu }: ServerViewProps) {
         .filter((msg): msg is Message & { type: "kanpe_message" } => {
           if (msg.type !== "kanpe_message") return false;
           const targetIds = msg.payload.target_monitor_ids;
-          return targetIds.includes("ALL") || targetIds.includes(monitor.id);
+          const listed = targetIds.includes("ALL") || targetIds.includes(monitor.id);
+          return msg.payload.invert ? !listed : listed;
         })
         .slice(-1)[0];
       if (last) map.set(monitor.id, last);
@@ -173,11 +183,19 @@ export function ServerView({ onBackToMenu }: ServerViewProps) {
     try {
       setError(null);
       setIsSendingMessage(true);
-      await invoke("send_kanpe_message", {
-        targetMonitorIds,
-        content: messageContent,
-        priority,
-      });
+      if (priority === "urgent") {
+        // Urgent cues keep flashing until a caster on a targeted monitor acknowledges them
+        await invoke("send_urgent_message", {
+          targetMonitorIds,
+          content: messageContent,
+        });
+      } else {
+        await invoke("send_kanpe_message", {
+          targetMonitorIds,
+          content: messageContent,
+          priority,
+        });
+      }
       setMessageContent("");
       setMessageSent(true);
       setTimeout(() => setMessageSent(false), 1500);
@@ -1319,6 +1337,11 @@ export function ServerView({ onBackToMenu }: ServerViewProps) {
                 ))}
               </ul>
             )}
+            {serverState.lastDisconnect && (
+              <p style={{ fontSize: "0.85rem", color: "var(--muted-text)", marginBottom: 0 }}>
+                最後の切断: {serverState.lastDisconnect.name} ({DISCONNECT_REASON_LABELS[serverState.lastDisconnect.reason]})
+              </p>
+            )}
           </div>
 
           {/* Sent Messages and Feedback */}
@@ -1393,6 +1416,9 @@ export function ServerView({ onBackToMenu }: ServerViewProps) {
                             </div>
                             <div style={{ fontSize: "0.85rem", color: "var(--muted-text)" }}>
                               送信先: モニター {msg.payload.target_monitor_ids.includes("ALL") ? "全て" : msg.payload.target_monitor_ids.join(", ")}
+                              {msg.payload.invert && " 以外"}
+                              {serverState.recipientCounts[msg.id] !== undefined &&
+                                ` (${serverState.recipientCounts[msg.id]}台に配信)`}
                             </div>
                           </div>
 
@@ -1423,7 +1449,7 @@ export function ServerView({ onBackToMenu }: ServerViewProps) {
                                       >
                                         <div style={{ display: "flex", justifyContent: "space-between", marginBottom: "0.25rem" }}>
                                           <strong style={{ color: "var(--accent-color)", fontSize: "0.9rem" }}>
-                                            {feedbackTypeEmoji[fb.payload.feedback_type] || "•"} {fb.payload.client_name}
+                                            {(typeof fb.payload.feedback_type === "string" && feedbackTypeEmoji[fb.payload.feedback_type]) || "•"} {fb.payload.client_name}
                                           </strong>
                                           <span style={{ fontSize: "0.75rem", color: "var(--muted-text)" }}>
                                             {formatTimestamp(fb.timestamp)}
@@ -1433,7 +1459,7 @@ export function ServerView({ onBackToMenu }: ServerViewProps) {
                                           {fb.payload.content}
                                         </div>
                                         <div style={{ fontSize: "0.75rem", color: "var(--muted-text)", marginTop: "0.125rem" }}>
-                                          [{fb.payload.feedback_type}]
+                                          [{typeof fb.payload.feedback_type === "string" ? fb.payload.feedback_type : fb.payload.feedback_type.custom}]
                                         </div>
                                       </div>
                                     );
@@ -1497,7 +1523,7 @@ export function ServerView({ onBackToMenu }: ServerViewProps) {
                         >
                           <div style={{ display: "flex", justifyContent: "space-between", marginBottom: "0.5rem" }}>
                             <strong style={{ color: "var(--accent-color)", fontSize: "1rem" }}>
-                              {feedbackTypeEmoji[fb.payload.feedback_type] || "•"} {fb.payload.client_name}
+                              {(typeof fb.payload.feedback_type === "string" && feedbackTypeEmoji[fb.payload.feedback_type]) || "•"} {fb.payload.client_name}
                             </strong>
                             <span style={{ fontSize: "0.85rem", color: "var(--muted-text)" }}>
                               {formatTimestamp(fb.timestamp)}
@@ -1507,7 +1533,7 @@ export function ServerView({ onBackToMenu }: ServerViewProps) {
                             {fb.payload.content}
                           </div>
                           <div style={{ fontSize: "0.75rem", color: "var(--muted-text)" }}>
-                            [{fb.payload.feedback_type}]
+                            [{typeof fb.payload.feedback_type === "string" ? fb.payload.feedback_type : fb.payload.feedback_type.custom}]
                           </div>
                         </div>
                       );
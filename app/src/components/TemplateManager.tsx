@@ -175,6 +175,7 @@ export function TemplateManager({
                   fontSize: "0.95rem",
                 }}
               >
+                <option value="low">参考</option>
                 <option value="normal">通常</option>
                 <option value="high">重要</option>
                 <option value="urgent">緊急</option>
@@ -263,6 +264,7 @@ export function TemplateManager({
                         fontSize: "0.95rem",
                       }}
                     >
+                      <option value="low">参考</option>
                       <option value="normal">通常</option>
                       <option value="high">重要</option>
                       <option value="urgent">緊急</option>
@@ -1,6 +1,7 @@
 import { invoke } from "@tauri-apps/api/core";
 
 export interface AppConfig {
+  schema_version: number;
   has_seen_donation_prompt: boolean;
   first_launch_timestamp: number | null;
 }
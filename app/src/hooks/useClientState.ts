@@ -1,7 +1,7 @@
 import { useEffect, useState, useMemo } from "react";
 import { listen } from "@tauri-apps/api/event";
 import { invoke } from "@tauri-apps/api/core";
-import type { Message, VirtualMonitor } from "../types/messages";
+import type { ConnectionState, Message, VirtualMonitor } from "../types/messages";
 
 export interface ClientState {
   isConnected: boolean;
@@ -12,6 +12,8 @@ export interface ClientState {
   flashTrigger: number;
   clearTrigger: number;
   disconnectReason: string | null;
+  /** The server has sent nothing for a while, though the connection is still open */
+  isStale: boolean;
 }
 
 export function useClientState(displayMonitorIds: string[] = []) {
@@ -28,14 +30,15 @@ export function useClientState(displayMonitorIds: string[] = []) {
     flashTrigger: 0,
     clearTrigger: 0,
     disconnectReason: null,
+    isStale: false,
   });
 
   // Check initial connection status (important for popout windows)
   useEffect(() => {
     const checkInitialConnection = async () => {
       try {
-        const isConnected = await invoke<boolean>("get_client_connection_status");
-        if (isConnected) {
+        const status = await invoke<ConnectionState>("get_client_connection_status");
+        if (status.state === "connected") {
           setState((prev) => ({
             ...prev,
             isConnected: true,
@@ -59,6 +62,7 @@ export function useClientState(displayMonitorIds: string[] = []) {
           isConnected: true,
           serverAddress: event.payload.server_address,
           disconnectReason: null, // Clear any previous disconnect reason
+          isStale: false,
         }));
       }
     );
@@ -74,6 +78,22 @@ export function useClientState(displayMonitorIds: string[] = []) {
           serverAddress: null,
           serverName: null,
           disconnectReason: event.payload.reason,
+          isStale: false,
+        }));
+      }
+    );
+
+    // Listen for server_shutting_down event (the director ended the session)
+    const unlistenShutdown = listen<{ reason: string }>(
+      "server_shutting_down",
+      (event) => {
+        console.log("Server shutting down:", event.payload.reason);
+        setState((prev) => ({
+          ...prev,
+          isConnected: false,
+          serverAddress: null,
+          serverName: null,
+          disconnectReason: "ディレクターがセッションを終了しました",
         }));
       }
     );
@@ -99,8 +119,11 @@ export function useClientState(displayMonitorIds: string[] = []) {
           const targetIds = message.payload.target_monitor_ids;
           const shouldDisplay =
             stableMonitorIds.length === 0 || // Main window: accept all messages
-            targetIds.includes("ALL") || // "ALL" means all monitors
-            stableMonitorIds.some((id) => targetIds.includes(id));
+            (message.payload.invert
+              ? // Inverted: the targets are the monitors to skip
+                !targetIds.includes("ALL") && stableMonitorIds.some((id) => !targetIds.includes(id))
+              : targetIds.includes("ALL") || // "ALL" means all monitors
+                stableMonitorIds.some((id) => targetIds.includes(id)));
 
           if (shouldDisplay) {
             setState((prev) => ({
@@ -125,39 +148,13 @@ export function useClientState(displayMonitorIds: string[] = []) {
       }
     );
 
-    // Listen for monitor_added event
-    const unlistenMonitorAdded = listen<VirtualMonitor>(
-      "monitor_added",
+    // Listen for monitors_changed event (the full list after any add/remove/update)
+    const unlistenMonitorsChanged = listen<VirtualMonitor[]>(
+      "monitors_changed",
       (event) => {
         setState((prev) => ({
           ...prev,
-          availableMonitors: [...prev.availableMonitors, event.payload],
-        }));
-      }
-    );
-
-    // Listen for monitor_removed event
-    const unlistenMonitorRemoved = listen<{ monitor_id: string }>(
-      "monitor_removed",
-      (event) => {
-        setState((prev) => ({
-          ...prev,
-          availableMonitors: prev.availableMonitors.filter(
-            (m) => m.id !== event.payload.monitor_id
-          ),
-        }));
-      }
-    );
-
-    // Listen for monitor_updated event
-    const unlistenMonitorUpdated = listen<VirtualMonitor>(
-      "monitor_updated",
-      (event) => {
-        setState((prev) => ({
-          ...prev,
-          availableMonitors: prev.availableMonitors.map((m) =>
-            m.id === event.payload.id ? event.payload : m
-          ),
+          availableMonitors: event.payload,
         }));
       }
     );
@@ -201,19 +198,48 @@ export function useClientState(displayMonitorIds: string[] = []) {
       }
     );
 
+    // Listen for message_recalled event
+    const unlistenRecalled = listen<{ message_id: string; target_monitor_ids: string[] }>(
+      "message_recalled",
+      (event) => {
+        const targetIds = event.payload.target_monitor_ids;
+        const shouldRemove =
+          stableMonitorIds.length === 0 || // Main window: accept all
+          targetIds.includes("ALL") || // "ALL" means all monitors
+          stableMonitorIds.some((id) => targetIds.includes(id));
+
+        if (shouldRemove) {
+          setState((prev) => ({
+            ...prev,
+            messages: prev.messages.filter((m) => m.id !== event.payload.message_id),
+          }));
+        }
+      }
+    );
+
+    // Listen for connection_stale / connection_active (the server went quiet, then spoke again)
+    const unlistenStale = listen<{ idle_ms: number }>("connection_stale", () => {
+      setState((prev) => ({ ...prev, isStale: true }));
+    });
+    const unlistenActive = listen("connection_active", () => {
+      setState((prev) => ({ ...prev, isStale: false }));
+    });
+
     // Cleanup listeners on unmount (parallel for optimal performance)
     return () => {
       Promise.all([
         unlistenConnected,
         unlistenDisconnected,
+        unlistenShutdown,
         unlistenWelcome,
         unlistenMessage,
         unlistenMonitorList,
-        unlistenMonitorAdded,
-        unlistenMonitorRemoved,
-        unlistenMonitorUpdated,
+        unlistenMonitorsChanged,
         unlistenFlash,
         unlistenClear,
+        unlistenRecalled,
+        unlistenStale,
+        unlistenActive,
       ]).then((unlisteners) => {
         unlisteners.forEach((fn) => fn());
       });
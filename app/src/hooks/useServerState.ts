@@ -1,6 +1,6 @@
 import { useEffect, useState } from "react";
 import { listen } from "@tauri-apps/api/event";
-import type { ConnectedClientInfo, Message, VirtualMonitor } from "../types/messages";
+import type { ConnectedClientInfo, DisconnectReason, Message, VirtualMonitor } from "../types/messages";
 
 export interface ServerState {
   isRunning: boolean;
@@ -8,6 +8,10 @@ export interface ServerState {
   clients: ConnectedClientInfo[];
   feedbackMessages: Message[];
   sentMessages: Message[];
+  /** Number of clients each sent cue reached, by message ID */
+  recipientCounts: Record<string, number>;
+  /** The most recent caster to leave, and why */
+  lastDisconnect: { name: string; reason: DisconnectReason } | null;
   monitors: VirtualMonitor[];
 }
 
@@ -18,6 +22,8 @@ export function useServerState() {
     clients: [],
     feedbackMessages: [],
     sentMessages: [],
+    recipientCounts: {},
+    lastDisconnect: null,
     monitors: [],
   });
 
@@ -43,6 +49,8 @@ export function useServerState() {
         clients: [],
         feedbackMessages: [],
         sentMessages: [],
+        recipientCounts: {},
+        lastDisconnect: null,
         monitors: [],
       });
     });
@@ -58,19 +66,41 @@ export function useServerState() {
       }
     );
 
-    // Listen for client_disconnected event
-    const unlistenClientDisconnected = listen<{ client_id: string }>(
-      "client_disconnected",
+    // Listen for client_reconnected event (same client ID as before the drop)
+    const unlistenClientReconnected = listen<ConnectedClientInfo>(
+      "client_reconnected",
       (event) => {
         setState((prev) => ({
           ...prev,
-          clients: prev.clients.filter(
-            (c) => c.client_id !== event.payload.client_id
-          ),
+          clients: [
+            ...prev.clients.filter(
+              (c) => c.client_id !== event.payload.client_id
+            ),
+            event.payload,
+          ],
         }));
       }
     );
 
+    // Listen for client_disconnected event
+    const unlistenClientDisconnected = listen<{ client_id: string; reason: DisconnectReason }>(
+      "client_disconnected",
+      (event) => {
+        setState((prev) => {
+          const client = prev.clients.find((c) => c.client_id === event.payload.client_id);
+          return {
+            ...prev,
+            clients: prev.clients.filter(
+              (c) => c.client_id !== event.payload.client_id
+            ),
+            lastDisconnect: client
+              ? { name: client.name, reason: event.payload.reason }
+              : prev.lastDisconnect,
+          };
+        });
+      }
+    );
+
     // Listen for feedback_received event
     const unlistenFeedback = listen<Message>("feedback_received", (event) => {
       setState((prev) => ({
@@ -116,6 +146,21 @@ export function useServerState() {
       }
     );
 
+    // Listen for message_broadcast event (how many clients a cue reached)
+    const unlistenMessageBroadcast = listen<{
+      message_id: string;
+      recipient_count: number;
+      targets: string[];
+    }>("message_broadcast", (event) => {
+      setState((prev) => ({
+        ...prev,
+        recipientCounts: {
+          ...prev.recipientCounts,
+          [event.payload.message_id]: event.payload.recipient_count,
+        },
+      }));
+    });
+
     // Listen for kanpe_message_sent event
     const unlistenMessageSent = listen<Message>("kanpe_message_sent", (event) => {
       setState((prev) => ({
@@ -130,12 +175,14 @@ export function useServerState() {
         unlistenServerStarted,
         unlistenServerStopped,
         unlistenClientConnected,
+        unlistenClientReconnected,
         unlistenClientDisconnected,
         unlistenFeedback,
         unlistenMonitorAdded,
         unlistenMonitorRemoved,
         unlistenMonitorUpdated,
         unlistenMessageSent,
+        unlistenMessageBroadcast,
       ]).then((unlisteners) => {
         unlisteners.forEach((fn) => fn());
       });
@@ -31,12 +31,17 @@ export function useTemplates() {
     }
   };
 
-  const addServerTemplate = async (content: string, priority: Priority): Promise<void> => {
+  const addServerTemplate = async (
+    content: string,
+    priority: Priority,
+    category?: string
+  ): Promise<void> => {
     try {
       setError(null);
       const template = await invoke<ServerTemplate>("add_server_template", {
         content,
         priority,
+        category,
       });
       if (config) {
         setConfig({
@@ -53,16 +58,25 @@ export function useTemplates() {
   const updateServerTemplate = async (
     id: string,
     content: string,
-    priority: Priority
+    priority: Priority,
+    category?: string
   ) => {
     try {
       setError(null);
-      await invoke("update_server_template", { id, content, priority });
+      // Keep the current category unless a new one is given
+      const nextCategory =
+        category ?? config?.server_templates.find((t) => t.id === id)?.category;
+      await invoke("update_server_template", {
+        id,
+        content,
+        priority,
+        category: nextCategory,
+      });
       if (config) {
         setConfig({
           ...config,
           server_templates: config.server_templates.map((t) =>
-            t.id === id ? { ...t, content, priority } : t
+            t.id === id ? { ...t, content, priority, category: nextCategory } : t
           ),
         });
       }
@@ -90,13 +104,15 @@ export function useTemplates() {
 
   const addClientTemplate = async (
     content: string,
-    feedbackType: FeedbackType
+    feedbackType: FeedbackType,
+    category?: string
   ): Promise<void> => {
     try {
       setError(null);
       const template = await invoke<ClientTemplate>("add_client_template", {
         content,
         feedbackType,
+        category,
       });
       if (config) {
         setConfig({
@@ -113,16 +129,27 @@ export function useTemplates() {
   const updateClientTemplate = async (
     id: string,
     content: string,
-    feedbackType: FeedbackType
+    feedbackType: FeedbackType,
+    category?: string
   ) => {
     try {
       setError(null);
-      await invoke("update_client_template", { id, content, feedbackType });
+      // Keep the current category unless a new one is given
+      const nextCategory =
+        category ?? config?.client_templates.find((t) => t.id === id)?.category;
+      await invoke("update_client_template", {
+        id,
+        content,
+        feedbackType,
+        category: nextCategory,
+      });
       if (config) {
         setConfig({
           ...config,
           client_templates: config.client_templates.map((t) =>
-            t.id === id ? { ...t, content, feedback_type: feedbackType } : t
+            t.id === id
+              ? { ...t, content, feedback_type: feedbackType, category: nextCategory }
+              : t
           ),
         });
       }
@@ -148,6 +175,47 @@ export function useTemplates() {
     }
   };
 
+  const reorderServerTemplates = async (ids: string[]): Promise<void> => {
+    try {
+      setError(null);
+      setConfig(await invoke<TemplateConfig>("reorder_server_templates", { ids }));
+    } catch (err) {
+      setError(String(err));
+      throw err;
+    }
+  };
+
+  const reorderClientTemplates = async (ids: string[]): Promise<void> => {
+    try {
+      setError(null);
+      setConfig(await invoke<TemplateConfig>("reorder_client_templates", { ids }));
+    } catch (err) {
+      setError(String(err));
+      throw err;
+    }
+  };
+
+  const exportTemplates = async (path: string): Promise<void> => {
+    try {
+      setError(null);
+      await invoke("export_templates", { path });
+    } catch (err) {
+      setError(String(err));
+      throw err;
+    }
+  };
+
+  const importTemplates = async (path: string, merge: boolean): Promise<void> => {
+    try {
+      setError(null);
+      const templates = await invoke<TemplateConfig>("import_templates", { path, merge });
+      setConfig(templates);
+    } catch (err) {
+      setError(String(err));
+      throw err;
+    }
+  };
+
   return {
     config,
     loading,
@@ -159,5 +227,9 @@ export function useTemplates() {
     addClientTemplate,
     updateClientTemplate,
     deleteClientTemplate,
+    reorderServerTemplates,
+    reorderClientTemplates,
+    exportTemplates,
+    importTemplates,
   };
 }
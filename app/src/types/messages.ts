@@ -1,37 +1,109 @@
 // TypeScript types matching Rust message types
 
-export type Priority = "normal" | "high" | "urgent";
+export type Priority = "low" | "normal" | "high" | "urgent";
 export type FeedbackType = "ack" | "question" | "issue" | "info";
+/** Feedback type on the wire: a fixed type, or a caster-defined category */
+export type FeedbackKind = FeedbackType | { custom: string };
+export type AirStatus = "on_air" | "standby" | "off_air";
+export type WireFormat = "json" | "msgpack";
+export type ContentFormat = "plain" | "markdown";
 
 export interface ClientHelloPayload {
   client_name: string;
   display_monitor_ids: string[];
+  wire_format?: WireFormat;
+  resume_token?: string;
 }
 
 export interface ServerWelcomePayload {
   server_name: string;
   assigned_client_id: string;
+  wire_format?: WireFormat;
+  resume_token?: string;
+  /** Set when the server renamed the client, e.g. "iPhone (2)" */
+  assigned_client_name?: string;
 }
 
 export interface KanpeMessagePayload {
   content: string;
   target_monitor_ids: string[];
   priority: Priority;
+  content_format?: ContentFormat;
+  expires_at?: number;
+  repeat_flash_interval_ms?: number;
+  invert?: boolean;
+}
+
+export interface ServerShutdownPayload {
+  reason: string;
+  grace_ms: number;
+}
+
+export interface MessageAckPayload {
+  message_id: string;
 }
 
 export interface FeedbackMessagePayload {
   content: string;
   client_name: string;
   reply_to_message_id: string;
-  feedback_type: FeedbackType;
+  feedback_type: FeedbackKind;
+}
+
+export interface ReactionPayload {
+  reply_to_message_id: string;
+  reaction: string;
+  client_name: string;
+}
+
+export interface FeedbackTypingPayload {
+  client_name: string;
+  is_typing: boolean;
+}
+
+export interface PongPayload {
+  ping_id: string;
 }
 
 export interface FlashCommandPayload {
   target_monitor_ids: string[];
+  invert?: boolean;
 }
 
 export interface ClearCommandPayload {
   target_monitor_ids: string[];
+  invert?: boolean;
+}
+
+export interface PresenterNoteChunkPayload {
+  note_id: string;
+  seq: number;
+  total: number;
+  data: string;
+  target_monitor_ids: string[];
+}
+
+export interface PresenterNoteResendPayload {
+  note_id: string;
+  missing_seqs: number[];
+}
+
+export interface UpdateSubscriptionPayload {
+  display_monitor_ids: string[];
+}
+
+export interface MonitorChangeAckPayload {
+  change_id: string;
+}
+
+export interface MessageRecalledPayload {
+  message_id: string;
+  target_monitor_ids: string[];
+}
+
+export interface AirStatusPayload {
+  status: AirStatus;
+  since: number;
 }
 
 export type Message =
@@ -68,6 +140,7 @@ export type Message =
       type: "pong";
       id: string;
       timestamp: number;
+      payload: PongPayload;
     }
   | {
       type: "flash_command";
@@ -80,34 +153,144 @@ export type Message =
       id: string;
       timestamp: number;
       payload: ClearCommandPayload;
+    }
+  | {
+      type: "presenter_note_chunk";
+      id: string;
+      timestamp: number;
+      payload: PresenterNoteChunkPayload;
+    }
+  | {
+      type: "presenter_note_resend";
+      id: string;
+      timestamp: number;
+      payload: PresenterNoteResendPayload;
+    }
+  | {
+      type: "update_subscription";
+      id: string;
+      timestamp: number;
+      payload: UpdateSubscriptionPayload;
+    }
+  | {
+      type: "monitor_change_ack";
+      id: string;
+      timestamp: number;
+      payload: MonitorChangeAckPayload;
+    }
+  | {
+      type: "message_recalled";
+      id: string;
+      timestamp: number;
+      payload: MessageRecalledPayload;
+    }
+  | {
+      type: "air_status";
+      id: string;
+      timestamp: number;
+      payload: AirStatusPayload;
+    }
+  | {
+      type: "reconnect_request";
+      id: string;
+      timestamp: number;
+    }
+  | {
+      type: "message_ack";
+      id: string;
+      timestamp: number;
+      payload: MessageAckPayload;
+    }
+  | {
+      type: "server_shutdown";
+      id: string;
+      timestamp: number;
+      payload: ServerShutdownPayload;
+    }
+  | {
+      type: "reaction";
+      id: string;
+      timestamp: number;
+      payload: ReactionPayload;
+    }
+  | {
+      type: "feedback_typing";
+      id: string;
+      timestamp: number;
+      payload: FeedbackTypingPayload;
     };
 
+/** Why a caster left, as reported with client_disconnected */
+export type DisconnectReason =
+  | "client_closed"
+  | "network_error"
+  | "kicked"
+  | "timeout"
+  | "server_shutdown";
+
 export interface ConnectedClientInfo {
   client_id: string;
   name: string;
   monitor_ids: string[];
+  /** Milliseconds since the epoch */
+  connected_at: number;
+  /** Milliseconds since the epoch; null until the client answers a ping */
+  last_pong_at: number | null;
 }
 
+export interface MonitorSyncStatus {
+  client_id: string;
+  client_name: string;
+  in_sync: boolean;
+}
+
+export type ConnectionState =
+  | { state: "disconnected" }
+  | { state: "connecting" }
+  | { state: "connected" }
+  | {
+      state: "reconnecting";
+      attempt: number;
+      max_attempts: number;
+      next_delay_ms: number;
+    }
+  | { state: "failed" };
+
 export interface VirtualMonitor {
   id: string;
   name: string;
   description?: string;
   color?: string;
+  icon?: string | null;
+  max_cues?: number | null;
+  position?: number;
+  enabled?: boolean;
+}
+
+export interface MonitorGroup {
+  id: string;
+  name: string;
+  monitor_ids: string[];
 }
 
 export interface ServerTemplate {
   id: string;
   content: string;
   priority: Priority;
+  order: number;
+  category?: string;
 }
 
 export interface ClientTemplate {
   id: string;
   content: string;
   feedback_type: FeedbackType;
+  order: number;
+  category?: string;
 }
 
 export interface TemplateConfig {
+  schema_version: number;
   server_templates: ServerTemplate[];
   client_templates: ClientTemplate[];
 }
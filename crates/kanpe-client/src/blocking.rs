@@ -0,0 +1,139 @@
+//! Synchronous wrapper around KanpeClient for embedders without a tokio runtime
+
+use crate::client::KanpeClient;
+use crate::error::KanpeClientError;
+use crate::events::ClientEvent;
+use kanpe_core::Message;
+use std::time::Duration;
+use tokio::runtime::{Builder, Runtime};
+use tokio::sync::mpsc;
+
+/// KanpeClient driven by its own runtime, with blocking methods
+///
+/// The runtime has a single worker thread, so the connection keeps answering
+/// pings and queueing events between calls. Must not be used from inside an
+/// async context.
+pub struct BlockingKanpeClient {
+    runtime: Runtime,
+    client: KanpeClient,
+    event_rx: mpsc::UnboundedReceiver<ClientEvent>,
+}
+
+// Same error type as the async client, whose WebSocket variant is large
+#[allow(clippy::result_large_err)]
+impl BlockingKanpeClient {
+    pub fn new() -> Result<Self, KanpeClientError> {
+        let runtime = Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("kanpe-client")
+            .enable_all()
+            .build()
+            .map_err(KanpeClientError::Runtime)?;
+        let (event_tx, event_rx) = mpsc::unbounded_channel();
+        let client = KanpeClient::new(event_tx);
+        Ok(Self {
+            runtime,
+            client,
+            event_rx,
+        })
+    }
+
+    /// Access the wrapped client, e.g. to call its `set_*` methods before connecting
+    pub fn client_mut(&mut self) -> &mut KanpeClient {
+        &mut self.client
+    }
+
+    /// Connect to a Kanpe server, blocking until the session is open
+    pub fn connect(
+        &mut self,
+        server_address: &str,
+        client_name: String,
+        display_monitor_ids: Vec<String>,
+    ) -> Result<(), KanpeClientError> {
+        self.runtime.block_on(self.client.connect(server_address, client_name, display_monitor_ids))
+    }
+
+    /// Send a message to the server
+    pub fn send_message(&self, message: &Message) -> Result<(), KanpeClientError> {
+        self.runtime.block_on(self.client.send_message(message))
+    }
+
+    /// Disconnect from the server
+    pub fn disconnect(&mut self) -> Result<(), KanpeClientError> {
+        self.runtime.block_on(self.client.disconnect())
+    }
+
+    /// Wait for the next client event
+    ///
+    /// Returns None once the client is gone and no events are left.
+    pub fn recv_event(&mut self) -> Option<ClientEvent> {
+        self.runtime.block_on(self.event_rx.recv())
+    }
+
+    /// Wait up to `timeout` for the next client event
+    pub fn recv_event_timeout(&mut self, timeout: Duration) -> Option<ClientEvent> {
+        let event_rx = &mut self.event_rx;
+        self.runtime
+            .block_on(async { tokio::time::timeout(timeout, event_rx.recv()).await })
+            .ok()
+            .flatten()
+    }
+
+    /// Take the next event if one is already queued, without waiting
+    pub fn try_recv_event(&mut self) -> Option<ClientEvent> {
+        self.event_rx.try_recv().ok()
+    }
+}
+
+impl Drop for BlockingKanpeClient {
+    /// Close the connection so its tasks end before the runtime shuts down
+    fn drop(&mut self) {
+        let _ = self.runtime.block_on(self.client.disconnect());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::{SinkExt, StreamExt};
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+    /// Start a WebSocket server that answers the client's hello with a welcome
+    async fn start_welcoming_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = crate::test_support::accept(stream).await;
+            let _hello = ws.next().await;
+            let welcome = Message::server_welcome("mock".to_string(), "c-1".to_string());
+            ws.send(WsMessage::Text(serde_json::to_string(&welcome).unwrap())).await.unwrap();
+            while let Some(Ok(frame)) = ws.next().await {
+                if frame.is_close() {
+                    break;
+                }
+            }
+        });
+        addr
+    }
+
+    #[test]
+    fn test_blocking_client_receives_welcome() {
+        let server_runtime = Runtime::new().unwrap();
+        let addr = server_runtime.block_on(start_welcoming_server());
+
+        let mut client = BlockingKanpeClient::new().unwrap();
+        client.connect(&addr, "plugin".to_string(), vec!["A".to_string()]).unwrap();
+
+        let mut welcomed_by = None;
+        while let Some(event) = client.recv_event_timeout(Duration::from_secs(2)) {
+            if let ClientEvent::ServerWelcomeReceived { server_name } = event {
+                welcomed_by = Some(server_name);
+                break;
+            }
+        }
+        assert_eq!(welcomed_by.as_deref(), Some("mock"));
+        client.disconnect().unwrap();
+    }
+}
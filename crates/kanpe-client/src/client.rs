@@ -1,27 +1,107 @@
 //! WebSocket client implementation
 
+use crate::connection_state::{ConnectionState, ReconnectPolicy};
+use crate::error::KanpeClientError;
+use crate::event_fanout::EventSender;
+use crate::ClientEventStream;
 use crate::events::ClientEvent;
+use crate::flash_coalescer::{FlashCoalescer, FLASH_COALESCE_WINDOW};
+use crate::seen_ids::{SeenIds, DEFAULT_DEDUP_CAPACITY};
+use crate::simulated_latency::{simulate, SimulatedLatency};
+use crate::rate_limiter::RateLimiter;
+use crate::presenter_notes::{NoteAssembler, NoteTimeout, NOTE_CHUNK_TIMEOUT, NOTE_MAX_RESEND_ATTEMPTS};
+use futures_util::future::BoxFuture;
 use futures_util::{SinkExt, StreamExt};
-use kanpe_core::{Message, message::KanpeMessagePayload, types::{VirtualMonitor, timestamp}};
+use kanpe_core::{Message, WEBSOCKET_SUBPROTOCOL, message::KanpeMessagePayload, types::{FeedbackType, VirtualMonitor, timestamp, ALL_MONITORS}};
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
-use tokio::sync::{mpsc, RwLock};
-use tokio_tungstenite::{connect_async, tungstenite::Message as WsMessage};
+use std::time::Instant;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio::sync::{broadcast, mpsc, RwLock};
+use tokio::task::JoinHandle;
+use tokio::time::{interval, Duration};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::error::UrlError;
+use tokio_tungstenite::tungstenite::handshake::client::Request;
+use tokio_tungstenite::tungstenite::http::header::{HeaderName, HeaderValue, AUTHORIZATION, SEC_WEBSOCKET_PROTOCOL};
+use tokio_tungstenite::{client_async, tungstenite::Message as WsMessage};
+use tracing::{info, info_span, warn, Instrument};
 
-type WsSink = futures_util::stream::SplitSink<
-    tokio_tungstenite::WebSocketStream<
-        tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>,
-    >,
-    WsMessage,
->;
+/// Byte stream a session's WebSocket runs over: TCP, or whatever a connector opens
+trait Transport: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Transport for T {}
+
+type WsSink = futures_util::stream::SplitSink<tokio_tungstenite::WebSocketStream<Box<dyn Transport>>, WsMessage>;
+
+type WsStream = futures_util::stream::SplitStream<tokio_tungstenite::WebSocketStream<Box<dyn Transport>>>;
+
+/// Opens the byte stream for each connection attempt in place of a TCP connect
+type Connector = Arc<dyn Fn() -> BoxFuture<'static, std::io::Result<Box<dyn Transport>>> + Send + Sync>;
+
+/// Everything needed to open a session, shared by `connect` and the reconnect loop
+#[derive(Clone)]
+struct SessionOptions {
+    auth_token: Option<String>,
+    extra_headers: Vec<(String, String)>,
+    connect_timeout: Duration,
+    simulated_latency: Option<SimulatedLatency>,
+    connector: Option<Connector>,
+}
+
+/// Default number of received messages kept in the client history
+pub const DEFAULT_MESSAGE_HISTORY_CAPACITY: usize = 20;
+
+/// How long opening a connection may take before `connect` gives up
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often the client pings the server to measure latency
+pub const CLIENT_PING_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long the server may stay silent before `ClientEvent::Stale` is sent
+///
+/// Three missed answers to the client's own pings.
+pub const DEFAULT_STALE_AFTER: Duration = Duration::from_secs(15);
+
+/// What `connect` does when the client is already connected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConnectPolicy {
+    /// Fail with `KanpeClientError::AlreadyConnected`
+    #[default]
+    RejectIfConnected,
+    /// Cleanly disconnect the current session, then connect
+    ReplaceExisting,
+}
 
 /// WebSocket client for Kanpe caster mode
 pub struct KanpeClient {
     sink: Arc<RwLock<Option<WsSink>>>,
-    event_tx: mpsc::UnboundedSender<ClientEvent>,
+    event_tx: EventSender,
     disconnect_tx: Option<mpsc::Sender<()>>,
+    read_task: Option<JoinHandle<()>>,
     client_name: Arc<RwLock<String>>,
+    display_monitor_ids: Arc<RwLock<Vec<String>>>,
+    state: Arc<RwLock<ConnectionState>>,
+    reconnect_policy: Option<ReconnectPolicy>,
     latest_message: Arc<RwLock<Option<(String, KanpeMessagePayload)>>>,
+    message_history: Arc<RwLock<VecDeque<(String, KanpeMessagePayload)>>>,
+    message_history_capacity: usize,
     monitors: Arc<RwLock<Vec<VirtualMonitor>>>,
+    granular_monitor_events: bool,
+    latency: Arc<RwLock<Option<Duration>>>,
+    last_activity: Arc<RwLock<Option<i64>>>,
+    stale_after: Option<Duration>,
+    resume_token: Arc<RwLock<Option<String>>>,
+    auth_token: Option<String>,
+    extra_headers: Vec<(String, String)>,
+    connect_policy: ConnectPolicy,
+    connect_timeout: Duration,
+    flash_coalesce_window: Duration,
+    dedup_capacity: usize,
+    simulated_latency: Option<SimulatedLatency>,
+    rate_limiter: Option<RateLimiter>,
+    connector: Option<Connector>,
 }
 
 impl KanpeClient {
@@ -29,46 +109,215 @@ impl KanpeClient {
     pub fn new(event_tx: mpsc::UnboundedSender<ClientEvent>) -> Self {
         Self {
             sink: Arc::new(RwLock::new(None)),
-            event_tx,
+            event_tx: EventSender::new(event_tx),
             disconnect_tx: None,
+            read_task: None,
             client_name: Arc::new(RwLock::new(String::new())),
+            display_monitor_ids: Arc::new(RwLock::new(Vec::new())),
+            state: Arc::new(RwLock::new(ConnectionState::Disconnected)),
+            reconnect_policy: None,
             latest_message: Arc::new(RwLock::new(None)),
+            message_history: Arc::new(RwLock::new(VecDeque::new())),
+            message_history_capacity: DEFAULT_MESSAGE_HISTORY_CAPACITY,
             monitors: Arc::new(RwLock::new(Vec::new())),
+            granular_monitor_events: true,
+            latency: Arc::new(RwLock::new(None)),
+            last_activity: Arc::new(RwLock::new(None)),
+            stale_after: Some(DEFAULT_STALE_AFTER),
+            resume_token: Arc::new(RwLock::new(None)),
+            auth_token: None,
+            extra_headers: Vec::new(),
+            connect_policy: ConnectPolicy::default(),
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            flash_coalesce_window: FLASH_COALESCE_WINDOW,
+            dedup_capacity: DEFAULT_DEDUP_CAPACITY,
+            simulated_latency: None,
+            rate_limiter: None,
+            connector: None,
+        }
+    }
+
+    /// Create a KanpeClient whose events are read as a `Stream`
+    pub fn with_event_stream() -> (Self, ClientEventStream) {
+        let (event_tx, event_rx) = mpsc::unbounded_channel();
+        (Self::new(event_tx), ClientEventStream::new(event_rx))
+    }
+
+    /// Receive every event from now on, alongside the channel passed to `new`
+    ///
+    /// Any number of subscribers can listen at once. One that falls more than
+    /// `EVENT_SUBSCRIBER_CAPACITY` events behind gets `RecvError::Lagged` and
+    /// misses the oldest events; the `new` channel never drops events.
+    pub fn subscribe(&self) -> broadcast::Receiver<ClientEvent> {
+        self.event_tx.subscribe()
+    }
+
+    /// Set what `connect` does when a session is already open
+    pub fn set_connect_policy(&mut self, connect_policy: ConnectPolicy) {
+        self.connect_policy = connect_policy;
+    }
+
+    /// Set how long opening a connection (TCP, WebSocket handshake and hello) may take
+    ///
+    /// Applies to `connect` and to each reconnect attempt.
+    pub fn set_connect_timeout(&mut self, connect_timeout: Duration) {
+        self.connect_timeout = connect_timeout;
+    }
+
+    /// Cap outbound messages at `max_per_sec` (None = unlimited, the default)
+    ///
+    /// Bursts of up to `max_per_sec` go through at once; beyond that, sends fail
+    /// with `KanpeClientError::RateLimited` until the budget refills. Every send
+    /// path shares the one budget. Keepalive pings are not counted.
+    pub fn set_rate_limit(&mut self, max_per_sec: Option<u32>) {
+        self.rate_limiter = max_per_sec.map(|max| RateLimiter::new(max, Instant::now()));
+    }
+
+    /// Set how the client retries after losing its connection (None = give up immediately)
+    ///
+    /// Takes effect on the next `connect`.
+    pub fn set_reconnect_policy(&mut self, reconnect_policy: Option<ReconnectPolicy>) {
+        self.reconnect_policy = reconnect_policy;
+    }
+
+    /// Set the token sent as `Authorization: Bearer` on future connections
+    pub fn set_auth_token(&mut self, auth_token: Option<String>) {
+        self.auth_token = auth_token;
+    }
+
+    /// Set extra HTTP headers sent on the WebSocket upgrade of future connections
+    ///
+    /// Useful behind a reverse proxy that authenticates or routes on headers.
+    /// Names and values are checked on `connect`, before any existing session is replaced.
+    pub fn set_extra_headers(&mut self, extra_headers: Vec<(String, String)>) {
+        self.extra_headers = extra_headers;
+    }
+
+    /// Set how many received messages `message_history` keeps
+    pub fn set_message_history_capacity(&mut self, capacity: usize) {
+        self.message_history_capacity = capacity;
+    }
+
+    /// Set whether MonitorAdded/Removed/Updated events are sent
+    ///
+    /// MonitorsChanged is sent after every monitor change either way, so a UI
+    /// that only needs the current list can turn the per-monitor events off.
+    pub fn set_granular_monitor_events(&mut self, enabled: bool) {
+        self.granular_monitor_events = enabled;
+    }
+
+    /// Set the window within which flashes for overlapping monitors are merged
+    ///
+    /// `Duration::ZERO` disables coalescing so every flash is delivered immediately.
+    pub fn set_flash_coalesce_window(&mut self, window: Duration) {
+        self.flash_coalesce_window = window;
+    }
+
+    /// Set how long the server may send nothing before `ClientEvent::Stale` (None = never warn)
+    pub fn set_stale_after(&mut self, stale_after: Option<Duration>) {
+        self.stale_after = stale_after;
+    }
+
+    /// Set how many recent cue ids are remembered to drop repeat deliveries
+    ///
+    /// A cue replayed after a reconnect is then not shown twice. Zero disables
+    /// de-duplication. Takes effect on the next `connect`.
+    pub fn set_dedup_capacity(&mut self, capacity: usize) {
+        self.dedup_capacity = capacity;
+    }
+
+    /// Delay every sent and received frame to mimic a slow venue network
+    ///
+    /// Each frame waits `delay_ms` plus a random share of `jitter_ms`. Passing zero
+    /// for both turns the simulation off. Takes effect on the next `connect`.
+    #[cfg(any(test, feature = "simulated-latency"))]
+    pub fn set_simulated_latency(&mut self, delay_ms: u64, jitter_ms: u64) {
+        self.simulated_latency = (delay_ms > 0 || jitter_ms > 0).then(|| SimulatedLatency {
+            delay: Duration::from_millis(delay_ms),
+            jitter: Duration::from_millis(jitter_ms),
+        });
+    }
+
+    /// Open every connection with `connect` instead of over TCP
+    ///
+    /// The WebSocket handshake still runs over the returned stream, addressed
+    /// to the URL passed to `connect`. Meant for in-memory test transports.
+    #[cfg(any(test, feature = "test-transport"))]
+    pub fn set_stream_connector<F, Fut, S>(&mut self, connect: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = std::io::Result<S>> + Send + 'static,
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        use futures_util::FutureExt;
+
+        self.connector = Some(Arc::new(move || {
+            connect()
+                .map(|stream| stream.map(|stream| Box::new(stream) as Box<dyn Transport>))
+                .boxed()
+        }));
+    }
+
+    fn session_options(&self) -> SessionOptions {
+        SessionOptions {
+            auth_token: self.auth_token.clone(),
+            extra_headers: self.extra_headers.clone(),
+            connect_timeout: self.connect_timeout,
+            simulated_latency: self.simulated_latency,
+            connector: self.connector.clone(),
         }
     }
 
     /// Connect to a Kanpe server
+    ///
+    /// `server_address` is a bare `host:port` (connecting to `/ws`) or a full
+    /// `ws://` URL whose path is used as given.
     pub async fn connect(
         &mut self,
         server_address: &str,
         client_name: String,
         display_monitor_ids: Vec<String>,
-    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        // Connect to WebSocket at /ws endpoint
-        let url = if server_address.starts_with("ws://") || server_address.starts_with("wss://") {
-            // If already has protocol, append /ws if not present
-            if server_address.ends_with("/ws") {
-                server_address.to_string()
-            } else {
-                format!("{}/ws", server_address.trim_end_matches('/'))
-            }
-        } else {
-            // Add protocol and /ws endpoint
-            format!("ws://{}/ws", server_address)
-        };
+    ) -> Result<(), KanpeClientError> {
+        let url = endpoint_url(server_address);
+        let session_options = self.session_options();
+        // Reject a bad URL or header up front so it never costs the current session
+        upgrade_request(&url, &session_options)?;
 
-        let (ws_stream, _) = connect_async(&url).await?;
-        let (sink, mut stream) = ws_stream.split();
+        // A session is open from the socket handshake on, before the server's welcome
+        if self.sink.read().await.is_some() {
+            match self.connect_policy {
+                ConnectPolicy::RejectIfConnected => return Err(KanpeClientError::AlreadyConnected),
+                ConnectPolicy::ReplaceExisting => self.disconnect().await?,
+            }
+        }
 
-        // Store sink
-        *self.sink.write().await = Some(sink);
+        // Stop a previous read loop (which may still be reconnecting) before its sink slot is reused
+        if let Some(disconnect_tx) = self.disconnect_tx.take() {
+            let _ = disconnect_tx.send(()).await;
+        }
+        if let Some(read_task) = self.read_task.take() {
+            let _ = read_task.await;
+        }
 
-        // Store client name
-        *self.client_name.write().await = client_name.clone();
+        *self.state.write().await = ConnectionState::Connecting;
 
-        // Send ClientHello
-        let hello = Message::client_hello(client_name, display_monitor_ids);
-        self.send_internal(&hello).await?;
+        // Open the socket and send ClientHello, resuming the last session if it dropped
+        let hello = Message::client_hello_resuming(
+            client_name.clone(),
+            display_monitor_ids.clone(),
+            self.resume_token.read().await.clone(),
+        );
+        let opened = open_session(&url, &session_options, &self.sink, &hello).await;
+        let mut stream = match opened {
+            Ok(stream) => stream,
+            Err(e) => {
+                *self.state.write().await = ConnectionState::Disconnected;
+                warn!(url = %url, error = %e, "failed to connect");
+                return Err(e);
+            }
+        };
+        *self.client_name.write().await = client_name;
+        *self.display_monitor_ids.write().await = display_monitor_ids;
 
         // Set up disconnect channel
         let (disconnect_tx, mut disconnect_rx) = mpsc::channel::<()>(1);
@@ -79,133 +328,404 @@ impl KanpeClient {
         let sink_for_handler = self.sink.clone();
         let server_addr = server_address.to_string();
         let latest_message = self.latest_message.clone();
+        let message_history = self.message_history.clone();
+        let message_history_capacity = self.message_history_capacity;
         let monitors = self.monitors.clone();
+        let granular_monitor_events = self.granular_monitor_events;
+        let flash_coalesce_window = self.flash_coalesce_window;
+        let dedup_capacity = self.dedup_capacity;
+        let latency = self.latency.clone();
+        let last_activity = self.last_activity.clone();
+        let stale_after = self.stale_after;
+        let resume_token = self.resume_token.clone();
+        let simulated_latency = self.simulated_latency;
+        let client_name = self.client_name.clone();
+        let display_monitor_ids = self.display_monitor_ids.clone();
+        let state = self.state.clone();
+        let reconnect_policy = self.reconnect_policy;
 
-        tokio::spawn(async move {
-            loop {
-                tokio::select! {
-                    msg = stream.next() => {
-                        match msg {
-                            Some(Ok(WsMessage::Text(text))) => {
-                                match serde_json::from_str::<Message>(&text) {
-                                    Ok(message) => {
-                                        match message {
-                                            Message::ServerWelcome { payload, .. } => {
-                                                let _ = event_tx.send(ClientEvent::ServerWelcomeReceived {
-                                                    server_name: payload.server_name,
-                                                });
-                                                let _ = event_tx.send(ClientEvent::ConnectionEstablished {
-                                                    server_address: server_addr.clone(),
-                                                });
-                                            }
-                                            Message::KanpeMessage { id, payload, .. } => {
-                                                // Store latest message with ID
-                                                *latest_message.write().await = Some((id.clone(), payload.clone()));
-                                                let _ = event_tx.send(ClientEvent::MessageReceived { 
-                                                    message: Message::KanpeMessage { 
-                                                        id,
-                                                        timestamp: timestamp(),
-                                                        payload 
-                                                    } 
-                                                });
-                                            }
-                                            Message::MonitorListSync { payload, .. } => {
-                                                // Store monitors
-                                                *monitors.write().await = payload.monitors.clone();
-                                                let _ = event_tx.send(ClientEvent::MonitorListReceived {
-                                                    monitors: payload.monitors,
-                                                });
-                                            }
-                                            Message::MonitorAdded { payload, .. } => {
-                                                let _ = event_tx.send(ClientEvent::MonitorAdded {
-                                                    monitor: payload.monitor,
-                                                });
-                                            }
-                                            Message::MonitorRemoved { payload, .. } => {
-                                                let _ = event_tx.send(ClientEvent::MonitorRemoved {
-                                                    monitor_id: payload.monitor_id,
-                                                });
-                                            }
-                                            Message::MonitorUpdated { payload, .. } => {
-                                                let _ = event_tx.send(ClientEvent::MonitorUpdated {
-                                                    monitor: payload.monitor,
-                                                });
-                                            }
-                                            Message::FlashCommand { payload, .. } => {
-                                                let _ = event_tx.send(ClientEvent::FlashReceived {
-                                                    target_monitor_ids: payload.target_monitor_ids,
-                                                });
-                                            }
-                                            Message::ClearCommand { payload, .. } => {
-                                                let _ = event_tx.send(ClientEvent::ClearReceived {
-                                                    target_monitor_ids: payload.target_monitor_ids,
-                                                });
-                                            }
-                                            Message::Ping { .. } => {
-                                                // Respond with pong
-                                                let pong = Message::pong();
-                                                if let Ok(json) = serde_json::to_string(&pong) {
-                                                    if let Some(sink) = sink_for_handler.write().await.as_mut() {
+        let span = info_span!("connection", server = %server_addr);
+        self.read_task = Some(tokio::spawn(async move {
+            let mut note_assembler = NoteAssembler::new(NOTE_CHUNK_TIMEOUT, NOTE_MAX_RESEND_ATTEMPTS);
+            let mut note_timer = interval(Duration::from_secs(1));
+            let mut flash_coalescer = FlashCoalescer::new(flash_coalesce_window);
+            let mut seen_ids = SeenIds::new(dedup_capacity);
+            let mut ping_timer = interval(CLIENT_PING_INTERVAL);
+            let mut pending_pings: HashMap<String, Instant> = HashMap::new();
+            // Broadcast sequence numbers restart with each connection
+            let mut last_seq: Option<u64> = None;
+            let mut last_inbound = tokio::time::Instant::now();
+            let mut stale = false;
+
+            'session: loop {
+                let lost_reason = loop {
+                    let flash_deadline = flash_coalescer.next_deadline();
+                    let stale_deadline = stale_after.map(|after| last_inbound + after);
+
+                    tokio::select! {
+                        msg = stream.next() => {
+                            if let Some(Ok(_)) = &msg {
+                                last_inbound = tokio::time::Instant::now();
+                                *last_activity.write().await = Some(timestamp());
+                                if stale {
+                                    stale = false;
+                                    event_tx.send(ClientEvent::ActivityResumed);
+                                }
+                            }
+                            match msg {
+                                Some(Ok(WsMessage::Text(text))) => {
+                                    simulate(simulated_latency).await;
+                                    let (seq, parsed) = Message::parse_frame(&text);
+                                    if let Some(seq) = seq {
+                                        let expected = last_seq.map_or(1, |last| last + 1);
+                                        if seq > expected {
+                                            event_tx.send(ClientEvent::SequenceGap { expected, got: seq });
+                                        }
+                                        last_seq = Some(seq);
+                                    }
+                                    match parsed {
+                                        Ok(message) => {
+                                            match message {
+                                                Message::ServerWelcome { payload, .. } => {
+                                                    *state.write().await = ConnectionState::Connected;
+                                                    *resume_token.write().await = payload.resume_token;
+                                                    info!(client_id = %payload.assigned_client_id, "connected");
+                                                    event_tx.send(ClientEvent::ServerWelcomeReceived {
+                                                        server_name: payload.server_name,
+                                                    });
+                                                    event_tx.send(ClientEvent::ConnectionEstablished {
+                                                        server_address: server_addr.clone(),
+                                                    });
+                                                }
+                                                Message::KanpeMessage { payload, .. } if payload.is_expired(timestamp()) => {
+                                                    // Stale cue (e.g. replayed after a reconnect); drop it
+                                                }
+                                                Message::KanpeMessage { id, .. } if !seen_ids.insert(&id) => {
+                                                    // Already delivered (e.g. replayed after a reconnect); drop it
+                                                }
+                                                Message::KanpeMessage { id, payload, .. } => {
+                                                    // Store latest message with ID
+                                                    *latest_message.write().await = Some((id.clone(), payload.clone()));
+                                                    let mut history = message_history.write().await;
+                                                    history.push_back((id.clone(), payload.clone()));
+                                                    while history.len() > message_history_capacity {
+                                                        history.pop_front();
+                                                    }
+                                                    drop(history);
+                                                    event_tx.send(ClientEvent::MessageReceived { 
+                                                        message: Message::KanpeMessage { 
+                                                            id,
+                                                            timestamp: timestamp(),
+                                                            payload 
+                                                        } 
+                                                    });
+                                                }
+                                                Message::MessageRecalled { payload, .. } => {
+                                                    let mut latest = latest_message.write().await;
+                                                    if latest.as_ref().is_some_and(|(id, _)| *id == payload.message_id) {
+                                                        *latest = None;
+                                                    }
+                                                    drop(latest);
+                                                    message_history.write().await.retain(|(id, _)| *id != payload.message_id);
+                                                    event_tx.send(ClientEvent::MessageRecalled {
+                                                        message_id: payload.message_id,
+                                                        target_monitor_ids: payload.target_monitor_ids,
+                                                    });
+                                                }
+                                                Message::MonitorListSync { id, payload, .. } => {
+                                                    // Store monitors
+                                                    *monitors.write().await = payload.monitors.clone();
+                                                    send_monitor_ack(&sink_for_handler, id).await;
+                                                    event_tx.send(ClientEvent::MonitorListReceived {
+                                                        monitors: payload.monitors.clone(),
+                                                    });
+                                                    event_tx.send(ClientEvent::MonitorsChanged {
+                                                        monitors: payload.monitors,
+                                                    });
+                                                }
+                                                Message::MonitorGroupSync { payload, .. } => {
+                                                    event_tx.send(ClientEvent::MonitorGroupsReceived {
+                                                        groups: payload.groups,
+                                                    });
+                                                }
+                                                Message::MonitorAdded { id, payload, .. } => {
+                                                    monitors.write().await.push(payload.monitor.clone());
+                                                    send_monitor_ack(&sink_for_handler, id).await;
+                                                    if granular_monitor_events {
+                                                        event_tx.send(ClientEvent::MonitorAdded {
+                                                            monitor: payload.monitor,
+                                                        });
+                                                    }
+                                                    send_monitors_changed(&event_tx, &monitors).await;
+                                                }
+                                                Message::MonitorRemoved { id, payload, .. } => {
+                                                    monitors.write().await.retain(|m| m.id != payload.monitor_id);
+                                                    send_monitor_ack(&sink_for_handler, id).await;
+                                                    if granular_monitor_events {
+                                                        event_tx.send(ClientEvent::MonitorRemoved {
+                                                            monitor_id: payload.monitor_id,
+                                                        });
+                                                    }
+                                                    send_monitors_changed(&event_tx, &monitors).await;
+                                                }
+                                                Message::MonitorUpdated { id, payload, .. } => {
+                                                    if let Some(existing) = monitors
+                                                        .write()
+                                                        .await
+                                                        .iter_mut()
+                                                        .find(|m| m.id == payload.monitor.id)
+                                                    {
+                                                        *existing = payload.monitor.clone();
+                                                    }
+                                                    send_monitor_ack(&sink_for_handler, id).await;
+                                                    if granular_monitor_events {
+                                                        event_tx.send(ClientEvent::MonitorUpdated {
+                                                            monitor: payload.monitor,
+                                                        });
+                                                    }
+                                                    send_monitors_changed(&event_tx, &monitors).await;
+                                                }
+                                                Message::FlashCommand { payload, .. } => {
+                                                    let targets = if payload.invert {
+                                                        monitors_except(&monitors, &payload.target_monitor_ids).await
+                                                    } else {
+                                                        payload.target_monitor_ids
+                                                    };
+                                                    if let Some(target_monitor_ids) =
+                                                        flash_coalescer.push(targets, Instant::now())
+                                                    {
+                                                        event_tx.send(ClientEvent::FlashReceived {
+                                                            target_monitor_ids,
+                                                        });
+                                                    }
+                                                }
+                                                Message::AirStatus { payload, .. } => {
+                                                    event_tx.send(ClientEvent::AirStatusChanged {
+                                                        status: payload.status,
+                                                        since: payload.since,
+                                                    });
+                                                }
+                                                Message::ClearCommand { payload, .. } => {
+                                                    let target_monitor_ids = if payload.invert {
+                                                        monitors_except(&monitors, &payload.target_monitor_ids).await
+                                                    } else {
+                                                        payload.target_monitor_ids
+                                                    };
+                                                    event_tx.send(ClientEvent::ClearReceived { target_monitor_ids });
+                                                }
+                                                Message::PresenterNoteChunk { payload, .. } => {
+                                                    if let Some(note) = note_assembler.push(payload, Instant::now()) {
+                                                        event_tx.send(ClientEvent::PresenterNoteReceived {
+                                                            note_id: note.note_id,
+                                                            content: note.content,
+                                                            target_monitor_ids: note.target_monitor_ids,
+                                                        });
+                                                    }
+                                                }
+                                                Message::Ping { id, .. } => {
+                                                    // Respond with pong
+                                                    let pong = Message::pong_for(id);
+                                                    if let Ok(json) = pong.to_json()
+                                                        && let Some(sink) = sink_for_handler.write().await.as_mut()
+                                                    {
                                                         let _ = sink.send(WsMessage::Text(json)).await;
                                                     }
                                                 }
-                                            }
-                                            _ => {
-                                                // Ignore other message types
+                                                Message::Pong { payload, .. } => {
+                                                    if let Some(sent_at) = pending_pings.remove(&payload.ping_id) {
+                                                        let round_trip = sent_at.elapsed();
+                                                        *latency.write().await = Some(round_trip);
+                                                        event_tx.send(ClientEvent::LatencyMeasured {
+                                                            millis: round_trip.as_millis() as u64,
+                                                        });
+                                                    }
+                                                }
+                                                Message::ServerShutdown { payload, .. } => {
+                                                    // The director ended the session; leave without retrying
+                                                    if let Some(sink) = sink_for_handler.write().await.as_mut() {
+                                                        let _ = sink.close().await;
+                                                    }
+                                                    *state.write().await = ConnectionState::Disconnected;
+                                                    event_tx.send(ClientEvent::ServerShuttingDown {
+                                                        reason: payload.reason,
+                                                    });
+                                                    break None;
+                                                }
+                                                Message::ReconnectRequest { .. } => {
+                                                    // Close cleanly; the retry below brings the session back
+                                                    if let Some(sink) = sink_for_handler.write().await.as_mut() {
+                                                        let _ = sink.close().await;
+                                                    }
+                                                    break Some("Server requested reconnect".to_string());
+                                                }
+                                                other => {
+                                                    // Nothing to do, but let embedders see it arrived
+                                                    event_tx.send(ClientEvent::UnhandledMessage {
+                                                        message_type: other.message_type().to_string(),
+                                                    });
+                                                }
                                             }
                                         }
+                                        Err(e) => {
+                                            warn!(error = %e, "failed to parse message");
+                                            event_tx.send(ClientEvent::ProtocolError {
+                                                raw: text,
+                                                reason: e.to_string(),
+                                            });
+                                        }
                                     }
-                                    Err(e) => {
-                                        eprintln!("Failed to parse message: {}", e);
-                                    }
                                 }
+                                Some(Ok(WsMessage::Close(_))) => {
+                                    break Some("Server closed connection".to_string());
+                                }
+                                Some(Err(e)) => {
+                                    break Some(format!("WebSocket error: {}", e));
+                                }
+                                None => {
+                                    break Some("Connection closed".to_string());
+                                }
+                                _ => {}
+                            }
+                        }
+                        _ = ping_timer.tick() => {
+                            // Forget pings that never got an answer
+                            let pending = pending_pings.len();
+                            pending_pings.retain(|_, sent_at| sent_at.elapsed() < CLIENT_PING_INTERVAL * 3);
+                            if pending_pings.len() < pending {
+                                warn!(unanswered = pending - pending_pings.len(), "ping timed out");
                             }
-                            Some(Ok(WsMessage::Close(_))) => {
-                                let _ = event_tx.send(ClientEvent::ConnectionLost {
-                                    reason: "Server closed connection".to_string(),
-                                });
-                                break;
+
+                            let ping = Message::ping();
+                            simulate(simulated_latency).await;
+                            if let Ok(json) = ping.to_json()
+                                && let Some(sink) = sink_for_handler.write().await.as_mut()
+                                && sink.send(WsMessage::Text(json)).await.is_ok()
+                            {
+                                pending_pings.insert(ping.id().to_string(), Instant::now());
                             }
-                            Some(Err(e)) => {
-                                let _ = event_tx.send(ClientEvent::ConnectionLost {
-                                    reason: format!("WebSocket error: {}", e),
-                                });
-                                break;
+                        }
+                        _ = note_timer.tick() => {
+                            for timeout in note_assembler.poll_timeouts(Instant::now()) {
+                                match timeout {
+                                    NoteTimeout::Resend { note_id, missing_seqs } => {
+                                        let resend = Message::presenter_note_resend(note_id, missing_seqs);
+                                        if let Ok(json) = resend.to_json()
+                                            && let Some(sink) = sink_for_handler.write().await.as_mut()
+                                        {
+                                            let _ = sink.send(WsMessage::Text(json)).await;
+                                        }
+                                    }
+                                    NoteTimeout::Abandoned { note_id, missing_seqs } => {
+                                        event_tx.send(ClientEvent::PresenterNoteIncomplete {
+                                            note_id,
+                                            missing_seqs,
+                                        });
+                                    }
+                                }
                             }
-                            None => {
-                                let _ = event_tx.send(ClientEvent::ConnectionLost {
-                                    reason: "Connection closed".to_string(),
-                                });
-                                break;
+                        }
+                        _ = tokio::time::sleep_until(
+                            flash_deadline.map_or_else(tokio::time::Instant::now, tokio::time::Instant::from_std)
+                        ), if flash_deadline.is_some() => {
+                            for target_monitor_ids in flash_coalescer.poll(Instant::now()) {
+                                event_tx.send(ClientEvent::FlashReceived { target_monitor_ids });
                             }
-                            _ => {}
+                        }
+                        _ = tokio::time::sleep_until(stale_deadline.unwrap_or_else(tokio::time::Instant::now)),
+                            if stale_deadline.is_some() && !stale =>
+                        {
+                            stale = true;
+                            warn!("no activity from server");
+                            event_tx.send(ClientEvent::Stale {
+                                idle_ms: last_inbound.elapsed().as_millis() as u64,
+                            });
+                        }
+                        _ = disconnect_rx.recv() => {
+                            break None;
                         }
                     }
-                    _ = disconnect_rx.recv() => {
+                };
+
+                // The session is over; nothing can be sent until a new one opens
+                *sink_for_handler.write().await = None;
+                let Some(reason) = lost_reason else {
+                    break;
+                };
+                warn!(reason = %reason, "connection lost");
+                let Some(policy) = reconnect_policy else {
+                    *state.write().await = ConnectionState::Disconnected;
+                    event_tx.send(ClientEvent::ConnectionLost { reason });
+                    break;
+                };
+
+                // Retry with growing delays until a connection opens or attempts run out
+                let mut reopened = None;
+                for attempt in 1..=policy.max_attempts {
+                    let delay = policy.delay_for(attempt);
+                    *state.write().await = ConnectionState::Reconnecting {
+                        attempt,
+                        max_attempts: policy.max_attempts,
+                        next_delay_ms: delay.as_millis() as u64,
+                    };
+                    tokio::select! {
+                        _ = tokio::time::sleep(delay) => {}
+                        _ = disconnect_rx.recv() => break 'session,
+                    }
+                    info!(attempt, max_attempts = policy.max_attempts, "reconnecting");
+
+                    let hello = Message::client_hello_resuming(
+                        client_name.read().await.clone(),
+                        display_monitor_ids.read().await.clone(),
+                        resume_token.read().await.clone(),
+                    );
+                    if let Ok(new_stream) = open_session(&url, &session_options, &sink_for_handler, &hello).await {
+                        reopened = Some(new_stream);
                         break;
                     }
                 }
-            }
 
-            // Clear sink on disconnect
-            *sink_for_handler.write().await = None;
-        });
+                match reopened {
+                    Some(new_stream) => {
+                        stream = new_stream;
+                        pending_pings.clear();
+                        last_seq = None;
+                        last_inbound = tokio::time::Instant::now();
+                        stale = false;
+                        *state.write().await = ConnectionState::Connecting;
+                    }
+                    None => {
+                        warn!(attempts = policy.max_attempts, "giving up reconnecting");
+                        *state.write().await = ConnectionState::Failed;
+                        event_tx.send(ClientEvent::ConnectionLost { reason });
+                        break;
+                    }
+                }
+            }
+        }.instrument(span)));
 
         Ok(())
     }
 
     /// Disconnect from server
-    pub async fn disconnect(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    pub async fn disconnect(&mut self) -> Result<(), KanpeClientError> {
+        let sink = self.sink.write().await.take();
+
+        // Stop the read loop and wait for it to finish
         if let Some(disconnect_tx) = self.disconnect_tx.take() {
             let _ = disconnect_tx.send(()).await;
         }
+        if let Some(read_task) = self.read_task.take() {
+            let _ = read_task.await;
+        }
 
         // Close WebSocket connection
-        if let Some(sink) = self.sink.write().await.as_mut() {
+        if let Some(mut sink) = sink {
             let _ = sink.send(WsMessage::Close(None)).await;
         }
-
-        *self.sink.write().await = None;
+        // Leaving on purpose ends the session; the next connect starts a new one
+        *self.resume_token.write().await = None;
+        *self.state.write().await = ConnectionState::Disconnected;
+        info!("disconnected");
 
         Ok(())
     }
@@ -214,60 +734,1082 @@ impl KanpeClient {
     pub async fn send_message(
         &self,
         message: &Message,
-    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ) -> Result<(), KanpeClientError> {
         self.send_internal(message).await
     }
 
+    /// Send feedback to the server, returning the feedback message's ID
+    pub async fn send_feedback(
+        &self,
+        content: String,
+        client_name: String,
+        reply_to_message_id: String,
+        feedback_type: FeedbackType,
+    ) -> Result<String, KanpeClientError> {
+        let message = Message::feedback_message(content, client_name, reply_to_message_id, feedback_type);
+        self.send_internal(&message).await?;
+        Ok(message.id().to_string())
+    }
+
+    /// Send a one-tap reaction to a cue, returning the reaction message's ID
+    pub async fn send_reaction(
+        &self,
+        reply_to_message_id: String,
+        reaction: String,
+        client_name: String,
+    ) -> Result<String, KanpeClientError> {
+        let message = Message::reaction(reply_to_message_id, reaction, client_name);
+        self.send_internal(&message).await?;
+        Ok(message.id().to_string())
+    }
+
+    /// Tell the server the operator has seen a cue, stopping any repeated urgent flash
+    pub async fn acknowledge_message(&self, message_id: String) -> Result<(), KanpeClientError> {
+        self.send_internal(&Message::message_ack(message_id)).await
+    }
+
+    /// Tell the server whether the operator is composing feedback
+    pub async fn send_typing(&self, is_typing: bool) -> Result<(), KanpeClientError> {
+        let client_name = self.client_name.read().await.clone();
+        self.send_internal(&Message::feedback_typing(client_name, is_typing)).await
+    }
+
+    /// Change which monitors this client displays without reconnecting
+    pub async fn update_subscription(
+        &self,
+        display_monitor_ids: Vec<String>,
+    ) -> Result<(), KanpeClientError> {
+        self.send_internal(&Message::update_subscription(display_monitor_ids.clone())).await?;
+        // Reconnects re-announce the current subscription
+        *self.display_monitor_ids.write().await = display_monitor_ids;
+        Ok(())
+    }
+
     /// Internal method to send a message
     async fn send_internal(
         &self,
         message: &Message,
-    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let json = serde_json::to_string(message)?;
+    ) -> Result<(), KanpeClientError> {
+        if let Some(limiter) = &self.rate_limiter
+            && !limiter.try_acquire(Instant::now())
+        {
+            return Err(KanpeClientError::RateLimited);
+        }
+
+        let json = message.to_json()?;
         let ws_message = WsMessage::Text(json);
+        simulate(self.simulated_latency).await;
 
         if let Some(sink) = self.sink.write().await.as_mut() {
             sink.send(ws_message).await?;
             Ok(())
         } else {
-            Err("Not connected".into())
+            Err(KanpeClientError::NotConnected)
         }
     }
 
-    /// Check if connected
+    /// Check if connected and welcomed by the server
     pub async fn is_connected(&self) -> bool {
-        self.sink.read().await.is_some()
+        *self.state.read().await == ConnectionState::Connected
+    }
+
+    /// Get where the client is in its connect/reconnect lifecycle
+    pub async fn connection_state(&self) -> ConnectionState {
+        self.state.read().await.clone()
     }
 
     /// Get the client name
+    pub async fn client_name(&self) -> Option<String> {
+        let name = self.client_name.read().await;
+        if name.is_empty() {
+            None
+        } else {
+            Some(name.clone())
+        }
+    }
+
+    /// Get the latest received message with its ID
+    pub async fn latest_message(&self) -> Option<(String, KanpeMessagePayload)> {
+        self.latest_message.read().await.clone()
+    }
+
+    /// Get recently received messages with their IDs, oldest first
+    pub async fn message_history(&self) -> Vec<(String, KanpeMessagePayload)> {
+        self.message_history.read().await.iter().cloned().collect()
+    }
+
+    /// Get the message `index` places back from the latest (0 = latest)
+    pub async fn message_at(&self, index: usize) -> Option<(String, KanpeMessagePayload)> {
+        self.message_history.read().await.iter().rev().nth(index).cloned()
+    }
+
+    /// When anything was last received from the server, in milliseconds since the epoch
+    pub async fn last_activity(&self) -> Option<i64> {
+        *self.last_activity.read().await
+    }
+
+    /// Get the most recently measured ping round-trip time
+    pub async fn latency(&self) -> Option<Duration> {
+        *self.latency.read().await
+    }
+
+    /// Get the list of monitors
+    pub async fn monitors(&self) -> Vec<VirtualMonitor> {
+        self.monitors.read().await.clone()
+    }
+
+    /// Get the client name
+    ///
+    /// Panics on a current-thread runtime; use [`KanpeClient::client_name`] instead.
+    #[deprecated(note = "use the async `client_name` instead")]
     pub fn get_client_name(&self) -> Option<String> {
         tokio::task::block_in_place(|| {
-            tokio::runtime::Handle::current().block_on(async {
-                let name = self.client_name.read().await;
-                if name.is_empty() {
-                    None
-                } else {
-                    Some(name.clone())
-                }
-            })
+            tokio::runtime::Handle::current().block_on(self.client_name())
         })
     }
 
     /// Get the latest received message with its ID
+    ///
+    /// Panics on a current-thread runtime; use [`KanpeClient::latest_message`] instead.
+    #[deprecated(note = "use the async `latest_message` instead")]
     pub fn get_latest_message(&self) -> Option<(String, KanpeMessagePayload)> {
         tokio::task::block_in_place(|| {
-            tokio::runtime::Handle::current().block_on(async {
-                self.latest_message.read().await.clone()
-            })
+            tokio::runtime::Handle::current().block_on(self.latest_message())
         })
     }
 
     /// Get the list of monitors
+    ///
+    /// Panics on a current-thread runtime; use [`KanpeClient::monitors`] instead.
+    #[deprecated(note = "use the async `monitors` instead")]
     pub fn get_monitors(&self) -> Vec<VirtualMonitor> {
         tokio::task::block_in_place(|| {
-            tokio::runtime::Handle::current().block_on(async {
-                self.monitors.read().await.clone()
+            tokio::runtime::Handle::current().block_on(self.monitors())
+        })
+    }
+}
+
+/// Open a WebSocket to `url` and introduce the client with `hello`
+///
+/// The sink is only stored once the hello is sent, so nothing can overtake it.
+async fn open_session(
+    url: &str,
+    options: &SessionOptions,
+    sink_slot: &RwLock<Option<WsSink>>,
+    hello: &Message,
+) -> Result<WsStream, KanpeClientError> {
+    let request = upgrade_request(url, options)?;
+    let json = hello.to_json()?;
+
+    let handshake = async {
+        let transport: Box<dyn Transport> = match &options.connector {
+            Some(connect) => connect().await.map_err(tokio_tungstenite::tungstenite::Error::Io)?,
+            None => Box::new(connect_tcp(&request).await?),
+        };
+        let (ws_stream, _) = client_async(request, transport).await?;
+        let (mut sink, stream) = ws_stream.split();
+        simulate(options.simulated_latency).await;
+        sink.send(WsMessage::Text(json)).await?;
+        Ok::<_, KanpeClientError>((sink, stream))
+    };
+    let connect_timeout = options.connect_timeout;
+    let (sink, stream) = tokio::time::timeout(connect_timeout, handshake)
+        .await
+        .map_err(|_| KanpeClientError::Timeout(connect_timeout))??;
+    *sink_slot.write().await = Some(sink);
+    Ok(stream)
+}
+
+/// Build the upgrade request for `url` with the auth and extra headers
+// Same error type as the rest of the client, whose WebSocket variant is large
+#[allow(clippy::result_large_err)]
+fn upgrade_request(url: &str, options: &SessionOptions) -> Result<Request, KanpeClientError> {
+    let mut request = url.into_client_request()?;
+    request
+        .headers_mut()
+        .insert(SEC_WEBSOCKET_PROTOCOL, HeaderValue::from_static(WEBSOCKET_SUBPROTOCOL));
+    if let Some(token) = &options.auth_token {
+        request
+            .headers_mut()
+            .insert(AUTHORIZATION, format!("Bearer {}", token).parse()?);
+    }
+    for (name, value) in &options.extra_headers {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| KanpeClientError::InvalidHeader(name.clone()))?;
+        let value = HeaderValue::from_str(value).map_err(|_| KanpeClientError::InvalidHeader(name.to_string()))?;
+        request.headers_mut().append(name, value);
+    }
+    Ok(request)
+}
+
+/// Build the WebSocket URL for a server address
+///
+/// A bare `host:port` gets `ws://` and the default `/ws` endpoint. An address
+/// that already names a path (e.g. `ws://proxy/kanpe/ws` behind a reverse
+/// proxy) is used as given, minus any trailing slash; an empty or `/` path is
+/// replaced with `/ws`. Any query string is kept after the path.
+fn endpoint_url(server_address: &str) -> String {
+    let (scheme, rest) = match server_address.split_once("://") {
+        Some((scheme, rest)) if scheme == "ws" || scheme == "wss" => (scheme, rest),
+        _ => ("ws", server_address),
+    };
+    let (location, query) = match rest.split_once('?') {
+        Some((location, query)) => (location, Some(query)),
+        None => (rest, None),
+    };
+    let (host, path) = match location.find('/') {
+        Some(slash) => location.split_at(slash),
+        None => (location, ""),
+    };
+    let path = match path.trim_end_matches('/') {
+        "" => "/ws",
+        path => path,
+    };
+    match query {
+        Some(query) => format!("{}://{}{}?{}", scheme, host, path, query),
+        None => format!("{}://{}{}", scheme, host, path),
+    }
+}
+
+/// Open a TCP connection to the request's host and port
+async fn connect_tcp(request: &Request) -> Result<TcpStream, tokio_tungstenite::tungstenite::Error> {
+    let uri = request.uri();
+    if uri.scheme_str() == Some("wss") {
+        return Err(UrlError::TlsFeatureNotEnabled.into());
+    }
+    let host = uri.host().ok_or(UrlError::NoHostName)?;
+    // IPv6 hosts come bracketed in URLs but not in socket addresses
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    Ok(TcpStream::connect((host, uri.port_u16().unwrap_or(80))).await?)
+}
+
+/// Confirm to the server that a monitor list change was applied
+async fn send_monitor_ack(sink: &Arc<RwLock<Option<WsSink>>>, change_id: String) {
+    let ack = Message::monitor_change_ack(change_id);
+    if let Ok(json) = ack.to_json()
+        && let Some(sink) = sink.write().await.as_mut()
+    {
+        let _ = sink.send(WsMessage::Text(json)).await;
+    }
+}
+
+/// Report the monitor list as it stands after a change
+async fn send_monitors_changed(event_tx: &EventSender, monitors: &RwLock<Vec<VirtualMonitor>>) {
+    let monitors = monitors.read().await.clone();
+    event_tx.send(ClientEvent::MonitorsChanged { monitors });
+}
+
+/// Resolve an inverted command's exclusion list to the known monitors it addresses
+async fn monitors_except(monitors: &RwLock<Vec<VirtualMonitor>>, excluded: &[String]) -> Vec<String> {
+    if excluded.iter().any(|id| id == ALL_MONITORS) {
+        return Vec::new();
+    }
+    monitors
+        .read()
+        .await
+        .iter()
+        .filter(|monitor| !excluded.contains(&monitor.id))
+        .map(|monitor| monitor.id.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kanpe_core::types::Priority;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_bare_host_gets_default_endpoint() {
+        assert_eq!(endpoint_url("192.168.1.10:9876"), "ws://192.168.1.10:9876/ws");
+        assert_eq!(endpoint_url("ws://director.local:9876/"), "ws://director.local:9876/ws");
+        assert_eq!(endpoint_url("director.local:9876?token=abc"), "ws://director.local:9876/ws?token=abc");
+    }
+
+    #[test]
+    fn test_custom_path_is_kept() {
+        assert_eq!(endpoint_url("wss://proxy.example/kanpe/ws"), "wss://proxy.example/kanpe/ws");
+        assert_eq!(endpoint_url("ws://proxy.example/kanpe/ws/"), "ws://proxy.example/kanpe/ws");
+        assert_eq!(
+            endpoint_url("ws://proxy.example/kanpe/ws?room=2"),
+            "ws://proxy.example/kanpe/ws?room=2"
+        );
+        assert_eq!(endpoint_url("proxy.example:8080/kanpe/ws"), "ws://proxy.example:8080/kanpe/ws");
+    }
+
+    #[test]
+    fn test_explicit_ws_path_is_not_doubled() {
+        assert_eq!(endpoint_url("localhost:9876/ws"), "ws://localhost:9876/ws");
+        assert_eq!(endpoint_url("ws://localhost:9876/ws"), "ws://localhost:9876/ws");
+        assert_eq!(endpoint_url("ws://[::1]:9876/ws"), "ws://[::1]:9876/ws");
+    }
+
+    /// Poll until the client reaches `expected`, giving up after two seconds
+    async fn wait_for_state(client: &KanpeClient, expected: ConnectionState) {
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while client.connection_state().await != expected && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        assert_eq!(client.connection_state().await, expected);
+    }
+
+    /// Start a WebSocket server that welcomes each client and counts opened and closed connections
+    async fn start_mock_server() -> (String, Arc<AtomicUsize>, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let opened = Arc::new(AtomicUsize::new(0));
+        let closed = Arc::new(AtomicUsize::new(0));
+
+        let (opened_counter, closed_counter) = (opened.clone(), closed.clone());
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let (opened, closed) = (opened_counter.clone(), closed_counter.clone());
+                tokio::spawn(async move {
+                    let mut ws = crate::test_support::accept(stream).await;
+                    opened.fetch_add(1, Ordering::SeqCst);
+                    while let Some(Ok(frame)) = ws.next().await {
+                        if frame.is_close() {
+                            break;
+                        }
+                        if frame.is_text() {
+                            let welcome = Message::server_welcome("mock".to_string(), "c-1".to_string());
+                            let _ = ws.send(WsMessage::Text(serde_json::to_string(&welcome).unwrap())).await;
+                        }
+                    }
+                    closed.fetch_add(1, Ordering::SeqCst);
+                });
+            }
+        });
+
+        (addr, opened, closed)
+    }
+
+    /// Start a WebSocket server that sends `messages` once the client's hello arrives
+    async fn start_scripted_server(messages: Vec<Message>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = crate::test_support::accept(stream).await;
+            let _hello = ws.next().await;
+            for message in messages {
+                let json = serde_json::to_string(&message).unwrap();
+                ws.send(WsMessage::Text(json)).await.unwrap();
+            }
+            while let Some(Ok(frame)) = ws.next().await {
+                if frame.is_close() {
+                    break;
+                }
+            }
+        });
+
+        addr
+    }
+
+    /// Start a WebSocket server that forwards every message it receives
+    async fn start_recording_server() -> (String, mpsc::UnboundedReceiver<Message>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let (received_tx, received_rx) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = crate::test_support::accept(stream).await;
+            while let Some(Ok(WsMessage::Text(text))) = ws.next().await {
+                if let Ok(message) = serde_json::from_str::<Message>(&text) {
+                    let _ = received_tx.send(message);
+                }
+            }
+        });
+
+        (addr, received_rx)
+    }
+
+    #[tokio::test]
+    async fn test_connect_while_connected_is_rejected_by_default() {
+        let (addr, opened, _closed) = start_mock_server().await;
+        let (event_tx, _event_rx) = mpsc::unbounded_channel();
+        let mut client = KanpeClient::new(event_tx);
+
+        client.connect(&addr, "caster".to_string(), vec![]).await.unwrap();
+        let second = client.connect(&addr, "caster".to_string(), vec![]).await;
+
+        assert!(matches!(second, Err(KanpeClientError::AlreadyConnected)));
+        wait_for_state(&client, ConnectionState::Connected).await;
+        assert!(client.is_connected().await);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(opened.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_connect_with_replace_policy_closes_old_session() {
+        let (addr, opened, closed) = start_mock_server().await;
+        let (event_tx, _event_rx) = mpsc::unbounded_channel();
+        let mut client = KanpeClient::new(event_tx);
+        client.set_connect_policy(ConnectPolicy::ReplaceExisting);
+
+        client.connect(&addr, "caster".to_string(), vec![]).await.unwrap();
+        client.connect(&addr, "caster".to_string(), vec![]).await.unwrap();
+
+        wait_for_state(&client, ConnectionState::Connected).await;
+        assert!(client.is_connected().await);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(opened.load(Ordering::SeqCst), 2);
+        assert_eq!(closed.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_async_accessors_on_current_thread_runtime() {
+        let (addr, _opened, _closed) = start_mock_server().await;
+        let (event_tx, _event_rx) = mpsc::unbounded_channel();
+        let mut client = KanpeClient::new(event_tx);
+
+        assert_eq!(client.client_name().await, None);
+
+        client.connect(&addr, "caster".to_string(), vec![]).await.unwrap();
+
+        assert_eq!(client.client_name().await.as_deref(), Some("caster"));
+        assert!(client.latest_message().await.is_none());
+        assert!(client.monitors().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rapid_flashes_are_coalesced() {
+        let addr = start_scripted_server(vec![
+            Message::flash_command(vec!["A".to_string()]),
+            Message::flash_command(vec!["A".to_string(), "B".to_string()]),
+            Message::flash_command(vec!["A".to_string()]),
+        ])
+        .await;
+        let (event_tx, mut event_rx) = mpsc::unbounded_channel();
+        let mut client = KanpeClient::new(event_tx);
+
+        client.connect(&addr, "caster".to_string(), vec![]).await.unwrap();
+        tokio::time::sleep(FLASH_COALESCE_WINDOW * 2).await;
+
+        let mut flashes = Vec::new();
+        while let Ok(event) = event_rx.try_recv() {
+            if let ClientEvent::FlashReceived { target_monitor_ids } = event {
+                flashes.push(target_monitor_ids);
+            }
+        }
+        assert_eq!(flashes, vec![vec!["A".to_string(), "B".to_string()]]);
+    }
+
+    #[tokio::test]
+    async fn test_events_can_be_read_as_a_stream() {
+        use futures_util::StreamExt;
+
+        let addr = start_scripted_server(vec![
+            Message::server_welcome("Director".to_string(), "c-1".to_string()),
+            Message::clear_command(vec!["B".to_string()]),
+        ])
+        .await;
+        let (mut client, events) = KanpeClient::with_event_stream();
+        client.connect(&addr, "caster".to_string(), vec![]).await.unwrap();
+
+        let interesting = events.filter_map(|event| async move {
+            match event {
+                ClientEvent::ServerWelcomeReceived { server_name } => Some(server_name),
+                ClientEvent::ClearReceived { target_monitor_ids } => Some(target_monitor_ids.join(",")),
+                _ => None,
+            }
+        });
+        let received: Vec<String> = tokio::time::timeout(Duration::from_secs(2), interesting.take(2).collect())
+            .await
+            .unwrap();
+
+        assert_eq!(received, vec!["Director", "B"]);
+    }
+
+    #[tokio::test]
+    async fn test_every_subscriber_receives_connection_established() {
+        let addr = start_scripted_server(vec![Message::server_welcome("Director".to_string(), "c-1".to_string())]).await;
+        let (event_tx, _event_rx) = mpsc::unbounded_channel();
+        let mut client = KanpeClient::new(event_tx);
+        let mut ui = client.subscribe();
+        let mut logger = client.subscribe();
+
+        client.connect(&addr, "caster".to_string(), vec![]).await.unwrap();
+
+        for subscriber in [&mut ui, &mut logger] {
+            let established = tokio::time::timeout(Duration::from_secs(2), async {
+                loop {
+                    if let ClientEvent::ConnectionEstablished { server_address } = subscriber.recv().await.unwrap() {
+                        return server_address;
+                    }
+                }
             })
+            .await
+            .unwrap();
+            assert_eq!(established, addr);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_message_history_keeps_latest_entries_in_order() {
+        let messages: Vec<Message> = (0..25)
+            .map(|i| Message::kanpe_message(format!("cue {}", i), vec!["ALL".to_string()], Priority::Normal))
+            .collect();
+        let addr = start_scripted_server(messages).await;
+        let (event_tx, _event_rx) = mpsc::unbounded_channel();
+        let mut client = KanpeClient::new(event_tx);
+
+        client.connect(&addr, "caster".to_string(), vec![]).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let contents: Vec<String> = client
+            .message_history()
+            .await
+            .into_iter()
+            .map(|(_, payload)| payload.content)
+            .collect();
+        let expected: Vec<String> = (5..25).map(|i| format!("cue {}", i)).collect();
+        assert_eq!(contents, expected);
+        assert_eq!(client.message_at(0).await.unwrap().1.content, "cue 24");
+        assert_eq!(client.message_at(19).await.unwrap().1.content, "cue 5");
+        assert!(client.message_at(20).await.is_none());
+    }
+
+    /// Start a WebSocket server that answers each ping after `pong_delay`
+    async fn start_pong_server(pong_delay: Duration) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = crate::test_support::accept(stream).await;
+            while let Some(Ok(WsMessage::Text(text))) = ws.next().await {
+                if let Ok(Message::Ping { id, .. }) = serde_json::from_str(&text) {
+                    tokio::time::sleep(pong_delay).await;
+                    let pong = serde_json::to_string(&Message::pong_for(id)).unwrap();
+                    ws.send(WsMessage::Text(pong)).await.unwrap();
+                }
+            }
+        });
+        addr
+    }
+
+    /// Wait for the next `LatencyMeasured` event
+    async fn next_latency(event_rx: &mut mpsc::UnboundedReceiver<ClientEvent>) -> u64 {
+        tokio::time::timeout(Duration::from_secs(2), async {
+            loop {
+                if let Some(ClientEvent::LatencyMeasured { millis }) = event_rx.recv().await {
+                    return millis;
+                }
+            }
         })
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_latency_is_measured_from_delayed_pong() {
+        let addr = start_pong_server(Duration::from_millis(150)).await;
+        let (event_tx, mut event_rx) = mpsc::unbounded_channel();
+        let mut client = KanpeClient::new(event_tx);
+        client.connect(&addr, "caster".to_string(), vec![]).await.unwrap();
+
+        let millis = next_latency(&mut event_rx).await;
+
+        assert!((150..400).contains(&millis), "measured {}ms", millis);
+        assert_eq!(client.latency().await.map(|latency| latency.as_millis() as u64), Some(millis));
+    }
+
+    #[tokio::test]
+    async fn test_silent_server_is_reported_stale() {
+        let welcome = Message::server_welcome("mock".to_string(), "c-1".to_string());
+        let addr = start_scripted_server(vec![welcome]).await;
+        let (event_tx, mut event_rx) = mpsc::unbounded_channel();
+        let mut client = KanpeClient::new(event_tx);
+        client.set_stale_after(Some(Duration::from_millis(200)));
+        client.connect(&addr, "caster".to_string(), vec![]).await.unwrap();
+
+        let idle_ms = tokio::time::timeout(Duration::from_secs(2), async {
+            loop {
+                if let Some(ClientEvent::Stale { idle_ms }) = event_rx.recv().await {
+                    return idle_ms;
+                }
+            }
+        })
+        .await
+        .unwrap();
+
+        assert!(idle_ms >= 200, "stale after {}ms", idle_ms);
+        assert!(client.last_activity().await.is_some());
+        assert!(client.is_connected().await);
+    }
+
+    #[tokio::test]
+    async fn test_simulated_latency_delays_round_trip() {
+        let addr = start_pong_server(Duration::ZERO).await;
+        let (event_tx, mut event_rx) = mpsc::unbounded_channel();
+        let mut client = KanpeClient::new(event_tx);
+        client.set_simulated_latency(100, 20);
+        client.connect(&addr, "caster".to_string(), vec![]).await.unwrap();
+
+        let millis = next_latency(&mut event_rx).await;
+
+        assert!(millis >= 100, "measured {}ms", millis);
+    }
+
+    #[tokio::test]
+    async fn test_repeated_cue_is_delivered_once() {
+        let cue = Message::kanpe_message("Go".to_string(), vec!["ALL".to_string()], Priority::Normal);
+        let addr = start_scripted_server(vec![cue.clone(), cue.clone()]).await;
+        let (event_tx, mut event_rx) = mpsc::unbounded_channel();
+        let mut client = KanpeClient::new(event_tx);
+
+        client.connect(&addr, "caster".to_string(), vec![]).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let mut received = 0;
+        while let Ok(event) = event_rx.try_recv() {
+            if let ClientEvent::MessageReceived { .. } = event {
+                received += 1;
+            }
+        }
+        assert_eq!(received, 1);
+        assert_eq!(client.message_history().await.len(), 1);
+
+        // With de-duplication off, both deliveries come through
+        let addr = start_scripted_server(vec![cue.clone(), cue]).await;
+        let (event_tx, mut event_rx) = mpsc::unbounded_channel();
+        let mut client = KanpeClient::new(event_tx);
+        client.set_dedup_capacity(0);
+
+        client.connect(&addr, "caster".to_string(), vec![]).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let mut received = 0;
+        while let Ok(event) = event_rx.try_recv() {
+            if let ClientEvent::MessageReceived { .. } = event {
+                received += 1;
+            }
+        }
+        assert_eq!(received, 2);
+    }
+
+    #[tokio::test]
+    async fn test_monitors_changed_reflects_net_list() {
+        let monitor = |id: &str| -> VirtualMonitor {
+            serde_json::from_value(serde_json::json!({ "id": id, "name": format!("Monitor {}", id) })).unwrap()
+        };
+        let addr = start_scripted_server(vec![
+            Message::monitor_list_sync(vec![monitor("A"), monitor("B")]),
+            Message::monitor_added(monitor("C")),
+            Message::monitor_removed("A".to_string()),
+        ])
+        .await;
+        let (event_tx, mut event_rx) = mpsc::unbounded_channel();
+        let mut client = KanpeClient::new(event_tx);
+        client.set_granular_monitor_events(false);
+
+        client.connect(&addr, "caster".to_string(), vec![]).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let mut lists = Vec::new();
+        while let Ok(event) = event_rx.try_recv() {
+            match event {
+                ClientEvent::MonitorsChanged { monitors } => {
+                    lists.push(monitors.into_iter().map(|m| m.id).collect::<Vec<_>>());
+                }
+                ClientEvent::MonitorAdded { .. } | ClientEvent::MonitorRemoved { .. } => {
+                    panic!("granular monitor events were turned off")
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(lists, vec![vec!["A", "B"], vec!["A", "B", "C"], vec!["B", "C"]]);
+    }
+
+    #[tokio::test]
+    async fn test_expired_cue_is_ignored_and_valid_cue_delivered() {
+        let targets = vec!["ALL".to_string()];
+        let addr = start_scripted_server(vec![
+            Message::kanpe_message_with_ttl("stale".to_string(), targets.clone(), Priority::Normal, -1000),
+            Message::kanpe_message_with_ttl("fresh".to_string(), targets, Priority::Normal, 60_000),
+        ])
+        .await;
+        let (event_tx, mut event_rx) = mpsc::unbounded_channel();
+        let mut client = KanpeClient::new(event_tx);
+
+        client.connect(&addr, "caster".to_string(), vec![]).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let mut received = Vec::new();
+        while let Ok(event) = event_rx.try_recv() {
+            if let ClientEvent::MessageReceived { message: Message::KanpeMessage { payload, .. } } = event {
+                received.push(payload.content);
+            }
+        }
+        assert_eq!(received, vec!["fresh".to_string()]);
+        assert_eq!(client.latest_message().await.unwrap().1.content, "fresh");
+        assert_eq!(client.message_history().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_rejects_sends_over_budget() {
+        let (addr, _received_rx) = start_recording_server().await;
+        let (event_tx, _event_rx) = mpsc::unbounded_channel();
+        let mut client = KanpeClient::new(event_tx);
+        client.set_rate_limit(Some(3));
+        client.connect(&addr, "caster".to_string(), vec![]).await.unwrap();
+
+        let mut sent = 0;
+        let mut limited = 0;
+        for _ in 0..10 {
+            match client
+                .send_feedback("了解".to_string(), "caster".to_string(), String::new(), FeedbackType::Ack)
+                .await
+            {
+                Ok(_) => sent += 1,
+                Err(KanpeClientError::RateLimited) => limited += 1,
+                Err(e) => panic!("unexpected error: {}", e),
+            }
+        }
+        // The burst budget goes through; the rest is refused, allowing for a refill on a slow run
+        assert!(sent >= 3, "sent {}", sent);
+        assert!(limited >= 6, "limited {}", limited);
+    }
+
+    #[tokio::test]
+    async fn test_send_feedback_returns_id_seen_by_server() {
+        let (addr, mut received_rx) = start_recording_server().await;
+        let (event_tx, _event_rx) = mpsc::unbounded_channel();
+        let mut client = KanpeClient::new(event_tx);
+        client.connect(&addr, "caster".to_string(), vec![]).await.unwrap();
+
+        let feedback_id = client
+            .send_feedback("了解".to_string(), "caster".to_string(), String::new(), FeedbackType::Ack)
+            .await
+            .unwrap();
+
+        let received_id = tokio::time::timeout(Duration::from_secs(2), async {
+            loop {
+                if let Some(Message::FeedbackMessage { id, .. }) = received_rx.recv().await {
+                    return id;
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(received_id, feedback_id);
+    }
+
+    #[tokio::test]
+    async fn test_connect_to_unresponsive_server_times_out() {
+        // The OS completes the TCP handshake into the backlog, but no WebSocket handshake ever follows
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let (event_tx, mut event_rx) = mpsc::unbounded_channel();
+        let mut client = KanpeClient::new(event_tx);
+        client.set_connect_timeout(Duration::from_millis(200));
+
+        let started = Instant::now();
+        let result = client.connect(&addr, "caster".to_string(), vec![]).await;
+
+        assert!(matches!(result, Err(KanpeClientError::Timeout(timeout)) if timeout == Duration::from_millis(200)));
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(client.connection_state().await, ConnectionState::Disconnected);
+        assert!(event_rx.try_recv().is_err());
+        drop(listener);
+    }
+
+    #[tokio::test]
+    async fn test_extra_headers_are_sent_on_upgrade() {
+        use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+        use tokio_tungstenite::tungstenite::http::StatusCode;
+
+        // Stand-in for a proxy that only upgrades requests carrying its routing header
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    // The callback signature is fixed by tungstenite
+                    #[allow(clippy::result_large_err)]
+                    let check = |request: &Request, response: Response| -> Result<Response, ErrorResponse> {
+                        if request.headers().get("x-kanpe-route").is_some_and(|v| v == "stage-1") {
+                            crate::test_support::echo_subprotocol(request, response)
+                        } else {
+                            let mut rejection = ErrorResponse::new(None);
+                            *rejection.status_mut() = StatusCode::FORBIDDEN;
+                            Err(rejection)
+                        }
+                    };
+                    if let Ok(mut ws) = tokio_tungstenite::accept_hdr_async(stream, check).await {
+                        while let Some(Ok(_)) = ws.next().await {}
+                    }
+                });
+            }
+        });
+
+        let (event_tx, _event_rx) = mpsc::unbounded_channel();
+        let mut client = KanpeClient::new(event_tx);
+        let rejected = client.connect(&addr, "caster".to_string(), vec![]).await;
+        assert!(matches!(rejected, Err(KanpeClientError::WebSocket(_))));
+
+        client.set_extra_headers(vec![("X-Kanpe-Route".to_string(), "stage-1".to_string())]);
+        client.connect(&addr, "caster".to_string(), vec![]).await.unwrap();
+
+        client.set_extra_headers(vec![("bad header".to_string(), "x".to_string())]);
+        client.set_connect_policy(ConnectPolicy::ReplaceExisting);
+        let invalid = client.connect(&addr, "caster".to_string(), vec![]).await;
+        assert!(matches!(invalid, Err(KanpeClientError::InvalidHeader(name)) if name == "bad header"));
+        // The bad header is caught before the existing session is torn down
+        assert!(client.sink.read().await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_state_is_connecting_until_server_welcome() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let (welcome_tx, welcome_rx) = tokio::sync::oneshot::channel::<()>();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = crate::test_support::accept(stream).await;
+            let _hello = ws.next().await;
+            let _ = welcome_rx.await;
+            let welcome = Message::server_welcome("mock".to_string(), "c-1".to_string());
+            ws.send(WsMessage::Text(serde_json::to_string(&welcome).unwrap())).await.unwrap();
+            while let Some(Ok(_)) = ws.next().await {}
+        });
+
+        let (event_tx, _event_rx) = mpsc::unbounded_channel();
+        let mut client = KanpeClient::new(event_tx);
+        client.connect(&addr, "caster".to_string(), vec![]).await.unwrap();
+
+        assert_eq!(client.connection_state().await, ConnectionState::Connecting);
+        assert!(!client.is_connected().await);
+
+        welcome_tx.send(()).unwrap();
+        wait_for_state(&client, ConnectionState::Connected).await;
+        assert!(client.is_connected().await);
+    }
+
+    #[tokio::test]
+    async fn test_send_reaction_reaches_server_intact() {
+        let (addr, mut received_rx) = start_recording_server().await;
+        let (event_tx, _event_rx) = mpsc::unbounded_channel();
+        let mut client = KanpeClient::new(event_tx);
+        client.connect(&addr, "caster".to_string(), vec![]).await.unwrap();
+
+        let reaction_id = client
+            .send_reaction("cue-1".to_string(), "👍".to_string(), "caster".to_string())
+            .await
+            .unwrap();
+
+        let (id, payload) = tokio::time::timeout(Duration::from_secs(2), async {
+            loop {
+                if let Some(Message::Reaction { id, payload, .. }) = received_rx.recv().await {
+                    return (id, payload);
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(id, reaction_id);
+        assert_eq!(payload.reply_to_message_id, "cue-1");
+        assert_eq!(payload.reaction, "👍");
+        assert_eq!(payload.client_name, "caster");
+    }
+
+    #[tokio::test]
+    async fn test_skipped_sequence_number_reports_gap() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = crate::test_support::accept(stream).await;
+            let _hello = ws.next().await;
+            // Frame 3 is lost on the way
+            for seq in [1, 2, 4] {
+                let json = Message::flash_command(vec!["A".to_string()]).to_json_with_seq(seq).unwrap();
+                ws.send(WsMessage::Text(json)).await.unwrap();
+            }
+            while let Some(Ok(_)) = ws.next().await {}
+        });
+
+        let (event_tx, mut event_rx) = mpsc::unbounded_channel();
+        let mut client = KanpeClient::new(event_tx);
+        client.connect(&addr, "caster".to_string(), vec![]).await.unwrap();
+
+        let gap = tokio::time::timeout(Duration::from_secs(2), async {
+            loop {
+                if let Some(ClientEvent::SequenceGap { expected, got }) = event_rx.recv().await {
+                    return (expected, got);
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(gap, (3, 4));
+    }
+
+    #[tokio::test]
+    async fn test_connection_state_through_forced_reconnect() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            // Drop the first session right after its hello; welcome the second late and drop it (with the listener) later still
+            for welcome_after in [None, Some(Duration::from_millis(50))] {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut ws = crate::test_support::accept(stream).await;
+                let _hello = ws.next().await;
+                if let Some(delay) = welcome_after {
+                    tokio::time::sleep(delay).await;
+                    let welcome = Message::server_welcome("mock".to_string(), "c-1".to_string());
+                    ws.send(WsMessage::Text(serde_json::to_string(&welcome).unwrap())).await.unwrap();
+                    tokio::time::sleep(Duration::from_millis(200)).await;
+                }
+            }
+        });
+
+        let (event_tx, mut event_rx) = mpsc::unbounded_channel();
+        let mut client = KanpeClient::new(event_tx);
+        client.set_reconnect_policy(Some(ReconnectPolicy {
+            max_attempts: 2,
+            initial_delay: Duration::from_millis(50),
+            max_delay: Duration::from_millis(100),
+        }));
+        assert_eq!(client.connection_state().await, ConnectionState::Disconnected);
+        client.connect(&addr, "caster".to_string(), vec![]).await.unwrap();
+
+        let mut states = vec![client.connection_state().await];
+        let deadline = Instant::now() + Duration::from_secs(3);
+        while states.last() != Some(&ConnectionState::Failed) && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            let state = client.connection_state().await;
+            if states.last() != Some(&state) {
+                states.push(state);
+            }
+        }
+
+        let reconnecting = |attempt, next_delay_ms| ConnectionState::Reconnecting {
+            attempt,
+            max_attempts: 2,
+            next_delay_ms,
+        };
+        assert_eq!(
+            states,
+            vec![
+                ConnectionState::Connecting,
+                reconnecting(1, 50),
+                ConnectionState::Connecting,
+                ConnectionState::Connected,
+                reconnecting(1, 50),
+                reconnecting(2, 100),
+                ConnectionState::Failed,
+            ]
+        );
+        // Only giving up is reported as a lost connection
+        let mut lost = 0;
+        while let Ok(event) = event_rx.try_recv() {
+            if matches!(event, ClientEvent::ConnectionLost { .. }) {
+                lost += 1;
+            }
+        }
+        assert_eq!(lost, 1);
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_request_reopens_session() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let (hello_tx, mut hello_rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            // Ask the first session to reconnect, then keep the second one open
+            for session in 0.. {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut ws = crate::test_support::accept(stream).await;
+                if let Some(Ok(WsMessage::Text(text))) = ws.next().await {
+                    let _ = hello_tx.send(serde_json::from_str::<Message>(&text).unwrap());
+                }
+                let reply = if session == 0 {
+                    Message::reconnect_request()
+                } else {
+                    Message::server_welcome("mock".to_string(), "c-1".to_string())
+                };
+                ws.send(WsMessage::Text(serde_json::to_string(&reply).unwrap())).await.unwrap();
+                tokio::spawn(async move { while let Some(Ok(_)) = ws.next().await {} });
+            }
+        });
+
+        let (event_tx, mut event_rx) = mpsc::unbounded_channel();
+        let mut client = KanpeClient::new(event_tx);
+        client.set_reconnect_policy(Some(ReconnectPolicy {
+            max_attempts: 3,
+            initial_delay: Duration::from_millis(20),
+            max_delay: Duration::from_millis(100),
+        }));
+        client.connect(&addr, "caster".to_string(), vec!["A".to_string()]).await.unwrap();
+
+        for _ in 0..2 {
+            let hello = tokio::time::timeout(Duration::from_secs(2), hello_rx.recv()).await.unwrap().unwrap();
+            assert!(matches!(hello, Message::ClientHello { .. }));
+        }
+        wait_for_state(&client, ConnectionState::Connected).await;
+        while let Ok(event) = event_rx.try_recv() {
+            assert!(!matches!(event, ClientEvent::ConnectionLost { .. }));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_garbage_frame_produces_protocol_error() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = crate::test_support::accept(stream).await;
+            let _hello = ws.next().await;
+            ws.send(WsMessage::Text("not a message".to_string())).await.unwrap();
+            let json = serde_json::to_string(&Message::monitor_change_ack("c1".to_string())).unwrap();
+            ws.send(WsMessage::Text(json)).await.unwrap();
+            while let Some(Ok(_)) = ws.next().await {}
+        });
+
+        let (event_tx, mut event_rx) = mpsc::unbounded_channel();
+        let mut client = KanpeClient::new(event_tx);
+        client.connect(&addr, "caster".to_string(), vec![]).await.unwrap();
+
+        let mut protocol_error = None;
+        let mut unhandled = None;
+        while protocol_error.is_none() || unhandled.is_none() {
+            match tokio::time::timeout(Duration::from_secs(2), event_rx.recv()).await.unwrap() {
+                Some(ClientEvent::ProtocolError { raw, reason }) => protocol_error = Some((raw, reason)),
+                Some(ClientEvent::UnhandledMessage { message_type }) => unhandled = Some(message_type),
+                Some(_) => {}
+                None => panic!("event channel closed"),
+            }
+        }
+        let (raw, reason) = protocol_error.unwrap();
+        assert_eq!(raw, "not a message");
+        assert!(reason.contains("not valid JSON"), "{}", reason);
+        assert_eq!(unhandled.as_deref(), Some("monitor_change_ack"));
+    }
+
+    #[tokio::test]
+    async fn test_server_shutdown_is_reported_without_reconnecting() {
+        let addr = start_scripted_server(vec![Message::server_shutdown("show over".to_string(), 500)]).await;
+
+        let (event_tx, mut event_rx) = mpsc::unbounded_channel();
+        let mut client = KanpeClient::new(event_tx);
+        client.set_reconnect_policy(Some(ReconnectPolicy::default()));
+        client.connect(&addr, "caster".to_string(), vec![]).await.unwrap();
+
+        loop {
+            match tokio::time::timeout(Duration::from_secs(2), event_rx.recv()).await.unwrap() {
+                Some(ClientEvent::ServerShuttingDown { reason }) => {
+                    assert_eq!(reason, "show over");
+                    break;
+                }
+                Some(ClientEvent::ConnectionLost { .. }) => panic!("shutdown reported as a lost connection"),
+                Some(_) => {}
+                None => panic!("event channel closed"),
+            }
+        }
+        assert_eq!(client.connection_state().await, ConnectionState::Disconnected);
     }
 }
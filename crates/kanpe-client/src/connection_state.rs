@@ -0,0 +1,53 @@
+//! Connection lifecycle state and automatic reconnection settings
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Where the client is in its connect/reconnect lifecycle
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum ConnectionState {
+    /// Not connected and not trying to be
+    Disconnected,
+    /// Opening a connection, or waiting for the server's welcome on one just opened
+    Connecting,
+    /// Connected to the server and welcomed
+    Connected,
+    /// Lost the connection and waiting `next_delay_ms` before retry number `attempt`
+    Reconnecting {
+        attempt: u32,
+        max_attempts: u32,
+        next_delay_ms: u64,
+    },
+    /// Gave up reconnecting after the last attempt failed
+    Failed,
+}
+
+/// How the client retries after losing its connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// Attempts made before giving up
+    pub max_attempts: u32,
+    /// Wait before the first attempt; doubled for each one after
+    pub initial_delay: Duration,
+    /// Upper bound on the wait between attempts
+    pub max_delay: Duration,
+}
+
+impl ReconnectPolicy {
+    /// Wait before the given (1-based) attempt
+    pub fn delay_for(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_delay.saturating_mul(factor).min(self.max_delay)
+    }
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(16),
+        }
+    }
+}
@@ -0,0 +1,27 @@
+//! Error types for the Kanpe client
+
+use thiserror::Error;
+use tokio_tungstenite::tungstenite::http::header::InvalidHeaderValue;
+
+/// Errors returned by KanpeClient operations
+#[derive(Debug, Error)]
+pub enum KanpeClientError {
+    #[error("already connected to a server")]
+    AlreadyConnected,
+    #[error("not connected to a server")]
+    NotConnected,
+    #[error("sending too fast; message dropped")]
+    RateLimited,
+    #[error("timed out connecting after {0:?}")]
+    Timeout(std::time::Duration),
+    #[error("invalid auth token: {0}")]
+    InvalidAuthToken(#[from] InvalidHeaderValue),
+    #[error("invalid header {0:?}")]
+    InvalidHeader(String),
+    #[error("WebSocket error: {0}")]
+    WebSocket(#[from] tokio_tungstenite::tungstenite::Error),
+    #[error("failed to start runtime: {0}")]
+    Runtime(std::io::Error),
+    #[error(transparent)]
+    Serialization(#[from] kanpe_core::MessageParseError),
+}
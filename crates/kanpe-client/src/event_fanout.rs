@@ -0,0 +1,33 @@
+//! Delivery of client events to the embedder's channel and any subscribers
+
+use crate::events::ClientEvent;
+use tokio::sync::{broadcast, mpsc};
+
+/// Number of events a subscriber can fall behind before it starts missing them
+pub const EVENT_SUBSCRIBER_CAPACITY: usize = 256;
+
+/// Sends each event to the mpsc channel given at construction and to every subscriber
+#[derive(Clone)]
+pub struct EventSender {
+    primary: mpsc::UnboundedSender<ClientEvent>,
+    subscribers: broadcast::Sender<ClientEvent>,
+}
+
+impl EventSender {
+    pub fn new(primary: mpsc::UnboundedSender<ClientEvent>) -> Self {
+        let (subscribers, _) = broadcast::channel(EVENT_SUBSCRIBER_CAPACITY);
+        Self { primary, subscribers }
+    }
+
+    /// Deliver an event; receivers that have been dropped are skipped
+    pub fn send(&self, event: ClientEvent) {
+        if self.subscribers.receiver_count() > 0 {
+            let _ = self.subscribers.send(event.clone());
+        }
+        let _ = self.primary.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ClientEvent> {
+        self.subscribers.subscribe()
+    }
+}
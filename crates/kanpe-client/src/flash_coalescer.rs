@@ -0,0 +1,117 @@
+//! Coalescing of rapid flash commands
+
+use kanpe_core::types::ALL_MONITORS;
+use std::time::{Duration, Instant};
+
+/// Default window within which flashes for overlapping monitors are merged
+pub const FLASH_COALESCE_WINDOW: Duration = Duration::from_millis(300);
+
+struct PendingFlash {
+    target_monitor_ids: Vec<String>,
+    deadline: Instant,
+}
+
+/// Holds back flash commands briefly so a burst for the same monitors fires once
+pub struct FlashCoalescer {
+    pending: Vec<PendingFlash>,
+    window: Duration,
+}
+
+impl FlashCoalescer {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            pending: Vec::new(),
+            window,
+        }
+    }
+
+    /// Add a flash, returning its targets right away if coalescing is disabled
+    pub fn push(&mut self, target_monitor_ids: Vec<String>, now: Instant) -> Option<Vec<String>> {
+        if self.window.is_zero() {
+            return Some(target_monitor_ids);
+        }
+
+        // Pending flashes never overlap each other, so every one the new flash
+        // touches is folded into a single entry where the first of them was
+        let mut merged = PendingFlash {
+            target_monitor_ids: Vec::new(),
+            deadline: now + self.window,
+        };
+        let mut position = None;
+        let mut index = 0;
+        while index < self.pending.len() {
+            if overlaps(&self.pending[index].target_monitor_ids, &target_monitor_ids) {
+                let flash = self.pending.remove(index);
+                position.get_or_insert(index);
+                merged.deadline = merged.deadline.min(flash.deadline);
+                add_unique(&mut merged.target_monitor_ids, flash.target_monitor_ids);
+            } else {
+                index += 1;
+            }
+        }
+        add_unique(&mut merged.target_monitor_ids, target_monitor_ids);
+        self.pending.insert(position.unwrap_or(self.pending.len()), merged);
+        None
+    }
+
+    /// Earliest time a pending flash is due
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.pending.iter().map(|flash| flash.deadline).min()
+    }
+
+    /// Take the targets of every flash whose window has elapsed
+    pub fn poll(&mut self, now: Instant) -> Vec<Vec<String>> {
+        let mut due = Vec::new();
+        self.pending.retain_mut(|flash| {
+            if flash.deadline <= now {
+                due.push(std::mem::take(&mut flash.target_monitor_ids));
+                false
+            } else {
+                true
+            }
+        });
+        due
+    }
+}
+
+fn add_unique(targets: &mut Vec<String>, ids: Vec<String>) {
+    for id in ids {
+        if !targets.contains(&id) {
+            targets.push(id);
+        }
+    }
+}
+
+fn overlaps(a: &[String], b: &[String]) -> bool {
+    a.iter().chain(b).any(|id| id == ALL_MONITORS) || a.iter().any(|id| b.contains(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn test_disjoint_flashes_fire_separately() {
+        let start = Instant::now();
+        let mut coalescer = FlashCoalescer::new(FLASH_COALESCE_WINDOW);
+
+        assert_eq!(coalescer.push(ids(&["A"]), start), None);
+        assert_eq!(coalescer.push(ids(&["B"]), start), None);
+        assert_eq!(coalescer.push(ids(&["A", "C"]), start), None);
+        assert!(coalescer.poll(start).is_empty());
+
+        let due = coalescer.poll(start + FLASH_COALESCE_WINDOW);
+        assert_eq!(due, vec![ids(&["A", "C"]), ids(&["B"])]);
+        assert_eq!(coalescer.next_deadline(), None);
+
+        // A flash spanning two pending ones merges them into one
+        assert_eq!(coalescer.push(ids(&["A"]), start), None);
+        assert_eq!(coalescer.push(ids(&["B"]), start), None);
+        assert_eq!(coalescer.push(ids(&["A", "B"]), start), None);
+        assert_eq!(coalescer.poll(start + FLASH_COALESCE_WINDOW), vec![ids(&["A", "B"])]);
+    }
+}
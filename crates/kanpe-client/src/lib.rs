@@ -3,9 +3,35 @@
 //! This crate provides the WebSocket client implementation for the caster
 //! (client) role in the Bi-Kanpe system.
 
+mod blocking;
 mod client;
+mod connection_state;
+mod error;
+mod event_fanout;
+mod flash_coalescer;
+mod presenter_notes;
+mod rate_limiter;
+mod seen_ids;
+mod simulated_latency;
+#[cfg(test)]
+mod test_support;
 
-pub use client::KanpeClient;
+pub use blocking::BlockingKanpeClient;
+pub use client::{
+    ConnectPolicy, KanpeClient, CLIENT_PING_INTERVAL, DEFAULT_CONNECT_TIMEOUT, DEFAULT_MESSAGE_HISTORY_CAPACITY,
+    DEFAULT_STALE_AFTER,
+};
+pub use connection_state::{ConnectionState, ReconnectPolicy};
+pub use error::KanpeClientError;
+pub use event_fanout::EVENT_SUBSCRIBER_CAPACITY;
+pub use flash_coalescer::FLASH_COALESCE_WINDOW;
+pub use seen_ids::DEFAULT_DEDUP_CAPACITY;
+
+/// Client events as a `Stream`, ending once the client and its tasks are gone
+///
+/// Reads the channel given to `KanpeClient::new`, which never drops events,
+/// unlike a `subscribe` receiver that can lag.
+pub type ClientEventStream = kanpe_core::EventStream<events::ClientEvent>;
 
 // Re-export events for integration
 pub mod events {
@@ -22,12 +48,19 @@ pub mod events {
         MessageReceived {
             message: kanpe_core::Message,
         },
+        MessageRecalled {
+            message_id: String,
+            target_monitor_ids: Vec<String>,
+        },
         ServerWelcomeReceived {
             server_name: String,
         },
         MonitorListReceived {
             monitors: Vec<kanpe_core::types::VirtualMonitor>,
         },
+        MonitorGroupsReceived {
+            groups: Vec<kanpe_core::types::MonitorGroup>,
+        },
         MonitorAdded {
             monitor: kanpe_core::types::VirtualMonitor,
         },
@@ -37,11 +70,57 @@ pub mod events {
         MonitorUpdated {
             monitor: kanpe_core::types::VirtualMonitor,
         },
+        /// The full monitor list after a sync, add, removal or update was applied
+        MonitorsChanged {
+            monitors: Vec<kanpe_core::types::VirtualMonitor>,
+        },
         FlashReceived {
             target_monitor_ids: Vec<String>,
         },
         ClearReceived {
             target_monitor_ids: Vec<String>,
         },
+        PresenterNoteReceived {
+            note_id: String,
+            content: String,
+            target_monitor_ids: Vec<String>,
+        },
+        PresenterNoteIncomplete {
+            note_id: String,
+            missing_seqs: Vec<u32>,
+        },
+        LatencyMeasured {
+            millis: u64,
+        },
+        AirStatusChanged {
+            status: kanpe_core::AirStatus,
+            since: i64,
+        },
+        /// The server announced it is shutting down; no reconnect is attempted
+        ServerShuttingDown {
+            reason: String,
+        },
+        /// An inbound frame could not be parsed as a protocol message
+        ProtocolError {
+            raw: String,
+            reason: String,
+        },
+        /// Broadcast frames were missed: `got` arrived when `expected` was next
+        SequenceGap {
+            expected: u64,
+            got: u64,
+        },
+        /// Nothing, not even a pong, has arrived from the server for `idle_ms`
+        ///
+        /// The connection is kept; `ActivityResumed` follows once anything arrives.
+        Stale {
+            idle_ms: u64,
+        },
+        /// A frame arrived after a `Stale` warning
+        ActivityResumed,
+        /// A valid message arrived that the client has no handling for
+        UnhandledMessage {
+            message_type: String,
+        },
     }
 }
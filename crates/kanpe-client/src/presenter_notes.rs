@@ -0,0 +1,228 @@
+//! Reassembly of chunked presenter notes
+
+use kanpe_core::message::PresenterNoteChunkPayload;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// How long a note may go without receiving a chunk before missing chunks are re-requested
+pub const NOTE_CHUNK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How many times missing chunks are re-requested before the note is abandoned
+pub const NOTE_MAX_RESEND_ATTEMPTS: u32 = 2;
+
+/// Largest chunk count accepted for one note; 1024 of the server's 16 KiB chunks is 16 MiB
+pub const NOTE_MAX_CHUNKS: u32 = 1024;
+
+/// Number of finished note IDs remembered so late duplicate chunks are ignored
+const MAX_FINISHED_NOTES: usize = 64;
+
+/// A fully reassembled presenter note
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompletedNote {
+    pub note_id: String,
+    pub content: String,
+    pub target_monitor_ids: Vec<String>,
+}
+
+/// Action to take for a note that stopped receiving chunks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NoteTimeout {
+    /// Ask the server to resend the missing chunks
+    Resend { note_id: String, missing_seqs: Vec<u32> },
+    /// Give up on the note after repeated re-requests
+    Abandoned { note_id: String, missing_seqs: Vec<u32> },
+}
+
+struct PendingNote {
+    chunks: Vec<Option<String>>,
+    target_monitor_ids: Vec<String>,
+    last_activity: Instant,
+    resend_attempts: u32,
+}
+
+impl PendingNote {
+    fn missing_seqs(&self) -> Vec<u32> {
+        self.chunks
+            .iter()
+            .enumerate()
+            .filter(|(_, chunk)| chunk.is_none())
+            .map(|(seq, _)| seq as u32)
+            .collect()
+    }
+}
+
+/// Collects presenter note chunks until every chunk of a note has arrived
+pub struct NoteAssembler {
+    pending: HashMap<String, PendingNote>,
+    finished: VecDeque<String>,
+    timeout: Duration,
+    max_resend_attempts: u32,
+}
+
+impl NoteAssembler {
+    pub fn new(timeout: Duration, max_resend_attempts: u32) -> Self {
+        Self {
+            pending: HashMap::new(),
+            finished: VecDeque::new(),
+            timeout,
+            max_resend_attempts,
+        }
+    }
+
+    /// Add a chunk, returning the full note once its last missing chunk arrives
+    ///
+    /// Chunks claiming more than [`NOTE_MAX_CHUNKS`] chunks, and late chunks of
+    /// a note that was already completed or abandoned, are ignored.
+    pub fn push(&mut self, chunk: PresenterNoteChunkPayload, now: Instant) -> Option<CompletedNote> {
+        if chunk.total == 0 || chunk.total > NOTE_MAX_CHUNKS || chunk.seq >= chunk.total {
+            return None;
+        }
+        if self.finished.contains(&chunk.note_id) {
+            return None;
+        }
+
+        let note = self
+            .pending
+            .entry(chunk.note_id.clone())
+            .or_insert_with(|| PendingNote {
+                chunks: vec![None; chunk.total as usize],
+                target_monitor_ids: chunk.target_monitor_ids.clone(),
+                last_activity: now,
+                resend_attempts: 0,
+            });
+        if let Some(slot) = note.chunks.get_mut(chunk.seq as usize) {
+            *slot = Some(chunk.data);
+        }
+        note.last_activity = now;
+
+        if note.chunks.iter().any(Option::is_none) {
+            return None;
+        }
+
+        let note = self.pending.remove(&chunk.note_id)?;
+        self.finish(chunk.note_id.clone());
+        Some(CompletedNote {
+            note_id: chunk.note_id,
+            content: note.chunks.into_iter().flatten().collect(),
+            target_monitor_ids: note.target_monitor_ids,
+        })
+    }
+
+    /// Check for notes that timed out waiting for chunks
+    pub fn poll_timeouts(&mut self, now: Instant) -> Vec<NoteTimeout> {
+        let mut actions = Vec::new();
+        let mut abandoned = Vec::new();
+
+        for (note_id, note) in self.pending.iter_mut() {
+            if now.duration_since(note.last_activity) < self.timeout {
+                continue;
+            }
+            let missing_seqs = note.missing_seqs();
+            if note.resend_attempts < self.max_resend_attempts {
+                note.resend_attempts += 1;
+                note.last_activity = now;
+                actions.push(NoteTimeout::Resend {
+                    note_id: note_id.clone(),
+                    missing_seqs,
+                });
+            } else {
+                abandoned.push(note_id.clone());
+                actions.push(NoteTimeout::Abandoned {
+                    note_id: note_id.clone(),
+                    missing_seqs,
+                });
+            }
+        }
+
+        for note_id in abandoned {
+            self.pending.remove(&note_id);
+            self.finish(note_id);
+        }
+        actions
+    }
+
+    /// Remember a note that will take no more chunks
+    fn finish(&mut self, note_id: String) {
+        if self.finished.len() >= MAX_FINISHED_NOTES {
+            self.finished.pop_front();
+        }
+        self.finished.push_back(note_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(seq: u32, data: &str) -> PresenterNoteChunkPayload {
+        PresenterNoteChunkPayload {
+            note_id: "note-1".to_string(),
+            seq,
+            total: 3,
+            data: data.to_string(),
+            target_monitor_ids: vec!["A".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_three_chunk_note_reassembles_out_of_order() {
+        let mut assembler = NoteAssembler::new(NOTE_CHUNK_TIMEOUT, NOTE_MAX_RESEND_ATTEMPTS);
+        let now = Instant::now();
+
+        assert!(assembler.push(chunk(2, "baz"), now).is_none());
+        assert!(assembler.push(chunk(0, "foo"), now).is_none());
+        let note = assembler.push(chunk(1, "bar"), now).unwrap();
+
+        assert_eq!(note.note_id, "note-1");
+        assert_eq!(note.content, "foobarbaz");
+        assert_eq!(note.target_monitor_ids, vec!["A".to_string()]);
+    }
+
+    #[test]
+    fn test_oversized_and_late_chunks_are_ignored() {
+        let mut assembler = NoteAssembler::new(NOTE_CHUNK_TIMEOUT, NOTE_MAX_RESEND_ATTEMPTS);
+        let now = Instant::now();
+
+        let oversized = PresenterNoteChunkPayload {
+            total: u32::MAX,
+            ..chunk(0, "foo")
+        };
+        assert!(assembler.push(oversized, now).is_none());
+        assert!(assembler.pending.is_empty());
+
+        assembler.push(chunk(0, "foo"), now);
+        assembler.push(chunk(1, "bar"), now);
+        assert!(assembler.push(chunk(2, "baz"), now).is_some());
+
+        // A duplicate of a chunk of the completed note does not start it again
+        assert!(assembler.push(chunk(1, "bar"), now).is_none());
+        assert!(assembler.pending.is_empty());
+    }
+
+    #[test]
+    fn test_missing_chunk_is_rerequested_then_abandoned() {
+        let mut assembler = NoteAssembler::new(Duration::from_secs(3), 1);
+        let start = Instant::now();
+        assembler.push(chunk(0, "foo"), start);
+        assembler.push(chunk(2, "baz"), start);
+
+        assert!(assembler.poll_timeouts(start + Duration::from_secs(1)).is_empty());
+
+        let later = start + Duration::from_secs(3);
+        assert_eq!(
+            assembler.poll_timeouts(later),
+            vec![NoteTimeout::Resend {
+                note_id: "note-1".to_string(),
+                missing_seqs: vec![1],
+            }]
+        );
+        assert_eq!(
+            assembler.poll_timeouts(later + Duration::from_secs(3)),
+            vec![NoteTimeout::Abandoned {
+                note_id: "note-1".to_string(),
+                missing_seqs: vec![1],
+            }]
+        );
+        assert!(assembler.poll_timeouts(later + Duration::from_secs(10)).is_empty());
+    }
+}
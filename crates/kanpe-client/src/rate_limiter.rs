@@ -0,0 +1,63 @@
+//! Token-bucket limit on outbound messages
+
+use std::sync::Mutex;
+use std::time::Instant;
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+/// Allows bursts of up to `max_per_sec` messages, refilled continuously at that rate
+pub struct RateLimiter {
+    max_per_sec: f64,
+    bucket: Mutex<Bucket>,
+}
+
+impl RateLimiter {
+    pub fn new(max_per_sec: u32, now: Instant) -> Self {
+        let max_per_sec = f64::from(max_per_sec.max(1));
+        Self {
+            max_per_sec,
+            bucket: Mutex::new(Bucket {
+                tokens: max_per_sec,
+                refilled_at: now,
+            }),
+        }
+    }
+
+    /// Take a token if one is available
+    pub fn try_acquire(&self, now: Instant) -> bool {
+        let mut bucket = self.bucket.lock().unwrap();
+        let elapsed = now.saturating_duration_since(bucket.refilled_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.max_per_sec).min(self.max_per_sec);
+        bucket.refilled_at = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_burst_is_capped_then_refills() {
+        let start = Instant::now();
+        let limiter = RateLimiter::new(5, start);
+
+        let allowed = (0..10).filter(|_| limiter.try_acquire(start)).count();
+        assert_eq!(allowed, 5);
+
+        // Refills at five per second: one token every 200ms
+        assert!(!limiter.try_acquire(start + Duration::from_millis(100)));
+        assert!(limiter.try_acquire(start + Duration::from_millis(300)));
+        assert!(!limiter.try_acquire(start + Duration::from_millis(300)));
+    }
+}
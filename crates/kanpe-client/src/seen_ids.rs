@@ -0,0 +1,68 @@
+//! Recently seen message ids, for dropping duplicate deliveries
+
+use std::collections::{HashSet, VecDeque};
+
+/// Default number of message ids remembered for de-duplication
+pub const DEFAULT_DEDUP_CAPACITY: usize = 256;
+
+/// LRU set of message ids; the least recently seen id is forgotten when full
+pub struct SeenIds {
+    capacity: usize,
+    order: VecDeque<String>,
+    ids: HashSet<String>,
+}
+
+impl SeenIds {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            order: VecDeque::with_capacity(capacity),
+            ids: HashSet::with_capacity(capacity),
+        }
+    }
+
+    /// Record an id, returning false if it was already seen
+    ///
+    /// With a capacity of zero nothing is remembered and every id counts as new.
+    pub fn insert(&mut self, id: &str) -> bool {
+        if self.capacity == 0 {
+            return true;
+        }
+
+        if self.ids.contains(id) {
+            if let Some(pos) = self.order.iter().position(|seen| seen == id) {
+                let seen = self.order.remove(pos).unwrap();
+                self.order.push_back(seen);
+            }
+            return false;
+        }
+
+        if self.order.len() >= self.capacity
+            && let Some(oldest) = self.order.pop_front()
+        {
+            self.ids.remove(&oldest);
+        }
+        self.order.push_back(id.to_string());
+        self.ids.insert(id.to_string());
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_least_recently_seen_id_is_forgotten() {
+        let mut seen = SeenIds::new(2);
+
+        assert!(seen.insert("a"));
+        assert!(seen.insert("b"));
+        assert!(!seen.insert("a"));
+
+        // "b" is now the least recently seen, so it makes room for "c"
+        assert!(seen.insert("c"));
+        assert!(!seen.insert("a"));
+        assert!(seen.insert("b"));
+    }
+}
@@ -0,0 +1,36 @@
+//! Artificial network delay for resilience testing
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Delay added to every frame the client sends or receives
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimulatedLatency {
+    pub delay: Duration,
+    pub jitter: Duration,
+}
+
+impl SimulatedLatency {
+    /// Pick a delay between `delay` and `delay + jitter`
+    fn sample(&self) -> Duration {
+        if self.jitter.is_zero() {
+            return self.delay;
+        }
+        // Cheap xorshift over the clock; the spread only needs to look random
+        let mut x = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|now| now.subsec_nanos() as u64)
+            .unwrap_or(0)
+            | 1;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.delay + Duration::from_nanos(x % (self.jitter.as_nanos() as u64 + 1))
+    }
+}
+
+/// Wait out the simulated delay, returning immediately when none is set
+pub async fn simulate(latency: Option<SimulatedLatency>) {
+    if let Some(latency) = latency {
+        tokio::time::sleep(latency.sample()).await;
+    }
+}
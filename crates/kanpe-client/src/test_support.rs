@@ -0,0 +1,24 @@
+//! Helpers shared by the client's tests
+
+use kanpe_core::WEBSOCKET_SUBPROTOCOL;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::header::{HeaderValue, SEC_WEBSOCKET_PROTOCOL};
+use tokio_tungstenite::WebSocketStream;
+
+/// Accept the upgrade the way a Kanpe server does, echoing the requested subprotocol
+pub async fn accept(stream: TcpStream) -> WebSocketStream<TcpStream> {
+    tokio_tungstenite::accept_hdr_async(stream, echo_subprotocol)
+        .await
+        .expect("failed to accept test client")
+}
+
+/// Handshake callback that answers with the `kanpe.v1` subprotocol
+// The callback signature is fixed by tungstenite
+#[allow(clippy::result_large_err)]
+pub fn echo_subprotocol(_request: &Request, mut response: Response) -> Result<Response, ErrorResponse> {
+    response
+        .headers_mut()
+        .insert(SEC_WEBSOCKET_PROTOCOL, HeaderValue::from_static(WEBSOCKET_SUBPROTOCOL));
+    Ok(response)
+}
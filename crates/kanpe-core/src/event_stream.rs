@@ -0,0 +1,58 @@
+//! `Stream` adapter over an event channel, shared by the server and client
+
+use futures_util::stream::Stream;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+
+/// Events as a `Stream`, ending once every sender is gone
+///
+/// Wraps the receiver half of an unbounded event channel, so `StreamExt`
+/// combinators like `filter` and `map` apply to it. Nothing is dropped, unlike
+/// a broadcast receiver that can lag.
+#[derive(Debug)]
+pub struct EventStream<T> {
+    event_rx: mpsc::UnboundedReceiver<T>,
+}
+
+impl<T> EventStream<T> {
+    pub fn new(event_rx: mpsc::UnboundedReceiver<T>) -> Self {
+        Self { event_rx }
+    }
+
+    /// Get the raw receiver back
+    pub fn into_inner(self) -> mpsc::UnboundedReceiver<T> {
+        self.event_rx
+    }
+}
+
+impl<T> From<mpsc::UnboundedReceiver<T>> for EventStream<T> {
+    fn from(event_rx: mpsc::UnboundedReceiver<T>) -> Self {
+        Self::new(event_rx)
+    }
+}
+
+impl<T> Stream for EventStream<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.event_rx.poll_recv(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+
+    #[tokio::test]
+    async fn test_stream_ends_when_senders_are_dropped() {
+        let (event_tx, event_rx) = mpsc::unbounded_channel();
+        event_tx.send(1).unwrap();
+        event_tx.send(2).unwrap();
+        drop(event_tx);
+
+        let events: Vec<i32> = EventStream::new(event_rx).collect().await;
+        assert_eq!(events, vec![1, 2]);
+    }
+}
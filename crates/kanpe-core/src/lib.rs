@@ -3,9 +3,15 @@
 //! This crate contains the core message protocol and types used by both
 //! the kanpe-server and kanpe-client crates.
 
+#[cfg(feature = "stream")]
+pub mod event_stream;
 pub mod message;
 pub mod types;
+pub mod versioned;
 
 // Re-export commonly used types
-pub use message::Message;
-pub use types::{Priority, FeedbackType};
+#[cfg(feature = "stream")]
+pub use event_stream::EventStream;
+pub use message::{Frame, KanpeMessageBuilder, Message, MessageParseError, MESSAGE_TYPES, WEBSOCKET_SUBPROTOCOL};
+pub use types::{AirStatus, ContentFormat, FixedClock, IdClock, Priority, FeedbackType, SystemClock, WireFormat};
+pub use versioned::{versioned_parse, VersionParseError};
@@ -1,7 +1,82 @@
 //! Message types for the Kanpe protocol
 
 use serde::{Deserialize, Serialize};
-use crate::types::{new_id, timestamp, Priority, FeedbackType, VirtualMonitor};
+use thiserror::Error;
+use crate::types::{
+    AirStatus, ContentFormat, IdClock, Priority, FeedbackType, MonitorGroup, SystemClock,
+    VirtualMonitor, WireFormat,
+};
+
+/// WebSocket subprotocol clients request and the server echoes, naming the protocol version
+pub const WEBSOCKET_SUBPROTOCOL: &str = "kanpe.v1";
+
+/// Every `type` tag a `Message` can carry on the wire, as returned by `Message::message_type`
+pub const MESSAGE_TYPES: &[&str] = &[
+    "client_hello",
+    "server_welcome",
+    "kanpe_message",
+    "feedback_message",
+    "ping",
+    "pong",
+    "monitor_list_sync",
+    "monitor_group_sync",
+    "monitor_added",
+    "monitor_removed",
+    "monitor_updated",
+    "flash_command",
+    "clear_command",
+    "presenter_note_chunk",
+    "presenter_note_resend",
+    "update_subscription",
+    "monitor_change_ack",
+    "message_recalled",
+    "air_status",
+    "reconnect_request",
+    "message_ack",
+    "server_shutdown",
+    "reaction",
+    "feedback_typing",
+];
+
+/// Why a protocol message could not be read or written
+#[derive(Debug, Error)]
+pub enum MessageParseError {
+    #[error("message is not valid JSON: {0}")]
+    InvalidJson(#[source] serde_json::Error),
+    #[error("unknown message type {0:?}")]
+    UnknownType(String),
+    #[error("message is missing field {0:?}")]
+    MissingField(String),
+    #[error("malformed message: {0}")]
+    Malformed(#[source] serde_json::Error),
+    #[error("failed to serialize message: {0}")]
+    Serialize(#[source] serde_json::Error),
+    #[error("message is not valid MessagePack: {0}")]
+    InvalidMsgpack(#[source] rmp_serde::decode::Error),
+    #[error("failed to encode message as MessagePack: {0}")]
+    EncodeMsgpack(#[source] rmp_serde::encode::Error),
+}
+
+/// A message encoded in a connection's wire format, ready to go out as a WebSocket frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Frame {
+    /// JSON, sent as a text frame
+    Text(String),
+    /// MessagePack, sent as a binary frame
+    Binary(Vec<u8>),
+}
+
+/// A message with the broadcast sequence number of the connection it goes to
+///
+/// Servers number every broadcast frame per connection, starting at 1;
+/// frames sent to a single client (such as ServerWelcome) carry no `seq`.
+#[derive(Serialize)]
+struct Sequenced<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seq: Option<u64>,
+    #[serde(flatten)]
+    message: &'a Message,
+}
 
 /// Main message enum for all Kanpe protocol messages
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,6 +115,8 @@ pub enum Message {
     Pong {
         id: String,
         timestamp: i64,
+        #[serde(default)]
+        payload: PongPayload,
     },
     /// Server sends monitor list synchronization
     MonitorListSync {
@@ -47,6 +124,12 @@ pub enum Message {
         timestamp: i64,
         payload: MonitorListSyncPayload,
     },
+    /// Server sends the full list of monitor groups
+    MonitorGroupSync {
+        id: String,
+        timestamp: i64,
+        payload: MonitorGroupSyncPayload,
+    },
     /// Server notifies monitor was added
     MonitorAdded {
         id: String,
@@ -77,6 +160,71 @@ pub enum Message {
         timestamp: i64,
         payload: ClearCommandPayload,
     },
+    /// Server sends one chunk of a presenter note
+    PresenterNoteChunk {
+        id: String,
+        timestamp: i64,
+        payload: PresenterNoteChunkPayload,
+    },
+    /// Client asks the server to resend missing presenter note chunks
+    PresenterNoteResend {
+        id: String,
+        timestamp: i64,
+        payload: PresenterNoteResendPayload,
+    },
+    /// Client changes which monitors it displays without reconnecting
+    UpdateSubscription {
+        id: String,
+        timestamp: i64,
+        payload: UpdateSubscriptionPayload,
+    },
+    /// Client confirms it applied a monitor list change
+    MonitorChangeAck {
+        id: String,
+        timestamp: i64,
+        payload: MonitorChangeAckPayload,
+    },
+    /// Server withdraws a previously sent cue from some monitors
+    MessageRecalled {
+        id: String,
+        timestamp: i64,
+        payload: MessageRecalledPayload,
+    },
+    /// Server announces the production's on-air state to every client
+    AirStatus {
+        id: String,
+        timestamp: i64,
+        payload: AirStatusPayload,
+    },
+    /// Server asks a client to drop its connection and reconnect
+    ReconnectRequest {
+        id: String,
+        timestamp: i64,
+    },
+    /// Client confirms the operator has seen a cue
+    MessageAck {
+        id: String,
+        timestamp: i64,
+        payload: MessageAckPayload,
+    },
+    /// Server is about to close; clients should disconnect within `grace_ms`
+    ServerShutdown {
+        id: String,
+        timestamp: i64,
+        payload: ServerShutdownPayload,
+    },
+    /// Client sends a one-tap reaction (e.g. an emoji) to a cue
+    Reaction {
+        id: String,
+        timestamp: i64,
+        payload: ReactionPayload,
+    },
+    /// Client reports the operator started or stopped composing feedback
+    FeedbackTyping {
+        id: String,
+        timestamp: i64,
+        payload: FeedbackTypingPayload,
+    },
 }
 
 /// Payload for ClientHello message
@@ -86,6 +234,12 @@ pub struct ClientHelloPayload {
     pub client_name: String,
     /// Virtual monitor IDs this client is displaying (e.g., ["A", "B"])
     pub display_monitor_ids: Vec<String>,
+    /// Encoding the client would like the server to use
+    #[serde(default)]
+    pub wire_format: WireFormat,
+    /// Token from a previous ServerWelcome, to keep the same client ID after a reconnect
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resume_token: Option<String>,
 }
 
 /// Payload for ServerWelcome message
@@ -95,6 +249,15 @@ pub struct ServerWelcomePayload {
     pub server_name: String,
     /// Client ID assigned by server
     pub assigned_client_id: String,
+    /// Encoding the server uses for the rest of the connection
+    #[serde(default)]
+    pub wire_format: WireFormat,
+    /// Token to send in the next ClientHello if this connection drops
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resume_token: Option<String>,
+    /// Name the client was registered under, when it differs from the one in its ClientHello
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assigned_client_name: Option<String>,
 }
 
 /// Payload for KanpeMessage (cue card)
@@ -102,10 +265,133 @@ pub struct ServerWelcomePayload {
 pub struct KanpeMessagePayload {
     /// Message content (text)
     pub content: String,
-    /// Target virtual monitor IDs ("ALL" = all monitors, or specific IDs like ["A", "B"])
+    /// Target virtual monitor IDs ([`ALL_MONITORS`](crate::types::ALL_MONITORS) = all monitors, or specific IDs like ["A", "B"])
     pub target_monitor_ids: Vec<String>,
     /// Message priority
     pub priority: Priority,
+    /// How `content` is formatted; omitted on the wire when plain
+    #[serde(default, skip_serializing_if = "ContentFormat::is_plain")]
+    pub content_format: ContentFormat,
+    /// Unix timestamp (ms) after which the cue is stale and should not be shown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+    /// For urgent cues: the server re-flashes the targets this often (ms) until the cue is acked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repeat_flash_interval_ms: Option<u64>,
+    /// Treat `target_monitor_ids` as monitors to skip, sending to everyone else
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub invert: bool,
+}
+
+impl KanpeMessagePayload {
+    /// Check whether the cue's expiry has passed at `now` (Unix ms)
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+/// When a built cue expires
+#[derive(Debug, Clone, Copy)]
+enum Expiry {
+    At(i64),
+    After(i64),
+}
+
+/// Chained construction of a KanpeMessage, for cues that set optional fields
+///
+/// Unset fields take the same values as `Message::kanpe_message`.
+#[derive(Debug, Clone, Default)]
+pub struct KanpeMessageBuilder {
+    content: String,
+    target_monitor_ids: Vec<String>,
+    priority: Priority,
+    content_format: ContentFormat,
+    expiry: Option<Expiry>,
+    repeat_flash_interval_ms: Option<u64>,
+    invert: bool,
+}
+
+impl KanpeMessageBuilder {
+    /// Start a normal-priority plain cue with no content or targets
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the cue text
+    pub fn content(mut self, content: impl Into<String>) -> Self {
+        self.content = content.into();
+        self
+    }
+
+    /// Set the virtual monitors the cue is shown on
+    pub fn targets(mut self, target_monitor_ids: Vec<String>) -> Self {
+        self.target_monitor_ids = target_monitor_ids;
+        self
+    }
+
+    /// Set how prominently the cue is shown (normal by default)
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Mark the content as markdown (or plain) for the UI to render
+    pub fn content_format(mut self, content_format: ContentFormat) -> Self {
+        self.content_format = content_format;
+        self
+    }
+
+    /// Expire the cue `ttl_ms` milliseconds after it is built
+    pub fn ttl(mut self, ttl_ms: i64) -> Self {
+        self.expiry = Some(Expiry::After(ttl_ms));
+        self
+    }
+
+    /// Expire the cue at a fixed Unix timestamp (ms)
+    pub fn expires_at(mut self, expires_at: i64) -> Self {
+        self.expiry = Some(Expiry::At(expires_at));
+        self
+    }
+
+    /// Have the server re-flash the targets this often (ms) until the cue is acked
+    pub fn repeat_flash_interval_ms(mut self, interval_ms: u64) -> Self {
+        self.repeat_flash_interval_ms = Some(interval_ms);
+        self
+    }
+
+    /// Show the cue on every monitor except the targets
+    pub fn invert(mut self, invert: bool) -> Self {
+        self.invert = invert;
+        self
+    }
+
+    /// Create the KanpeMessage with a fresh ID and timestamp
+    pub fn build(self) -> Message {
+        self.build_with(&SystemClock)
+    }
+
+    /// Create the KanpeMessage taking its ID and timestamp from `clock`
+    ///
+    /// A TTL is counted from the clock's timestamp too.
+    pub fn build_with(self, clock: &dyn IdClock) -> Message {
+        let now = clock.timestamp();
+        Message::KanpeMessage {
+            id: clock.new_id(),
+            timestamp: now,
+            payload: KanpeMessagePayload {
+                content: self.content,
+                target_monitor_ids: self.target_monitor_ids,
+                priority: self.priority,
+                content_format: self.content_format,
+                expires_at: self.expiry.map(|expiry| match expiry {
+                    Expiry::At(at) => at,
+                    Expiry::After(ttl_ms) => now + ttl_ms,
+                }),
+                repeat_flash_interval_ms: self.repeat_flash_interval_ms,
+                invert: self.invert,
+            },
+        }
+    }
 }
 
 /// Payload for FeedbackMessage
@@ -128,6 +414,13 @@ pub struct MonitorListSyncPayload {
     pub monitors: Vec<VirtualMonitor>,
 }
 
+/// Payload for MonitorGroupSync message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonitorGroupSyncPayload {
+    /// List of all monitor groups
+    pub groups: Vec<MonitorGroup>,
+}
+
 /// Payload for MonitorAdded message
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonitorAddedPayload {
@@ -152,38 +445,268 @@ pub struct MonitorUpdatedPayload {
 /// Payload for FlashCommand
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FlashCommandPayload {
-    /// Target virtual monitor IDs ("ALL" = all monitors, or specific IDs like ["A", "B"])
+    /// Target virtual monitor IDs ([`ALL_MONITORS`](crate::types::ALL_MONITORS) = all monitors, or specific IDs like ["A", "B"])
     pub target_monitor_ids: Vec<String>,
+    /// Treat `target_monitor_ids` as monitors to skip, sending to everyone else
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub invert: bool,
 }
 
 /// Payload for ClearCommand
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClearCommandPayload {
-    /// Target virtual monitor IDs ("ALL" = all monitors, or specific IDs like ["A", "B"])
+    /// Target virtual monitor IDs ([`ALL_MONITORS`](crate::types::ALL_MONITORS) = all monitors, or specific IDs like ["A", "B"])
+    pub target_monitor_ids: Vec<String>,
+    /// Treat `target_monitor_ids` as monitors to skip, sending to everyone else
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub invert: bool,
+}
+
+/// Payload for PresenterNoteChunk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PresenterNoteChunkPayload {
+    /// ID shared by all chunks of the same note
+    pub note_id: String,
+    /// Zero-based position of this chunk
+    pub seq: u32,
+    /// Total number of chunks in the note
+    pub total: u32,
+    /// Chunk content (text)
+    pub data: String,
+    /// Target virtual monitor IDs ([`ALL_MONITORS`](crate::types::ALL_MONITORS) = all monitors, or specific IDs like ["A", "B"])
+    pub target_monitor_ids: Vec<String>,
+}
+
+/// Payload for PresenterNoteResend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PresenterNoteResendPayload {
+    /// ID of the incomplete note
+    pub note_id: String,
+    /// Sequence numbers of the chunks that never arrived
+    pub missing_seqs: Vec<u32>,
+}
+
+/// Payload for UpdateSubscription
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateSubscriptionPayload {
+    /// Virtual monitor IDs the client now displays
+    pub display_monitor_ids: Vec<String>,
+}
+
+/// Payload for MonitorChangeAck
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonitorChangeAckPayload {
+    /// ID of the MonitorListSync/Added/Removed/Updated message that was applied
+    pub change_id: String,
+}
+
+/// Payload for MessageRecalled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageRecalledPayload {
+    /// ID of the KanpeMessage being withdrawn
+    pub message_id: String,
+    /// Monitors the cue should be removed from
     pub target_monitor_ids: Vec<String>,
 }
 
+/// Payload for Pong
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PongPayload {
+    /// ID of the Ping being answered (empty from peers that predate it)
+    #[serde(default)]
+    pub ping_id: String,
+}
+
+/// Payload for MessageAck
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageAckPayload {
+    /// ID of the KanpeMessage being acknowledged
+    pub message_id: String,
+}
+
+/// Payload for ServerShutdown
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerShutdownPayload {
+    pub reason: String,
+    /// How long (ms) the server waits before closing the remaining connections
+    pub grace_ms: u64,
+}
+
+/// Payload for Reaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReactionPayload {
+    /// ID of the cue being reacted to
+    pub reply_to_message_id: String,
+    /// The reaction itself, usually a single emoji such as "👍"
+    pub reaction: String,
+    /// Client name who reacted
+    pub client_name: String,
+}
+
+/// Payload for FeedbackTyping
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedbackTypingPayload {
+    /// Client name of the operator composing
+    pub client_name: String,
+    /// True when composition starts, false when it stops or is sent
+    pub is_typing: bool,
+}
+
+/// Payload for AirStatus
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AirStatusPayload {
+    pub status: AirStatus,
+    /// Unix timestamp (ms) the status took effect
+    pub since: i64,
+}
+
 impl Message {
+    /// Parse a message from its JSON text
+    pub fn parse(s: &str) -> Result<Self, MessageParseError> {
+        Self::parse_frame(s).1
+    }
+
+    /// Parse a received frame into its broadcast sequence number and message
+    ///
+    /// The text is only parsed once. The sequence number is returned even when
+    /// the message itself cannot be read, so a client can still track gaps.
+    pub fn parse_frame(s: &str) -> (Option<u64>, Result<Self, MessageParseError>) {
+        match serde_json::from_str(s) {
+            Ok(value) => Self::split_seq(value),
+            Err(e) => (None, Err(MessageParseError::InvalidJson(e))),
+        }
+    }
+
+    /// Parse a binary MessagePack frame into its broadcast sequence number and message
+    pub fn parse_msgpack_frame(bytes: &[u8]) -> (Option<u64>, Result<Self, MessageParseError>) {
+        match rmp_serde::from_slice(bytes) {
+            Ok(value) => Self::split_seq(value),
+            Err(e) => (None, Err(MessageParseError::InvalidMsgpack(e))),
+        }
+    }
+
+    /// Take the sequence number off a decoded frame and read the message from the rest
+    fn split_seq(mut value: serde_json::Value) -> (Option<u64>, Result<Self, MessageParseError>) {
+        let seq = value
+            .as_object_mut()
+            .and_then(|object| object.remove("seq"))
+            .and_then(|seq| seq.as_u64());
+        (seq, Self::from_value(value))
+    }
+
+    /// Read a message from a parsed JSON value
+    fn from_value(value: serde_json::Value) -> Result<Self, MessageParseError> {
+        let message_type = match value.get("type") {
+            Some(serde_json::Value::String(message_type)) => message_type.clone(),
+            _ => return Err(MessageParseError::MissingField("type".to_string())),
+        };
+
+        if !MESSAGE_TYPES.contains(&message_type.as_str()) {
+            return Err(MessageParseError::UnknownType(message_type));
+        }
+
+        serde_json::from_value(value).map_err(|e| {
+            // serde_json only names the missing field in its message text
+            match e
+                .to_string()
+                .strip_prefix("missing field `")
+                .and_then(|rest| rest.split('`').next())
+            {
+                Some(field) => MessageParseError::MissingField(field.to_string()),
+                None => MessageParseError::Malformed(e),
+            }
+        })
+    }
+
+    /// Serialize the message to JSON text
+    pub fn to_json(&self) -> Result<String, MessageParseError> {
+        serde_json::to_string(self).map_err(MessageParseError::Serialize)
+    }
+
+    /// Serialize the message to JSON text as broadcast frame number `seq`
+    pub fn to_json_with_seq(&self, seq: u64) -> Result<String, MessageParseError> {
+        serde_json::to_string(&Sequenced { seq: Some(seq), message: self }).map_err(MessageParseError::Serialize)
+    }
+
+    /// Encode the message in `format`, as broadcast frame number `seq` if given
+    ///
+    /// MessagePack frames use named fields, so they decode to the same
+    /// structure as the JSON ones.
+    pub fn encode(&self, format: WireFormat, seq: Option<u64>) -> Result<Frame, MessageParseError> {
+        let sequenced = Sequenced { seq, message: self };
+        match format {
+            WireFormat::Json => serde_json::to_string(&sequenced)
+                .map(Frame::Text)
+                .map_err(MessageParseError::Serialize),
+            WireFormat::Msgpack => rmp_serde::to_vec_named(&sequenced)
+                .map(Frame::Binary)
+                .map_err(MessageParseError::EncodeMsgpack),
+        }
+    }
+
     /// Create a new ClientHello message
     pub fn client_hello(client_name: String, display_monitor_ids: Vec<String>) -> Self {
+        Self::client_hello_resuming(client_name, display_monitor_ids, None)
+    }
+
+    /// Create a new ClientHello message that asks to resume an earlier session
+    pub fn client_hello_resuming(
+        client_name: String,
+        display_monitor_ids: Vec<String>,
+        resume_token: Option<String>,
+    ) -> Self {
+        Self::client_hello_resuming_with(client_name, display_monitor_ids, resume_token, &SystemClock)
+    }
+
+    /// Like [`client_hello_resuming`](Self::client_hello_resuming), taking the ID and timestamp from `clock`
+    pub fn client_hello_resuming_with(
+        client_name: String,
+        display_monitor_ids: Vec<String>,
+        resume_token: Option<String>,
+        clock: &dyn IdClock,
+    ) -> Self {
         Message::ClientHello {
-            id: new_id(),
-            timestamp: timestamp(),
+            id: clock.new_id(),
+            timestamp: clock.timestamp(),
             payload: ClientHelloPayload {
                 client_name,
                 display_monitor_ids,
+                wire_format: WireFormat::Json,
+                resume_token,
             },
         }
     }
 
     /// Create a new ServerWelcome message
     pub fn server_welcome(server_name: String, assigned_client_id: String) -> Self {
+        Self::server_welcome_with_resume_token(server_name, assigned_client_id, None)
+    }
+
+    /// Create a new ServerWelcome message handing the client a resume token
+    pub fn server_welcome_with_resume_token(
+        server_name: String,
+        assigned_client_id: String,
+        resume_token: Option<String>,
+    ) -> Self {
+        Self::server_welcome_with_resume_token_with(server_name, assigned_client_id, resume_token, &SystemClock)
+    }
+
+    /// Like [`server_welcome_with_resume_token`](Self::server_welcome_with_resume_token), taking the ID and timestamp from `clock`
+    pub fn server_welcome_with_resume_token_with(
+        server_name: String,
+        assigned_client_id: String,
+        resume_token: Option<String>,
+        clock: &dyn IdClock,
+    ) -> Self {
         Message::ServerWelcome {
-            id: new_id(),
-            timestamp: timestamp(),
+            id: clock.new_id(),
+            timestamp: clock.timestamp(),
             payload: ServerWelcomePayload {
                 server_name,
                 assigned_client_id,
+                wire_format: WireFormat::Json,
+                resume_token,
+                assigned_client_name: None,
             },
         }
     }
@@ -194,15 +717,54 @@ impl Message {
         target_monitor_ids: Vec<String>,
         priority: Priority,
     ) -> Self {
-        Message::KanpeMessage {
-            id: new_id(),
-            timestamp: timestamp(),
-            payload: KanpeMessagePayload {
-                content,
-                target_monitor_ids,
-                priority,
-            },
-        }
+        KanpeMessageBuilder::new()
+            .content(content)
+            .targets(target_monitor_ids)
+            .priority(priority)
+            .build()
+    }
+
+    /// Create a new urgent KanpeMessage whose targets are re-flashed every `repeat_flash_interval_ms` until acked
+    pub fn urgent_kanpe_message(
+        content: String,
+        target_monitor_ids: Vec<String>,
+        repeat_flash_interval_ms: u64,
+    ) -> Self {
+        KanpeMessageBuilder::new()
+            .content(content)
+            .targets(target_monitor_ids)
+            .priority(Priority::Urgent)
+            .repeat_flash_interval_ms(repeat_flash_interval_ms)
+            .build()
+    }
+
+    /// Create a new KanpeMessage that expires `ttl_ms` milliseconds from now
+    pub fn kanpe_message_with_ttl(
+        content: String,
+        target_monitor_ids: Vec<String>,
+        priority: Priority,
+        ttl_ms: i64,
+    ) -> Self {
+        KanpeMessageBuilder::new()
+            .content(content)
+            .targets(target_monitor_ids)
+            .priority(priority)
+            .ttl(ttl_ms)
+            .build()
+    }
+
+    /// Create a new KanpeMessage shown on every monitor except `excluded_monitor_ids`
+    pub fn kanpe_message_except(
+        content: String,
+        excluded_monitor_ids: Vec<String>,
+        priority: Priority,
+    ) -> Self {
+        KanpeMessageBuilder::new()
+            .content(content)
+            .targets(excluded_monitor_ids)
+            .priority(priority)
+            .invert(true)
+            .build()
     }
 
     /// Create a new FeedbackMessage
@@ -211,10 +773,21 @@ impl Message {
         client_name: String,
         reply_to_message_id: String,
         feedback_type: FeedbackType,
+    ) -> Self {
+        Self::feedback_message_with(content, client_name, reply_to_message_id, feedback_type, &SystemClock)
+    }
+
+    /// Like [`feedback_message`](Self::feedback_message), taking the ID and timestamp from `clock`
+    pub fn feedback_message_with(
+        content: String,
+        client_name: String,
+        reply_to_message_id: String,
+        feedback_type: FeedbackType,
+        clock: &dyn IdClock,
     ) -> Self {
         Message::FeedbackMessage {
-            id: new_id(),
-            timestamp: timestamp(),
+            id: clock.new_id(),
+            timestamp: clock.timestamp(),
             payload: FeedbackMessagePayload {
                 content,
                 client_name,
@@ -226,71 +799,432 @@ impl Message {
 
     /// Create a new Ping message
     pub fn ping() -> Self {
+        Self::ping_with(&SystemClock)
+    }
+
+    /// Like [`ping`](Self::ping), taking the ID and timestamp from `clock`
+    pub fn ping_with(clock: &dyn IdClock) -> Self {
         Message::Ping {
-            id: new_id(),
-            timestamp: timestamp(),
+            id: clock.new_id(),
+            timestamp: clock.timestamp(),
         }
     }
 
-    /// Create a new Pong message
-    pub fn pong() -> Self {
+    /// Create a new Pong message answering the Ping with `ping_id`
+    pub fn pong_for(ping_id: String) -> Self {
+        Self::pong_for_with(ping_id, &SystemClock)
+    }
+
+    /// Like [`pong_for`](Self::pong_for), taking the ID and timestamp from `clock`
+    pub fn pong_for_with(ping_id: String, clock: &dyn IdClock) -> Self {
         Message::Pong {
-            id: new_id(),
-            timestamp: timestamp(),
+            id: clock.new_id(),
+            timestamp: clock.timestamp(),
+            payload: PongPayload { ping_id },
         }
     }
 
     /// Create a new MonitorListSync message
     pub fn monitor_list_sync(monitors: Vec<VirtualMonitor>) -> Self {
+        Self::monitor_list_sync_with(monitors, &SystemClock)
+    }
+
+    /// Like [`monitor_list_sync`](Self::monitor_list_sync), taking the ID and timestamp from `clock`
+    pub fn monitor_list_sync_with(monitors: Vec<VirtualMonitor>, clock: &dyn IdClock) -> Self {
         Message::MonitorListSync {
-            id: new_id(),
-            timestamp: timestamp(),
+            id: clock.new_id(),
+            timestamp: clock.timestamp(),
             payload: MonitorListSyncPayload { monitors },
         }
     }
 
+    /// Create a new MonitorGroupSync message
+    pub fn monitor_group_sync(groups: Vec<MonitorGroup>) -> Self {
+        Self::monitor_group_sync_with(groups, &SystemClock)
+    }
+
+    /// Like [`monitor_group_sync`](Self::monitor_group_sync), taking the ID and timestamp from `clock`
+    pub fn monitor_group_sync_with(groups: Vec<MonitorGroup>, clock: &dyn IdClock) -> Self {
+        Message::MonitorGroupSync {
+            id: clock.new_id(),
+            timestamp: clock.timestamp(),
+            payload: MonitorGroupSyncPayload { groups },
+        }
+    }
+
     /// Create a new MonitorAdded message
     pub fn monitor_added(monitor: VirtualMonitor) -> Self {
+        Self::monitor_added_with(monitor, &SystemClock)
+    }
+
+    /// Like [`monitor_added`](Self::monitor_added), taking the ID and timestamp from `clock`
+    pub fn monitor_added_with(monitor: VirtualMonitor, clock: &dyn IdClock) -> Self {
         Message::MonitorAdded {
-            id: new_id(),
-            timestamp: timestamp(),
+            id: clock.new_id(),
+            timestamp: clock.timestamp(),
             payload: MonitorAddedPayload { monitor },
         }
     }
 
     /// Create a new MonitorRemoved message
     pub fn monitor_removed(monitor_id: String) -> Self {
+        Self::monitor_removed_with(monitor_id, &SystemClock)
+    }
+
+    /// Like [`monitor_removed`](Self::monitor_removed), taking the ID and timestamp from `clock`
+    pub fn monitor_removed_with(monitor_id: String, clock: &dyn IdClock) -> Self {
         Message::MonitorRemoved {
-            id: new_id(),
-            timestamp: timestamp(),
+            id: clock.new_id(),
+            timestamp: clock.timestamp(),
             payload: MonitorRemovedPayload { monitor_id },
         }
     }
 
     /// Create a new MonitorUpdated message
     pub fn monitor_updated(monitor: VirtualMonitor) -> Self {
+        Self::monitor_updated_with(monitor, &SystemClock)
+    }
+
+    /// Like [`monitor_updated`](Self::monitor_updated), taking the ID and timestamp from `clock`
+    pub fn monitor_updated_with(monitor: VirtualMonitor, clock: &dyn IdClock) -> Self {
         Message::MonitorUpdated {
-            id: new_id(),
-            timestamp: timestamp(),
+            id: clock.new_id(),
+            timestamp: clock.timestamp(),
             payload: MonitorUpdatedPayload { monitor },
         }
     }
 
     /// Create a new FlashCommand message
     pub fn flash_command(target_monitor_ids: Vec<String>) -> Self {
+        Self::flash_command_with(target_monitor_ids, &SystemClock)
+    }
+
+    /// Like [`flash_command`](Self::flash_command), taking the ID and timestamp from `clock`
+    pub fn flash_command_with(target_monitor_ids: Vec<String>, clock: &dyn IdClock) -> Self {
         Message::FlashCommand {
-            id: new_id(),
-            timestamp: timestamp(),
-            payload: FlashCommandPayload { target_monitor_ids },
+            id: clock.new_id(),
+            timestamp: clock.timestamp(),
+            payload: FlashCommandPayload {
+                target_monitor_ids,
+                invert: false,
+            },
+        }
+    }
+
+    /// Create a FlashCommand for every monitor except `excluded_monitor_ids`
+    pub fn flash_command_except(excluded_monitor_ids: Vec<String>) -> Self {
+        Self::flash_command_except_with(excluded_monitor_ids, &SystemClock)
+    }
+
+    /// Like [`flash_command_except`](Self::flash_command_except), taking the ID and timestamp from `clock`
+    pub fn flash_command_except_with(excluded_monitor_ids: Vec<String>, clock: &dyn IdClock) -> Self {
+        Message::FlashCommand {
+            id: clock.new_id(),
+            timestamp: clock.timestamp(),
+            payload: FlashCommandPayload {
+                target_monitor_ids: excluded_monitor_ids,
+                invert: true,
+            },
         }
     }
 
     /// Create a new ClearCommand message
     pub fn clear_command(target_monitor_ids: Vec<String>) -> Self {
+        Self::clear_command_with(target_monitor_ids, &SystemClock)
+    }
+
+    /// Like [`clear_command`](Self::clear_command), taking the ID and timestamp from `clock`
+    pub fn clear_command_with(target_monitor_ids: Vec<String>, clock: &dyn IdClock) -> Self {
+        Message::ClearCommand {
+            id: clock.new_id(),
+            timestamp: clock.timestamp(),
+            payload: ClearCommandPayload {
+                target_monitor_ids,
+                invert: false,
+            },
+        }
+    }
+
+    /// Create a ClearCommand for every monitor except `excluded_monitor_ids`
+    pub fn clear_command_except(excluded_monitor_ids: Vec<String>) -> Self {
+        Self::clear_command_except_with(excluded_monitor_ids, &SystemClock)
+    }
+
+    /// Like [`clear_command_except`](Self::clear_command_except), taking the ID and timestamp from `clock`
+    pub fn clear_command_except_with(excluded_monitor_ids: Vec<String>, clock: &dyn IdClock) -> Self {
         Message::ClearCommand {
-            id: new_id(),
-            timestamp: timestamp(),
-            payload: ClearCommandPayload { target_monitor_ids },
+            id: clock.new_id(),
+            timestamp: clock.timestamp(),
+            payload: ClearCommandPayload {
+                target_monitor_ids: excluded_monitor_ids,
+                invert: true,
+            },
+        }
+    }
+
+    /// Split a presenter note into PresenterNoteChunk messages of at most `max_chunk_bytes` each
+    ///
+    /// Chunks are split on character boundaries, so a chunk may be slightly smaller than the limit.
+    pub fn presenter_note_chunks(
+        content: &str,
+        target_monitor_ids: Vec<String>,
+        max_chunk_bytes: usize,
+    ) -> Vec<Self> {
+        Self::presenter_note_chunks_with(content, target_monitor_ids, max_chunk_bytes, &SystemClock)
+    }
+
+    /// Like [`presenter_note_chunks`](Self::presenter_note_chunks), taking the note ID,
+    /// chunk IDs and timestamps from `clock`
+    pub fn presenter_note_chunks_with(
+        content: &str,
+        target_monitor_ids: Vec<String>,
+        max_chunk_bytes: usize,
+        clock: &dyn IdClock,
+    ) -> Vec<Self> {
+        let max_chunk_bytes = max_chunk_bytes.max(4);
+        let mut parts = Vec::new();
+        let mut rest = content;
+        while !rest.is_empty() {
+            let mut end = max_chunk_bytes.min(rest.len());
+            while !rest.is_char_boundary(end) {
+                end -= 1;
+            }
+            parts.push(&rest[..end]);
+            rest = &rest[end..];
+        }
+        if parts.is_empty() {
+            parts.push("");
+        }
+
+        let note_id = clock.new_id();
+        let total = parts.len() as u32;
+        parts
+            .into_iter()
+            .enumerate()
+            .map(|(seq, data)| Message::PresenterNoteChunk {
+                id: clock.new_id(),
+                timestamp: clock.timestamp(),
+                payload: PresenterNoteChunkPayload {
+                    note_id: note_id.clone(),
+                    seq: seq as u32,
+                    total,
+                    data: data.to_string(),
+                    target_monitor_ids: target_monitor_ids.clone(),
+                },
+            })
+            .collect()
+    }
+
+    /// Create a new PresenterNoteResend message
+    pub fn presenter_note_resend(note_id: String, missing_seqs: Vec<u32>) -> Self {
+        Self::presenter_note_resend_with(note_id, missing_seqs, &SystemClock)
+    }
+
+    /// Like [`presenter_note_resend`](Self::presenter_note_resend), taking the ID and timestamp from `clock`
+    pub fn presenter_note_resend_with(note_id: String, missing_seqs: Vec<u32>, clock: &dyn IdClock) -> Self {
+        Message::PresenterNoteResend {
+            id: clock.new_id(),
+            timestamp: clock.timestamp(),
+            payload: PresenterNoteResendPayload {
+                note_id,
+                missing_seqs,
+            },
+        }
+    }
+
+    /// Create a new UpdateSubscription message
+    pub fn update_subscription(display_monitor_ids: Vec<String>) -> Self {
+        Self::update_subscription_with(display_monitor_ids, &SystemClock)
+    }
+
+    /// Like [`update_subscription`](Self::update_subscription), taking the ID and timestamp from `clock`
+    pub fn update_subscription_with(display_monitor_ids: Vec<String>, clock: &dyn IdClock) -> Self {
+        Message::UpdateSubscription {
+            id: clock.new_id(),
+            timestamp: clock.timestamp(),
+            payload: UpdateSubscriptionPayload { display_monitor_ids },
+        }
+    }
+
+    /// Create a new MonitorChangeAck message
+    pub fn monitor_change_ack(change_id: String) -> Self {
+        Self::monitor_change_ack_with(change_id, &SystemClock)
+    }
+
+    /// Like [`monitor_change_ack`](Self::monitor_change_ack), taking the ID and timestamp from `clock`
+    pub fn monitor_change_ack_with(change_id: String, clock: &dyn IdClock) -> Self {
+        Message::MonitorChangeAck {
+            id: clock.new_id(),
+            timestamp: clock.timestamp(),
+            payload: MonitorChangeAckPayload { change_id },
+        }
+    }
+
+    /// Create a new MessageRecalled message
+    pub fn message_recalled(message_id: String, target_monitor_ids: Vec<String>) -> Self {
+        Self::message_recalled_with(message_id, target_monitor_ids, &SystemClock)
+    }
+
+    /// Like [`message_recalled`](Self::message_recalled), taking the ID and timestamp from `clock`
+    pub fn message_recalled_with(message_id: String, target_monitor_ids: Vec<String>, clock: &dyn IdClock) -> Self {
+        Message::MessageRecalled {
+            id: clock.new_id(),
+            timestamp: clock.timestamp(),
+            payload: MessageRecalledPayload {
+                message_id,
+                target_monitor_ids,
+            },
+        }
+    }
+
+    /// Create a new AirStatus message
+    pub fn air_status(status: AirStatus, since: i64) -> Self {
+        Self::air_status_with(status, since, &SystemClock)
+    }
+
+    /// Like [`air_status`](Self::air_status), taking the ID and timestamp from `clock`
+    pub fn air_status_with(status: AirStatus, since: i64, clock: &dyn IdClock) -> Self {
+        Message::AirStatus {
+            id: clock.new_id(),
+            timestamp: clock.timestamp(),
+            payload: AirStatusPayload { status, since },
+        }
+    }
+
+    /// Create a new ReconnectRequest message
+    pub fn reconnect_request() -> Self {
+        Self::reconnect_request_with(&SystemClock)
+    }
+
+    /// Like [`reconnect_request`](Self::reconnect_request), taking the ID and timestamp from `clock`
+    pub fn reconnect_request_with(clock: &dyn IdClock) -> Self {
+        Message::ReconnectRequest {
+            id: clock.new_id(),
+            timestamp: clock.timestamp(),
+        }
+    }
+
+    /// Create a new MessageAck message
+    pub fn message_ack(message_id: String) -> Self {
+        Self::message_ack_with(message_id, &SystemClock)
+    }
+
+    /// Like [`message_ack`](Self::message_ack), taking the ID and timestamp from `clock`
+    pub fn message_ack_with(message_id: String, clock: &dyn IdClock) -> Self {
+        Message::MessageAck {
+            id: clock.new_id(),
+            timestamp: clock.timestamp(),
+            payload: MessageAckPayload { message_id },
+        }
+    }
+
+    /// Create a new ServerShutdown message
+    pub fn server_shutdown(reason: String, grace_ms: u64) -> Self {
+        Self::server_shutdown_with(reason, grace_ms, &SystemClock)
+    }
+
+    /// Like [`server_shutdown`](Self::server_shutdown), taking the ID and timestamp from `clock`
+    pub fn server_shutdown_with(reason: String, grace_ms: u64, clock: &dyn IdClock) -> Self {
+        Message::ServerShutdown {
+            id: clock.new_id(),
+            timestamp: clock.timestamp(),
+            payload: ServerShutdownPayload { reason, grace_ms },
+        }
+    }
+
+    /// Create a new Reaction message
+    pub fn reaction(reply_to_message_id: String, reaction: String, client_name: String) -> Self {
+        Self::reaction_with(reply_to_message_id, reaction, client_name, &SystemClock)
+    }
+
+    /// Like [`reaction`](Self::reaction), taking the ID and timestamp from `clock`
+    pub fn reaction_with(reply_to_message_id: String, reaction: String, client_name: String, clock: &dyn IdClock) -> Self {
+        Message::Reaction {
+            id: clock.new_id(),
+            timestamp: clock.timestamp(),
+            payload: ReactionPayload {
+                reply_to_message_id,
+                reaction,
+                client_name,
+            },
+        }
+    }
+
+    /// Create a new FeedbackTyping message
+    pub fn feedback_typing(client_name: String, is_typing: bool) -> Self {
+        Self::feedback_typing_with(client_name, is_typing, &SystemClock)
+    }
+
+    /// Like [`feedback_typing`](Self::feedback_typing), taking the ID and timestamp from `clock`
+    pub fn feedback_typing_with(client_name: String, is_typing: bool, clock: &dyn IdClock) -> Self {
+        Message::FeedbackTyping {
+            id: clock.new_id(),
+            timestamp: clock.timestamp(),
+            payload: FeedbackTypingPayload { client_name, is_typing },
+        }
+    }
+
+    /// Get the monitors a message is addressed to, or None if it goes to every client
+    pub fn target_monitor_ids(&self) -> Option<&[String]> {
+        match self {
+            Message::KanpeMessage { payload, .. } => Some(&payload.target_monitor_ids),
+            Message::FlashCommand { payload, .. } => Some(&payload.target_monitor_ids),
+            Message::ClearCommand { payload, .. } => Some(&payload.target_monitor_ids),
+            Message::PresenterNoteChunk { payload, .. } => Some(&payload.target_monitor_ids),
+            Message::MessageRecalled { payload, .. } => Some(&payload.target_monitor_ids),
+            _ => None,
+        }
+    }
+
+    /// Check whether the message's targets are monitors to skip rather than to send to
+    pub fn targets_inverted(&self) -> bool {
+        match self {
+            Message::KanpeMessage { payload, .. } => payload.invert,
+            Message::FlashCommand { payload, .. } => payload.invert,
+            Message::ClearCommand { payload, .. } => payload.invert,
+            _ => false,
+        }
+    }
+
+    /// Mutable access to the monitors a message is addressed to, for rewriting targets before sending
+    pub fn target_monitor_ids_mut(&mut self) -> Option<&mut Vec<String>> {
+        match self {
+            Message::KanpeMessage { payload, .. } => Some(&mut payload.target_monitor_ids),
+            Message::FlashCommand { payload, .. } => Some(&mut payload.target_monitor_ids),
+            Message::ClearCommand { payload, .. } => Some(&mut payload.target_monitor_ids),
+            Message::PresenterNoteChunk { payload, .. } => Some(&mut payload.target_monitor_ids),
+            Message::MessageRecalled { payload, .. } => Some(&mut payload.target_monitor_ids),
+            _ => None,
+        }
+    }
+
+    /// Get the message's `type` tag as it appears on the wire
+    pub fn message_type(&self) -> &'static str {
+        match self {
+            Message::ClientHello { .. } => "client_hello",
+            Message::ServerWelcome { .. } => "server_welcome",
+            Message::KanpeMessage { .. } => "kanpe_message",
+            Message::FeedbackMessage { .. } => "feedback_message",
+            Message::Ping { .. } => "ping",
+            Message::Pong { .. } => "pong",
+            Message::MonitorListSync { .. } => "monitor_list_sync",
+            Message::MonitorGroupSync { .. } => "monitor_group_sync",
+            Message::MonitorAdded { .. } => "monitor_added",
+            Message::MonitorRemoved { .. } => "monitor_removed",
+            Message::MonitorUpdated { .. } => "monitor_updated",
+            Message::FlashCommand { .. } => "flash_command",
+            Message::ClearCommand { .. } => "clear_command",
+            Message::PresenterNoteChunk { .. } => "presenter_note_chunk",
+            Message::PresenterNoteResend { .. } => "presenter_note_resend",
+            Message::UpdateSubscription { .. } => "update_subscription",
+            Message::MonitorChangeAck { .. } => "monitor_change_ack",
+            Message::MessageRecalled { .. } => "message_recalled",
+            Message::AirStatus { .. } => "air_status",
+            Message::ReconnectRequest { .. } => "reconnect_request",
+            Message::MessageAck { .. } => "message_ack",
+            Message::ServerShutdown { .. } => "server_shutdown",
+            Message::Reaction { .. } => "reaction",
+            Message::FeedbackTyping { .. } => "feedback_typing",
         }
     }
 
@@ -304,11 +1238,23 @@ impl Message {
             Message::Ping { id, .. } => id,
             Message::Pong { id, .. } => id,
             Message::MonitorListSync { id, .. } => id,
+            Message::MonitorGroupSync { id, .. } => id,
             Message::MonitorAdded { id, .. } => id,
             Message::MonitorRemoved { id, .. } => id,
             Message::MonitorUpdated { id, .. } => id,
             Message::FlashCommand { id, .. } => id,
             Message::ClearCommand { id, .. } => id,
+            Message::PresenterNoteChunk { id, .. } => id,
+            Message::PresenterNoteResend { id, .. } => id,
+            Message::UpdateSubscription { id, .. } => id,
+            Message::MonitorChangeAck { id, .. } => id,
+            Message::MessageRecalled { id, .. } => id,
+            Message::AirStatus { id, .. } => id,
+            Message::ReconnectRequest { id, .. } => id,
+            Message::MessageAck { id, .. } => id,
+            Message::ServerShutdown { id, .. } => id,
+            Message::Reaction { id, .. } => id,
+            Message::FeedbackTyping { id, .. } => id,
         }
     }
 
@@ -322,11 +1268,23 @@ impl Message {
             Message::Ping { timestamp, .. } => *timestamp,
             Message::Pong { timestamp, .. } => *timestamp,
             Message::MonitorListSync { timestamp, .. } => *timestamp,
+            Message::MonitorGroupSync { timestamp, .. } => *timestamp,
             Message::MonitorAdded { timestamp, .. } => *timestamp,
             Message::MonitorRemoved { timestamp, .. } => *timestamp,
             Message::MonitorUpdated { timestamp, .. } => *timestamp,
             Message::FlashCommand { timestamp, .. } => *timestamp,
             Message::ClearCommand { timestamp, .. } => *timestamp,
+            Message::PresenterNoteChunk { timestamp, .. } => *timestamp,
+            Message::PresenterNoteResend { timestamp, .. } => *timestamp,
+            Message::UpdateSubscription { timestamp, .. } => *timestamp,
+            Message::MonitorChangeAck { timestamp, .. } => *timestamp,
+            Message::MessageRecalled { timestamp, .. } => *timestamp,
+            Message::AirStatus { timestamp, .. } => *timestamp,
+            Message::ReconnectRequest { timestamp, .. } => *timestamp,
+            Message::MessageAck { timestamp, .. } => *timestamp,
+            Message::ServerShutdown { timestamp, .. } => *timestamp,
+            Message::Reaction { timestamp, .. } => *timestamp,
+            Message::FeedbackTyping { timestamp, .. } => *timestamp,
         }
     }
 }
@@ -353,6 +1311,18 @@ mod tests {
         assert!(json.contains("\"assigned_client_id\":\"client-123\""));
     }
 
+    #[test]
+    fn test_wire_format_defaults_to_json() {
+        let json = r#"{"type":"client_hello","id":"1","timestamp":0,"payload":{"client_name":"Old","display_monitor_ids":[]}}"#;
+        match Message::parse(json).unwrap() {
+            Message::ClientHello { payload, .. } => assert_eq!(payload.wire_format, WireFormat::Json),
+            other => panic!("expected ClientHello, got {:?}", other),
+        }
+
+        let msg = Message::server_welcome("TestServer".to_string(), "client-123".to_string());
+        assert!(msg.to_json().unwrap().contains("\"wire_format\":\"json\""));
+    }
+
     #[test]
     fn test_kanpe_message_serialization() {
         let msg = Message::kanpe_message(
@@ -367,6 +1337,119 @@ mod tests {
         assert!(json.contains("\"priority\":\"high\""));
     }
 
+    #[test]
+    fn test_builder_matches_positional_constructor() {
+        let payload = |message: Message| match message {
+            Message::KanpeMessage { payload, .. } => payload,
+            other => panic!("expected KanpeMessage, got {:?}", other),
+        };
+        let targets = vec!["A".to_string(), "B".to_string()];
+
+        let built = payload(
+            KanpeMessageBuilder::new()
+                .content("Wrap up")
+                .targets(targets.clone())
+                .priority(Priority::High)
+                .build(),
+        );
+        assert_eq!(built.content, "Wrap up");
+        assert_eq!(built.target_monitor_ids, targets);
+        assert_eq!(built.priority, Priority::High);
+        let positional = payload(Message::kanpe_message("Wrap up".to_string(), targets, Priority::High));
+        assert_eq!(serde_json::to_value(built).unwrap(), serde_json::to_value(positional).unwrap());
+
+        // Defaults are a normal-priority plain cue with no optional fields
+        let built = payload(KanpeMessageBuilder::new().build());
+        assert_eq!(built.content, "");
+        assert!(built.target_monitor_ids.is_empty());
+        assert_eq!(built.priority, Priority::Normal);
+        assert_eq!(built.content_format, ContentFormat::Plain);
+        assert_eq!(built.expires_at, None);
+        assert_eq!(built.repeat_flash_interval_ms, None);
+        assert!(!built.invert);
+    }
+
+    #[test]
+    fn test_builder_sets_optional_fields() {
+        let msg = KanpeMessageBuilder::new().content("Stretch").ttl(5_000).repeat_flash_interval_ms(500).build();
+        match msg {
+            Message::KanpeMessage { timestamp, payload, .. } => {
+                assert_eq!(payload.expires_at, Some(timestamp + 5_000));
+                assert_eq!(payload.repeat_flash_interval_ms, Some(500));
+            }
+            other => panic!("expected KanpeMessage, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_build_with_fixed_clock_is_reproducible() {
+        let clock = crate::types::FixedClock {
+            id: "cue-1".to_string(),
+            timestamp: 1_700_000_000_000,
+        };
+        let msg = KanpeMessageBuilder::new().content("Stand by").ttl(5_000).build_with(&clock);
+        assert_eq!(msg.id(), "cue-1");
+        assert_eq!(msg.timestamp(), 1_700_000_000_000);
+        match msg {
+            Message::KanpeMessage { payload, .. } => assert_eq!(payload.expires_at, Some(1_700_000_005_000)),
+            other => panic!("expected KanpeMessage, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_plain_constructors_accept_a_fixed_clock() {
+        let clock = crate::types::FixedClock {
+            id: "msg-1".to_string(),
+            timestamp: 1_700_000_000_000,
+        };
+        let messages = [
+            Message::feedback_message_with("了解".to_string(), "caster".to_string(), "cue-1".to_string(), FeedbackType::Ack, &clock),
+            Message::flash_command_with(vec!["A".to_string()], &clock),
+            Message::clear_command_except_with(vec!["A".to_string()], &clock),
+            Message::ping_with(&clock),
+        ];
+        for msg in &messages {
+            assert_eq!(msg.id(), "msg-1");
+            assert_eq!(msg.timestamp(), 1_700_000_000_000);
+        }
+
+        let chunks = Message::presenter_note_chunks_with("abcdefgh", vec!["A".to_string()], 4, &clock);
+        assert_eq!(chunks.len(), 2);
+        for chunk in &chunks {
+            let Message::PresenterNoteChunk { id, timestamp, payload } = chunk else {
+                panic!("expected PresenterNoteChunk, got {:?}", chunk);
+            };
+            assert_eq!((id.as_str(), *timestamp, payload.note_id.as_str()), ("msg-1", 1_700_000_000_000, "msg-1"));
+        }
+    }
+
+    #[test]
+    fn test_content_format_plain_is_omitted_and_markdown_round_trips() {
+        let plain = Message::kanpe_message("mic check".to_string(), vec!["A".to_string()], Priority::Normal);
+        assert!(!plain.to_json().unwrap().contains("content_format"));
+
+        // Cues from before the field existed parse as plain
+        let legacy = r#"{"type":"kanpe_message","id":"1","timestamp":0,"payload":{"content":"hi","target_monitor_ids":["A"],"priority":"normal"}}"#;
+        match Message::parse(legacy).unwrap() {
+            Message::KanpeMessage { payload, .. } => assert_eq!(payload.content_format, ContentFormat::Plain),
+            other => panic!("expected KanpeMessage, got {:?}", other),
+        }
+
+        let markdown = KanpeMessageBuilder::new()
+            .content("**STOP** — mic check")
+            .content_format(ContentFormat::Markdown)
+            .build();
+        let json = markdown.to_json().unwrap();
+        assert!(json.contains("\"content_format\":\"markdown\""));
+        match Message::parse(&json).unwrap() {
+            Message::KanpeMessage { payload, .. } => {
+                assert_eq!(payload.content_format, ContentFormat::Markdown);
+                assert_eq!(payload.content, "**STOP** — mic check");
+            }
+            other => panic!("expected KanpeMessage, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_feedback_message_serialization() {
         let msg = Message::feedback_message(
@@ -383,15 +1466,65 @@ mod tests {
         assert!(json.contains("\"feedback_type\":\"ack\""));
     }
 
+    #[test]
+    fn test_reaction_serialization() {
+        let msg = Message::reaction("msg-123".to_string(), "👍".to_string(), "TestClient".to_string());
+        let json = msg.to_json().unwrap();
+        assert!(json.contains("\"type\":\"reaction\""));
+        assert!(json.contains("\"reply_to_message_id\":\"msg-123\""));
+        assert!(json.contains("\"reaction\":\"👍\""));
+        assert!(json.contains("\"client_name\":\"TestClient\""));
+        assert_eq!(msg.message_type(), "reaction");
+
+        match Message::parse(&json).unwrap() {
+            Message::Reaction { payload, .. } => assert_eq!(payload.reaction, "👍"),
+            other => panic!("expected Reaction, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_feedback_typing_serialization() {
+        let msg = Message::feedback_typing("Caster B".to_string(), true);
+        let json = msg.to_json().unwrap();
+        assert!(json.contains("\"type\":\"feedback_typing\""));
+        assert!(json.contains("\"client_name\":\"Caster B\""));
+        assert!(json.contains("\"is_typing\":true"));
+
+        match Message::parse(&json).unwrap() {
+            Message::FeedbackTyping { payload, .. } => assert!(payload.is_typing),
+            other => panic!("expected FeedbackTyping, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_ping_pong_serialization() {
         let ping = Message::ping();
         let json = serde_json::to_string(&ping).unwrap();
         assert!(json.contains("\"type\":\"ping\""));
 
-        let pong = Message::pong();
+        let pong = Message::pong_for(ping.id().to_string());
         let json = serde_json::to_string(&pong).unwrap();
         assert!(json.contains("\"type\":\"pong\""));
+        assert!(json.contains(&format!("\"ping_id\":\"{}\"", ping.id())));
+
+        // Pongs without a payload still parse
+        let legacy: Message = serde_json::from_str(r#"{"type":"pong","id":"p","timestamp":1}"#).unwrap();
+        assert!(matches!(legacy, Message::Pong { payload, .. } if payload.ping_id.is_empty()));
+    }
+
+    #[test]
+    fn test_kanpe_message_with_ttl_expires() {
+        let msg = Message::kanpe_message_with_ttl("Test".to_string(), vec!["ALL".to_string()], Priority::Normal, 1000);
+        let Message::KanpeMessage { timestamp, payload, .. } = msg else {
+            panic!("expected KanpeMessage");
+        };
+        assert_eq!(payload.expires_at, Some(timestamp + 1000));
+        assert!(!payload.is_expired(timestamp + 999));
+        assert!(payload.is_expired(timestamp + 1000));
+
+        // Cues without a TTL never expire and omit the field on the wire
+        let plain = Message::kanpe_message("Test".to_string(), vec!["ALL".to_string()], Priority::Normal);
+        assert!(!serde_json::to_string(&plain).unwrap().contains("expires_at"));
     }
 
     #[test]
@@ -423,6 +1556,18 @@ mod tests {
         assert!(json.contains("\"target_monitor_ids\":[\"1\",\"2\"]"));
     }
 
+    #[test]
+    fn test_invert_is_omitted_unless_set() {
+        let plain = serde_json::to_string(&Message::flash_command(vec!["A".to_string()])).unwrap();
+        assert!(!plain.contains("invert"));
+
+        let msg = Message::clear_command_except(vec!["A".to_string()]);
+        let json = serde_json::to_string(&msg).unwrap();
+        assert!(json.contains("\"invert\":true"));
+        assert!(Message::parse(&json).unwrap().targets_inverted());
+        assert!(KanpeMessageBuilder::new().invert(true).build().targets_inverted());
+    }
+
     #[test]
     fn test_clear_command_serialization() {
         let msg = Message::clear_command(vec!["ALL".to_string()]);
@@ -430,4 +1575,174 @@ mod tests {
         assert!(json.contains("\"type\":\"clear_command\""));
         assert!(json.contains("\"target_monitor_ids\":[\"ALL\"]"));
     }
+
+    #[test]
+    fn test_presenter_note_chunks_split_and_rejoin() {
+        let content = "あいうえお".repeat(10);
+        let chunks = Message::presenter_note_chunks(&content, vec!["A".to_string()], 64);
+        assert!(chunks.len() > 1);
+
+        let mut rejoined = String::new();
+        for (i, chunk) in chunks.iter().enumerate() {
+            match chunk {
+                Message::PresenterNoteChunk { payload, .. } => {
+                    assert_eq!(payload.seq, i as u32);
+                    assert_eq!(payload.total, chunks.len() as u32);
+                    assert!(payload.data.len() <= 64);
+                    rejoined.push_str(&payload.data);
+                }
+                _ => panic!("expected PresenterNoteChunk"),
+            }
+        }
+        assert_eq!(rejoined, content);
+    }
+
+    #[test]
+    fn test_presenter_note_resend_serialization() {
+        let msg = Message::presenter_note_resend("note-1".to_string(), vec![1, 3]);
+        let json = serde_json::to_string(&msg).unwrap();
+        assert!(json.contains("\"type\":\"presenter_note_resend\""));
+        assert!(json.contains("\"missing_seqs\":[1,3]"));
+    }
+
+    #[test]
+    fn test_update_subscription_serialization() {
+        let msg = Message::update_subscription(vec!["B".to_string()]);
+        let json = serde_json::to_string(&msg).unwrap();
+        assert!(json.contains("\"type\":\"update_subscription\""));
+        assert!(json.contains("\"display_monitor_ids\":[\"B\"]"));
+        assert!(msg.target_monitor_ids().is_none());
+    }
+
+    #[test]
+    fn test_air_status_serialization() {
+        let msg = Message::air_status(AirStatus::OnAir, 1234);
+        let json = serde_json::to_string(&msg).unwrap();
+        assert!(json.contains("\"type\":\"air_status\""));
+        assert!(json.contains("\"status\":\"on_air\""));
+        assert!(json.contains("\"since\":1234"));
+        assert!(msg.target_monitor_ids().is_none());
+    }
+
+    #[test]
+    fn test_seq_reads_back_and_still_parses() {
+        let msg = Message::flash_command(vec!["A".to_string()]);
+        let (seq, parsed) = Message::parse_frame(&msg.to_json().unwrap());
+        assert_eq!(seq, None);
+        assert_eq!(parsed.unwrap().id(), msg.id());
+
+        let stamped = msg.to_json_with_seq(42).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&stamped).unwrap();
+        assert_eq!(value["seq"], 42);
+        assert_eq!(value["type"], "flash_command");
+        let (seq, parsed) = Message::parse_frame(&stamped);
+        assert_eq!(seq, Some(42));
+        assert_eq!(parsed.unwrap().id(), msg.id());
+    }
+
+    #[test]
+    fn test_seq_is_read_from_unparseable_messages() {
+        let (seq, parsed) = Message::parse_frame(r#"{"seq":7,"type":"from_the_future"}"#);
+        assert_eq!(seq, Some(7));
+        assert!(matches!(parsed, Err(MessageParseError::UnknownType(_))));
+    }
+
+    #[test]
+    fn test_msgpack_frames_round_trip() {
+        let msg = Message::kanpe_message("Wrap up".to_string(), vec!["A".to_string()], Priority::High);
+
+        let Frame::Binary(bytes) = msg.encode(WireFormat::Msgpack, Some(3)).unwrap() else {
+            panic!("MessagePack frames are binary");
+        };
+        assert!(serde_json::from_slice::<serde_json::Value>(&bytes).is_err());
+        let (seq, parsed) = Message::parse_msgpack_frame(&bytes);
+        assert_eq!(seq, Some(3));
+        match parsed.unwrap() {
+            Message::KanpeMessage { id, payload, .. } => {
+                assert_eq!(id, msg.id());
+                assert_eq!(payload.content, "Wrap up");
+                assert_eq!(payload.target_monitor_ids, vec!["A".to_string()]);
+                assert_eq!(payload.priority, Priority::High);
+            }
+            other => panic!("expected KanpeMessage, got {:?}", other),
+        }
+
+        let Frame::Binary(bytes) = Message::ping().encode(WireFormat::Msgpack, None).unwrap() else {
+            panic!("MessagePack frames are binary");
+        };
+        let (seq, parsed) = Message::parse_msgpack_frame(&bytes);
+        assert_eq!(seq, None);
+        assert!(matches!(parsed.unwrap(), Message::Ping { .. }));
+
+        assert!(matches!(
+            Message::parse_msgpack_frame(b"\xc1").1,
+            Err(MessageParseError::InvalidMsgpack(_))
+        ));
+    }
+
+    #[test]
+    fn test_json_encoding_matches_to_json() {
+        let msg = Message::flash_command(vec!["A".to_string()]);
+        assert_eq!(msg.encode(WireFormat::Json, None).unwrap(), Frame::Text(msg.to_json().unwrap()));
+        assert_eq!(msg.encode(WireFormat::Json, Some(5)).unwrap(), Frame::Text(msg.to_json_with_seq(5).unwrap()));
+    }
+
+    #[test]
+    fn test_parse_round_trips_to_json() {
+        let msg = Message::flash_command(vec!["A".to_string()]);
+        let parsed = Message::parse(&msg.to_json().unwrap()).unwrap();
+        assert_eq!(parsed.id(), msg.id());
+        assert!(matches!(parsed, Message::FlashCommand { .. }));
+    }
+
+    #[test]
+    fn test_message_type_matches_wire_tag() {
+        for msg in [
+            Message::ping(),
+            Message::flash_command(vec![]),
+            Message::monitor_change_ack("c1".to_string()),
+            Message::reconnect_request(),
+            Message::monitor_group_sync(vec![]),
+        ] {
+            let value: serde_json::Value = serde_json::from_str(&msg.to_json().unwrap()).unwrap();
+            assert_eq!(value["type"], msg.message_type());
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_json() {
+        assert!(matches!(Message::parse("{not json"), Err(MessageParseError::InvalidJson(_))));
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_type() {
+        let err = Message::parse(r#"{"type":"foo","id":"1","timestamp":0}"#).unwrap_err();
+        assert!(matches!(err, MessageParseError::UnknownType(ref t) if t == "foo"), "{:?}", err);
+    }
+
+    #[test]
+    fn test_message_types_lists_every_variant() {
+        // Each listed tag is a real variant: it fails on its missing fields, not its type
+        for message_type in MESSAGE_TYPES {
+            let err = Message::parse(&format!(r#"{{"type":"{}"}}"#, message_type)).unwrap_err();
+            assert!(matches!(err, MessageParseError::MissingField(_)), "{}: {:?}", message_type, err);
+        }
+        for msg in [
+            Message::ping(),
+            Message::flash_command(vec![]),
+            Message::reconnect_request(),
+            Message::feedback_typing("caster".to_string(), true),
+        ] {
+            assert!(MESSAGE_TYPES.contains(&msg.message_type()));
+        }
+    }
+
+    #[test]
+    fn test_parse_reports_missing_field() {
+        let err = Message::parse(r#"{"type":"flash_command","id":"1","timestamp":0}"#).unwrap_err();
+        assert!(matches!(err, MessageParseError::MissingField(ref f) if f == "payload"), "{:?}", err);
+
+        let err = Message::parse(r#"{"id":"1","timestamp":0}"#).unwrap_err();
+        assert!(matches!(err, MessageParseError::MissingField(ref f) if f == "type"), "{:?}", err);
+    }
 }
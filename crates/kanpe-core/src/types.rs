@@ -3,36 +3,101 @@
 use serde::{Deserialize, Serialize};
 
 /// Priority levels for Kanpe messages
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Priority {
+    /// FYI notes, shown de-emphasized
+    Low,
+    #[default]
     Normal,
     High,
     Urgent,
 }
 
-impl Default for Priority {
-    fn default() -> Self {
-        Priority::Normal
+impl Priority {
+    /// Sort key for UIs, higher is more important
+    pub fn rank(self) -> u8 {
+        match self {
+            Priority::Low => 0,
+            Priority::Normal => 1,
+            Priority::High => 2,
+            Priority::Urgent => 3,
+        }
     }
 }
 
 /// Feedback type classification
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+///
+/// The fixed types serialize as lowercase strings (`"ack"`); custom ones as
+/// `{"custom": "Props"}`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum FeedbackType {
+    #[default]
     Ack,        // Simple acknowledgment
     Question,   // Question for clarification
     Issue,      // Problem or concern
     Info,       // General information
+    Custom(String), // Caster-defined category such as "Props"
 }
 
-impl Default for FeedbackType {
-    fn default() -> Self {
-        FeedbackType::Ack
+impl FeedbackType {
+    /// Parse a feedback type name from a UI or plugin
+    ///
+    /// The fixed names match case-insensitively; any other non-empty name
+    /// becomes a custom category. Returns None for a blank name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim();
+        match name.to_lowercase().as_str() {
+            "" => None,
+            "ack" => Some(FeedbackType::Ack),
+            "question" => Some(FeedbackType::Question),
+            "issue" => Some(FeedbackType::Issue),
+            "info" => Some(FeedbackType::Info),
+            _ => Some(FeedbackType::Custom(name.to_string())),
+        }
     }
 }
 
+/// Production on-air state shown as a banner on every caster screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AirStatus {
+    OnAir,
+    Standby,
+    #[default]
+    OffAir,
+}
+
+/// How a cue's content should be interpreted; rendering is left to the UI
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentFormat {
+    #[default]
+    Plain,
+    Markdown,
+}
+
+impl ContentFormat {
+    /// Check whether this is the default, so it can be left off the wire
+    pub fn is_plain(&self) -> bool {
+        *self == ContentFormat::Plain
+    }
+}
+
+/// Encoding of protocol frames on a connection, chosen by the client in ClientHello
+///
+/// JSON goes in text frames and MessagePack in binary frames. The ClientHello
+/// and ServerWelcome are always JSON; the welcome confirms the format used
+/// for the rest of the connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WireFormat {
+    #[default]
+    Json,
+    Msgpack,
+}
+
 /// Helper function to generate a new UUID string
 pub fn new_id() -> String {
     uuid::Uuid::new_v4().to_string()
@@ -43,6 +108,44 @@ pub fn timestamp() -> i64 {
     chrono::Utc::now().timestamp_millis()
 }
 
+/// Source of message IDs and timestamps, swappable for reproducible messages in tests
+pub trait IdClock {
+    fn new_id(&self) -> String;
+    /// Current Unix timestamp in milliseconds
+    fn timestamp(&self) -> i64;
+}
+
+/// Random UUIDs and the wall clock, as used by the plain constructors
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl IdClock for SystemClock {
+    fn new_id(&self) -> String {
+        new_id()
+    }
+
+    fn timestamp(&self) -> i64 {
+        timestamp()
+    }
+}
+
+/// Always hands out the same ID and timestamp
+#[derive(Debug, Clone)]
+pub struct FixedClock {
+    pub id: String,
+    pub timestamp: i64,
+}
+
+impl IdClock for FixedClock {
+    fn new_id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+}
+
 /// Virtual monitor definition
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VirtualMonitor {
@@ -54,6 +157,38 @@ pub struct VirtualMonitor {
     pub description: Option<String>,
     /// Optional color in hex format (e.g., "#FF5733")
     pub color: Option<String>,
+    /// Optional short icon, such as an emoji or an icon key
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// Maximum number of cues shown at once (None = unlimited)
+    #[serde(default)]
+    pub max_cues: Option<u8>,
+    /// Display order, lowest first (ties fall back to the ID)
+    #[serde(default)]
+    pub position: u32,
+    /// Disabled monitors keep their config but receive no targeted cues
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+/// `target_monitor_ids` entry addressing every monitor, and every client whatever it displays
+///
+/// Reserved: no monitor may be created with this ID.
+pub const ALL_MONITORS: &str = "ALL";
+
+/// Prefix of a `target_monitor_ids` entry naming a group, e.g. "group:Pit"
+pub const GROUP_TARGET_PREFIX: &str = "group:";
+
+/// Named set of monitors (e.g. a stage zone) that cues can target as "group:<id>"
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MonitorGroup {
+    pub id: String,
+    pub name: String,
+    pub monitor_ids: Vec<String>,
 }
 
 #[cfg(test)]
@@ -62,6 +197,14 @@ mod tests {
 
     #[test]
     fn test_priority_serialization() {
+        assert_eq!(
+            serde_json::to_string(&Priority::Low).unwrap(),
+            "\"low\""
+        );
+        assert_eq!(
+            serde_json::from_str::<Priority>("\"low\"").unwrap(),
+            Priority::Low
+        );
         assert_eq!(
             serde_json::to_string(&Priority::Normal).unwrap(),
             "\"normal\""
@@ -76,6 +219,43 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_feedback_type_serialization_round_trips() {
+        assert_eq!(serde_json::to_string(&FeedbackType::Ack).unwrap(), "\"ack\"");
+        assert_eq!(serde_json::to_string(&FeedbackType::Question).unwrap(), "\"question\"");
+        assert_eq!(
+            serde_json::to_string(&FeedbackType::Custom("Props".to_string())).unwrap(),
+            r#"{"custom":"Props"}"#
+        );
+
+        for feedback_type in [
+            FeedbackType::Ack,
+            FeedbackType::Question,
+            FeedbackType::Issue,
+            FeedbackType::Info,
+            FeedbackType::Custom("Costume".to_string()),
+        ] {
+            let json = serde_json::to_string(&feedback_type).unwrap();
+            assert_eq!(serde_json::from_str::<FeedbackType>(&json).unwrap(), feedback_type);
+        }
+    }
+
+    #[test]
+    fn test_feedback_type_from_name() {
+        assert_eq!(FeedbackType::from_name("Question"), Some(FeedbackType::Question));
+        assert_eq!(FeedbackType::from_name("info"), Some(FeedbackType::Info));
+        assert_eq!(FeedbackType::from_name(" Props "), Some(FeedbackType::Custom("Props".to_string())));
+        assert_eq!(FeedbackType::from_name("  "), None);
+    }
+
+    #[test]
+    fn test_priority_rank_orders_low_to_urgent() {
+        assert_eq!(Priority::default(), Priority::Normal);
+        let mut priorities = vec![Priority::Urgent, Priority::Low, Priority::High, Priority::Normal];
+        priorities.sort_by_key(|p| p.rank());
+        assert_eq!(priorities, vec![Priority::Low, Priority::Normal, Priority::High, Priority::Urgent]);
+    }
+
     #[test]
     fn test_feedback_type_serialization() {
         assert_eq!(
@@ -101,4 +281,27 @@ mod tests {
         // Should be within 1 second
         assert!((now - ts).abs() < 1000);
     }
+
+    #[test]
+    fn test_virtual_monitor_round_trips_icon() {
+        let monitor = VirtualMonitor {
+            id: "A".to_string(),
+            name: "Monitor A".to_string(),
+            description: None,
+            color: Some("#3b82f6".to_string()),
+            icon: Some("🎤".to_string()),
+            max_cues: None,
+            position: 0,
+            enabled: true,
+        };
+        let json = serde_json::to_string(&monitor).unwrap();
+        let restored: VirtualMonitor = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.icon.as_deref(), Some("🎤"));
+
+        // Monitors saved before icons existed still load
+        let legacy: VirtualMonitor =
+            serde_json::from_str(r#"{"id":"B","name":"Monitor B","description":null,"color":null}"#).unwrap();
+        assert_eq!(legacy.icon, None);
+        assert!(legacy.enabled);
+    }
 }
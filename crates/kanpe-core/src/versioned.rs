@@ -0,0 +1,66 @@
+//! Parsing of persisted JSON documents that carry a schema version
+
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+/// Top-level key holding a persisted file's schema version (files without it count as 0)
+pub const SCHEMA_VERSION_KEY: &str = "schema_version";
+
+/// Errors returned by [`versioned_parse`]
+#[derive(Debug, Error)]
+pub enum VersionParseError {
+    #[error(transparent)]
+    Parse(#[from] serde_json::Error),
+    #[error("schema version {found} is newer than the supported version {supported}")]
+    TooNew { found: u64, supported: u64 },
+}
+
+/// Parse a JSON document written with schema version `supported` or older
+///
+/// A document from a newer schema is rejected instead of being parsed, so a
+/// downgraded app never misreads it. Reading the file and keeping a backup of
+/// rejected files is left to the caller.
+pub fn versioned_parse<T: DeserializeOwned>(content: &str, supported: u32) -> Result<T, VersionParseError> {
+    let value: serde_json::Value = serde_json::from_str(content)?;
+
+    let found = value
+        .get(SCHEMA_VERSION_KEY)
+        .and_then(serde_json::Value::as_u64)
+        .unwrap_or(0);
+    if found > u64::from(supported) {
+        return Err(VersionParseError::TooNew {
+            found,
+            supported: u64::from(supported),
+        });
+    }
+
+    Ok(serde_json::from_value(value)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    struct Settings {
+        #[serde(default)]
+        schema_version: u32,
+        name: String,
+    }
+
+    #[test]
+    fn test_future_version_is_rejected() {
+        let err = versioned_parse::<Settings>(r#"{ "schema_version": 7, "name": "from the future" }"#, 1).unwrap_err();
+        let VersionParseError::TooNew { found, supported } = err else {
+            panic!("expected TooNew, got {:?}", err);
+        };
+        assert_eq!((found, supported), (7, 1));
+
+        // Current and unversioned documents still parse
+        let settings: Settings = versioned_parse(r#"{ "schema_version": 1, "name": "current" }"#, 1).unwrap();
+        assert_eq!((settings.schema_version, settings.name.as_str()), (1, "current"));
+        let settings: Settings = versioned_parse(r#"{ "name": "legacy" }"#, 1).unwrap();
+        assert_eq!((settings.schema_version, settings.name.as_str()), (0, "legacy"));
+    }
+}
@@ -0,0 +1,64 @@
+//! Waiting for clients to acknowledge cues
+
+use crate::client_manager::is_subscribed;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tokio::sync::oneshot;
+
+/// A task waiting for an ack, and the monitors the acked message went to
+struct Waiter {
+    target_monitor_ids: Vec<String>,
+    tx: oneshot::Sender<String>,
+}
+
+/// Tasks waiting for some client to acknowledge a particular message
+pub struct AckWaiters {
+    waiters: Mutex<HashMap<String, Waiter>>,
+}
+
+impl AckWaiters {
+    pub fn new() -> Self {
+        Self {
+            waiters: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Wait for the first ack of `message_id` from a client displaying one of
+    /// `target_monitor_ids`; resolves with the acking client's ID
+    pub fn wait_for(&self, message_id: &str, target_monitor_ids: Vec<String>) -> oneshot::Receiver<String> {
+        let (tx, rx) = oneshot::channel();
+        self.waiters
+            .lock()
+            .unwrap()
+            .insert(message_id.to_string(), Waiter { target_monitor_ids, tx });
+        rx
+    }
+
+    /// Deliver an ack from a client displaying `display_monitor_ids`, returning
+    /// whether anything was waiting for it
+    ///
+    /// Acks from clients that do not display any of the message's targets are
+    /// ignored, so an operator on another monitor cannot silence the cue.
+    pub fn acknowledge(&self, message_id: &str, client_id: &str, display_monitor_ids: &[String]) -> bool {
+        let mut waiters = self.waiters.lock().unwrap();
+        match waiters.get(message_id) {
+            Some(waiter) if is_subscribed(display_monitor_ids, &waiter.target_monitor_ids, false) => {}
+            _ => return false,
+        }
+        match waiters.remove(message_id) {
+            Some(waiter) => waiter.tx.send(client_id.to_string()).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Stop waiting for `message_id`
+    pub fn forget(&self, message_id: &str) {
+        self.waiters.lock().unwrap().remove(message_id);
+    }
+}
+
+impl Default for AckWaiters {
+    fn default() -> Self {
+        Self::new()
+    }
+}
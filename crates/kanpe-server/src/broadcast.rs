@@ -1,25 +1,285 @@
 //! Broadcasting logic for sending messages to clients
 
 use crate::client_manager::ClientManager;
+use crate::config::{CueOverflow, UnknownTargetPolicy};
+use crate::cue_latch::CueLatch;
+use crate::error::KanpeError;
+use crate::events::{DisconnectReason, ServerEvent};
+use crate::history::MessageHistory;
+use crate::metrics::MetricsCounters;
+use crate::monitor_manager::MonitorManager;
 use axum::extract::ws::Message as WsMessage;
+use futures_util::future::join_all;
 use futures_util::SinkExt;
-use kanpe_core::Message;
+use kanpe_core::types::ALL_MONITORS;
+use kanpe_core::{Frame, Message, MessageParseError, WireFormat};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use tokio::sync::mpsc;
 
-/// Broadcast a message to all connected clients
+/// Everything needed to send a cue, detached from the server so timers can own it
+#[derive(Clone)]
+pub struct Dispatcher {
+    pub client_manager: Arc<ClientManager>,
+    pub monitor_manager: Arc<MonitorManager>,
+    pub cue_latch: Arc<CueLatch>,
+    pub message_history: Arc<MessageHistory>,
+    pub metrics: Arc<MetricsCounters>,
+    pub event_tx: mpsc::UnboundedSender<ServerEvent>,
+    pub cue_overflow: CueOverflow,
+    pub unknown_targets: UnknownTargetPolicy,
+}
+
+impl Dispatcher {
+    /// Latch, record and broadcast a message, withdrawing any cues it evicts first
+    ///
+    /// Cues with no targets are rejected, as are cues for unknown monitors
+    /// under `UnknownTargetPolicy::Reject`. "group:<id>" targets are expanded to
+    /// the group's members, and targeted messages are not delivered for
+    /// disabled monitors. Inverted messages go to every monitor except their
+    /// targets, and may have an empty target list to reach everyone.
+    pub async fn send(&self, message: &Message) -> Result<Vec<(String, KanpeError)>, KanpeError> {
+        let mut message = message.clone();
+        if let Some(targets) = message.target_monitor_ids_mut() {
+            *targets = self.monitor_manager.expand_targets(targets).await;
+        }
+        let message = &message;
+
+        if let Message::KanpeMessage { id, payload, .. } = message {
+            self.check_targets(id, &payload.target_monitor_ids, payload.invert).await?;
+        }
+
+        let evicted = self
+            .cue_latch
+            .apply(&self.resolve_inverted(message).await, &self.cue_limits().await, self.cue_overflow)
+            .await?;
+        if matches!(message, Message::KanpeMessage { .. }) {
+            self.message_history.push(message.clone()).await;
+        }
+
+        // Withdraw evicted cues before the new one arrives
+        let mut failures = Vec::new();
+        for (message_id, monitor_id) in evicted {
+            failures.extend(self.broadcast(&Message::message_recalled(message_id, vec![monitor_id])).await?);
+        }
+        match self.enabled_targets(message).await {
+            Some(targets) if targets.is_empty() && !message.targets_inverted() => report_cue_broadcast(&self.event_tx, message, &targets, 0),
+            Some(targets) => {
+                MetricsCounters::increment(&self.metrics.messages_broadcast);
+                failures.extend(broadcast_message_to(&self.client_manager, &self.event_tx, message, Some(&targets)).await?);
+            }
+            None => failures.extend(self.broadcast(message).await?),
+        }
+        Ok(failures)
+    }
+
+    /// Broadcast a message as-is, counting it in the metrics
+    pub async fn broadcast(&self, message: &Message) -> Result<Vec<(String, KanpeError)>, KanpeError> {
+        MetricsCounters::increment(&self.metrics.messages_broadcast);
+        broadcast_message(&self.client_manager, &self.event_tx, message).await
+    }
+
+    /// Get a targeted message's targets minus disabled monitors (None if untargeted)
+    ///
+    /// For inverted messages the targets are an exclusion list, so disabled
+    /// monitors are added to it instead.
+    async fn enabled_targets(&self, message: &Message) -> Option<Vec<String>> {
+        let targets = message.target_monitor_ids()?;
+        let disabled: HashSet<String> = self
+            .monitor_manager
+            .get_all_monitors()
+            .await
+            .into_iter()
+            .filter(|monitor| !monitor.enabled)
+            .map(|monitor| monitor.id)
+            .collect();
+        if message.targets_inverted() {
+            let mut excluded = targets.to_vec();
+            excluded.extend(disabled.into_iter().filter(|id| !targets.contains(id)));
+            return Some(excluded);
+        }
+        Some(targets.iter().filter(|id| !disabled.contains(*id)).cloned().collect())
+    }
+
+    /// Rewrite an inverted message to list the known monitors it reaches, for latching
+    async fn resolve_inverted(&self, message: &Message) -> Message {
+        let mut message = message.clone();
+        if !message.targets_inverted() {
+            return message;
+        }
+        let known: Vec<String> = self
+            .monitor_manager
+            .get_all_monitors()
+            .await
+            .into_iter()
+            .map(|monitor| monitor.id)
+            .collect();
+        match &mut message {
+            Message::KanpeMessage { payload, .. } => {
+                payload.target_monitor_ids = complement(&known, &payload.target_monitor_ids);
+                payload.invert = false;
+            }
+            Message::FlashCommand { payload, .. } => {
+                payload.target_monitor_ids = complement(&known, &payload.target_monitor_ids);
+                payload.invert = false;
+            }
+            Message::ClearCommand { payload, .. } => {
+                payload.target_monitor_ids = complement(&known, &payload.target_monitor_ids);
+                payload.invert = false;
+            }
+            _ => {}
+        }
+        message
+    }
+
+    /// Validate a cue's targets, warning about unknown monitors unless configured to reject them
+    ///
+    /// An inverted cue with no targets excludes nothing, so it is allowed.
+    async fn check_targets(
+        &self,
+        message_id: &str,
+        target_monitor_ids: &[String],
+        invert: bool,
+    ) -> Result<(), KanpeError> {
+        if target_monitor_ids.is_empty() && !invert {
+            return Err(KanpeError::InvalidTarget("no target monitors".to_string()));
+        }
+
+        let known: HashSet<String> = self
+            .monitor_manager
+            .get_all_monitors()
+            .await
+            .into_iter()
+            .map(|monitor| monitor.id)
+            .collect();
+        let unknown: Vec<String> = target_monitor_ids
+            .iter()
+            .filter(|id| *id != ALL_MONITORS && !known.contains(*id))
+            .cloned()
+            .collect();
+        if unknown.is_empty() {
+            return Ok(());
+        }
+
+        match self.unknown_targets {
+            UnknownTargetPolicy::Reject => Err(KanpeError::InvalidTarget(format!(
+                "unknown monitors: {}",
+                unknown.join(", ")
+            ))),
+            UnknownTargetPolicy::Warn => {
+                let _ = self.event_tx.send(ServerEvent::UnknownTargets {
+                    message_id: message_id.to_string(),
+                    monitor_ids: unknown,
+                });
+                Ok(())
+            }
+        }
+    }
+
+    /// Get the `max_cues` limit of every monitor that has one
+    pub async fn cue_limits(&self) -> HashMap<String, u8> {
+        self.monitor_manager
+            .get_all_monitors()
+            .await
+            .into_iter()
+            .filter_map(|monitor| monitor.max_cues.map(|max| (monitor.id, max)))
+            .collect()
+    }
+}
+
+/// Broadcast a message to the clients it targets (all clients for untargeted messages)
+///
+/// Sends to every client concurrently so one slow connection cannot hold up
+/// the rest. Clients whose send fails are removed and reported as
+/// disconnected, and returned along with their errors.
 pub async fn broadcast_message(
     client_manager: &ClientManager,
+    event_tx: &mpsc::UnboundedSender<ServerEvent>,
     message: &Message,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let json = serde_json::to_string(message)?;
-    let ws_message = WsMessage::Text(json);
+) -> Result<Vec<(String, KanpeError)>, KanpeError> {
+    broadcast_message_to(client_manager, event_tx, message, message.target_monitor_ids()).await
+}
 
-    let sinks = client_manager.get_all_sinks().await;
+/// Broadcast a message to the clients displaying `target_monitor_ids` (all clients for `None`)
+///
+/// Each frame is stamped with the next sequence number of the connection it
+/// goes to, so a client can tell it missed one. Numbers are per connection
+/// because targeted messages skip clients that do not display their monitors.
+pub async fn broadcast_message_to(
+    client_manager: &ClientManager,
+    event_tx: &mpsc::UnboundedSender<ServerEvent>,
+    message: &Message,
+    target_monitor_ids: Option<&[String]>,
+) -> Result<Vec<(String, KanpeError)>, KanpeError> {
+    let sinks = client_manager
+        .get_sinks_for(target_monitor_ids, message.targets_inverted())
+        .await;
+    let matched = sinks.len();
+
+    let sends = sinks.into_iter().map(|(client_id, sink, broadcast_seq, wire_format)| async move {
+        // Number the frame under the sink lock so numbers go out in order
+        let mut sink = sink.write().await;
+        let seq = broadcast_seq.fetch_add(1, Ordering::Relaxed) + 1;
+        let result = match ws_frame(message, wire_format, Some(seq)) {
+            Ok(frame) => sink.send(frame).await.map_err(KanpeError::Send),
+            Err(e) => Err(KanpeError::Serialization(e)),
+        };
+        result.err().map(|e| (client_id, e))
+    });
+    let failures: Vec<(String, KanpeError)> = join_all(sends).await.into_iter().flatten().collect();
 
-    for (_client_id, sink) in sinks {
-        let mut sink_guard = sink.write().await;
-        // Ignore individual send failures (client will be cleaned up by disconnect handler)
-        let _ = sink_guard.send(ws_message.clone()).await;
+    // All sink locks are released by now, so removing cannot deadlock with a sender.
+    // The connection's own cleanup skips clients that are already gone.
+    for (client_id, _) in failures.iter().filter(|(_, e)| matches!(e, KanpeError::Send(_))) {
+        if client_manager.remove_client(client_id, "send failed").await.is_some() {
+            let _ = event_tx.send(ServerEvent::ClientDisconnected {
+                client_id: client_id.clone(),
+                reason: DisconnectReason::NetworkError,
+            });
+        }
     }
 
-    Ok(())
+    tracing::debug!(
+        message_type = message.message_type(),
+        recipients = matched - failures.len(),
+        failed = failures.len(),
+        "broadcast message"
+    );
+    let targets = target_monitor_ids.unwrap_or_default();
+    report_cue_broadcast(event_tx, message, targets, matched - failures.len());
+
+    Ok(failures)
+}
+
+/// Encode a message as a WebSocket frame in a client's wire format
+pub(crate) fn ws_frame(message: &Message, wire_format: WireFormat, seq: Option<u64>) -> Result<WsMessage, MessageParseError> {
+    Ok(match message.encode(wire_format, seq)? {
+        Frame::Text(text) => WsMessage::Text(text),
+        Frame::Binary(bytes) => WsMessage::Binary(bytes),
+    })
+}
+
+/// Get the monitors in `known` that `excluded` does not list ("ALL" excludes every monitor)
+fn complement(known: &[String], excluded: &[String]) -> Vec<String> {
+    if excluded.iter().any(|id| id == ALL_MONITORS) {
+        return Vec::new();
+    }
+    known.iter().filter(|id| !excluded.contains(id)).cloned().collect()
+}
+
+/// Tell the embedder how many clients a cue reached (other messages are not reported)
+fn report_cue_broadcast(
+    event_tx: &mpsc::UnboundedSender<ServerEvent>,
+    message: &Message,
+    targets: &[String],
+    recipient_count: usize,
+) {
+    if let Message::KanpeMessage { id, .. } = message {
+        let _ = event_tx.send(ServerEvent::MessageBroadcast {
+            message_id: id.clone(),
+            recipient_count,
+            targets: targets.to_vec(),
+        });
+    }
 }
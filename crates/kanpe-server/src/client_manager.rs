@@ -1,50 +1,185 @@
 //! Client connection management
 
+use crate::config::DuplicateNamePolicy;
+use crate::connection_log::{ConnectionLog, ConnectionLogEntry};
 use axum::extract::ws::Message as WsMessage;
 use futures_util::stream::SplitSink;
+use kanpe_core::types::ALL_MONITORS;
+use kanpe_core::WireFormat;
+use serde::Serialize;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{RwLock, RwLockReadGuard};
 
 pub type WsSink = SplitSink<axum::extract::ws::WebSocket, WsMessage>;
 
+/// A registered client's socket and the last sequence number broadcast to it
+pub(crate) struct ClientEntry {
+    pub(crate) info: ClientInfo,
+    sink: Arc<RwLock<WsSink>>,
+    broadcast_seq: Arc<AtomicU64>,
+}
+
+/// A client a broadcast goes to: its ID, socket, broadcast sequence counter and wire format
+pub type BroadcastTarget = (String, Arc<RwLock<WsSink>>, Arc<AtomicU64>, WireFormat);
+
 /// Information about a connected client
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ClientInfo {
     pub client_id: String,
     pub client_name: String,
     pub display_monitor_ids: Vec<String>,
+    /// When the client registered, in milliseconds since the epoch
+    pub connected_at: i64,
+    /// When the client last answered a ping, in milliseconds since the epoch
+    pub last_pong_at: Option<i64>,
+    /// Encoding of the frames sent to the client, as agreed in its ClientHello
+    pub wire_format: WireFormat,
+}
+
+/// Why a client could not be registered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisterError {
+    /// `max_clients` are already registered
+    Full,
+    /// Another client uses the name and duplicates are rejected
+    DuplicateName,
 }
 
 /// Manager for tracking connected clients
 pub struct ClientManager {
-    clients: Arc<RwLock<HashMap<String, (ClientInfo, Arc<RwLock<WsSink>>)>>>,
+    clients: Arc<RwLock<HashMap<String, ClientEntry>>>,
+    connection_log: ConnectionLog,
+    peak_clients: AtomicUsize,
 }
 
 impl ClientManager {
     pub fn new() -> Self {
         Self {
             clients: Arc::new(RwLock::new(HashMap::new())),
+            connection_log: ConnectionLog::new(),
+            peak_clients: AtomicUsize::new(0),
         }
     }
 
-    /// Add a new client
-    pub async fn add_client(
+    /// Add a new client unless `max_clients` are already registered or its name is refused
+    ///
+    /// Returns the name the client was registered under, which `duplicate_names`
+    /// may have suffixed. The checks and insert happen under one lock, so
+    /// concurrent registrations can never push the count past the limit or
+    /// slip in a duplicate name. A client resuming an ID that is still
+    /// registered replaces that entry, so it neither counts against the limit
+    /// nor clashes with its own name.
+    pub async fn try_add_client(
         &self,
         client_id: String,
-        info: ClientInfo,
+        mut info: ClientInfo,
         sink: Arc<RwLock<WsSink>>,
-    ) {
-        self.clients.write().await.insert(client_id, (info, sink));
+        max_clients: Option<usize>,
+        duplicate_names: DuplicateNamePolicy,
+    ) -> Result<String, RegisterError> {
+        let mut clients = self.clients.write().await;
+        let replacing = clients.contains_key(&client_id);
+        if !replacing && max_clients.is_some_and(|max| clients.len() >= max) {
+            return Err(RegisterError::Full);
+        }
+        let taken = |name: &str| {
+            clients
+                .iter()
+                .any(|(id, entry)| *id != client_id && entry.info.client_name == name)
+        };
+        if taken(&info.client_name) {
+            match duplicate_names {
+                DuplicateNamePolicy::Allow => {}
+                DuplicateNamePolicy::Reject => return Err(RegisterError::DuplicateName),
+                DuplicateNamePolicy::Suffix => {
+                    let name = (2..)
+                        .map(|n| format!("{} ({})", info.client_name, n))
+                        .find(|name| !taken(name))
+                        .expect("unbounded suffixes");
+                    info.client_name = name;
+                }
+            }
+        }
+        let client_name = info.client_name.clone();
+        self.connection_log.record_connected(&client_id, &info.client_name).await;
+        clients.insert(
+            client_id,
+            ClientEntry {
+                info,
+                sink,
+                broadcast_seq: Arc::new(AtomicU64::new(0)),
+            },
+        );
+        self.peak_clients.fetch_max(clients.len(), Ordering::Relaxed);
+        Ok(client_name)
     }
 
-    /// Remove a client
-    pub async fn remove_client(&self, client_id: &str) -> Option<ClientInfo> {
-        self.clients
+    /// Remove a client, logging why it went away
+    pub async fn remove_client(&self, client_id: &str, reason: &str) -> Option<ClientInfo> {
+        let info = self
+            .clients
             .write()
             .await
             .remove(client_id)
-            .map(|(info, _)| info)
+            .map(|entry| entry.info)?;
+        self.connection_log
+            .record_disconnected(client_id, &info.client_name, reason)
+            .await;
+        Some(info)
+    }
+
+    /// Get the number of registered clients
+    pub async fn client_count(&self) -> usize {
+        self.clients.read().await.len()
+    }
+
+    /// Get the highest number of clients registered at once
+    pub fn peak_clients(&self) -> usize {
+        self.peak_clients.load(Ordering::Relaxed)
+    }
+
+    /// Get the connection/disconnection timeline, oldest first
+    pub async fn connection_log(&self) -> Vec<ConnectionLogEntry> {
+        self.connection_log.entries().await
+    }
+
+    /// Get the reason a client was last disconnected, while it is still in the log
+    pub async fn last_disconnect_reason(&self, client_id: &str) -> Option<String> {
+        self.connection_log.last_disconnect_reason(client_id).await
+    }
+
+    /// Get the sink for a single client
+    pub async fn get_sink(&self, client_id: &str) -> Option<Arc<RwLock<WsSink>>> {
+        self.clients
+            .read()
+            .await
+            .get(client_id)
+            .map(|entry| entry.sink.clone())
+    }
+
+    /// Get the monitors a single client displays
+    pub async fn get_display_monitor_ids(&self, client_id: &str) -> Option<Vec<String>> {
+        self.clients
+            .read()
+            .await
+            .get(client_id)
+            .map(|entry| entry.info.display_monitor_ids.clone())
+    }
+
+    /// Get the wire format a single client asked for
+    pub async fn get_wire_format(&self, client_id: &str) -> Option<WireFormat> {
+        self.clients
+            .read()
+            .await
+            .get(client_id)
+            .map(|entry| entry.info.wire_format)
+    }
+
+    /// Hold the client table for reading, e.g. to copy it alongside other state
+    pub(crate) async fn read_clients(&self) -> RwLockReadGuard<'_, HashMap<String, ClientEntry>> {
+        self.clients.read().await
     }
 
     /// Get all client infos
@@ -53,27 +188,66 @@ impl ClientManager {
             .read()
             .await
             .values()
-            .map(|(info, _)| info.clone())
+            .map(|entry| entry.info.clone())
             .collect()
     }
 
-    /// Get all client sinks for broadcasting
-    pub async fn get_all_sinks(&self) -> Vec<(String, Arc<RwLock<WsSink>>)> {
+    /// Replace the monitors a client displays, returning the updated info
+    pub async fn update_subscription(
+        &self,
+        client_id: &str,
+        display_monitor_ids: Vec<String>,
+    ) -> Option<ClientInfo> {
+        let mut clients = self.clients.write().await;
+        let entry = clients.get_mut(client_id)?;
+        entry.info.display_monitor_ids = display_monitor_ids;
+        Some(entry.info.clone())
+    }
+
+    /// Note that a client answered a ping
+    pub async fn record_pong(&self, client_id: &str, at: i64) {
+        if let Some(entry) = self.clients.write().await.get_mut(client_id) {
+            entry.info.last_pong_at = Some(at);
+        }
+    }
+
+    /// Get the clients that should receive a message for the given targets
+    ///
+    /// `None` targets every client. With `invert`, the targets are monitors to
+    /// skip: clients displaying any of them are left out.
+    pub async fn get_sinks_for(&self, target_monitor_ids: Option<&[String]>, invert: bool) -> Vec<BroadcastTarget> {
         self.clients
             .read()
             .await
             .iter()
-            .map(|(id, (_, sink))| (id.clone(), sink.clone()))
+            .filter(|(_, entry)| match target_monitor_ids {
+                Some(targets) => is_subscribed(&entry.info.display_monitor_ids, targets, invert),
+                None => true,
+            })
+            .map(|(id, entry)| (id.clone(), entry.sink.clone(), entry.broadcast_seq.clone(), entry.info.wire_format))
             .collect()
     }
 
     /// Check if client exists
-    #[allow(dead_code)]
     pub async fn has_client(&self, client_id: &str) -> bool {
         self.clients.read().await.contains_key(client_id)
     }
 }
 
+/// Check whether a client displaying `display_monitor_ids` is addressed by `targets`
+///
+/// Clients that list no monitors (or "ALL") receive everything and filter locally.
+/// When `invert` is set, a client is addressed if none of its monitors are in `targets`.
+pub(crate) fn is_subscribed(display_monitor_ids: &[String], targets: &[String], invert: bool) -> bool {
+    if display_monitor_ids.is_empty() || display_monitor_ids.iter().any(|id| id == ALL_MONITORS) {
+        return true;
+    }
+    let listed = targets
+        .iter()
+        .any(|target| target == ALL_MONITORS || display_monitor_ids.contains(target));
+    listed != invert
+}
+
 impl Default for ClientManager {
     fn default() -> Self {
         Self::new()
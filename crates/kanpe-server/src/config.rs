@@ -0,0 +1,137 @@
+//! Server configuration
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Suggested window for `ServerConfig::feedback_dedup_window`
+pub const DEFAULT_FEEDBACK_DEDUP_WINDOW: Duration = Duration::from_secs(3);
+
+/// Default number of cues and feedback messages kept in history
+pub const DEFAULT_HISTORY_CAPACITY: usize = 200;
+
+/// Default time clients get to disconnect after a shutdown notice
+pub const DEFAULT_SHUTDOWN_GRACE: Duration = Duration::from_secs(2);
+
+/// Default time a dropped client has to reconnect and keep its client ID
+pub const DEFAULT_RESUME_GRACE: Duration = Duration::from_secs(30);
+
+/// Default time a new connection has to send its ClientHello
+pub const DEFAULT_HELLO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Default number of messages per second each client may send
+pub const DEFAULT_CLIENT_RATE_LIMIT: u32 = 50;
+
+/// Default largest inbound text message the server will parse
+pub const DEFAULT_MAX_MESSAGE_BYTES: usize = 64 * 1024;
+
+/// Default time between keepalive pings to each client
+pub const DEFAULT_PING_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Default time a pinged client may stay silent before it is dropped (three missed pings)
+pub const DEFAULT_PONG_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// What happens when a cue arrives for a monitor already showing its `max_cues`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CueOverflow {
+    /// Recall the monitor's oldest cue to make room
+    #[default]
+    EvictOldest,
+    /// Refuse to send the new cue
+    Reject,
+}
+
+/// What happens when a cue targets monitor ids the server does not know
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnknownTargetPolicy {
+    /// Send the cue anyway and emit `ServerEvent::UnknownTargets`
+    #[default]
+    Warn,
+    /// Refuse to send the cue
+    Reject,
+}
+
+/// What happens when a client says hello with a name another client is already using
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateNamePolicy {
+    /// Register both clients under the same name
+    #[default]
+    Allow,
+    /// Close the newcomer's connection
+    Reject,
+    /// Register the newcomer as e.g. "iPhone (2)" and tell it so in the welcome
+    Suffix,
+}
+
+/// Configuration options for a KanpeServer
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// Maximum number of concurrently registered clients (None = unlimited)
+    pub max_clients: Option<usize>,
+    /// File saved scenes are persisted to (None = kept in memory only)
+    pub scenes_path: Option<PathBuf>,
+    /// File the monitor layout is loaded from at start and saved to on change (None = defaults each start)
+    pub monitors_path: Option<PathBuf>,
+    /// Drop feedback identical to one the same client sent within this window (None = off)
+    pub feedback_dedup_window: Option<Duration>,
+    /// Shared secret casters must present as a bearer token or `token` query param (None = open)
+    pub auth_token: Option<String>,
+    /// Refuse connections that do not request the `kanpe.v1` WebSocket subprotocol
+    ///
+    /// Off by default so older casters, which do not send it, can still connect.
+    pub require_subprotocol: bool,
+    /// Number of sent cues (and, separately, received feedback messages) kept in history
+    pub history_capacity: usize,
+    /// Behaviour when a monitor's `max_cues` limit is reached
+    pub cue_overflow: CueOverflow,
+    /// Behaviour when a cue targets monitors that do not exist
+    pub unknown_targets: UnknownTargetPolicy,
+    /// Behaviour when a client's name is already taken by a connected client
+    pub duplicate_names: DuplicateNamePolicy,
+    /// How long `stop` waits for clients to leave after announcing the shutdown
+    pub shutdown_grace: Duration,
+    /// Time between keepalive pings to each client (zero = never ping)
+    ///
+    /// Somewhere between 5 and 60 seconds suits most venues: shorter notices
+    /// dropped casters sooner on flaky networks, longer saves battery on tablets.
+    /// Intervals under a second mostly add traffic, since a drop is still only
+    /// seen when a send fails.
+    pub ping_interval: Duration,
+    /// How long a registered client may send nothing, not even a pong, before it is closed (zero = forever)
+    ///
+    /// Only enforced while pings are being sent, since an unpinged client has nothing to answer.
+    pub pong_timeout: Duration,
+    /// How long after a drop a client may present its resume token to keep its client ID (zero = never)
+    pub resume_grace: Duration,
+    /// Largest inbound message a client may send; bigger ones close the connection
+    pub max_message_bytes: usize,
+    /// How long a new connection may go without sending ClientHello before it is closed (zero = forever)
+    pub hello_timeout: Duration,
+    /// Messages per second each connection may send before extras are dropped (None = unlimited)
+    ///
+    /// A connection that keeps flooding for a few seconds is closed.
+    pub client_rate_limit: Option<u32>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            max_clients: None,
+            scenes_path: None,
+            monitors_path: None,
+            feedback_dedup_window: None,
+            auth_token: None,
+            require_subprotocol: false,
+            history_capacity: DEFAULT_HISTORY_CAPACITY,
+            cue_overflow: CueOverflow::default(),
+            unknown_targets: UnknownTargetPolicy::default(),
+            duplicate_names: DuplicateNamePolicy::default(),
+            shutdown_grace: DEFAULT_SHUTDOWN_GRACE,
+            ping_interval: DEFAULT_PING_INTERVAL,
+            pong_timeout: DEFAULT_PONG_TIMEOUT,
+            resume_grace: DEFAULT_RESUME_GRACE,
+            max_message_bytes: DEFAULT_MAX_MESSAGE_BYTES,
+            hello_timeout: DEFAULT_HELLO_TIMEOUT,
+            client_rate_limit: Some(DEFAULT_CLIENT_RATE_LIMIT),
+        }
+    }
+}
@@ -0,0 +1,111 @@
+//! Timeline of client connections and disconnections
+
+use kanpe_core::types::timestamp;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use tokio::sync::RwLock;
+
+/// Number of entries kept in the connection log
+pub const CONNECTION_LOG_CAPACITY: usize = 200;
+
+/// Kind of connection change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionEventKind {
+    Connected,
+    Disconnected,
+    /// A client with the same name connected again after dropping
+    Reconnected,
+}
+
+/// One entry in the connection log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionLogEntry {
+    pub timestamp: i64,
+    pub client_id: String,
+    pub name: String,
+    pub event: ConnectionEventKind,
+    pub reason: Option<String>,
+}
+
+/// Bounded log of connection changes, oldest first
+pub struct ConnectionLog {
+    entries: RwLock<VecDeque<ConnectionLogEntry>>,
+}
+
+impl ConnectionLog {
+    pub fn new() -> Self {
+        Self {
+            entries: RwLock::new(VecDeque::with_capacity(CONNECTION_LOG_CAPACITY)),
+        }
+    }
+
+    /// Record a client registering, as a reconnect if its name last dropped
+    pub async fn record_connected(&self, client_id: &str, name: &str) {
+        let mut entries = self.entries.write().await;
+        let dropped_before = entries
+            .iter()
+            .rev()
+            .find(|entry| entry.name == name)
+            .is_some_and(|entry| entry.event == ConnectionEventKind::Disconnected);
+        let event = if dropped_before {
+            ConnectionEventKind::Reconnected
+        } else {
+            ConnectionEventKind::Connected
+        };
+        push(&mut entries, client_id, name, event, None);
+    }
+
+    /// Record a client being removed
+    pub async fn record_disconnected(&self, client_id: &str, name: &str, reason: &str) {
+        let mut entries = self.entries.write().await;
+        push(
+            &mut entries,
+            client_id,
+            name,
+            ConnectionEventKind::Disconnected,
+            Some(reason.to_string()),
+        );
+    }
+
+    /// Get the reason a client was last dropped, if it has been
+    pub async fn last_disconnect_reason(&self, client_id: &str) -> Option<String> {
+        self.entries
+            .read()
+            .await
+            .iter()
+            .rev()
+            .find(|entry| entry.client_id == client_id && entry.event == ConnectionEventKind::Disconnected)
+            .and_then(|entry| entry.reason.clone())
+    }
+
+    /// Get all entries, oldest first
+    pub async fn entries(&self) -> Vec<ConnectionLogEntry> {
+        self.entries.read().await.iter().cloned().collect()
+    }
+}
+
+impl Default for ConnectionLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn push(
+    entries: &mut VecDeque<ConnectionLogEntry>,
+    client_id: &str,
+    name: &str,
+    event: ConnectionEventKind,
+    reason: Option<String>,
+) {
+    if entries.len() >= CONNECTION_LOG_CAPACITY {
+        entries.pop_front();
+    }
+    entries.push_back(ConnectionLogEntry {
+        timestamp: timestamp(),
+        client_id: client_id.to_string(),
+        name: name.to_string(),
+        event,
+        reason,
+    });
+}
@@ -0,0 +1,108 @@
+//! Tracking of the cues currently latched on each monitor
+
+use crate::config::CueOverflow;
+use crate::error::KanpeError;
+use kanpe_core::types::ALL_MONITORS;
+use kanpe_core::Message;
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+
+/// Cues that have been sent and not yet cleared, keyed by target monitor ID
+///
+/// A cue targeting several monitors is latched on each of them; "ALL" is kept
+/// as its own key. A ClearCommand removes the cleared monitors' cues, and
+/// clearing "ALL" removes everything. Per-monitor `max_cues` limits apply to
+/// cues addressed to that monitor by ID, not to "ALL" cues.
+pub struct CueLatch {
+    cues: RwLock<LatchState>,
+}
+
+#[derive(Default)]
+struct LatchState {
+    by_monitor: HashMap<String, Vec<(u64, Message)>>,
+    next_seq: u64,
+}
+
+impl CueLatch {
+    pub fn new() -> Self {
+        Self {
+            cues: RwLock::new(LatchState::default()),
+        }
+    }
+
+    /// Update the latch for a message about to be broadcast
+    ///
+    /// `max_cues` maps monitor IDs to their cue limits. Returns the
+    /// `(message_id, monitor_id)` pairs evicted to make room, or
+    /// `KanpeError::MonitorFull` (leaving the latch untouched) when the
+    /// overflow policy is to reject.
+    pub async fn apply(
+        &self,
+        message: &Message,
+        max_cues: &HashMap<String, u8>,
+        overflow: CueOverflow,
+    ) -> Result<Vec<(String, String)>, KanpeError> {
+        let mut evicted = Vec::new();
+        match message {
+            Message::KanpeMessage { payload, .. } => {
+                let mut state = self.cues.write().await;
+                // A limit of 0 would hide every cue, so it behaves like 1
+                let limit = |monitor_id: &String| max_cues.get(monitor_id).map(|max| (*max as usize).max(1));
+
+                if overflow == CueOverflow::Reject {
+                    for monitor_id in &payload.target_monitor_ids {
+                        let latched = state.by_monitor.get(monitor_id).map_or(0, Vec::len);
+                        if limit(monitor_id).is_some_and(|max| latched >= max) {
+                            return Err(KanpeError::MonitorFull(monitor_id.clone()));
+                        }
+                    }
+                }
+
+                let seq = state.next_seq;
+                state.next_seq += 1;
+                for monitor_id in &payload.target_monitor_ids {
+                    let cues = state.by_monitor.entry(monitor_id.clone()).or_default();
+                    if let Some(max) = limit(monitor_id) {
+                        while cues.len() >= max {
+                            let (_, oldest) = cues.remove(0);
+                            evicted.push((oldest.id().to_string(), monitor_id.clone()));
+                        }
+                    }
+                    cues.push((seq, message.clone()));
+                }
+            }
+            Message::ClearCommand { payload, .. } => {
+                let mut state = self.cues.write().await;
+                if payload.target_monitor_ids.iter().any(|id| id == ALL_MONITORS) {
+                    state.by_monitor.clear();
+                } else {
+                    for monitor_id in &payload.target_monitor_ids {
+                        state.by_monitor.remove(monitor_id);
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(evicted)
+    }
+
+    /// Get all latched cues in the order they were sent, without duplicates
+    pub async fn active_cues(&self) -> Vec<Message> {
+        let state = self.cues.read().await;
+        let mut cues: Vec<&(u64, Message)> = state.by_monitor.values().flatten().collect();
+        cues.sort_by_key(|(seq, _)| *seq);
+        cues.dedup_by_key(|(seq, _)| *seq);
+        cues.into_iter().map(|(_, message)| message.clone()).collect()
+    }
+
+    /// Remove all latched cues
+    pub async fn clear(&self) {
+        self.cues.write().await.by_monitor.clear();
+    }
+}
+
+impl Default for CueLatch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
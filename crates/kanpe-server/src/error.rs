@@ -0,0 +1,37 @@
+//! Error types for the Kanpe server
+
+use thiserror::Error;
+
+/// Errors returned by KanpeServer operations
+#[derive(Debug, Error)]
+pub enum KanpeError {
+    #[error("client not found: {0}")]
+    ClientNotFound(String),
+    #[error("invalid target: {0}")]
+    InvalidTarget(String),
+    #[error("monitor {0} is already showing its maximum number of cues")]
+    MonitorFull(String),
+    #[error(transparent)]
+    Serialization(#[from] kanpe_core::MessageParseError),
+    #[error("failed to send to client: {0}")]
+    Send(#[source] axum::Error),
+    #[error(transparent)]
+    Monitor(#[from] MonitorError),
+}
+
+/// Errors returned when adding or changing virtual monitors
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum MonitorError {
+    #[error("a monitor named {0:?} already exists")]
+    DuplicateName(String),
+    #[error("a monitor with id {0:?} already exists")]
+    DuplicateId(String),
+    #[error("invalid color {0:?}: expected #rgb or #rrggbb")]
+    InvalidColor(String),
+    #[error("a group with id {0:?} already exists")]
+    DuplicateGroup(String),
+    #[error("no monitor with id {0:?}")]
+    UnknownMonitor(String),
+    #[error("monitor id {0:?} is reserved")]
+    ReservedId(String),
+}
@@ -0,0 +1,73 @@
+//! Suppression of accidentally repeated feedback
+
+use kanpe_core::FeedbackType;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Remembers recent feedback per client and flags repeats within a time window
+pub struct FeedbackDeduplicator {
+    window: Duration,
+    recent: Mutex<HashMap<String, Vec<(u64, Instant)>>>,
+}
+
+impl FeedbackDeduplicator {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            recent: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Check whether feedback repeats one received within the window, recording it if not
+    pub async fn is_duplicate(
+        &self,
+        client_id: &str,
+        content: &str,
+        feedback_type: &FeedbackType,
+        now: Instant,
+    ) -> bool {
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+        feedback_type.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        let mut recent = self.recent.lock().await;
+        let entries = recent.entry(client_id.to_string()).or_default();
+        entries.retain(|(_, received_at)| now.duration_since(*received_at) < self.window);
+
+        if entries.iter().any(|(h, _)| *h == hash) {
+            return true;
+        }
+        entries.push((hash, now));
+        false
+    }
+
+    /// Drop the history of a client that has disconnected
+    pub async fn forget(&self, client_id: &str) {
+        self.recent.lock().await.remove(client_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_duplicate_within_window_is_dropped() {
+        let dedup = FeedbackDeduplicator::new(Duration::from_secs(3));
+        let start = Instant::now();
+
+        assert!(!dedup.is_duplicate("c1", "了解", &FeedbackType::Ack, start).await);
+        assert!(dedup.is_duplicate("c1", "了解", &FeedbackType::Ack, start + Duration::from_secs(1)).await);
+
+        // Different client, content or type is not a repeat
+        assert!(!dedup.is_duplicate("c2", "了解", &FeedbackType::Ack, start).await);
+        assert!(!dedup.is_duplicate("c1", "了解", &FeedbackType::Info, start).await);
+
+        // Once the window has passed the same feedback goes through again
+        assert!(!dedup.is_duplicate("c1", "了解", &FeedbackType::Ack, start + Duration::from_secs(4)).await);
+    }
+}
@@ -0,0 +1,79 @@
+//! Bounded history of messages exchanged during a session
+
+use kanpe_core::Message;
+use std::collections::VecDeque;
+use tokio::sync::{RwLock, RwLockReadGuard};
+
+/// Ring buffer of messages, oldest first; the oldest entry is dropped when full
+pub struct MessageHistory {
+    capacity: usize,
+    messages: RwLock<VecDeque<Message>>,
+}
+
+impl MessageHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            messages: RwLock::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    /// Record a message, evicting the oldest one if the buffer is full
+    pub async fn push(&self, message: Message) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut messages = self.messages.write().await;
+        if messages.len() >= self.capacity {
+            messages.pop_front();
+        }
+        messages.push_back(message);
+    }
+
+    /// Get all recorded messages, oldest first
+    pub async fn all(&self) -> Vec<Message> {
+        self.messages.read().await.iter().cloned().collect()
+    }
+
+    /// Hold the buffer for reading, e.g. to copy it alongside other state
+    pub(crate) async fn read(&self) -> RwLockReadGuard<'_, VecDeque<Message>> {
+        self.messages.read().await
+    }
+
+    /// Remove all recorded messages
+    pub async fn clear(&self) {
+        self.messages.write().await.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cue(content: &str) -> Message {
+        Message::kanpe_message(content.to_string(), vec!["ALL".to_string()], Default::default())
+    }
+
+    fn contents(messages: &[Message]) -> Vec<String> {
+        messages
+            .iter()
+            .map(|m| match m {
+                Message::KanpeMessage { payload, .. } => payload.content.clone(),
+                other => panic!("unexpected message {:?}", other),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_history_evicts_oldest_and_keeps_order() {
+        let history = MessageHistory::new(3);
+        for content in ["1", "2", "3", "4", "5"] {
+            history.push(cue(content)).await;
+        }
+
+        assert_eq!(contents(&history.all().await), vec!["3", "4", "5"]);
+
+        history.clear().await;
+        assert!(history.all().await.is_empty());
+    }
+}
@@ -0,0 +1,96 @@
+//! Per-connection limit on inbound messages
+
+use std::time::Instant;
+
+/// Seconds' worth of dropped messages a connection may rack up before it is closed
+pub const RATE_LIMIT_TOLERANCE_SECS: u32 = 3;
+
+/// What to do with an inbound message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Accept,
+    Drop,
+    /// The client kept flooding past the tolerance; close the connection
+    Close,
+}
+
+/// Token bucket for one connection's inbound messages
+///
+/// Bursts of up to `max_per_sec` are accepted. Messages beyond that are
+/// dropped, and a connection that drops `RATE_LIMIT_TOLERANCE_SECS` seconds'
+/// worth without ever letting its bucket fill back up is closed.
+pub struct InboundLimit {
+    max_per_sec: f64,
+    tokens: f64,
+    refilled_at: Instant,
+    dropped: u32,
+}
+
+impl InboundLimit {
+    pub fn new(max_per_sec: u32, now: Instant) -> Self {
+        let max_per_sec = f64::from(max_per_sec.max(1));
+        Self {
+            max_per_sec,
+            tokens: max_per_sec,
+            refilled_at: now,
+            dropped: 0,
+        }
+    }
+
+    /// Judge one inbound message
+    pub fn check(&mut self, now: Instant) -> Verdict {
+        let elapsed = now.saturating_duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.max_per_sec).min(self.max_per_sec);
+        self.refilled_at = now;
+
+        // A full bucket means the client calmed down; forgive earlier drops
+        if self.tokens >= self.max_per_sec {
+            self.dropped = 0;
+        }
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Verdict::Accept;
+        }
+
+        self.dropped += 1;
+        if f64::from(self.dropped) > self.max_per_sec * f64::from(RATE_LIMIT_TOLERANCE_SECS) {
+            Verdict::Close
+        } else {
+            Verdict::Drop
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_flood_is_dropped_then_closed() {
+        let start = Instant::now();
+        let mut limit = InboundLimit::new(2, start);
+
+        assert_eq!(limit.check(start), Verdict::Accept);
+        assert_eq!(limit.check(start), Verdict::Accept);
+        for _ in 0..6 {
+            assert_eq!(limit.check(start), Verdict::Drop);
+        }
+        assert_eq!(limit.check(start), Verdict::Close);
+    }
+
+    #[test]
+    fn test_quiet_period_forgives_drops() {
+        let start = Instant::now();
+        let mut limit = InboundLimit::new(2, start);
+        for _ in 0..8 {
+            limit.check(start);
+        }
+
+        let later = start + Duration::from_secs(2);
+        assert_eq!(limit.check(later), Verdict::Accept);
+        assert_eq!(limit.check(later), Verdict::Accept);
+        assert_eq!(limit.check(later), Verdict::Drop);
+    }
+}
@@ -7,27 +7,100 @@ mod server;
 mod client_manager;
 mod broadcast;
 mod monitor_manager;
+mod monitor_sync;
+mod config;
+mod connection_log;
+mod error;
+mod presenter_notes;
+mod history;
+mod metrics;
+mod reactions;
+mod snapshot;
+mod feedback_dedup;
+mod cue_latch;
+mod scenes;
+mod timers;
+mod acks;
+mod sessions;
+mod inbound_limit;
+mod versioned_file;
+#[cfg(any(test, feature = "test-transport"))]
+mod memory_transport;
+#[cfg(test)]
+mod test_support;
 
-pub use server::KanpeServer;
+pub use server::{KanpeServer, DEFAULT_SERVER_NAME};
+#[cfg(any(test, feature = "test-transport"))]
+pub use memory_transport::InMemoryAcceptor;
 pub use monitor_manager::MonitorManager;
+pub use metrics::ServerMetrics;
+pub use snapshot::ServerSnapshot;
+pub use monitor_sync::MonitorSyncStatus;
+pub use config::{
+    CueOverflow, DuplicateNamePolicy, ServerConfig, UnknownTargetPolicy, DEFAULT_CLIENT_RATE_LIMIT, DEFAULT_FEEDBACK_DEDUP_WINDOW,
+    DEFAULT_HELLO_TIMEOUT,
+    DEFAULT_HISTORY_CAPACITY, DEFAULT_MAX_MESSAGE_BYTES, DEFAULT_PING_INTERVAL, DEFAULT_PONG_TIMEOUT, DEFAULT_RESUME_GRACE,
+    DEFAULT_SHUTDOWN_GRACE,
+};
+pub use connection_log::{ConnectionEventKind, ConnectionLogEntry};
+pub use error::{KanpeError, MonitorError};
+pub use scenes::Scene;
+
+/// Server events as a `Stream`, ending once the server and its tasks are gone
+pub type ServerEventStream = kanpe_core::EventStream<events::ServerEvent>;
 
 // Re-export events for integration
 pub mod events {
     use serde::{Serialize, Deserialize};
 
+    /// Why a registered client left
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum DisconnectReason {
+        /// The client closed the connection itself
+        ClientClosed,
+        /// The connection dropped or failed
+        NetworkError,
+        /// The server closed it: disconnected by the director or broke a limit
+        Kicked,
+        /// The client stopped answering pings
+        Timeout,
+        /// The server is stopping
+        ServerShutdown,
+    }
+
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub enum ServerEvent {
         ClientConnected {
             client_id: String,
             name: String,
             monitor_ids: Vec<String>,
+            connected_at: i64,
+        },
+        /// A dropped client came back with its resume token and kept its ID
+        ClientReconnected {
+            client_id: String,
+            name: String,
+            monitor_ids: Vec<String>,
+            connected_at: i64,
         },
         ClientDisconnected {
             client_id: String,
+            reason: DisconnectReason,
+        },
+        ClientSubscriptionUpdated {
+            client_id: String,
+            monitor_ids: Vec<String>,
+        },
+        ClientRejected {
+            reason: String,
         },
         FeedbackReceived {
             message: kanpe_core::Message,
         },
+        ReactionReceived {
+            message: kanpe_core::Message,
+        },
         MonitorAdded {
             monitor: kanpe_core::types::VirtualMonitor,
         },
@@ -37,5 +110,24 @@ pub mod events {
         MonitorUpdated {
             monitor: kanpe_core::types::VirtualMonitor,
         },
+        MessageAcknowledged {
+            client_id: String,
+            message_id: String,
+        },
+        ClientTyping {
+            client_id: String,
+            client_name: String,
+            is_typing: bool,
+        },
+        UnknownTargets {
+            message_id: String,
+            monitor_ids: Vec<String>,
+        },
+        /// A cue went out; `recipient_count` clients received it without error
+        MessageBroadcast {
+            message_id: String,
+            recipient_count: usize,
+            targets: Vec<String>,
+        },
     }
 }
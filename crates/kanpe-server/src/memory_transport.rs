@@ -0,0 +1,108 @@
+//! In-memory connections to a running server, for tests that should not open sockets
+
+use axum::Router;
+use hyper::server::conn::http1;
+use hyper_util::rt::TokioIo;
+use hyper_util::service::TowerToHyperService;
+use tokio::io::DuplexStream;
+
+/// Bytes buffered in each direction of an in-memory connection
+const DUPLEX_BUFFER: usize = 64 * 1024;
+
+/// Opens connections served by the server's router over `tokio::io::duplex` pipes
+///
+/// Each connection goes through the same HTTP upgrade and handlers as one made
+/// over TCP.
+#[derive(Clone)]
+pub struct InMemoryAcceptor {
+    router: Router,
+}
+
+impl InMemoryAcceptor {
+    pub(crate) fn new(router: Router) -> Self {
+        Self { router }
+    }
+
+    /// Open a connection, returning the client's end of the pipe
+    pub fn connect(&self) -> DuplexStream {
+        let (client, server) = tokio::io::duplex(DUPLEX_BUFFER);
+        let service = TowerToHyperService::new(self.router.clone());
+        tokio::spawn(async move {
+            let _ = http1::Builder::new()
+                .serve_connection(TokioIo::new(server), service)
+                .with_upgrades()
+                .await;
+        });
+        client
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::events::ServerEvent;
+    use crate::server::KanpeServer;
+    use kanpe_client::events::ClientEvent;
+    use kanpe_client::KanpeClient;
+    use kanpe_core::{Message, Priority};
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+
+    /// Wait for the first event `pick` accepts, failing after two seconds
+    async fn wait_for<E, T>(rx: &mut mpsc::UnboundedReceiver<E>, mut pick: impl FnMut(E) -> Option<T>) -> T {
+        tokio::time::timeout(Duration::from_secs(2), async {
+            loop {
+                if let Some(found) = pick(rx.recv().await.expect("event channel closed")) {
+                    return found;
+                }
+            }
+        })
+        .await
+        .expect("timed out waiting for event")
+    }
+
+    #[tokio::test]
+    async fn test_cue_round_trip_in_memory() {
+        let (server_tx, mut server_rx) = mpsc::unbounded_channel();
+        let mut server = KanpeServer::new(server_tx);
+        let acceptor = server.start_in_memory().await.unwrap();
+
+        let (client_tx, mut client_rx) = mpsc::unbounded_channel();
+        let mut client = KanpeClient::new(client_tx);
+        client.set_stream_connector(move || {
+            let acceptor = acceptor.clone();
+            async move { Ok(acceptor.connect()) }
+        });
+        client
+            .connect("ws://kanpe.test", "caster".to_string(), vec!["A".to_string()])
+            .await
+            .unwrap();
+
+        let client_id = wait_for(&mut server_rx, |event| match event {
+            ServerEvent::ClientConnected { client_id, .. } => Some(client_id),
+            _ => None,
+        })
+        .await;
+        wait_for(&mut client_rx, |event| {
+            matches!(event, ClientEvent::ServerWelcomeReceived { .. }).then_some(())
+        })
+        .await;
+
+        let cue = Message::kanpe_message("Stand by".to_string(), vec!["A".to_string()], Priority::High);
+        let cue_id = cue.id().to_string();
+        server.broadcast_message(cue).await.unwrap();
+        let received_id = wait_for(&mut client_rx, |event| match event {
+            ClientEvent::MessageReceived { message } => Some(message.id().to_string()),
+            _ => None,
+        })
+        .await;
+        assert_eq!(received_id, cue_id);
+
+        client.acknowledge_message(received_id).await.unwrap();
+        let (acked_by, acked_id) = wait_for(&mut server_rx, |event| match event {
+            ServerEvent::MessageAcknowledged { client_id, message_id } => Some((client_id, message_id)),
+            _ => None,
+        })
+        .await;
+        assert_eq!((acked_by, acked_id), (client_id, cue_id));
+    }
+}
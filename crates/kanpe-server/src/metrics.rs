@@ -0,0 +1,47 @@
+//! Per-session server counters
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Snapshot of server activity since it was started
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerMetrics {
+    /// When the server started listening (Unix timestamp in milliseconds)
+    pub started_at: Option<i64>,
+    pub messages_broadcast: u64,
+    pub feedback_received: u64,
+    pub pings_sent: u64,
+    /// Inbound messages dropped for exceeding a client's rate limit
+    #[serde(default)]
+    pub messages_rate_limited: u64,
+    pub current_clients: usize,
+    pub peak_clients: usize,
+}
+
+/// Counters updated from the broadcast paths, feedback handler and ping tasks
+#[derive(Default)]
+pub struct MetricsCounters {
+    pub messages_broadcast: AtomicU64,
+    pub feedback_received: AtomicU64,
+    pub pings_sent: AtomicU64,
+    pub messages_rate_limited: AtomicU64,
+}
+
+impl MetricsCounters {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Increment a counter by one
+    pub fn increment(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Reset all counters for a new session
+    pub fn reset(&self) {
+        self.messages_broadcast.store(0, Ordering::Relaxed);
+        self.feedback_received.store(0, Ordering::Relaxed);
+        self.pings_sent.store(0, Ordering::Relaxed);
+        self.messages_rate_limited.store(0, Ordering::Relaxed);
+    }
+}
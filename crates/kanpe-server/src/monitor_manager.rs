@@ -1,14 +1,37 @@
 //! Monitor management for virtual monitors
 
 use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use kanpe_core::types::VirtualMonitor;
+use serde::Deserialize;
+use tokio::sync::{RwLock, RwLockReadGuard};
+use kanpe_core::types::{MonitorGroup, VirtualMonitor, ALL_MONITORS, GROUP_TARGET_PREFIX};
+use kanpe_core::versioned::SCHEMA_VERSION_KEY;
+use crate::error::MonitorError;
+use crate::versioned_file::load_versioned;
+
+/// Current schema version of the monitors file
+pub const MONITORS_SCHEMA_VERSION: u32 = 1;
+
+/// On-disk monitors file: versioned, or a bare map from before versioning
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredMonitors {
+    Versioned {
+        monitors: HashMap<String, VirtualMonitor>,
+        #[serde(default)]
+        groups: HashMap<String, MonitorGroup>,
+    },
+    Legacy(HashMap<String, VirtualMonitor>),
+}
 
 /// Manages virtual monitors for the server
 #[derive(Clone)]
 pub struct MonitorManager {
     monitors: Arc<RwLock<HashMap<String, VirtualMonitor>>>,
+    groups: Arc<RwLock<HashMap<String, MonitorGroup>>>,
+    path: Option<PathBuf>,
 }
 
 impl MonitorManager {
@@ -16,15 +39,72 @@ impl MonitorManager {
     pub fn new() -> Self {
         Self {
             monitors: Arc::new(RwLock::new(HashMap::new())),
+            groups: Arc::new(RwLock::new(HashMap::new())),
+            path: None,
+        }
+    }
+
+    /// Create a MonitorManager that saves to `path` whenever a monitor is added, removed or updated
+    pub fn with_path(path: PathBuf) -> Self {
+        Self {
+            monitors: Arc::new(RwLock::new(HashMap::new())),
+            groups: Arc::new(RwLock::new(HashMap::new())),
+            path: Some(path),
+        }
+    }
+
+    /// Load monitors from the configured file, returning false if there is none yet
+    pub async fn load(&self) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        match &self.path {
+            Some(path) if path.exists() => {
+                self.load_from(path).await?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Replace all monitors and groups with those saved in a JSON file
+    pub async fn load_from(&self, path: &Path) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let (monitors, groups) = match load_versioned(path, MONITORS_SCHEMA_VERSION)? {
+            StoredMonitors::Versioned { monitors, groups } => (monitors, groups),
+            StoredMonitors::Legacy(monitors) => (monitors, HashMap::new()),
+        };
+        *self.monitors.write().await = monitors;
+        *self.groups.write().await = groups;
+        Ok(())
+    }
+
+    /// Write all monitors and groups to a JSON file
+    pub async fn save_to(&self, path: &Path) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let monitors = self.monitors.read().await;
+        let groups = self.groups.read().await;
+        let file = serde_json::json!({
+            SCHEMA_VERSION_KEY: MONITORS_SCHEMA_VERSION,
+            "monitors": &*monitors,
+            "groups": &*groups,
+        });
+        fs::write(path, serde_json::to_string_pretty(&file)?)?;
+        Ok(())
+    }
+
+    /// Save to the configured file, if any
+    async fn persist(&self) {
+        if let Some(path) = &self.path
+            && let Err(e) = self.save_to(path).await
+        {
+            tracing::warn!(path = %path.display(), error = %e, "failed to save monitors");
         }
     }
 
     /// Initialize with default monitors (A, B, C, D)
+    ///
+    /// Defaults that collide with an existing monitor's id or name are skipped.
     pub async fn initialize_default_monitors(&self) {
-        self.add_monitor_with_id("A".to_string(), "Monitor A".to_string(), Some("モニター A".to_string()), Some("#3b82f6".to_string())).await;
-        self.add_monitor_with_id("B".to_string(), "Monitor B".to_string(), Some("モニター B".to_string()), Some("#10b981".to_string())).await;
-        self.add_monitor_with_id("C".to_string(), "Monitor C".to_string(), Some("モニター C".to_string()), Some("#f59e0b".to_string())).await;
-        self.add_monitor_with_id("D".to_string(), "Monitor D".to_string(), Some("モニター D".to_string()), Some("#ef4444".to_string())).await;
+        let _ = self.add_monitor_with_id("A".to_string(), "Monitor A".to_string(), Some("モニター A".to_string()), Some("#3b82f6".to_string()), Some("🎤".to_string())).await;
+        let _ = self.add_monitor_with_id("B".to_string(), "Monitor B".to_string(), Some("モニター B".to_string()), Some("#10b981".to_string()), Some("🎬".to_string())).await;
+        let _ = self.add_monitor_with_id("C".to_string(), "Monitor C".to_string(), Some("モニター C".to_string()), Some("#f59e0b".to_string()), Some("🎧".to_string())).await;
+        let _ = self.add_monitor_with_id("D".to_string(), "Monitor D".to_string(), Some("モニター D".to_string()), Some("#ef4444".to_string()), Some("📣".to_string())).await;
     }
 
     /// Add a new monitor with auto-generated ID
@@ -33,32 +113,58 @@ impl MonitorManager {
         name: String,
         description: Option<String>,
         color: Option<String>,
-    ) -> VirtualMonitor {
+        icon: Option<String>,
+    ) -> Result<VirtualMonitor, MonitorError> {
         // Generate a new unique ID (use timestamp-based or UUID-based approach)
         let id = self.generate_new_id().await;
-        self.add_monitor_with_id(id, name, description, color).await
+        let monitor = self.add_monitor_with_id(id, name, description, color, icon).await?;
+        self.persist().await;
+        Ok(monitor)
     }
 
     /// Add a new monitor with a specific ID
+    ///
+    /// Fails if the ID is taken or reserved, or the name matches an existing one, ignoring case.
     pub async fn add_monitor_with_id(
         &self,
         id: String,
         name: String,
         description: Option<String>,
         color: Option<String>,
-    ) -> VirtualMonitor {
+        icon: Option<String>,
+    ) -> Result<VirtualMonitor, MonitorError> {
+        if id == ALL_MONITORS {
+            return Err(MonitorError::ReservedId(id));
+        }
+        let color = normalize_color(color)?;
+        let mut monitors = self.monitors.write().await;
+        if monitors.contains_key(&id) {
+            return Err(MonitorError::DuplicateId(id));
+        }
+        if name_in_use(monitors.values(), &name, None) {
+            return Err(MonitorError::DuplicateName(name));
+        }
+
+        // New monitors go to the end of the current order
+        let position = monitors
+            .values()
+            .map(|m| m.position + 1)
+            .max()
+            .unwrap_or(0);
         let monitor = VirtualMonitor {
             id: id.clone(),
             name,
             description,
             color,
+            icon,
+            max_cues: None,
+            position,
+            enabled: true,
         };
-
-        let mut monitors = self.monitors.write().await;
         monitors.insert(id, monitor.clone());
         drop(monitors);
 
-        monitor
+        Ok(monitor)
     }
 
     /// Generate a new unique ID for a monitor
@@ -75,29 +181,122 @@ impl MonitorManager {
     }
 
     /// Remove a monitor by ID
+    ///
+    /// The monitor is also dropped from any group it belonged to.
     pub async fn remove_monitor(&self, id: String) -> Option<VirtualMonitor> {
         let mut monitors = self.monitors.write().await;
-        monitors.remove(&id)
+        let removed = monitors.remove(&id);
+        if removed.is_some() {
+            prune_groups(&mut *self.groups.write().await, &monitors);
+            drop(monitors);
+            self.persist().await;
+        }
+        removed
     }
 
-    /// Update an existing monitor
-    pub async fn update_monitor(&self, monitor: VirtualMonitor) -> bool {
+    /// Update an existing monitor, returning it as stored or `None` if the ID is unknown
+    ///
+    /// Fails if the new name matches another monitor's, ignoring case.
+    pub async fn update_monitor(
+        &self,
+        mut monitor: VirtualMonitor,
+    ) -> Result<Option<VirtualMonitor>, MonitorError> {
+        monitor.color = normalize_color(monitor.color)?;
         let mut monitors = self.monitors.write().await;
         if monitors.contains_key(&monitor.id) {
-            monitors.insert(monitor.id.clone(), monitor);
-            true
+            if name_in_use(monitors.values(), &monitor.name, Some(&monitor.id)) {
+                return Err(MonitorError::DuplicateName(monitor.name));
+            }
+            monitors.insert(monitor.id.clone(), monitor.clone());
+            drop(monitors);
+            self.persist().await;
+            Ok(Some(monitor))
         } else {
-            false
+            Ok(None)
         }
     }
 
-    /// Get all monitors, sorted by ID for consistent ordering
+    /// Enable or disable a monitor, returning it as stored or `None` if the ID is unknown
+    pub async fn set_enabled(&self, id: &str, enabled: bool) -> Option<VirtualMonitor> {
+        let mut monitors = self.monitors.write().await;
+        let monitor = monitors.get_mut(id)?;
+        monitor.enabled = enabled;
+        let monitor = monitor.clone();
+        drop(monitors);
+        self.persist().await;
+        Some(monitor)
+    }
+
+    /// Get all monitors in display order
     pub async fn get_all_monitors(&self) -> Vec<VirtualMonitor> {
-        let monitors = self.monitors.read().await;
-        let mut monitor_list: Vec<VirtualMonitor> = monitors.values().cloned().collect();
-        // Sort by ID to ensure consistent ordering (A, B, C, D, etc.)
-        monitor_list.sort_by(|a, b| a.id.cmp(&b.id));
-        monitor_list
+        ordered(&*self.monitors.read().await)
+    }
+
+    /// Hold the monitor table for reading, e.g. to copy it alongside other state
+    pub(crate) async fn read_monitors(&self) -> RwLockReadGuard<'_, HashMap<String, VirtualMonitor>> {
+        self.monitors.read().await
+    }
+
+    /// Put the listed monitors first, in the given order
+    ///
+    /// Monitors not listed keep their relative order after the listed ones.
+    pub async fn reorder(&self, ordered_ids: Vec<String>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let current = self.get_all_monitors().await;
+        if let Some(unknown) = ordered_ids.iter().find(|id| !current.iter().any(|m| &m.id == *id)) {
+            return Err(format!("Monitor not found: {}", unknown).into());
+        }
+
+        let rest = current.iter().map(|m| &m.id).filter(|id| !ordered_ids.contains(id));
+        let order: Vec<String> = ordered_ids.iter().chain(rest).cloned().collect();
+
+        let mut monitors = self.monitors.write().await;
+        for (position, id) in order.iter().enumerate() {
+            if let Some(monitor) = monitors.get_mut(id) {
+                monitor.position = position as u32;
+            }
+        }
+        drop(monitors);
+        self.persist().await;
+        Ok(())
+    }
+
+    /// Remove every monitor
+    ///
+    /// Groups are kept but left empty; they can be refilled or deleted separately.
+    pub async fn clear_all(&self) {
+        let mut monitors = self.monitors.write().await;
+        monitors.clear();
+        prune_groups(&mut *self.groups.write().await, &monitors);
+        drop(monitors);
+        self.persist().await;
+    }
+
+    /// Replace all monitors with the given set
+    ///
+    /// Fails without changing anything if two monitors share an ID or a name (ignoring case),
+    /// an ID is reserved, or a color is invalid.
+    /// Groups keep only the members that are still present.
+    pub async fn import_monitors(&self, new_monitors: Vec<VirtualMonitor>) -> Result<(), MonitorError> {
+        let mut imported = HashMap::with_capacity(new_monitors.len());
+        for mut monitor in new_monitors {
+            if monitor.id == ALL_MONITORS {
+                return Err(MonitorError::ReservedId(monitor.id));
+            }
+            if imported.contains_key(&monitor.id) {
+                return Err(MonitorError::DuplicateId(monitor.id));
+            }
+            if name_in_use(imported.values(), &monitor.name, None) {
+                return Err(MonitorError::DuplicateName(monitor.name));
+            }
+            monitor.color = normalize_color(monitor.color)?;
+            imported.insert(monitor.id.clone(), monitor);
+        }
+        let mut monitors = self.monitors.write().await;
+        *monitors = imported;
+        prune_groups(&mut *self.groups.write().await, &monitors);
+        drop(monitors);
+        self.persist().await;
+        Ok(())
     }
 
     /// Get a specific monitor by ID
@@ -105,6 +304,100 @@ impl MonitorManager {
         let monitors = self.monitors.read().await;
         monitors.get(&id).cloned()
     }
+
+    /// Create a named group of existing monitors, targetable as "group:<id>"
+    pub async fn create_group(
+        &self,
+        id: String,
+        name: String,
+        monitor_ids: Vec<String>,
+    ) -> Result<MonitorGroup, MonitorError> {
+        // Hold the monitor table until the group is in so a concurrent removal can't slip between
+        let monitors = self.monitors.read().await;
+        if let Some(unknown) = monitor_ids.iter().find(|id| !monitors.contains_key(*id)) {
+            return Err(MonitorError::UnknownMonitor(unknown.clone()));
+        }
+
+        let mut groups = self.groups.write().await;
+        if groups.contains_key(&id) {
+            return Err(MonitorError::DuplicateGroup(id));
+        }
+        let group = MonitorGroup { id: id.clone(), name, monitor_ids };
+        groups.insert(id, group.clone());
+        drop(groups);
+        drop(monitors);
+        self.persist().await;
+        Ok(group)
+    }
+
+    /// Delete a group, leaving its monitors untouched
+    pub async fn delete_group(&self, id: &str) -> Option<MonitorGroup> {
+        let removed = self.groups.write().await.remove(id);
+        if removed.is_some() {
+            self.persist().await;
+        }
+        removed
+    }
+
+    /// Get the monitor ids in a group, or `None` if there is no such group
+    pub async fn members(&self, group_id: &str) -> Option<Vec<String>> {
+        self.groups.read().await.get(group_id).map(|group| group.monitor_ids.clone())
+    }
+
+    /// Get all groups, sorted by ID
+    pub async fn get_all_groups(&self) -> Vec<MonitorGroup> {
+        let mut groups: Vec<MonitorGroup> = self.groups.read().await.values().cloned().collect();
+        groups.sort_by(|a, b| a.id.cmp(&b.id));
+        groups
+    }
+
+    /// Replace "group:<id>" entries with the group's members, dropping duplicates
+    ///
+    /// Unknown groups are left as-is so target validation can report them.
+    pub async fn expand_targets(&self, target_monitor_ids: &[String]) -> Vec<String> {
+        let groups = self.groups.read().await;
+        let mut expanded: Vec<String> = Vec::new();
+        for target in target_monitor_ids {
+            let members = target
+                .strip_prefix(GROUP_TARGET_PREFIX)
+                .and_then(|group_id| groups.get(group_id))
+                .map(|group| group.monitor_ids.clone())
+                .unwrap_or_else(|| vec![target.clone()]);
+            for id in members {
+                if !expanded.contains(&id) {
+                    expanded.push(id);
+                }
+            }
+        }
+        expanded
+    }
+}
+
+/// Drop group members that are no longer monitors
+fn prune_groups(groups: &mut HashMap<String, MonitorGroup>, monitors: &HashMap<String, VirtualMonitor>) {
+    for group in groups.values_mut() {
+        group.monitor_ids.retain(|id| monitors.contains_key(id));
+    }
+}
+
+/// Check whether a monitor other than `except_id` already has this name, ignoring case
+fn name_in_use<'a>(
+    monitors: impl IntoIterator<Item = &'a VirtualMonitor>,
+    name: &str,
+    except_id: Option<&str>,
+) -> bool {
+    let name = name.to_lowercase();
+    monitors
+        .into_iter()
+        .any(|m| Some(m.id.as_str()) != except_id && m.name.to_lowercase() == name)
+}
+
+/// List monitors in display order
+pub(crate) fn ordered(monitors: &HashMap<String, VirtualMonitor>) -> Vec<VirtualMonitor> {
+    let mut monitor_list: Vec<VirtualMonitor> = monitors.values().cloned().collect();
+    // Sort by position, then ID so equal positions stay stable (A, B, C, D, etc.)
+    monitor_list.sort_by(|a, b| a.position.cmp(&b.position).then_with(|| a.id.cmp(&b.id)));
+    monitor_list
 }
 
 impl Default for MonitorManager {
@@ -112,3 +405,209 @@ impl Default for MonitorManager {
         Self::new()
     }
 }
+
+/// Check that a color is `#rgb` or `#rrggbb` and expand it to lowercase `#rrggbb`
+fn normalize_color(color: Option<String>) -> Result<Option<String>, MonitorError> {
+    let Some(color) = color else {
+        return Ok(None);
+    };
+    let digits = match color.strip_prefix('#') {
+        Some(digits) if matches!(digits.len(), 3 | 6) && digits.chars().all(|c| c.is_ascii_hexdigit()) => digits,
+        _ => return Err(MonitorError::InvalidColor(color)),
+    };
+    let digits = digits.to_ascii_lowercase();
+    if digits.len() == 3 {
+        Ok(Some(digits.chars().fold(String::from("#"), |mut out, c| {
+            out.push(c);
+            out.push(c);
+            out
+        })))
+    } else {
+        Ok(Some(format!("#{}", digits)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_added_monitor_survives_reload() {
+        let path = std::env::temp_dir().join(format!("kanpe-monitors-{}.json", kanpe_core::types::new_id()));
+
+        let manager = MonitorManager::with_path(path.clone());
+        manager.initialize_default_monitors().await;
+        let added = manager.add_monitor("Stage Left".to_string(), None, Some("#123456".to_string()), None).await.unwrap();
+        drop(manager);
+
+        let reloaded = MonitorManager::with_path(path.clone());
+        assert!(reloaded.load().await.unwrap());
+        let monitors = reloaded.get_all_monitors().await;
+        assert_eq!(monitors.len(), 5);
+        let restored = reloaded.get_monitor(added.id).await.unwrap();
+        assert_eq!(restored.name, "Stage Left");
+        assert_eq!(restored.color.as_deref(), Some("#123456"));
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_reorder_moves_d_before_a() {
+        let manager = MonitorManager::new();
+        manager.initialize_default_monitors().await;
+        let positions: Vec<u32> = manager.get_all_monitors().await.iter().map(|m| m.position).collect();
+        assert_eq!(positions, vec![0, 1, 2, 3]);
+
+        manager.reorder(vec!["D".to_string(), "A".to_string()]).await.unwrap();
+
+        let ids: Vec<String> = manager.get_all_monitors().await.into_iter().map(|m| m.id).collect();
+        assert_eq!(ids, vec!["D", "A", "B", "C"]);
+        assert!(manager.reorder(vec!["Z".to_string()]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_name_is_rejected_ignoring_case() {
+        let manager = MonitorManager::new();
+        manager.initialize_default_monitors().await;
+
+        let result = manager.add_monitor("monitor a".to_string(), None, None, None).await;
+
+        assert_eq!(result.unwrap_err(), MonitorError::DuplicateName("monitor a".to_string()));
+        assert_eq!(manager.get_all_monitors().await.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_rename_to_existing_name_is_rejected() {
+        let manager = MonitorManager::new();
+        manager.initialize_default_monitors().await;
+
+        let mut renamed = manager.get_monitor("B".to_string()).await.unwrap();
+        renamed.name = "monitor a".to_string();
+        assert_eq!(
+            manager.update_monitor(renamed.clone()).await.unwrap_err(),
+            MonitorError::DuplicateName("monitor a".to_string())
+        );
+        assert_eq!(manager.get_monitor("B".to_string()).await.unwrap().name, "Monitor B");
+
+        // Changing only the case of a monitor's own name is fine
+        renamed.name = "MONITOR B".to_string();
+        assert!(manager.update_monitor(renamed).await.unwrap().is_some());
+
+        let mut imported = manager.get_all_monitors().await;
+        imported[1].name = "Monitor A".to_string();
+        assert_eq!(
+            manager.import_monitors(imported).await.unwrap_err(),
+            MonitorError::DuplicateName("Monitor A".to_string())
+        );
+        assert_eq!(manager.get_all_monitors().await.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_all_is_a_reserved_id() {
+        let manager = MonitorManager::new();
+        manager.initialize_default_monitors().await;
+
+        let result = manager
+            .add_monitor_with_id(ALL_MONITORS.to_string(), "Everything".to_string(), None, None, None)
+            .await;
+        assert_eq!(result.unwrap_err(), MonitorError::ReservedId(ALL_MONITORS.to_string()));
+
+        let mut imported = manager.get_all_monitors().await;
+        imported[0].id = ALL_MONITORS.to_string();
+        assert_eq!(
+            manager.import_monitors(imported).await.unwrap_err(),
+            MonitorError::ReservedId(ALL_MONITORS.to_string())
+        );
+        assert!(manager.get_monitor(ALL_MONITORS.to_string()).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_id_is_rejected() {
+        let manager = MonitorManager::new();
+        manager.initialize_default_monitors().await;
+
+        let result = manager
+            .add_monitor_with_id("B".to_string(), "Backstage".to_string(), None, None, None)
+            .await;
+
+        assert_eq!(result.unwrap_err(), MonitorError::DuplicateId("B".to_string()));
+        assert_eq!(manager.get_monitor("B".to_string()).await.unwrap().name, "Monitor B");
+    }
+
+    #[tokio::test]
+    async fn test_colors_are_validated_and_normalized() {
+        let manager = MonitorManager::new();
+
+        let short = manager.add_monitor("Short".to_string(), None, Some("#FFF".to_string()), None).await.unwrap();
+        assert_eq!(short.color.as_deref(), Some("#ffffff"));
+        let long = manager.add_monitor("Long".to_string(), None, Some("#3b82f6".to_string()), None).await.unwrap();
+        assert_eq!(long.color.as_deref(), Some("#3b82f6"));
+
+        for bad in ["blue", "#12"] {
+            let result = manager.add_monitor(format!("Bad {}", bad), None, Some(bad.to_string()), None).await;
+            assert_eq!(result.unwrap_err(), MonitorError::InvalidColor(bad.to_string()));
+        }
+
+        let mut updated = short.clone();
+        updated.color = Some("blue".to_string());
+        assert_eq!(
+            manager.update_monitor(updated).await.unwrap_err(),
+            MonitorError::InvalidColor("blue".to_string())
+        );
+        assert_eq!(manager.get_monitor(short.id).await.unwrap().color.as_deref(), Some("#ffffff"));
+    }
+
+    #[tokio::test]
+    async fn test_group_members_and_target_expansion() {
+        let manager = MonitorManager::new();
+        manager.initialize_default_monitors().await;
+
+        let result = manager.create_group("Pit".to_string(), "Pit".to_string(), vec!["Z".to_string()]).await;
+        assert_eq!(result.unwrap_err(), MonitorError::UnknownMonitor("Z".to_string()));
+
+        manager
+            .create_group("Pit".to_string(), "Pit".to_string(), vec!["A".to_string(), "B".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(manager.members("Pit").await, Some(vec!["A".to_string(), "B".to_string()]));
+
+        let targets = vec!["B".to_string(), "group:Pit".to_string(), "group:Wings".to_string()];
+        assert_eq!(manager.expand_targets(&targets).await, vec!["B", "A", "group:Wings"]);
+
+        assert!(manager.delete_group("Pit").await.is_some());
+        assert_eq!(manager.members("Pit").await, None);
+    }
+
+    #[tokio::test]
+    async fn test_removed_monitors_leave_their_groups() {
+        let manager = MonitorManager::new();
+        manager.initialize_default_monitors().await;
+        manager
+            .create_group("Pit".to_string(), "Pit".to_string(), vec!["A".to_string(), "B".to_string()])
+            .await
+            .unwrap();
+
+        manager.remove_monitor("A".to_string()).await;
+        assert_eq!(manager.members("Pit").await, Some(vec!["B".to_string()]));
+
+        let mut kept = manager.get_monitor("C".to_string()).await.unwrap();
+        kept.id = "B2".to_string();
+        manager.import_monitors(vec![kept]).await.unwrap();
+        assert_eq!(manager.members("Pit").await, Some(vec![]));
+    }
+
+    #[tokio::test]
+    async fn test_import_with_duplicate_ids_keeps_current_monitors() {
+        let manager = MonitorManager::new();
+        manager.initialize_default_monitors().await;
+        let monitor = manager.get_monitor("A".to_string()).await.unwrap();
+
+        let result = manager.import_monitors(vec![monitor.clone(), monitor]).await;
+
+        assert_eq!(result.unwrap_err(), MonitorError::DuplicateId("A".to_string()));
+        assert_eq!(manager.get_all_monitors().await.len(), 4);
+
+        manager.clear_all().await;
+        assert!(manager.get_all_monitors().await.is_empty());
+    }
+}
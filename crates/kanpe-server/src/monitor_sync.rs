@@ -0,0 +1,85 @@
+//! Tracking which clients have applied the current monitor list
+
+use kanpe_core::types::VirtualMonitor;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use tokio::sync::RwLock;
+
+/// Number of recent monitor changes kept for matching late acks
+const MAX_TRACKED_CHANGES: usize = 64;
+
+/// Whether a client has acknowledged the current monitor list
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonitorSyncStatus {
+    pub client_id: String,
+    pub client_name: String,
+    pub in_sync: bool,
+}
+
+/// Hash a monitor list so clients' acknowledged states can be compared with the current one
+pub fn state_hash(monitors: &[VirtualMonitor]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_string(monitors).unwrap_or_default().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Maps monitor change messages to the state they produce and records client acks
+pub struct MonitorSyncTracker {
+    state: RwLock<SyncState>,
+}
+
+#[derive(Default)]
+struct SyncState {
+    changes: VecDeque<(String, u64)>,
+    client_hashes: HashMap<String, u64>,
+}
+
+impl MonitorSyncTracker {
+    pub fn new() -> Self {
+        Self {
+            state: RwLock::new(SyncState::default()),
+        }
+    }
+
+    /// Remember the monitor state a change message leads to
+    pub async fn record_change(&self, change_id: &str, hash: u64) {
+        let mut state = self.state.write().await;
+        if state.changes.len() >= MAX_TRACKED_CHANGES {
+            state.changes.pop_front();
+        }
+        state.changes.push_back((change_id.to_string(), hash));
+    }
+
+    /// Record that a client applied a change, returning false for unknown change IDs
+    pub async fn ack(&self, client_id: &str, change_id: &str) -> bool {
+        let mut state = self.state.write().await;
+        let Some(hash) = state
+            .changes
+            .iter()
+            .find(|(id, _)| id == change_id)
+            .map(|(_, hash)| *hash)
+        else {
+            return false;
+        };
+        state.client_hashes.insert(client_id.to_string(), hash);
+        true
+    }
+
+    /// Check whether a client's last acknowledged state matches `current_hash`
+    pub async fn is_in_sync(&self, client_id: &str, current_hash: u64) -> bool {
+        self.state.read().await.client_hashes.get(client_id) == Some(&current_hash)
+    }
+
+    /// Drop the state of a client that has disconnected
+    pub async fn forget(&self, client_id: &str) {
+        self.state.write().await.client_hashes.remove(client_id);
+    }
+}
+
+impl Default for MonitorSyncTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,53 @@
+//! Storage of recently sent presenter notes for chunk re-requests
+
+use kanpe_core::Message;
+use std::collections::VecDeque;
+use tokio::sync::RwLock;
+
+/// Maximum size of a single presenter note chunk
+pub const PRESENTER_NOTE_CHUNK_BYTES: usize = 16 * 1024;
+
+/// Number of recent notes kept for resending missing chunks
+const RECENT_NOTE_CAPACITY: usize = 16;
+
+/// Keeps the chunks of recently sent notes so clients can re-request missing ones
+pub struct PresenterNoteStore {
+    notes: RwLock<VecDeque<(String, Vec<Message>)>>,
+}
+
+impl PresenterNoteStore {
+    pub fn new() -> Self {
+        Self {
+            notes: RwLock::new(VecDeque::new()),
+        }
+    }
+
+    /// Remember the chunks of a sent note, evicting the oldest note when full
+    pub async fn insert(&self, note_id: String, chunks: Vec<Message>) {
+        let mut notes = self.notes.write().await;
+        if notes.len() >= RECENT_NOTE_CAPACITY {
+            notes.pop_front();
+        }
+        notes.push_back((note_id, chunks));
+    }
+
+    /// Get the requested chunks of a note, if it is still stored
+    pub async fn chunks(&self, note_id: &str, seqs: &[u32]) -> Vec<Message> {
+        let notes = self.notes.read().await;
+        notes
+            .iter()
+            .find(|(id, _)| id == note_id)
+            .map(|(_, chunks)| {
+                seqs.iter()
+                    .filter_map(|seq| chunks.get(*seq as usize).cloned())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl Default for PresenterNoteStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
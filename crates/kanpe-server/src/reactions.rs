@@ -0,0 +1,96 @@
+//! Per-cue reaction tallies, kept apart from the feedback history
+
+use std::collections::{HashMap, VecDeque};
+use tokio::sync::RwLock;
+
+/// Number of cues whose reactions are kept; the oldest cue is dropped first
+const MAX_TRACKED_CUES: usize = 256;
+
+/// Longest accepted reaction, in chars; enough for ZWJ emoji sequences
+pub const MAX_REACTION_CHARS: usize = 16;
+
+/// Check that a reaction is a short, printable token such as an emoji
+pub fn is_valid_reaction(reaction: &str) -> bool {
+    !reaction.trim().is_empty()
+        && reaction.chars().count() <= MAX_REACTION_CHARS
+        && !reaction.chars().any(char::is_control)
+}
+
+/// Counts reactions per cue, e.g. {"cue-1": {"👍": 3, "❓": 1}}
+pub struct ReactionTally {
+    state: RwLock<TallyState>,
+}
+
+#[derive(Default)]
+struct TallyState {
+    order: VecDeque<String>,
+    counts: HashMap<String, HashMap<String, usize>>,
+}
+
+impl ReactionTally {
+    pub fn new() -> Self {
+        Self {
+            state: RwLock::new(TallyState::default()),
+        }
+    }
+
+    /// Count one reaction to a cue
+    pub async fn record(&self, message_id: &str, reaction: &str) {
+        let mut state = self.state.write().await;
+        if !state.counts.contains_key(message_id) {
+            if state.order.len() >= MAX_TRACKED_CUES
+                && let Some(oldest) = state.order.pop_front()
+            {
+                state.counts.remove(&oldest);
+            }
+            state.order.push_back(message_id.to_string());
+        }
+        *state
+            .counts
+            .entry(message_id.to_string())
+            .or_default()
+            .entry(reaction.to_string())
+            .or_default() += 1;
+    }
+
+    /// Get the reaction counts for a cue
+    pub async fn counts(&self, message_id: &str) -> HashMap<String, usize> {
+        self.state.read().await.counts.get(message_id).cloned().unwrap_or_default()
+    }
+
+    /// Forget all reactions
+    pub async fn clear(&self) {
+        let mut state = self.state.write().await;
+        state.order.clear();
+        state.counts.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reaction_validation() {
+        assert!(is_valid_reaction("👍"));
+        assert!(is_valid_reaction("👩‍💻"));
+        assert!(!is_valid_reaction(""));
+        assert!(!is_valid_reaction("  "));
+        assert!(!is_valid_reaction("a\nb"));
+        assert!(!is_valid_reaction(&"x".repeat(MAX_REACTION_CHARS + 1)));
+    }
+
+    #[tokio::test]
+    async fn test_oldest_cue_is_dropped_at_capacity() {
+        let tally = ReactionTally::new();
+        for i in 0..MAX_TRACKED_CUES {
+            tally.record(&format!("cue-{i}"), "👍").await;
+        }
+        tally.record("cue-0", "👍").await;
+        tally.record("cue-new", "👍").await;
+
+        assert!(tally.counts("cue-0").await.is_empty());
+        assert_eq!(tally.counts("cue-1").await["👍"], 1);
+        assert_eq!(tally.counts("cue-new").await["👍"], 1);
+    }
+}
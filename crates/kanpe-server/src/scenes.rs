@@ -0,0 +1,127 @@
+//! Named snapshots of the monitor layout and latched cues
+
+use kanpe_core::types::VirtualMonitor;
+use kanpe_core::versioned::SCHEMA_VERSION_KEY;
+use kanpe_core::Message;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tokio::sync::RwLock;
+use crate::versioned_file::load_versioned;
+
+/// A saved monitor layout together with the cues latched at the time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scene {
+    pub name: String,
+    pub monitors: Vec<VirtualMonitor>,
+    pub cues: Vec<Message>,
+}
+
+/// Current schema version of the scenes file
+pub const SCENES_SCHEMA_VERSION: u32 = 1;
+
+/// On-disk scenes file: versioned, or a bare list from before versioning
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredScenes {
+    Versioned { scenes: Vec<Scene> },
+    Legacy(Vec<Scene>),
+}
+
+/// Stores scenes in memory, optionally persisting them to a JSON file
+pub struct SceneStore {
+    scenes: RwLock<Vec<Scene>>,
+    path: Option<PathBuf>,
+}
+
+impl SceneStore {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            scenes: RwLock::new(Vec::new()),
+            path,
+        }
+    }
+
+    /// Load scenes from the backing file, if one is configured and exists
+    pub async fn load(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if let Some(path) = &self.path
+            && path.exists()
+        {
+            let scenes = match load_versioned(path, SCENES_SCHEMA_VERSION)? {
+                StoredScenes::Versioned { scenes } | StoredScenes::Legacy(scenes) => scenes,
+            };
+            *self.scenes.write().await = scenes;
+        }
+        Ok(())
+    }
+
+    /// Save a scene, replacing any existing scene with the same name
+    pub async fn save(&self, scene: Scene) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut scenes = self.scenes.write().await;
+        match scenes.iter_mut().find(|s| s.name == scene.name) {
+            Some(existing) => *existing = scene,
+            None => scenes.push(scene),
+        }
+        self.persist(&scenes)
+    }
+
+    /// Get a scene by name
+    pub async fn get(&self, name: &str) -> Option<Scene> {
+        self.scenes.read().await.iter().find(|s| s.name == name).cloned()
+    }
+
+    /// Get the names of all saved scenes
+    pub async fn names(&self) -> Vec<String> {
+        self.scenes.read().await.iter().map(|s| s.name.clone()).collect()
+    }
+
+    /// Delete a scene by name, returning whether it existed
+    pub async fn delete(&self, name: &str) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let mut scenes = self.scenes.write().await;
+        let before = scenes.len();
+        scenes.retain(|s| s.name != name);
+        if scenes.len() == before {
+            return Ok(false);
+        }
+        self.persist(&scenes)?;
+        Ok(true)
+    }
+
+    fn persist(&self, scenes: &[Scene]) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if let Some(path) = &self.path {
+            let file = serde_json::json!({
+                SCHEMA_VERSION_KEY: SCENES_SCHEMA_VERSION,
+                "scenes": scenes,
+            });
+            fs::write(path, serde_json::to_string_pretty(&file)?)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_scenes_persist_across_stores() {
+        let path = std::env::temp_dir().join(format!("kanpe-scenes-{}.json", kanpe_core::types::new_id()));
+
+        let store = SceneStore::new(Some(path.clone()));
+        store
+            .save(Scene {
+                name: "Opening".to_string(),
+                monitors: vec![],
+                cues: vec![Message::kanpe_message("Go".to_string(), vec!["A".to_string()], Default::default())],
+            })
+            .await
+            .unwrap();
+
+        let reloaded = SceneStore::new(Some(path.clone()));
+        reloaded.load().await.unwrap();
+        assert_eq!(reloaded.names().await, vec!["Opening".to_string()]);
+        assert_eq!(reloaded.get("Opening").await.unwrap().cues.len(), 1);
+
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -1,23 +1,53 @@
 //! HTTP + WebSocket server implementation
 
-use crate::broadcast::broadcast_message;
-use crate::client_manager::{ClientInfo, ClientManager};
-use crate::events::ServerEvent;
-use crate::monitor_manager::MonitorManager;
+use crate::acks::AckWaiters;
+use crate::broadcast::{ws_frame, Dispatcher};
+use crate::client_manager::{ClientInfo, ClientManager, RegisterError, WsSink};
+use crate::connection_log::ConnectionLogEntry;
+use crate::config::{CueOverflow, ServerConfig};
+use crate::cue_latch::CueLatch;
+use crate::error::KanpeError;
+use crate::ServerEventStream;
+use crate::events::{DisconnectReason, ServerEvent};
+use crate::feedback_dedup::FeedbackDeduplicator;
+use crate::history::MessageHistory;
+use crate::inbound_limit::{InboundLimit, Verdict};
+use crate::metrics::{MetricsCounters, ServerMetrics};
+use crate::monitor_manager::{ordered, MonitorManager};
+use crate::monitor_sync::{state_hash, MonitorSyncStatus, MonitorSyncTracker};
+use crate::presenter_notes::{PresenterNoteStore, PRESENTER_NOTE_CHUNK_BYTES};
+use crate::reactions::{is_valid_reaction, ReactionTally};
+use crate::scenes::{Scene, SceneStore};
+use crate::snapshot::ServerSnapshot;
+use crate::sessions::ResumeTokens;
+use crate::timers::TimerSet;
 use axum::{
-    extract::{ws::WebSocketUpgrade, State},
-    response::Response,
-    routing::get,
-    Router,
+    extract::{ws::WebSocketUpgrade, Query, State},
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode, Uri},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
 };
-use axum::extract::ws::{Message as WsMessage, WebSocket};
+use axum::extract::ws::{close_code, CloseFrame, Message as WsMessage, WebSocket};
 use futures_util::{SinkExt, StreamExt};
-use kanpe_core::Message;
+use kanpe_core::message::FeedbackMessagePayload;
+use kanpe_core::types::{MonitorGroup, ALL_MONITORS};
+use kanpe_core::{AirStatus, Message, MessageParseError, Priority, WireFormat, WEBSOCKET_SUBPROTOCOL};
 use rust_embed::RustEmbed;
-use std::sync::Arc;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
 use tokio::sync::{mpsc, RwLock};
 use tokio::time::{interval, Duration};
 use tower_http::cors::CorsLayer;
+use tower_http::services::ServeDir;
+use tracing::{debug, info, info_span, warn, Instrument};
+
+/// Name sent in ServerWelcome until `KanpeServer::set_name` is called
+pub const DEFAULT_SERVER_NAME: &str = "Kanpe Server";
 
 #[derive(RustEmbed)]
 #[folder = "web-caster/"]
@@ -29,49 +59,167 @@ struct AppState {
     client_manager: Arc<ClientManager>,
     monitor_manager: Arc<MonitorManager>,
     event_tx: mpsc::UnboundedSender<ServerEvent>,
+    config: Arc<ServerConfig>,
+    presenter_notes: Arc<PresenterNoteStore>,
+    feedback_dedup: Option<Arc<FeedbackDeduplicator>>,
+    monitor_sync: Arc<MonitorSyncTracker>,
+    ping_enabled: Arc<AtomicBool>,
+    feedback_history: Arc<MessageHistory>,
+    reactions: Arc<ReactionTally>,
+    metrics: Arc<MetricsCounters>,
+    air_status: Arc<RwLock<Option<Message>>>,
+    acks: Arc<AckWaiters>,
+    resume_tokens: Arc<ResumeTokens>,
+    server_name: Arc<std::sync::RwLock<String>>,
+    dispatcher: Dispatcher,
 }
 
 /// HTTP + WebSocket server for Kanpe director mode
 pub struct KanpeServer {
+    config: Arc<ServerConfig>,
     client_manager: Arc<ClientManager>,
     monitor_manager: Arc<MonitorManager>,
+    presenter_notes: Arc<PresenterNoteStore>,
+    cue_latch: Arc<CueLatch>,
+    scenes: Arc<SceneStore>,
+    feedback_dedup: Option<Arc<FeedbackDeduplicator>>,
+    monitor_sync: Arc<MonitorSyncTracker>,
+    ping_enabled: Arc<AtomicBool>,
+    message_history: Arc<MessageHistory>,
+    feedback_history: Arc<MessageHistory>,
+    reactions: Arc<ReactionTally>,
+    metrics: Arc<MetricsCounters>,
+    air_status: Arc<RwLock<Option<Message>>>,
+    acks: Arc<AckWaiters>,
+    resume_tokens: Arc<ResumeTokens>,
+    server_name: Arc<std::sync::RwLock<String>>,
+    timers: TimerSet,
+    started_at: Option<i64>,
     event_tx: mpsc::UnboundedSender<ServerEvent>,
     shutdown_tx: Option<mpsc::Sender<()>>,
+    local_addr: Option<SocketAddr>,
+    asset_dir: Option<PathBuf>,
 }
 
 impl KanpeServer {
-    /// Create a new KanpeServer
+    /// Create a new KanpeServer with the default configuration
     pub fn new(event_tx: mpsc::UnboundedSender<ServerEvent>) -> Self {
+        Self::with_config(event_tx, ServerConfig::default())
+    }
+
+    /// Create a new KanpeServer with the given configuration
+    pub fn with_config(event_tx: mpsc::UnboundedSender<ServerEvent>, config: ServerConfig) -> Self {
+        let scenes = Arc::new(SceneStore::new(config.scenes_path.clone()));
+        let feedback_dedup = config
+            .feedback_dedup_window
+            .map(|window| Arc::new(FeedbackDeduplicator::new(window)));
+        let monitor_manager = match config.monitors_path.clone() {
+            Some(path) => MonitorManager::with_path(path),
+            None => MonitorManager::new(),
+        };
         Self {
             client_manager: Arc::new(ClientManager::new()),
-            monitor_manager: Arc::new(MonitorManager::new()),
+            monitor_manager: Arc::new(monitor_manager),
+            presenter_notes: Arc::new(PresenterNoteStore::new()),
+            cue_latch: Arc::new(CueLatch::new()),
+            scenes,
+            feedback_dedup,
+            monitor_sync: Arc::new(MonitorSyncTracker::new()),
+            ping_enabled: Arc::new(AtomicBool::new(true)),
+            message_history: Arc::new(MessageHistory::new(config.history_capacity)),
+            feedback_history: Arc::new(MessageHistory::new(config.history_capacity)),
+            reactions: Arc::new(ReactionTally::new()),
+            metrics: Arc::new(MetricsCounters::new()),
+            air_status: Arc::new(RwLock::new(None)),
+            acks: Arc::new(AckWaiters::new()),
+            resume_tokens: Arc::new(ResumeTokens::new(config.resume_grace)),
+            server_name: Arc::new(std::sync::RwLock::new(DEFAULT_SERVER_NAME.to_string())),
+            timers: TimerSet::new(),
+            started_at: None,
+            config: Arc::new(config),
             event_tx,
             shutdown_tx: None,
+            local_addr: None,
+            asset_dir: None,
         }
     }
 
-    /// Start the HTTP + WebSocket server on the specified port
-    pub async fn start(&mut self, port: u16) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        // Initialize default monitors
-        self.monitor_manager.initialize_default_monitors().await;
+    /// Create a KanpeServer whose events are read as a `Stream`
+    pub fn with_event_stream(config: ServerConfig) -> (Self, ServerEventStream) {
+        let (event_tx, event_rx) = mpsc::unbounded_channel();
+        (Self::with_config(event_tx, config), ServerEventStream::new(event_rx))
+    }
 
-        let state = AppState {
-            client_manager: self.client_manager.clone(),
-            monitor_manager: self.monitor_manager.clone(),
-            event_tx: self.event_tx.clone(),
-        };
+    /// Serve the web caster from `dir`, falling back to the embedded files it lacks
+    ///
+    /// Files are read on every request, so edits show up on reload. Takes
+    /// effect the next time the server is started.
+    pub fn with_asset_dir(mut self, dir: PathBuf) -> Self {
+        self.asset_dir = Some(dir);
+        self
+    }
 
-        // Build router with static file serving and WebSocket endpoint
-        let app = Router::new()
-            .route("/", get(serve_index))
-            .route("/styles.css", get(serve_css))
-            .route("/app.js", get(serve_js))
-            .route("/ws", get(websocket_handler))
-            .layer(CorsLayer::permissive())
-            .with_state(state);
+    /// Start the HTTP + WebSocket server on all interfaces at the specified port
+    ///
+    /// Returns the address actually bound, which carries the real port when `0` was passed.
+    pub async fn start(&mut self, port: u16) -> Result<SocketAddr, Box<dyn std::error::Error + Send + Sync>> {
+        self.start_on(SocketAddr::from((Ipv4Addr::UNSPECIFIED, port))).await
+    }
+
+    /// Start the HTTP + WebSocket server on a specific bind address
+    ///
+    /// Returns the address actually bound, which carries the real port when `0` was passed.
+    pub async fn start_on(
+        &mut self,
+        addr: SocketAddr,
+    ) -> Result<SocketAddr, Box<dyn std::error::Error + Send + Sync>> {
+        self.load_state().await?;
+        self.listen(addr).await
+    }
+
+    /// Stop the listener and start it again on `port`, keeping monitors, scenes and history
+    ///
+    /// Connected clients are drained as in [`stop`](Self::stop); when they
+    /// reconnect they are welcomed with the current monitor list. The bind
+    /// address stays the same (all interfaces if the server was not running).
+    ///
+    /// The new port is bound before the old listener stops, so a port that is
+    /// already taken leaves the running server untouched. Restarting on the
+    /// port currently in use has to free it first.
+    pub async fn restart(&mut self, port: u16) -> Result<SocketAddr, Box<dyn std::error::Error + Send + Sync>> {
+        let ip = self
+            .local_addr
+            .map(|addr| addr.ip())
+            .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        let addr = SocketAddr::new(ip, port);
+        if self.is_running() && self.local_addr.is_some_and(|current| current.port() == port) {
+            self.stop_listener("server restarting").await;
+            return self.listen(addr).await;
+        }
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        self.stop_listener("server restarting").await;
+        self.serve(listener)
+    }
+
+    /// Check whether the server has been started and not stopped since
+    pub fn is_running(&self) -> bool {
+        self.shutdown_tx.is_some()
+    }
 
-        let addr = format!("0.0.0.0:{}", port);
-        let listener = tokio::net::TcpListener::bind(&addr).await?;
+    /// Bind `addr` and serve the router on it, without reloading persisted state
+    async fn listen(&mut self, addr: SocketAddr) -> Result<SocketAddr, Box<dyn std::error::Error + Send + Sync>> {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        self.serve(listener)
+    }
+
+    /// Serve the router on an already bound listener
+    fn serve(&mut self, listener: tokio::net::TcpListener) -> Result<SocketAddr, Box<dyn std::error::Error + Send + Sync>> {
+        let app = self.build_router();
+
+        let local_addr = listener.local_addr()?;
+        self.local_addr = Some(local_addr);
+        self.started_at = Some(kanpe_core::types::timestamp());
+        self.metrics.reset();
 
         let (shutdown_tx, mut shutdown_rx) = mpsc::channel::<()>(1);
         self.shutdown_tx = Some(shutdown_tx);
@@ -85,23 +233,420 @@ impl KanpeServer {
                 .expect("Server error");
         });
 
+        Ok(local_addr)
+    }
+
+    /// Start the server without a socket, returning an acceptor for in-memory connections
+    ///
+    /// `stop` still drains the connected clients; the acceptor keeps working
+    /// until the server is dropped.
+    #[cfg(any(test, feature = "test-transport"))]
+    pub async fn start_in_memory(
+        &mut self,
+    ) -> Result<crate::memory_transport::InMemoryAcceptor, Box<dyn std::error::Error + Send + Sync>> {
+        self.load_state().await?;
+        let app = self.build_router();
+        self.started_at = Some(kanpe_core::types::timestamp());
+        self.metrics.reset();
+
+        // Nothing listens for the shutdown signal; holding the sender marks the server as running
+        let (shutdown_tx, _) = mpsc::channel::<()>(1);
+        self.shutdown_tx = Some(shutdown_tx);
+
+        Ok(crate::memory_transport::InMemoryAcceptor::new(app))
+    }
+
+    /// Load the persisted monitor layout and scenes
+    async fn load_state(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        // Restore the saved monitor layout, falling back to the defaults
+        if !self.monitor_manager.load().await? {
+            self.monitor_manager.initialize_default_monitors().await;
+        }
+
+        // Load previously saved scenes
+        self.scenes.load().await?;
         Ok(())
     }
 
+    /// Build the HTTP + WebSocket router
+    fn build_router(&self) -> Router {
+        let state = AppState {
+            client_manager: self.client_manager.clone(),
+            monitor_manager: self.monitor_manager.clone(),
+            event_tx: self.event_tx.clone(),
+            config: self.config.clone(),
+            presenter_notes: self.presenter_notes.clone(),
+            feedback_dedup: self.feedback_dedup.clone(),
+            monitor_sync: self.monitor_sync.clone(),
+            ping_enabled: self.ping_enabled.clone(),
+            feedback_history: self.feedback_history.clone(),
+            reactions: self.reactions.clone(),
+            metrics: self.metrics.clone(),
+            air_status: self.air_status.clone(),
+            acks: self.acks.clone(),
+            resume_tokens: self.resume_tokens.clone(),
+            server_name: self.server_name.clone(),
+            dispatcher: self.dispatcher(),
+        };
+
+        // Build router with static file serving and WebSocket endpoint
+        let app = Router::new().route("/ws", get(websocket_handler));
+        let app = match &self.asset_dir {
+            Some(dir) => app.fallback_service(ServeDir::new(dir).fallback(get(serve_embedded))),
+            None => app.fallback(serve_embedded),
+        };
+        // /api/message is added after the CORS layer so browsers refuse cross-origin
+        // posts: without it any web page could inject cues when no auth token is set
+        app.layer(CorsLayer::permissive())
+            .route("/api/message", post(post_message))
+            .with_state(state)
+    }
+
     /// Stop the server
+    ///
+    /// Connected clients are sent a ServerShutdown notice and given up to
+    /// `ServerConfig::shutdown_grace` to disconnect; the rest are then closed.
     pub async fn stop(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.stop_listener("server stopped").await;
+        self.local_addr = None;
+        self.started_at = None;
+        Ok(())
+    }
+
+    /// Cancel timers, drain clients and shut the listener down
+    async fn stop_listener(&mut self, reason: &str) {
+        self.timers.cancel_all();
         if let Some(shutdown_tx) = self.shutdown_tx.take() {
+            self.drain_clients(reason).await;
             let _ = shutdown_tx.send(()).await;
         }
-        Ok(())
     }
 
-    /// Broadcast a message to all connected clients
+    /// Announce the shutdown, wait for clients to leave, then close whoever is left
+    async fn drain_clients(&self, reason: &str) {
+        if self.client_manager.client_count().await == 0 {
+            return;
+        }
+
+        let grace = self.config.shutdown_grace;
+        let notice = Message::server_shutdown(reason.to_string(), grace.as_millis() as u64);
+        let _ = self.dispatcher().broadcast(&notice).await;
+
+        let deadline = tokio::time::Instant::now() + grace;
+        while self.client_manager.client_count().await > 0 && tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        for client in self.client_manager.get_all_clients().await {
+            let _ = self.close_client(&client.client_id, reason, DisconnectReason::ServerShutdown).await;
+        }
+    }
+
+    /// Pause or resume keepalive pings to all clients
+    ///
+    /// While disabled, ping ticks are skipped and resume on the next tick once
+    /// re-enabled. Clients are not probed in the meantime, so a dead connection
+    /// is only noticed when a send fails or the socket closes.
+    pub fn set_ping_enabled(&self, enabled: bool) {
+        self.ping_enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Check whether keepalive pings are currently sent
+    pub fn is_ping_enabled(&self) -> bool {
+        self.ping_enabled.load(Ordering::Relaxed)
+    }
+
+    /// Set the name announced to casters in ServerWelcome (e.g. "Stage Left")
+    ///
+    /// Takes effect for connections made after the call; clients already
+    /// connected keep the name they were welcomed with.
+    pub fn set_name(&self, name: impl Into<String>) {
+        *self.server_name.write().unwrap() = name.into();
+    }
+
+    /// Get the name announced to casters in ServerWelcome
+    pub fn name(&self) -> String {
+        self.server_name.read().unwrap().clone()
+    }
+
+    /// Get the address the server is bound to, if it is running
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+
+    /// Get the WebSocket URLs casters can use to reach this server (e.g. "ws://192.168.1.10:9876")
+    ///
+    /// When bound to all interfaces this lists the machine's LAN IPv4 addresses, falling back
+    /// to loopback if none are found. Returns an empty list when the server is not running.
+    pub fn listening_addresses(&self) -> Vec<String> {
+        let Some(local_addr) = self.local_addr else {
+            return Vec::new();
+        };
+        let port = local_addr.port();
+
+        let mut ips = Vec::new();
+        if local_addr.ip().is_unspecified() {
+            if let Ok(interfaces) = local_ip_address::list_afinet_netifas() {
+                for (_name, ip) in interfaces {
+                    // Only include IPv4 addresses that are accessible from other local PCs
+                    if let IpAddr::V4(ipv4) = ip
+                        && !ipv4.is_loopback()
+                        && !ipv4.is_link_local()
+                        && !ips.contains(&ip)
+                    {
+                        ips.push(ip);
+                    }
+                }
+            }
+            if ips.is_empty() {
+                ips.push(IpAddr::V4(Ipv4Addr::LOCALHOST));
+            }
+        } else {
+            ips.push(local_addr.ip());
+        }
+
+        ips.into_iter()
+            .map(|ip| format!("ws://{}", SocketAddr::new(ip, port)))
+            .collect()
+    }
+
+    /// Broadcast a message to all connected clients it targets
+    ///
+    /// Returns the clients that could not be reached, with the error for each.
     pub async fn broadcast_message(
         &self,
         message: Message,
-    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        broadcast_message(&self.client_manager, &message).await
+    ) -> Result<Vec<(String, KanpeError)>, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(self.dispatcher().send(&message).await?)
+    }
+
+    /// Flash the given monitors on every client displaying them
+    pub async fn broadcast_flash_command(
+        &self,
+        target_monitor_ids: Vec<String>,
+    ) -> Result<Vec<(String, KanpeError)>, Box<dyn std::error::Error + Send + Sync>> {
+        self.broadcast_message(Message::flash_command(target_monitor_ids)).await
+    }
+
+    /// Clear the given monitors on every client displaying them
+    pub async fn broadcast_clear_command(
+        &self,
+        target_monitor_ids: Vec<String>,
+    ) -> Result<Vec<(String, KanpeError)>, Box<dyn std::error::Error + Send + Sync>> {
+        self.broadcast_message(Message::clear_command(target_monitor_ids)).await
+    }
+
+    /// Flash every monitor on every connected client
+    pub async fn flash_all(&self) -> Result<Vec<(String, KanpeError)>, Box<dyn std::error::Error + Send + Sync>> {
+        self.broadcast_flash_command(vec![ALL_MONITORS.to_string()]).await
+    }
+
+    /// Clear every monitor on every connected client
+    pub async fn clear_all(&self) -> Result<Vec<(String, KanpeError)>, Box<dyn std::error::Error + Send + Sync>> {
+        self.broadcast_clear_command(vec![ALL_MONITORS.to_string()]).await
+    }
+
+    /// Broadcast an urgent cue and re-flash its targets every `interval` until a
+    /// client displaying one of them acks it
+    ///
+    /// Repeats stop after `timeout`, or when the server stops. Returns the cue's message ID.
+    pub async fn broadcast_urgent(
+        &self,
+        content: String,
+        target_monitor_ids: Vec<String>,
+        interval: Duration,
+        timeout: Duration,
+    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        // Expand groups up front so the repeated flashes reach the same monitors
+        let target_monitor_ids = self.monitor_manager.expand_targets(&target_monitor_ids).await;
+        let message = Message::urgent_kanpe_message(content, target_monitor_ids.clone(), interval.as_millis() as u64);
+        let message_id = message.id().to_string();
+        let mut acked = self.acks.wait_for(&message_id, target_monitor_ids.clone());
+        let dispatcher = self.dispatcher();
+        if let Err(e) = dispatcher.send(&message).await {
+            self.acks.forget(&message_id);
+            return Err(e.into());
+        }
+
+        let acks = self.acks.clone();
+        let repeat_id = message_id.clone();
+        self.timers.spawn(async move {
+            let deadline = tokio::time::sleep(timeout);
+            tokio::pin!(deadline);
+            let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+            loop {
+                tokio::select! {
+                    _ = &mut acked => break,
+                    _ = &mut deadline => break,
+                    _ = ticker.tick() => {
+                        let _ = dispatcher.broadcast(&Message::flash_command(target_monitor_ids.clone())).await;
+                    }
+                }
+            }
+            acks.forget(&repeat_id);
+        });
+
+        Ok(message_id)
+    }
+
+    /// Broadcast a message after `delay`
+    ///
+    /// The timer is cancelled if the server stops before it fires.
+    pub fn schedule_message(&self, message: Message, delay: Duration) {
+        let dispatcher = self.dispatcher();
+        self.timers.spawn(async move {
+            tokio::time::sleep(delay).await;
+            let _ = dispatcher.send(&message).await;
+        });
+    }
+
+    /// Get the number of timers that have not fired or been cancelled yet
+    pub fn pending_timers(&self) -> usize {
+        self.timers.active_count()
+    }
+
+    /// Bundle the state needed to send cues from a detached task
+    fn dispatcher(&self) -> Dispatcher {
+        Dispatcher {
+            client_manager: self.client_manager.clone(),
+            monitor_manager: self.monitor_manager.clone(),
+            cue_latch: self.cue_latch.clone(),
+            message_history: self.message_history.clone(),
+            metrics: self.metrics.clone(),
+            event_tx: self.event_tx.clone(),
+            cue_overflow: self.config.cue_overflow,
+            unknown_targets: self.config.unknown_targets,
+        }
+    }
+
+    /// Set the on-air banner shown on every caster, including ones that connect later
+    pub async fn set_air_status(&self, status: AirStatus) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let message = Message::air_status(status, kanpe_core::types::timestamp());
+        *self.air_status.write().await = Some(message.clone());
+        self.broadcast(&message).await?;
+        Ok(())
+    }
+
+    /// Get the current air status and when it took effect, if one was ever set
+    pub async fn air_status(&self) -> Option<(AirStatus, i64)> {
+        match self.air_status.read().await.as_ref() {
+            Some(Message::AirStatus { payload, .. }) => Some((payload.status, payload.since)),
+            _ => None,
+        }
+    }
+
+    /// Get the cues sent this session, oldest first
+    pub async fn message_history(&self) -> Vec<Message> {
+        self.message_history.all().await
+    }
+
+    /// Get the feedback received this session, oldest first
+    pub async fn feedback_history(&self) -> Vec<Message> {
+        self.feedback_history.all().await
+    }
+
+    /// Get the feedback replying to a cue, oldest first
+    ///
+    /// Drawn from the feedback history, so only the last `history_capacity` replies are kept.
+    pub async fn feedback_for(&self, message_id: &str) -> Vec<FeedbackMessagePayload> {
+        self.all_feedback().await.remove(message_id).unwrap_or_default()
+    }
+
+    /// Get the feedback in history grouped by the cue it replies to, oldest first within each group
+    pub async fn all_feedback(&self) -> HashMap<String, Vec<FeedbackMessagePayload>> {
+        let mut grouped: HashMap<String, Vec<FeedbackMessagePayload>> = HashMap::new();
+        for message in self.feedback_history.all().await {
+            if let Message::FeedbackMessage { payload, .. } = message {
+                grouped.entry(payload.reply_to_message_id.clone()).or_default().push(payload);
+            }
+        }
+        grouped
+    }
+
+    /// Count the reactions to a cue by reaction, e.g. {"👍": 3, "❓": 1}
+    pub async fn reaction_counts(&self, message_id: &str) -> HashMap<String, usize> {
+        self.reactions.counts(message_id).await
+    }
+
+    /// Clear the cue and feedback history along with the reaction counts
+    pub async fn clear_history(&self) {
+        self.message_history.clear().await;
+        self.feedback_history.clear().await;
+        self.reactions.clear().await;
+    }
+
+    /// Get the cues currently latched on monitors, in the order they were sent
+    pub async fn active_cues(&self) -> Vec<Message> {
+        self.cue_latch.active_cues().await
+    }
+
+    /// Save the current monitors and latched cues as a named scene
+    ///
+    /// An existing scene with the same name is overwritten.
+    pub async fn save_scene(&self, name: String) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let scene = Scene {
+            name,
+            monitors: self.monitor_manager.get_all_monitors().await,
+            cues: self.cue_latch.active_cues().await,
+        };
+        self.scenes.save(scene).await
+    }
+
+    /// Restore a saved scene's monitors and cues and push them to all clients
+    pub async fn recall_scene(&self, name: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let scene = self
+            .scenes
+            .get(name)
+            .await
+            .ok_or_else(|| format!("Scene not found: {}", name))?;
+
+        self.monitor_manager.import_monitors(scene.monitors).await?;
+        let monitors = self.monitor_manager.get_all_monitors().await;
+        self.broadcast_monitor_change(Message::monitor_list_sync(monitors)).await?;
+
+        // Clear whatever is showing, then re-apply the scene's cues in their original order
+        self.cue_latch.clear().await;
+        self.broadcast(&Message::clear_command(vec![ALL_MONITORS.to_string()])).await?;
+        let limits = self.dispatcher().cue_limits().await;
+        for cue in &scene.cues {
+            self.cue_latch.apply(cue, &limits, CueOverflow::EvictOldest).await?;
+            self.broadcast(cue).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Get the names of all saved scenes
+    pub async fn list_scenes(&self) -> Vec<String> {
+        self.scenes.names().await
+    }
+
+    /// Delete a saved scene, returning whether it existed
+    pub async fn delete_scene(&self, name: &str) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        self.scenes.delete(name).await
+    }
+
+    /// Send a presenter note, split into chunks so long notes fit within frame limits
+    ///
+    /// Returns the note ID shared by all chunks.
+    pub async fn send_presenter_note(
+        &self,
+        content: &str,
+        target_monitor_ids: Vec<String>,
+    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let chunks = Message::presenter_note_chunks(content, target_monitor_ids, PRESENTER_NOTE_CHUNK_BYTES);
+        let note_id = match chunks.first() {
+            Some(Message::PresenterNoteChunk { payload, .. }) => payload.note_id.clone(),
+            _ => return Err("Failed to split presenter note".into()),
+        };
+
+        // Keep the chunks around so clients can re-request any that go missing
+        self.presenter_notes.insert(note_id.clone(), chunks.clone()).await;
+
+        for chunk in &chunks {
+            self.broadcast(chunk).await?;
+        }
+
+        Ok(note_id)
     }
 
     /// Get list of connected clients
@@ -109,18 +654,86 @@ impl KanpeServer {
         self.client_manager.get_all_clients().await
     }
 
+    /// Forcibly disconnect a client
+    ///
+    /// The client is removed immediately and sent a close frame; its connection
+    /// task exits once the close completes.
+    pub async fn disconnect_client(&self, client_id: &str) -> Result<(), KanpeError> {
+        self.close_client(client_id, "disconnected by server", DisconnectReason::Kicked).await
+    }
+
+    /// Remove a client and send it a close frame, reporting why it was dropped
+    async fn close_client(&self, client_id: &str, close_reason: &str, reason: DisconnectReason) -> Result<(), KanpeError> {
+        let sink = self
+            .client_manager
+            .get_sink(client_id)
+            .await
+            .ok_or_else(|| KanpeError::ClientNotFound(client_id.to_string()))?;
+
+        // Only the side that actually removes the client reports the disconnect
+        if self.client_manager.remove_client(client_id, close_reason).await.is_none() {
+            return Err(KanpeError::ClientNotFound(client_id.to_string()));
+        }
+
+        let close = WsMessage::Close(Some(CloseFrame {
+            code: close_code::NORMAL,
+            reason: close_reason.to_string().into(),
+        }));
+        let _ = sink.write().await.send(close).await;
+
+        let _ = self.event_tx.send(ServerEvent::ClientDisconnected {
+            client_id: client_id.to_string(),
+            reason,
+        });
+
+        Ok(())
+    }
+
+    /// Ask a client to drop its connection and reconnect
+    ///
+    /// Unlike [`disconnect_client`](Self::disconnect_client) the client is not
+    /// removed here; it closes the connection itself and comes back if it has
+    /// auto-reconnect enabled.
+    pub async fn request_client_reconnect(&self, client_id: &str) -> Result<(), KanpeError> {
+        let sink = self
+            .client_manager
+            .get_sink(client_id)
+            .await
+            .ok_or_else(|| KanpeError::ClientNotFound(client_id.to_string()))?;
+
+        let wire_format = self.client_manager.get_wire_format(client_id).await.unwrap_or_default();
+        let frame = ws_frame(&Message::reconnect_request(), wire_format, None)?;
+        sink.write().await.send(frame).await.map_err(KanpeError::Send)
+    }
+
+    /// Get the timeline of client connections and disconnections, oldest first
+    pub async fn connection_log(&self) -> Vec<ConnectionLogEntry> {
+        self.client_manager.connection_log().await
+    }
+
+    /// Check whether a client is currently connected
+    pub async fn is_client_connected(&self, client_id: &str) -> bool {
+        self.client_manager.has_client(client_id).await
+    }
+
+    /// Get why a client was last dropped, if it was recently connected
+    pub async fn last_disconnect_reason(&self, client_id: &str) -> Option<String> {
+        self.client_manager.last_disconnect_reason(client_id).await
+    }
+
     /// Add a new virtual monitor
     pub async fn add_monitor(
         &self,
         name: String,
         description: Option<String>,
         color: Option<String>,
-    ) -> Result<kanpe_core::types::VirtualMonitor, Box<dyn std::error::Error + Send + Sync>> {
-        let monitor = self.monitor_manager.add_monitor(name, description, color).await;
+        icon: Option<String>,
+    ) -> Result<kanpe_core::types::VirtualMonitor, KanpeError> {
+        let monitor = self.monitor_manager.add_monitor(name, description, color, icon).await?;
 
         // Broadcast MonitorAdded message to all clients
         let msg = Message::monitor_added(monitor.clone());
-        broadcast_message(&self.client_manager, &msg).await?;
+        self.broadcast_monitor_change(msg).await?;
 
         // Emit event
         let _ = self.event_tx.send(ServerEvent::MonitorAdded {
@@ -135,10 +748,15 @@ impl KanpeServer {
         &self,
         monitor_id: String,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let groups_before = self.monitor_manager.get_all_groups().await;
         if let Some(_monitor) = self.monitor_manager.remove_monitor(monitor_id.clone()).await {
             // Broadcast MonitorRemoved message to all clients
             let msg = Message::monitor_removed(monitor_id.clone());
-            broadcast_message(&self.client_manager, &msg).await?;
+            self.broadcast_monitor_change(msg).await?;
+            // The manager dropped it from its groups; push the pruned list
+            if self.monitor_manager.get_all_groups().await != groups_before {
+                self.broadcast_group_sync().await?;
+            }
 
             // Emit event
             let _ = self.event_tx.send(ServerEvent::MonitorRemoved { monitor_id });
@@ -151,10 +769,10 @@ impl KanpeServer {
         &self,
         monitor: kanpe_core::types::VirtualMonitor,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        if self.monitor_manager.update_monitor(monitor.clone()).await {
+        if let Some(monitor) = self.monitor_manager.update_monitor(monitor).await? {
             // Broadcast MonitorUpdated message to all clients
             let msg = Message::monitor_updated(monitor.clone());
-            broadcast_message(&self.client_manager, &msg).await?;
+            self.broadcast_monitor_change(msg).await?;
 
             // Emit event
             let _ = self.event_tx.send(ServerEvent::MonitorUpdated {
@@ -164,34 +782,163 @@ impl KanpeServer {
         Ok(())
     }
 
+    /// Take a monitor out of rotation (or put it back) without losing its config
+    ///
+    /// Clients are sent MonitorUpdated and keep the monitor in their list.
+    pub async fn set_monitor_enabled(
+        &self,
+        monitor_id: &str,
+        enabled: bool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if let Some(monitor) = self.monitor_manager.set_enabled(monitor_id, enabled).await {
+            let msg = Message::monitor_updated(monitor.clone());
+            self.broadcast_monitor_change(msg).await?;
+
+            let _ = self.event_tx.send(ServerEvent::MonitorUpdated { monitor });
+        }
+        Ok(())
+    }
+
+    /// Change the display order of monitors and push the new list to all clients
+    pub async fn reorder_monitors(
+        &self,
+        ordered_ids: Vec<String>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.monitor_manager.reorder(ordered_ids).await?;
+        let monitors = self.monitor_manager.get_all_monitors().await;
+        self.broadcast_monitor_change(Message::monitor_list_sync(monitors)).await?;
+        Ok(())
+    }
+
+    /// Swap the whole monitor set and push it to all clients as a single list sync
+    pub async fn replace_monitors(
+        &self,
+        monitors: Vec<kanpe_core::types::VirtualMonitor>,
+    ) -> Result<(), KanpeError> {
+        let groups_before = self.monitor_manager.get_all_groups().await;
+        self.monitor_manager.import_monitors(monitors).await?;
+        let monitors = self.monitor_manager.get_all_monitors().await;
+        self.broadcast_monitor_change(Message::monitor_list_sync(monitors)).await?;
+        // Groups lose members that were not imported
+        if self.monitor_manager.get_all_groups().await != groups_before {
+            self.broadcast_group_sync().await?;
+        }
+        Ok(())
+    }
+
+    /// Create a named group of monitors that cues can target as "group:<id>"
+    pub async fn create_monitor_group(
+        &self,
+        id: String,
+        name: String,
+        monitor_ids: Vec<String>,
+    ) -> Result<MonitorGroup, KanpeError> {
+        let group = self.monitor_manager.create_group(id, name, monitor_ids).await?;
+        self.broadcast_group_sync().await?;
+        Ok(group)
+    }
+
+    /// Delete a monitor group, returning whether it existed
+    pub async fn delete_monitor_group(&self, group_id: &str) -> Result<bool, KanpeError> {
+        let existed = self.monitor_manager.delete_group(group_id).await.is_some();
+        if existed {
+            self.broadcast_group_sync().await?;
+        }
+        Ok(existed)
+    }
+
+    /// Get all monitor groups
+    pub async fn get_monitor_groups(&self) -> Vec<MonitorGroup> {
+        self.monitor_manager.get_all_groups().await
+    }
+
+    /// Push the full group list to every client
+    async fn broadcast_group_sync(&self) -> Result<(), KanpeError> {
+        let groups = self.monitor_manager.get_all_groups().await;
+        self.broadcast(&Message::monitor_group_sync(groups)).await?;
+        Ok(())
+    }
+
     /// Get all virtual monitors
     pub async fn get_monitors(&self) -> Vec<kanpe_core::types::VirtualMonitor> {
         self.monitor_manager.get_all_monitors().await
     }
-}
 
-/// Serve index.html
-async fn serve_index() -> Response {
-    serve_static_file("index.html", "text/html")
-}
+    /// Report which connected clients have acknowledged the current monitor list
+    pub async fn get_monitor_sync_status(&self) -> Vec<MonitorSyncStatus> {
+        let current_hash = state_hash(&self.monitor_manager.get_all_monitors().await);
+        let mut statuses = Vec::new();
+        for client in self.client_manager.get_all_clients().await {
+            statuses.push(MonitorSyncStatus {
+                in_sync: self.monitor_sync.is_in_sync(&client.client_id, current_hash).await,
+                client_id: client.client_id,
+                client_name: client.client_name,
+            });
+        }
+        statuses
+    }
 
-/// Serve styles.css
-async fn serve_css() -> Response {
-    serve_static_file("styles.css", "text/css")
-}
+    /// Broadcast a message and count it in the session metrics
+    async fn broadcast(&self, message: &Message) -> Result<Vec<(String, KanpeError)>, KanpeError> {
+        self.dispatcher().broadcast(message).await
+    }
+
+    /// Get counters for the current session
+    pub async fn metrics(&self) -> ServerMetrics {
+        self.metrics_with_clients(self.client_manager.client_count().await)
+    }
+
+    /// Build the metrics given the current client count, so callers already holding the client table can use it
+    fn metrics_with_clients(&self, current_clients: usize) -> ServerMetrics {
+        ServerMetrics {
+            started_at: self.started_at,
+            messages_broadcast: self.metrics.messages_broadcast.load(Ordering::Relaxed),
+            feedback_received: self.metrics.feedback_received.load(Ordering::Relaxed),
+            pings_sent: self.metrics.pings_sent.load(Ordering::Relaxed),
+            messages_rate_limited: self.metrics.messages_rate_limited.load(Ordering::Relaxed),
+            current_clients,
+            peak_clients: self.client_manager.peak_clients(),
+        }
+    }
 
-/// Serve app.js
-async fn serve_js() -> Response {
-    serve_static_file("app.js", "application/javascript")
+    /// Capture monitors, clients, history and metrics as one consistent view
+    ///
+    /// The monitor, client and history locks are taken in that order and held
+    /// until all three are copied, so no change can land between the parts.
+    pub async fn snapshot(&self) -> ServerSnapshot {
+        let monitors = self.monitor_manager.read_monitors().await;
+        let clients = self.client_manager.read_clients().await;
+        let history = self.message_history.read().await;
+        ServerSnapshot {
+            monitors: ordered(&monitors),
+            clients: clients.values().map(|entry| entry.info.clone()).collect(),
+            recent_messages: history.iter().cloned().collect(),
+            metrics: self.metrics_with_clients(clients.len()),
+            pings_paused: !self.is_ping_enabled(),
+        }
+    }
+
+    /// Broadcast a monitor list change, remembering the state it leads to for acks
+    async fn broadcast_monitor_change(&self, message: Message) -> Result<(), KanpeError> {
+        let hash = state_hash(&self.monitor_manager.get_all_monitors().await);
+        self.monitor_sync.record_change(message.id(), hash).await;
+        self.broadcast(&message).await?;
+        Ok(())
+    }
 }
 
-/// Generic static file server
-fn serve_static_file(path: &str, content_type: &str) -> Response {
+/// Serve a web caster file from the embedded bundle ("/" is index.html)
+async fn serve_embedded(uri: Uri) -> Response {
+    let path = match uri.path().trim_start_matches('/') {
+        "" => "index.html",
+        path => path,
+    };
     match WebAssets::get(path) {
         Some(content) => {
+            let content_type = mime_guess::from_path(path).first_or_octet_stream();
             let body = content.data.into_owned();
             Response::builder()
-                .header("Content-Type", content_type)
+                .header("Content-Type", content_type.as_ref())
                 .body(body.into())
                 .unwrap()
         }
@@ -202,97 +949,429 @@ fn serve_static_file(path: &str, content_type: &str) -> Response {
     }
 }
 
+/// Body of a `POST /api/message` request
+#[derive(Debug, Deserialize)]
+struct PostMessageRequest {
+    content: String,
+    target_monitor_ids: Vec<String>,
+    #[serde(default)]
+    priority: Priority,
+}
+
+/// Broadcast a cue for show-control systems that can send HTTP but not speak WebSocket
+///
+/// Responds with `{ "message_id": ... }`. Requires the same auth token as `/ws` when one is set.
+async fn post_message(
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+    State(state): State<AppState>,
+    Json(request): Json<PostMessageRequest>,
+) -> Response {
+    if !is_authorized(state.config.auth_token.as_deref(), &headers, &params) {
+        return (StatusCode::UNAUTHORIZED, "unauthorized").into_response();
+    }
+
+    let message = Message::kanpe_message(request.content, request.target_monitor_ids, request.priority);
+    match state.dispatcher.send(&message).await {
+        Ok(_) => Json(serde_json::json!({ "message_id": message.id() })).into_response(),
+        Err(e @ KanpeError::InvalidTarget(_)) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+        Err(e @ KanpeError::MonitorFull(_)) => (StatusCode::CONFLICT, e.to_string()).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Get the payload size of a text or binary frame (control frames count as empty)
+fn payload_len(frame: &WsMessage) -> usize {
+    match frame {
+        WsMessage::Text(text) => text.len(),
+        WsMessage::Binary(bytes) => bytes.len(),
+        _ => 0,
+    }
+}
+
+/// Parse a data frame: JSON arrives in text frames and MessagePack in binary ones
+fn parse_data_frame(frame: &WsMessage) -> Result<Message, MessageParseError> {
+    match frame {
+        WsMessage::Binary(bytes) => Message::parse_msgpack_frame(bytes).1,
+        _ => Message::parse(frame.to_text().unwrap_or_default()),
+    }
+}
+
+/// Close a connection with a reason and emit ClientRejected
+async fn reject_client(sink: &Arc<RwLock<WsSink>>, state: &AppState, code: u16, reason: &str) {
+    let close = WsMessage::Close(Some(CloseFrame {
+        code,
+        reason: reason.to_string().into(),
+    }));
+    let _ = sink.write().await.send(close).await;
+    warn!(reason, "client rejected");
+    let _ = state.event_tx.send(ServerEvent::ClientRejected {
+        reason: reason.to_string(),
+    });
+}
+
 /// WebSocket upgrade handler
 async fn websocket_handler(
     ws: WebSocketUpgrade,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
     State(state): State<AppState>,
 ) -> Response {
-    ws.on_upgrade(move |socket| handle_websocket(socket, state))
+    let authorized = is_authorized(state.config.auth_token.as_deref(), &headers, &params);
+    // Frames somewhat over the limit reach handle_websocket and are refused with a
+    // reason; anything far bigger is cut off by the socket before it is buffered
+    let socket_limit = state.config.max_message_bytes.saturating_mul(2);
+    // One span per connection; client_id is filled in once ClientHello arrives
+    let span = info_span!("connection", client_id = tracing::field::Empty);
+    ws.protocols([WEBSOCKET_SUBPROTOCOL])
+        .max_message_size(socket_limit)
+        .max_frame_size(socket_limit)
+        .on_upgrade(move |socket| handle_websocket(socket, state, authorized).instrument(span))
+}
+
+/// Check the handshake against the configured auth token, if any
+///
+/// The token may come from an `Authorization: Bearer` header or, for browsers
+/// that cannot set headers on WebSocket requests, a `token` query param.
+fn is_authorized(expected: Option<&str>, headers: &HeaderMap, params: &HashMap<String, String>) -> bool {
+    let Some(expected) = expected else {
+        return true;
+    };
+
+    let bearer = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    bearer == Some(expected) || params.get("token").map(String::as_str) == Some(expected)
 }
 
 /// Handle a WebSocket connection
-async fn handle_websocket(socket: WebSocket, state: AppState) {
+async fn handle_websocket(socket: WebSocket, state: AppState, authorized: bool) {
+    let negotiated = socket.protocol().is_some();
     let (sink, mut stream) = socket.split();
     let sink = Arc::new(RwLock::new(sink));
 
+    if !authorized {
+        reject_client(&sink, &state, close_code::POLICY, "unauthorized").await;
+        return;
+    }
+    if state.config.require_subprotocol && !negotiated {
+        reject_client(&sink, &state, close_code::PROTOCOL, "missing subprotocol").await;
+        return;
+    }
+
     let mut client_id: Option<String> = None;
+    // Set from ClientHello; frames before it are JSON
+    let wire_format: Arc<OnceLock<WireFormat>> = Arc::new(OnceLock::new());
+    let mut disconnect_reason = "connection lost".to_string();
+    let mut disconnect_cause = DisconnectReason::NetworkError;
 
-    // Start ping interval
+    // Start ping interval, unless pings are configured off
     let sink_for_ping = sink.clone();
+    let ping_enabled = state.ping_enabled.clone();
+    let metrics = state.metrics.clone();
+    let ping_interval = state.config.ping_interval;
+    let ping_format = wire_format.clone();
     let ping_task = tokio::spawn(async move {
-        let mut interval = interval(Duration::from_secs(30));
+        if ping_interval.is_zero() {
+            return;
+        }
+        let mut interval = interval(ping_interval);
         loop {
             interval.tick().await;
+            if !ping_enabled.load(Ordering::Relaxed) {
+                continue;
+            }
             let ping = Message::ping();
-            if let Ok(json) = serde_json::to_string(&ping) {
+            if let Ok(frame) = ws_frame(&ping, ping_format.get().copied().unwrap_or_default(), None) {
                 let mut sink_guard = sink_for_ping.write().await;
-                if sink_guard.send(WsMessage::Text(json)).await.is_err() {
+                if sink_guard.send(frame).await.is_err() {
+                    debug!("ping failed, stopping keepalive");
                     break;
                 }
+                MetricsCounters::increment(&metrics.pings_sent);
             }
         }
     });
 
-    // Handle incoming messages
-    while let Some(msg) = stream.next().await {
-        match msg {
-            Ok(WsMessage::Text(text)) => {
-                match serde_json::from_str::<Message>(&text) {
-                    Ok(message) => {
-                        match message {
-                            Message::ClientHello { payload, .. } => {
-                                // Generate client ID and register client
-                                let assigned_client_id = kanpe_core::types::new_id();
-                                let info = ClientInfo {
-                                    client_id: assigned_client_id.clone(),
-                                    client_name: payload.client_name.clone(),
-                                    display_monitor_ids: payload.display_monitor_ids.clone(),
-                                };
-
-                                state.client_manager
-                                    .add_client(assigned_client_id.clone(), info.clone(), sink.clone())
-                                    .await;
-
-                                client_id = Some(assigned_client_id.clone());
+    let mut inbound_limit = state
+        .config
+        .client_rate_limit
+        .map(|max| InboundLimit::new(max, std::time::Instant::now()));
 
-                                // Send ServerWelcome
-                                let welcome = Message::server_welcome(
-                                    "Kanpe Server".to_string(),
+    // Handle incoming messages, closing connections that never introduce themselves
+    // and, while they are being pinged, clients that go silent
+    let hello_deadline = tokio::time::Instant::now() + state.config.hello_timeout;
+    let pong_timeout = state.config.pong_timeout;
+    let mut last_heard = tokio::time::Instant::now();
+    loop {
+        let deadline = if client_id.is_none() {
+            (!state.config.hello_timeout.is_zero()).then_some(hello_deadline)
+        } else {
+            (!pong_timeout.is_zero() && !ping_interval.is_zero()).then(|| last_heard + pong_timeout)
+        };
+        let next = match deadline {
+            Some(deadline) => match tokio::time::timeout_at(deadline, stream.next()).await {
+                Ok(next) => next,
+                Err(_) if client_id.is_none() => {
+                    reject_client(&sink, &state, close_code::POLICY, "no hello received").await;
+                    disconnect_reason = "no hello received".to_string();
+                    break;
+                }
+                Err(_) if !state.ping_enabled.load(Ordering::Relaxed) => {
+                    // Nothing was asked of the client, so silence is fine
+                    last_heard = tokio::time::Instant::now();
+                    continue;
+                }
+                Err(_) => {
+                    let close = WsMessage::Close(Some(CloseFrame {
+                        code: close_code::AWAY,
+                        reason: "ping timeout".into(),
+                    }));
+                    let _ = sink.write().await.send(close).await;
+                    disconnect_reason = "ping timeout".to_string();
+                    disconnect_cause = DisconnectReason::Timeout;
+                    break;
+                }
+            },
+            None => stream.next().await,
+        };
+        let Some(msg) = next else {
+            break;
+        };
+        last_heard = tokio::time::Instant::now();
+        match msg {
+            Ok(frame) if payload_len(&frame) > state.config.max_message_bytes => {
+                reject_client(&sink, &state, close_code::SIZE, "message too large").await;
+                disconnect_reason = "message too large".to_string();
+                disconnect_cause = DisconnectReason::Kicked;
+                break;
+            }
+            Ok(WsMessage::Text(_) | WsMessage::Binary(_))
+                if let Some(limit) = inbound_limit.as_mut()
+                    && let verdict = limit.check(std::time::Instant::now())
+                    && verdict != Verdict::Accept =>
+            {
+                MetricsCounters::increment(&state.metrics.messages_rate_limited);
+                if verdict == Verdict::Close {
+                    reject_client(&sink, &state, close_code::POLICY, "rate limit").await;
+                    disconnect_reason = "rate limit".to_string();
+                    disconnect_cause = DisconnectReason::Kicked;
+                    break;
+                }
+            }
+            Ok(frame @ (WsMessage::Text(_) | WsMessage::Binary(_))) => {
+                match parse_data_frame(&frame) {
+                    Ok(message) => {
+                        match message {
+                            Message::ClientHello { payload, .. } => {
+                                // Reuse the ID of a recently dropped session, or generate a new one
+                                let resumed_id = match &payload.resume_token {
+                                    Some(token) => state.resume_tokens.resume(token, std::time::Instant::now()).await,
+                                    None => None,
+                                };
+                                let resumed = resumed_id.is_some();
+                                let assigned_client_id = resumed_id.unwrap_or_else(kanpe_core::types::new_id);
+                                let mut info = ClientInfo {
+                                    client_id: assigned_client_id.clone(),
+                                    client_name: payload.client_name.clone(),
+                                    display_monitor_ids: payload.display_monitor_ids.clone(),
+                                    connected_at: kanpe_core::types::timestamp(),
+                                    last_pong_at: None,
+                                    wire_format: payload.wire_format,
+                                };
+
+                                let registered = state.client_manager
+                                    .try_add_client(
+                                        assigned_client_id.clone(),
+                                        info.clone(),
+                                        sink.clone(),
+                                        state.config.max_clients,
+                                        state.config.duplicate_names,
+                                    )
+                                    .await;
+                                match registered {
+                                    Ok(client_name) => info.client_name = client_name,
+                                    Err(RegisterError::Full) => {
+                                        reject_client(&sink, &state, close_code::AGAIN, "server full").await;
+                                        break;
+                                    }
+                                    Err(RegisterError::DuplicateName) => {
+                                        reject_client(&sink, &state, close_code::POLICY, "duplicate name").await;
+                                        break;
+                                    }
+                                }
+
+                                client_id = Some(assigned_client_id.clone());
+                                let _ = wire_format.set(payload.wire_format);
+
+                                // Send ServerWelcome, always as JSON since it confirms the wire format
+                                // the client gets every later frame in
+                                let server_name = state.server_name.read().unwrap().clone();
+                                let resume_token = if state.config.resume_grace.is_zero() {
+                                    None
+                                } else {
+                                    Some(state.resume_tokens.issue(&assigned_client_id).await)
+                                };
+                                let mut welcome = Message::server_welcome_with_resume_token(
+                                    server_name,
                                     assigned_client_id.clone(),
+                                    resume_token,
                                 );
-                                if let Ok(json) = serde_json::to_string(&welcome) {
+                                if let Message::ServerWelcome { payload: welcome_payload, .. } = &mut welcome {
+                                    welcome_payload.wire_format = payload.wire_format;
+                                    if info.client_name != payload.client_name {
+                                        welcome_payload.assigned_client_name = Some(info.client_name.clone());
+                                    }
+                                }
+                                if let Ok(json) = welcome.to_json() {
                                     let mut sink_guard = sink.write().await;
                                     let _ = sink_guard.send(WsMessage::Text(json)).await;
                                 }
 
                                 // Send MonitorListSync
                                 let monitors = state.monitor_manager.get_all_monitors().await;
+                                let monitor_hash = state_hash(&monitors);
                                 let monitor_sync = Message::monitor_list_sync(monitors);
-                                if let Ok(json) = serde_json::to_string(&monitor_sync) {
+                                state.monitor_sync.record_change(monitor_sync.id(), monitor_hash).await;
+                                if let Ok(frame) = ws_frame(&monitor_sync, payload.wire_format, None) {
                                     let mut sink_guard = sink.write().await;
-                                    let _ = sink_guard.send(WsMessage::Text(json)).await;
+                                    let _ = sink_guard.send(frame).await;
                                 }
 
-                                // Emit ClientConnected event
-                                let _ = state.event_tx.send(ServerEvent::ClientConnected {
-                                    client_id: info.client_id,
-                                    name: info.client_name,
-                                    monitor_ids: info.display_monitor_ids,
-                                });
+                                // Send the monitor groups, if any are defined
+                                let groups = state.monitor_manager.get_all_groups().await;
+                                if !groups.is_empty()
+                                    && let Ok(frame) = ws_frame(&Message::monitor_group_sync(groups), payload.wire_format, None)
+                                {
+                                    let mut sink_guard = sink.write().await;
+                                    let _ = sink_guard.send(frame).await;
+                                }
+
+                                // Replay the current air status banner
+                                if let Some(air_status) = state.air_status.read().await.as_ref()
+                                    && let Ok(frame) = ws_frame(air_status, payload.wire_format, None)
+                                {
+                                    let mut sink_guard = sink.write().await;
+                                    let _ = sink_guard.send(frame).await;
+                                }
+
+                                tracing::Span::current().record("client_id", tracing::field::display(&info.client_id));
+                                info!(name = %info.client_name, resumed, "client connected");
+
+                                // Emit ClientConnected, or ClientReconnected for a resumed session
+                                let event = if resumed {
+                                    ServerEvent::ClientReconnected {
+                                        client_id: info.client_id,
+                                        name: info.client_name,
+                                        monitor_ids: info.display_monitor_ids,
+                                        connected_at: info.connected_at,
+                                    }
+                                } else {
+                                    ServerEvent::ClientConnected {
+                                        client_id: info.client_id,
+                                        name: info.client_name,
+                                        monitor_ids: info.display_monitor_ids,
+                                        connected_at: info.connected_at,
+                                    }
+                                };
+                                let _ = state.event_tx.send(event);
                             }
-                            Message::FeedbackMessage { .. } => {
+                            Message::FeedbackMessage { ref payload, .. } => {
+                                // Silently drop accidental repeats when dedup is enabled
+                                if let Some(dedup) = &state.feedback_dedup
+                                    && dedup
+                                        .is_duplicate(
+                                            client_id.as_deref().unwrap_or_default(),
+                                            &payload.content,
+                                            &payload.feedback_type,
+                                            std::time::Instant::now(),
+                                        )
+                                        .await
+                                {
+                                    continue;
+                                }
+
+                                state.feedback_history.push(message.clone()).await;
+                                MetricsCounters::increment(&state.metrics.feedback_received);
+
                                 // Emit FeedbackReceived event
                                 let _ = state.event_tx.send(ServerEvent::FeedbackReceived { message });
                             }
+                            Message::MessageAck { payload, .. } => {
+                                let Some(id) = &client_id else {
+                                    continue;
+                                };
+                                if let Some(display_monitor_ids) = state.client_manager.get_display_monitor_ids(id).await {
+                                    state.acks.acknowledge(&payload.message_id, id, &display_monitor_ids);
+                                }
+                                let _ = state.event_tx.send(ServerEvent::MessageAcknowledged {
+                                    client_id: id.clone(),
+                                    message_id: payload.message_id,
+                                });
+                            }
+                            Message::Reaction { ref payload, .. } => {
+                                if !is_valid_reaction(&payload.reaction) {
+                                    warn!("dropping malformed reaction");
+                                    continue;
+                                }
+                                state.reactions.record(&payload.reply_to_message_id, &payload.reaction).await;
+                                let _ = state.event_tx.send(ServerEvent::ReactionReceived { message });
+                            }
+                            Message::FeedbackTyping { payload, .. } => {
+                                let Some(id) = &client_id else {
+                                    continue;
+                                };
+                                let _ = state.event_tx.send(ServerEvent::ClientTyping {
+                                    client_id: id.clone(),
+                                    client_name: payload.client_name,
+                                    is_typing: payload.is_typing,
+                                });
+                            }
+                            Message::MonitorChangeAck { payload, .. } => {
+                                if let Some(id) = &client_id {
+                                    state.monitor_sync.ack(id, &payload.change_id).await;
+                                }
+                            }
+                            Message::UpdateSubscription { payload, .. } => {
+                                let Some(id) = &client_id else {
+                                    continue;
+                                };
+                                if let Some(info) = state.client_manager
+                                    .update_subscription(id, payload.display_monitor_ids)
+                                    .await
+                                {
+                                    let _ = state.event_tx.send(ServerEvent::ClientSubscriptionUpdated {
+                                        client_id: info.client_id,
+                                        monitor_ids: info.display_monitor_ids,
+                                    });
+                                }
+                            }
+                            Message::PresenterNoteResend { payload, .. } => {
+                                // Resend only to the client that asked
+                                let chunks = state.presenter_notes
+                                    .chunks(&payload.note_id, &payload.missing_seqs)
+                                    .await;
+                                let format = wire_format.get().copied().unwrap_or_default();
+                                let mut sink_guard = sink.write().await;
+                                for chunk in chunks {
+                                    if let Ok(frame) = ws_frame(&chunk, format, None) {
+                                        let _ = sink_guard.send(frame).await;
+                                    }
+                                }
+                            }
                             Message::Pong { .. } => {
-                                // Just acknowledge pong, no action needed
+                                if let Some(id) = &client_id {
+                                    state.client_manager.record_pong(id, kanpe_core::types::timestamp()).await;
+                                }
                             }
-                            Message::Ping { .. } => {
+                            Message::Ping { id, .. } => {
                                 // Respond with pong
-                                let pong = Message::pong();
-                                if let Ok(json) = serde_json::to_string(&pong) {
+                                let pong = Message::pong_for(id);
+                                if let Ok(frame) = ws_frame(&pong, wire_format.get().copied().unwrap_or_default(), None) {
                                     let mut sink_guard = sink.write().await;
-                                    let _ = sink_guard.send(WsMessage::Text(json)).await;
+                                    let _ = sink_guard.send(frame).await;
                                 }
                             }
                             _ => {
@@ -301,15 +1380,18 @@ async fn handle_websocket(socket: WebSocket, state: AppState) {
                         }
                     }
                     Err(e) => {
-                        eprintln!("Failed to parse message: {}", e);
+                        warn!(error = %e, "failed to parse message");
                     }
                 }
             }
             Ok(WsMessage::Close(_)) => {
+                disconnect_reason = "closed by client".to_string();
+                disconnect_cause = DisconnectReason::ClientClosed;
                 break;
             }
             Err(e) => {
-                eprintln!("WebSocket error: {}", e);
+                warn!(error = %e, "websocket error");
+                disconnect_reason = format!("websocket error: {}", e);
                 break;
             }
             _ => {}
@@ -318,8 +1400,1590 @@ async fn handle_websocket(socket: WebSocket, state: AppState) {
 
     // Cleanup on disconnect
     ping_task.abort();
-    if let Some(id) = client_id {
-        state.client_manager.remove_client(&id).await;
-        let _ = state.event_tx.send(ServerEvent::ClientDisconnected { client_id: id });
+    if let (Some(dedup), Some(id)) = (&state.feedback_dedup, &client_id) {
+        dedup.forget(id).await;
+    }
+    if let Some(id) = &client_id {
+        state.monitor_sync.forget(id).await;
+    }
+    // Clients kicked or dropped after a failed send were already removed and reported
+    if let Some(id) = &client_id
+        && state.client_manager.remove_client(id, &disconnect_reason).await.is_some()
+    {
+        info!(reason = %disconnect_reason, "client disconnected");
+        let _ = state.event_tx.send(ServerEvent::ClientDisconnected {
+            client_id: id.clone(),
+            reason: disconnect_cause,
+        });
+    }
+    // Only once the client is gone can its token be redeemed, so a resume never finds the old entry
+    if let Some(id) = &client_id {
+        state.resume_tokens.disconnected(id, std::time::Instant::now()).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::broadcast::broadcast_message;
+    use crate::test_support::{
+        connect, connect_with_token, expect_closed, hello, http_get, post_json, recv, recv_text, send, start_test_server,
+        TestSocket,
+    };
+    use crate::config::{DuplicateNamePolicy, UnknownTargetPolicy};
+    use kanpe_core::FeedbackType;
+    use tracing_test::traced_test;
+
+    #[tokio::test]
+    async fn test_start_on_ephemeral_port_is_reachable() {
+        let (event_tx, _event_rx) = mpsc::unbounded_channel();
+        let mut server = KanpeServer::new(event_tx);
+
+        let addr = server
+            .start_on(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)))
+            .await
+            .unwrap();
+        assert_ne!(addr.port(), 0);
+        assert_eq!(server.local_addr(), Some(addr));
+        assert!(tokio::net::TcpStream::connect(addr).await.is_ok());
+
+        server.stop().await.unwrap();
+        assert_eq!(server.local_addr(), None);
+    }
+
+    #[tokio::test]
+    async fn test_restart_on_taken_port_keeps_serving() {
+        let (mut server, addr, _event_rx) = start_test_server(ServerConfig::default()).await;
+        let taken = tokio::net::TcpListener::bind((addr.ip(), 0)).await.unwrap();
+        let mut ws = connect(addr).await;
+        hello(&mut ws, "Caster", &["A"]).await;
+
+        assert!(server.restart(taken.local_addr().unwrap().port()).await.is_err());
+        assert!(server.is_running());
+        assert_eq!(server.local_addr(), Some(addr));
+        assert_eq!(server.get_connected_clients().await.len(), 1);
+        let mut late = connect(addr).await;
+        hello(&mut late, "Late", &["B"]).await;
+
+        server.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_restart_keeps_monitors() {
+        let config = ServerConfig {
+            shutdown_grace: Duration::from_millis(200),
+            ..ServerConfig::default()
+        };
+        let (mut server, addr, _event_rx) = start_test_server(config).await;
+        assert!(server.is_running());
+        server.add_monitor("Foyer".to_string(), None, None, None).await.unwrap();
+        server.remove_monitor("A".to_string()).await.unwrap();
+        let mut ws = connect(addr).await;
+        hello(&mut ws, "Caster", &["B"]).await;
+
+        let new_addr = server.restart(0).await.unwrap();
+        assert!(server.is_running());
+        assert_eq!(new_addr.ip(), addr.ip());
+        assert_eq!(server.local_addr(), Some(new_addr));
+        assert!(matches!(recv(&mut ws).await, Some(Message::ServerShutdown { .. })));
+
+        // A reconnecting client is synced with the monitors as they were before the restart
+        let mut ws = connect(new_addr).await;
+        send(&mut ws, &Message::client_hello("Caster".to_string(), vec!["B".to_string()])).await;
+        assert!(matches!(recv(&mut ws).await, Some(Message::ServerWelcome { .. })));
+        let Some(Message::MonitorListSync { payload, .. }) = recv(&mut ws).await else {
+            panic!("expected MonitorListSync");
+        };
+        let names: Vec<String> = payload.monitors.into_iter().map(|m| m.name).collect();
+        assert_eq!(names, vec!["Monitor B", "Monitor C", "Monitor D", "Foyer"]);
+
+        server.stop().await.unwrap();
+        assert!(!server.is_running());
+    }
+
+    #[tokio::test]
+    async fn test_events_can_be_read_as_a_stream() {
+        use futures_util::StreamExt;
+
+        let (mut server, events) = KanpeServer::with_event_stream(ServerConfig::default());
+        let addr = server
+            .start_on(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)))
+            .await
+            .unwrap();
+        let mut ws = connect(addr).await;
+        hello(&mut ws, "caster", &["A"]).await;
+        server.add_monitor("Foyer".to_string(), None, None, None).await.unwrap();
+
+        let names = events.filter_map(|event| async move {
+            match event {
+                ServerEvent::ClientConnected { name, .. } => Some(name),
+                ServerEvent::MonitorAdded { monitor } => Some(monitor.name),
+                _ => None,
+            }
+        });
+        let received: Vec<String> = tokio::time::timeout(Duration::from_secs(2), names.take(2).collect())
+            .await
+            .unwrap();
+
+        assert_eq!(received, vec!["caster", "Foyer"]);
+    }
+
+    #[tokio::test]
+    async fn test_listening_addresses_includes_loopback() {
+        let (event_tx, _event_rx) = mpsc::unbounded_channel();
+        let mut server = KanpeServer::new(event_tx);
+        assert!(server.listening_addresses().is_empty());
+
+        let addr = server
+            .start_on(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)))
+            .await
+            .unwrap();
+        let addresses = server.listening_addresses();
+        assert!(addresses.contains(&format!("ws://127.0.0.1:{}", addr.port())));
+
+        server.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_client_over_max_clients_is_rejected() {
+        let config = ServerConfig {
+            max_clients: Some(2),
+            ..Default::default()
+        };
+        let (server, addr, mut event_rx) = start_test_server(config).await;
+
+        let mut first = connect(addr).await;
+        hello(&mut first, "first", &["A"]).await;
+        let mut second = connect(addr).await;
+        hello(&mut second, "second", &["B"]).await;
+
+        let mut third = connect(addr).await;
+        send(&mut third, &Message::client_hello("third".to_string(), vec![])).await;
+        expect_closed(&mut third).await;
+
+        assert_eq!(server.get_connected_clients().await.len(), 2);
+        let mut rejected = false;
+        while let Ok(event) = event_rx.try_recv() {
+            if let ServerEvent::ClientRejected { reason } = event {
+                assert_eq!(reason, "server full");
+                rejected = true;
+            }
+        }
+        assert!(rejected);
+    }
+
+    /// Register a client named "iPhone", then send a hello with the same name from a second one
+    async fn connect_same_named_pair(
+        duplicate_names: DuplicateNamePolicy,
+    ) -> (KanpeServer, [TestSocket; 2], mpsc::UnboundedReceiver<ServerEvent>) {
+        let config = ServerConfig {
+            duplicate_names,
+            ..Default::default()
+        };
+        let (server, addr, event_rx) = start_test_server(config).await;
+        let mut first = connect(addr).await;
+        hello(&mut first, "iPhone", &["A"]).await;
+        let mut second = connect(addr).await;
+        send(&mut second, &Message::client_hello("iPhone".to_string(), vec!["B".to_string()])).await;
+        (server, [first, second], event_rx)
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_name_is_allowed_by_default() {
+        let (server, [_first, mut second], _event_rx) = connect_same_named_pair(DuplicateNamePolicy::Allow).await;
+        match recv(&mut second).await {
+            Some(Message::ServerWelcome { payload, .. }) => assert_eq!(payload.assigned_client_name, None),
+            other => panic!("expected ServerWelcome, got {:?}", other),
+        }
+        let names: Vec<String> = server.get_connected_clients().await.into_iter().map(|c| c.client_name).collect();
+        assert_eq!(names, vec!["iPhone", "iPhone"]);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_name_is_rejected() {
+        let (server, [_first, mut second], mut event_rx) = connect_same_named_pair(DuplicateNamePolicy::Reject).await;
+        expect_closed(&mut second).await;
+
+        assert_eq!(server.get_connected_clients().await.len(), 1);
+        let mut rejected = false;
+        while let Ok(event) = event_rx.try_recv() {
+            if let ServerEvent::ClientRejected { reason } = event {
+                assert_eq!(reason, "duplicate name");
+                rejected = true;
+            }
+        }
+        assert!(rejected);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_name_is_suffixed() {
+        let (server, [_first, mut second], _event_rx) = connect_same_named_pair(DuplicateNamePolicy::Suffix).await;
+        match recv(&mut second).await {
+            Some(Message::ServerWelcome { payload, .. }) => {
+                assert_eq!(payload.assigned_client_name.as_deref(), Some("iPhone (2)"))
+            }
+            other => panic!("expected ServerWelcome, got {:?}", other),
+        }
+        let mut names: Vec<String> = server.get_connected_clients().await.into_iter().map(|c| c.client_name).collect();
+        names.sort();
+        assert_eq!(names, vec!["iPhone", "iPhone (2)"]);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_message_closes_connection() {
+        let config = ServerConfig {
+            max_message_bytes: 1024,
+            ..Default::default()
+        };
+        let (server, addr, mut event_rx) = start_test_server(config).await;
+
+        let mut ws = connect(addr).await;
+        hello(&mut ws, "caster", &["A"]).await;
+        let huge = Message::feedback_message("x".repeat(1500), "caster".to_string(), "cue".to_string(), FeedbackType::Info);
+        send(&mut ws, &huge).await;
+        expect_closed(&mut ws).await;
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(server.get_connected_clients().await.is_empty());
+        assert!(server.feedback_history().await.is_empty());
+        let mut rejected = false;
+        while let Ok(event) = event_rx.try_recv() {
+            if let ServerEvent::ClientRejected { reason } = event {
+                assert_eq!(reason, "message too large");
+                rejected = true;
+            }
+        }
+        assert!(rejected);
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_hello_logs_client_connected() {
+        let (_server, addr, _event_rx) = start_test_server(ServerConfig::default()).await;
+
+        let mut ws = connect(addr).await;
+        let client_id = hello(&mut ws, "caster", &["A"]).await;
+
+        assert!(logs_contain("client connected"));
+        assert!(logs_contain(&format!("client_id={}", client_id)));
+    }
+
+    #[tokio::test]
+    async fn test_flooding_client_is_throttled_then_closed() {
+        let config = ServerConfig {
+            client_rate_limit: Some(5),
+            ..Default::default()
+        };
+        let (server, addr, mut event_rx) = start_test_server(config).await;
+
+        let mut ws = connect(addr).await;
+        hello(&mut ws, "caster", &["A"]).await;
+        for i in 0..40 {
+            let feedback =
+                Message::feedback_message(format!("spam {}", i), "caster".to_string(), String::new(), FeedbackType::Info);
+            // The server may close mid-flood, so a failed send ends the loop
+            let frame = tokio_tungstenite::tungstenite::Message::Text(serde_json::to_string(&feedback).unwrap());
+            if ws.send(frame).await.is_err() {
+                break;
+            }
+        }
+        expect_closed(&mut ws).await;
+
+        let metrics = server.metrics().await;
+        assert!(metrics.messages_rate_limited > 0);
+        assert!(metrics.feedback_received < 10, "{} feedback accepted", metrics.feedback_received);
+
+        let mut rejected = false;
+        while let Ok(Some(event)) = tokio::time::timeout(Duration::from_secs(1), event_rx.recv()).await {
+            if let ServerEvent::ClientRejected { reason } = event {
+                assert_eq!(reason, "rate limit");
+                rejected = true;
+                break;
+            }
+        }
+        assert!(rejected);
+    }
+
+    #[tokio::test]
+    async fn test_connection_without_hello_is_closed() {
+        let config = ServerConfig {
+            hello_timeout: Duration::from_millis(200),
+            ..Default::default()
+        };
+        let (_server, addr, mut event_rx) = start_test_server(config).await;
+
+        let mut ws = connect(addr).await;
+        let started = std::time::Instant::now();
+        expect_closed(&mut ws).await;
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        match tokio::time::timeout(Duration::from_secs(2), event_rx.recv()).await {
+            Ok(Some(ServerEvent::ClientRejected { reason })) => assert_eq!(reason, "no hello received"),
+            other => panic!("expected ClientRejected, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_recall_scene_restores_monitors_and_cues() {
+        let (server, _addr, _event_rx) = start_test_server(ServerConfig::default()).await;
+
+        let cue = Message::kanpe_message("Opening".to_string(), vec!["A".to_string()], Default::default());
+        server.broadcast_message(cue.clone()).await.unwrap();
+        let original_monitors = server.get_monitors().await;
+        server.save_scene("opening".to_string()).await.unwrap();
+        assert_eq!(server.list_scenes().await, vec!["opening".to_string()]);
+
+        // Change the live state
+        server.add_monitor("Extra".to_string(), None, None, None).await.unwrap();
+        server.broadcast_message(Message::clear_command(vec!["A".to_string()])).await.unwrap();
+        server
+            .broadcast_message(Message::kanpe_message("Later".to_string(), vec!["B".to_string()], Default::default()))
+            .await
+            .unwrap();
+
+        server.recall_scene("opening").await.unwrap();
+
+        let monitor_ids: Vec<String> = server.get_monitors().await.into_iter().map(|m| m.id).collect();
+        let original_ids: Vec<String> = original_monitors.into_iter().map(|m| m.id).collect();
+        assert_eq!(monitor_ids, original_ids);
+        let cue_ids: Vec<String> = server.active_cues().await.iter().map(|m| m.id().to_string()).collect();
+        assert_eq!(cue_ids, vec![cue.id().to_string()]);
+
+        assert!(server.delete_scene("opening").await.unwrap());
+        assert!(server.recall_scene("opening").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_disconnect_client_kicks_and_reports_once() {
+        let (server, addr, mut event_rx) = start_test_server(ServerConfig::default()).await;
+
+        let mut ws = connect(addr).await;
+        let client_id = hello(&mut ws, "caster", &["A"]).await;
+
+        server.disconnect_client(&client_id).await.unwrap();
+        expect_closed(&mut ws).await;
+        drop(ws);
+
+        assert!(server
+            .get_connected_clients()
+            .await
+            .iter()
+            .all(|c| c.client_id != client_id));
+        assert!(matches!(
+            server.disconnect_client(&client_id).await,
+            Err(KanpeError::ClientNotFound(_))
+        ));
+
+        // Give the connection task time to run its cleanup
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let mut disconnects = 0;
+        while let Ok(event) = event_rx.try_recv() {
+            if let ServerEvent::ClientDisconnected { reason, .. } = event {
+                assert_eq!(reason, DisconnectReason::Kicked);
+                disconnects += 1;
+            }
+        }
+        assert_eq!(disconnects, 1);
+    }
+
+    /// Wait for the next ClientDisconnected and return its reason
+    async fn next_disconnect_reason(event_rx: &mut mpsc::UnboundedReceiver<ServerEvent>) -> DisconnectReason {
+        loop {
+            match tokio::time::timeout(Duration::from_secs(2), event_rx.recv()).await {
+                Ok(Some(ServerEvent::ClientDisconnected { reason, .. })) => return reason,
+                Ok(Some(_)) => {}
+                other => panic!("expected ClientDisconnected, got {:?}", other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_client_close_is_reported_as_client_closed() {
+        let (_server, addr, mut event_rx) = start_test_server(ServerConfig::default()).await;
+        let mut ws = connect(addr).await;
+        hello(&mut ws, "caster", &["A"]).await;
+
+        ws.close(None).await.unwrap();
+        assert_eq!(next_disconnect_reason(&mut event_rx).await, DisconnectReason::ClientClosed);
+    }
+
+    #[tokio::test]
+    async fn test_silent_client_is_dropped_with_timeout() {
+        let config = ServerConfig {
+            ping_interval: Duration::from_millis(50),
+            pong_timeout: Duration::from_millis(200),
+            ..ServerConfig::default()
+        };
+        let (server, addr, mut event_rx) = start_test_server(config).await;
+        let mut ws = connect(addr).await;
+        let client_id = hello(&mut ws, "caster", &["A"]).await;
+
+        // The socket is never read again, so no pong goes back
+        assert_eq!(next_disconnect_reason(&mut event_rx).await, DisconnectReason::Timeout);
+        assert_eq!(server.last_disconnect_reason(&client_id).await.as_deref(), Some("ping timeout"));
+    }
+
+    #[tokio::test]
+    async fn test_ack_stops_urgent_flash_repeats() {
+        let (server, addr, _event_rx) = start_test_server(ServerConfig::default()).await;
+        let mut ws = connect(addr).await;
+        hello(&mut ws, "caster", &["A"]).await;
+        let mut elsewhere = connect(addr).await;
+        hello(&mut elsewhere, "caster-b", &["B"]).await;
+
+        let message_id = server
+            .broadcast_urgent(
+                "止めてください".to_string(),
+                vec!["A".to_string()],
+                Duration::from_millis(30),
+                Duration::from_secs(5),
+            )
+            .await
+            .unwrap();
+        match recv(&mut ws).await {
+            Some(Message::KanpeMessage { id, payload, .. }) => {
+                assert_eq!(id, message_id);
+                assert_eq!(payload.repeat_flash_interval_ms, Some(30));
+            }
+            other => panic!("expected the urgent cue, got {:?}", other),
+        }
+        for _ in 0..2 {
+            assert!(matches!(recv(&mut ws).await, Some(Message::FlashCommand { .. })));
+        }
+
+        // An operator who is not shown the cue cannot silence it
+        send(&mut elsewhere, &Message::message_ack(message_id.clone())).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(server.pending_timers(), 1);
+
+        send(&mut ws, &Message::message_ack(message_id)).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(server.pending_timers(), 0);
+
+        // Drain flashes sent before the ack landed, then expect silence
+        while let Ok(Some(Ok(_))) = tokio::time::timeout(Duration::from_millis(100), ws.next()).await {}
+        assert!(tokio::time::timeout(Duration::from_millis(150), ws.next()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_request_is_sent_without_kicking() {
+        let (server, addr, _event_rx) = start_test_server(ServerConfig::default()).await;
+
+        let mut ws = connect(addr).await;
+        let client_id = hello(&mut ws, "caster", &["A"]).await;
+
+        server.request_client_reconnect(&client_id).await.unwrap();
+        assert!(matches!(recv(&mut ws).await, Some(Message::ReconnectRequest { .. })));
+        assert!(server.is_client_connected(&client_id).await);
+        assert!(matches!(
+            server.request_client_reconnect("missing").await,
+            Err(KanpeError::ClientNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_update_subscription_redirects_targeted_broadcast() {
+        let (server, addr, mut event_rx) = start_test_server(ServerConfig::default()).await;
+
+        let mut ws = connect(addr).await;
+        let client_id = hello(&mut ws, "caster", &["A"]).await;
+
+        send(&mut ws, &Message::update_subscription(vec!["B".to_string()])).await;
+        loop {
+            match tokio::time::timeout(Duration::from_secs(2), event_rx.recv()).await.unwrap() {
+                Some(ServerEvent::ClientSubscriptionUpdated { client_id: id, monitor_ids }) => {
+                    assert_eq!(id, client_id);
+                    assert_eq!(monitor_ids, vec!["B".to_string()]);
+                    break;
+                }
+                Some(_) => {}
+                None => panic!("event channel closed"),
+            }
+        }
+
+        // A cue for A no longer reaches the client, one for B does
+        let for_a = Message::kanpe_message("to A".to_string(), vec!["A".to_string()], Default::default());
+        let for_b = Message::kanpe_message("to B".to_string(), vec!["B".to_string()], Default::default());
+        server.broadcast_message(for_a).await.unwrap();
+        server.broadcast_message(for_b.clone()).await.unwrap();
+
+        let received = recv(&mut ws).await.unwrap();
+        assert_eq!(received.id(), for_b.id());
+    }
+
+    #[tokio::test]
+    async fn test_message_broadcast_reports_recipient_count() {
+        let (server, addr, mut event_rx) = start_test_server(ServerConfig::default()).await;
+        let mut first = connect(addr).await;
+        hello(&mut first, "first", &["A"]).await;
+        let mut second = connect(addr).await;
+        hello(&mut second, "second", &["A"]).await;
+        let mut other = connect(addr).await;
+        hello(&mut other, "other", &["B"]).await;
+
+        let cue = Message::kanpe_message("to A".to_string(), vec!["A".to_string()], Default::default());
+        server.broadcast_message(cue.clone()).await.unwrap();
+
+        loop {
+            match tokio::time::timeout(Duration::from_secs(2), event_rx.recv()).await {
+                Ok(Some(ServerEvent::MessageBroadcast {
+                    message_id,
+                    recipient_count,
+                    targets,
+                })) => {
+                    assert_eq!(message_id, cue.id());
+                    assert_eq!(recipient_count, 2);
+                    assert_eq!(targets, vec!["A".to_string()]);
+                    break;
+                }
+                Ok(Some(_)) => {}
+                other => panic!("expected MessageBroadcast, got {:?}", other),
+            }
+        }
+    }
+
+    fn auth_config() -> ServerConfig {
+        ServerConfig {
+            auth_token: Some("secret".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_auth_token_accepts_bearer_header_and_query_param() {
+        let (server, addr, _event_rx) = start_test_server(auth_config()).await;
+
+        let mut with_header = connect_with_token(addr, "secret").await;
+        hello(&mut with_header, "header", &["A"]).await;
+
+        let (mut with_query, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws?token=secret", addr))
+            .await
+            .unwrap();
+        hello(&mut with_query, "query", &["B"]).await;
+
+        assert_eq!(server.get_connected_clients().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_auth_token_rejects_missing_or_wrong_token() {
+        let (server, addr, mut event_rx) = start_test_server(auth_config()).await;
+
+        let mut anonymous = connect(addr).await;
+        expect_closed(&mut anonymous).await;
+        let mut wrong = connect_with_token(addr, "guess").await;
+        expect_closed(&mut wrong).await;
+
+        assert!(server.get_connected_clients().await.is_empty());
+        for _ in 0..2 {
+            match event_rx.recv().await {
+                Some(ServerEvent::ClientRejected { reason }) => assert_eq!(reason, "unauthorized"),
+                other => panic!("expected ClientRejected, got {:?}", other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subprotocol_is_echoed() {
+        use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+
+        let (_server, addr, _event_rx) = start_test_server(ServerConfig::default()).await;
+        let mut request = format!("ws://{}/ws", addr).into_client_request().unwrap();
+        request
+            .headers_mut()
+            .insert("Sec-WebSocket-Protocol", WEBSOCKET_SUBPROTOCOL.parse().unwrap());
+
+        let (_ws, response) = tokio_tungstenite::connect_async(request).await.unwrap();
+
+        assert_eq!(response.headers()["Sec-WebSocket-Protocol"], WEBSOCKET_SUBPROTOCOL);
+    }
+
+    #[tokio::test]
+    async fn test_missing_subprotocol_is_handled_per_config() {
+        let (server, addr, _event_rx) = start_test_server(ServerConfig::default()).await;
+        let mut lenient = connect(addr).await;
+        hello(&mut lenient, "old caster", &["A"]).await;
+        assert_eq!(server.get_connected_clients().await.len(), 1);
+
+        let config = ServerConfig {
+            require_subprotocol: true,
+            ..Default::default()
+        };
+        let (server, addr, mut event_rx) = start_test_server(config).await;
+        let mut strict = connect(addr).await;
+        expect_closed(&mut strict).await;
+        assert!(server.get_connected_clients().await.is_empty());
+        match event_rx.recv().await {
+            Some(ServerEvent::ClientRejected { reason }) => assert_eq!(reason, "missing subprotocol"),
+            other => panic!("expected ClientRejected, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_monitor_change_ack_reports_client_in_sync() {
+        let (server, addr, _event_rx) = start_test_server(ServerConfig::default()).await;
+
+        let mut ws = connect(addr).await;
+        let client_id = hello(&mut ws, "caster", &["A"]).await;
+
+        server.add_monitor("Extra".to_string(), None, None, None).await.unwrap();
+        let change_id = match recv(&mut ws).await {
+            Some(message @ Message::MonitorAdded { .. }) => message.id().to_string(),
+            other => panic!("expected MonitorAdded, got {:?}", other),
+        };
+        let status = server.get_monitor_sync_status().await;
+        assert_eq!(status.len(), 1);
+        assert!(!status[0].in_sync);
+
+        send(&mut ws, &Message::monitor_change_ack(change_id)).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let status = server.get_monitor_sync_status().await;
+        assert_eq!(status[0].client_id, client_id);
+        assert!(status[0].in_sync);
+    }
+
+    #[tokio::test]
+    async fn test_disabled_ping_sends_no_ping_frames() {
+        use futures_util::StreamExt;
+
+        let (server, addr, _event_rx) = start_test_server(ServerConfig::default()).await;
+
+        // The first ping tick fires as soon as a socket connects
+        let mut enabled = connect(addr).await;
+        let frame = tokio::time::timeout(Duration::from_secs(2), enabled.next()).await.unwrap();
+        let text = frame.unwrap().unwrap().into_text().unwrap();
+        assert!(matches!(serde_json::from_str(&text).unwrap(), Message::Ping { .. }));
+
+        server.set_ping_enabled(false);
+        assert!(!server.is_ping_enabled());
+        let mut disabled = connect(addr).await;
+        assert!(tokio::time::timeout(Duration::from_millis(300), disabled.next()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_embedded_assets_are_served_with_their_content_type() {
+        let response = serve_embedded(Uri::from_static("/favicon.ico")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["Content-Type"], "image/x-icon");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body.as_ref(), WebAssets::get("favicon.ico").unwrap().data.as_ref());
+
+        let response = serve_embedded(Uri::from_static("/")).await;
+        assert_eq!(response.headers()["Content-Type"], "text/html");
+        assert_eq!(serve_embedded(Uri::from_static("/nope.png")).await.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_asset_dir_overrides_embedded_files() {
+        let dir = std::env::temp_dir().join(format!("kanpe-assets-{}", kanpe_core::types::new_id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("styles.css"), "body { color: hotpink; }").unwrap();
+
+        let (event_tx, _event_rx) = mpsc::unbounded_channel();
+        let mut server = KanpeServer::new(event_tx).with_asset_dir(dir.clone());
+        let addr = server.start_on(SocketAddr::from((Ipv4Addr::LOCALHOST, 0))).await.unwrap();
+
+        assert_eq!(http_get(addr, "/styles.css").await, (200, "body { color: hotpink; }".to_string()));
+
+        // Files missing from the directory fall through to the embedded bundle
+        let embedded = String::from_utf8(WebAssets::get("app.js").unwrap().data.into_owned()).unwrap();
+        assert_eq!(http_get(addr, "/app.js").await, (200, embedded));
+        let index = String::from_utf8(WebAssets::get("index.html").unwrap().data.into_owned()).unwrap();
+        assert_eq!(http_get(addr, "/").await, (200, index));
+        assert_eq!(http_get(addr, "/missing.txt").await.0, 404);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_post_message_is_not_open_to_cross_origin_pages() {
+        use crate::test_support::preflight;
+
+        let (_server, addr, _event_rx) = start_test_server(ServerConfig::default()).await;
+
+        let (_, headers) = preflight(addr, "/api/message").await;
+        assert!(!headers.contains("access-control-allow-origin"), "{}", headers);
+
+        // Everything else stays readable from other origins
+        let (_, headers) = preflight(addr, "/app.js").await;
+        assert!(headers.contains("access-control-allow-origin"), "{}", headers);
+    }
+
+    #[tokio::test]
+    async fn test_post_message_broadcasts_to_websocket_clients() {
+        let config = ServerConfig {
+            auth_token: Some("secret".to_string()),
+            ..ServerConfig::default()
+        };
+        let (_server, addr, _event_rx) = start_test_server(config).await;
+        let mut ws = connect_with_token(addr, "secret").await;
+        hello(&mut ws, "caster", &["A"]).await;
+
+        let body = r#"{"content":"Go to VT","target_monitor_ids":["A"],"priority":"high"}"#;
+        let (status, _) = post_json(addr, "/api/message", body, None).await;
+        assert_eq!(status, 401);
+
+        let (status, response) = post_json(addr, "/api/message", body, Some("secret")).await;
+        assert_eq!(status, 200);
+        let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+
+        match recv(&mut ws).await {
+            Some(Message::KanpeMessage { id, payload, .. }) => {
+                assert_eq!(response["message_id"], id);
+                assert_eq!(payload.content, "Go to VT");
+                assert_eq!(payload.priority, Priority::High);
+            }
+            other => panic!("expected KanpeMessage, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ping_interval_is_configurable() {
+        use futures_util::StreamExt;
+
+        let config = ServerConfig {
+            ping_interval: Duration::from_millis(50),
+            ..ServerConfig::default()
+        };
+        let (_server, addr, _event_rx) = start_test_server(config).await;
+        let mut ws = connect(addr).await;
+
+        let mut pings = 0;
+        let window = tokio::time::sleep(Duration::from_millis(400));
+        tokio::pin!(window);
+        loop {
+            tokio::select! {
+                _ = &mut window => break,
+                frame = ws.next() => {
+                    let text = frame.unwrap().unwrap().into_text().unwrap();
+                    if matches!(serde_json::from_str(&text).unwrap(), Message::Ping { .. }) {
+                        pings += 1;
+                    }
+                }
+            }
+        }
+        assert!(pings >= 3, "expected several pings, got {}", pings);
+
+        // A zero interval turns pings off altogether
+        let config = ServerConfig {
+            ping_interval: Duration::ZERO,
+            ..ServerConfig::default()
+        };
+        let (_server, addr, _event_rx) = start_test_server(config).await;
+        let mut silent = connect(addr).await;
+        assert!(tokio::time::timeout(Duration::from_millis(300), silent.next()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_msgpack_connection_round_trips_binary_frames() {
+        use crate::test_support::recv_msgpack;
+        use kanpe_core::Frame;
+        use tokio_tungstenite::tungstenite::Message as TungsteniteMessage;
+
+        let (server, addr, mut event_rx) = start_test_server(ServerConfig::default()).await;
+        let mut ws = connect(addr).await;
+
+        let mut hello = Message::client_hello("caster".to_string(), vec!["A".to_string()]);
+        if let Message::ClientHello { payload, .. } = &mut hello {
+            payload.wire_format = WireFormat::Msgpack;
+        }
+        send(&mut ws, &hello).await;
+
+        // The welcome is JSON and confirms the format; everything after it is MessagePack
+        match recv(&mut ws).await {
+            Some(Message::ServerWelcome { payload, .. }) => assert_eq!(payload.wire_format, WireFormat::Msgpack),
+            other => panic!("expected ServerWelcome, got {:?}", other),
+        }
+        assert!(matches!(recv_msgpack(&mut ws).await, Some((None, Message::MonitorListSync { .. }))));
+
+        let cue = Message::kanpe_message("Wrap up".to_string(), vec!["A".to_string()], Priority::High);
+        server.broadcast_message(cue.clone()).await.unwrap();
+        match recv_msgpack(&mut ws).await {
+            Some((Some(1), Message::KanpeMessage { id, payload, .. })) => {
+                assert_eq!(id, cue.id());
+                assert_eq!(payload.content, "Wrap up");
+            }
+            other => panic!("expected the cue as frame 1, got {:?}", other),
+        }
+
+        // Binary frames from the client are decoded as MessagePack
+        let feedback = Message::feedback_message(
+            "Got it".to_string(),
+            "caster".to_string(),
+            cue.id().to_string(),
+            FeedbackType::Ack,
+        );
+        let Frame::Binary(bytes) = feedback.encode(WireFormat::Msgpack, None).unwrap() else {
+            panic!("MessagePack frames are binary");
+        };
+        ws.send(TungsteniteMessage::Binary(bytes)).await.unwrap();
+        loop {
+            match tokio::time::timeout(Duration::from_secs(2), event_rx.recv()).await.unwrap() {
+                Some(ServerEvent::FeedbackReceived { message }) => {
+                    assert_eq!(message.id(), feedback.id());
+                    break;
+                }
+                Some(_) => {}
+                None => panic!("event channel closed"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_welcome_uses_configured_server_name() {
+        let (server, addr, _event_rx) = start_test_server(ServerConfig::default()).await;
+        let welcomed_name = |mut ws: TestSocket| async move {
+            send(&mut ws, &Message::client_hello("caster".to_string(), vec![])).await;
+            match recv(&mut ws).await {
+                Some(Message::ServerWelcome { payload, .. }) => payload.server_name,
+                other => panic!("expected ServerWelcome, got {:?}", other),
+            }
+        };
+
+        assert_eq!(welcomed_name(connect(addr).await).await, DEFAULT_SERVER_NAME);
+
+        server.set_name("Stage Left");
+        assert_eq!(server.name(), "Stage Left");
+        assert_eq!(welcomed_name(connect(addr).await).await, "Stage Left");
+    }
+
+    #[tokio::test]
+    async fn test_slow_client_does_not_block_broadcast() {
+        let (server, addr, _event_rx) = start_test_server(ServerConfig::default()).await;
+
+        let mut slow = connect(addr).await;
+        let slow_id = hello(&mut slow, "slow", &["A"]).await;
+        let mut fast = Vec::new();
+        for name in ["fast-1", "fast-2", "fast-3"] {
+            let mut ws = connect(addr).await;
+            hello(&mut ws, name, &["A"]).await;
+            fast.push(ws);
+        }
+
+        // Hold the slow client's sink so any send to it stalls
+        let slow_sink = server.client_manager.get_sink(&slow_id).await.unwrap();
+        let slow_guard = slow_sink.write().await;
+
+        let cue = Message::kanpe_message("Go".to_string(), vec!["A".to_string()], Default::default());
+        let broadcast = tokio::spawn({
+            let client_manager = server.client_manager.clone();
+            let event_tx = server.event_tx.clone();
+            let cue = cue.clone();
+            async move { broadcast_message(&client_manager, &event_tx, &cue).await }
+        });
+
+        for ws in &mut fast {
+            assert_eq!(recv(ws).await.unwrap().id(), cue.id());
+        }
+        assert!(!broadcast.is_finished());
+
+        drop(slow_guard);
+        assert!(broadcast.await.unwrap().unwrap().is_empty());
+        assert_eq!(recv(&mut slow).await.unwrap().id(), cue.id());
+    }
+
+    #[tokio::test]
+    async fn test_failed_send_removes_client() {
+        use futures_util::SinkExt;
+
+        let (server, addr, mut event_rx) = start_test_server(ServerConfig::default()).await;
+
+        let mut healthy = connect(addr).await;
+        hello(&mut healthy, "healthy", &["A"]).await;
+        // Never read from this socket, so the server only learns it is gone on send
+        let mut broken = connect(addr).await;
+        let broken_id = hello(&mut broken, "broken", &["A"]).await;
+
+        let broken_sink = server.client_manager.get_sink(&broken_id).await.unwrap();
+        broken_sink.write().await.close().await.unwrap();
+        while event_rx.try_recv().is_ok() {}
+
+        let failures = server
+            .broadcast_message(Message::flash_command(vec!["ALL".to_string()]))
+            .await
+            .unwrap();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0, broken_id);
+
+        let clients = server.get_connected_clients().await;
+        assert_eq!(clients.len(), 1);
+        assert_eq!(clients[0].client_name, "healthy");
+        match event_rx.try_recv() {
+            Ok(ServerEvent::ClientDisconnected { client_id, reason }) => {
+                assert_eq!(client_id, broken_id);
+                assert_eq!(reason, DisconnectReason::NetworkError);
+            }
+            other => panic!("expected ClientDisconnected, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_history_records_sent_cues_and_received_feedback() {
+        let config = ServerConfig {
+            history_capacity: 2,
+            ..Default::default()
+        };
+        let (server, addr, _event_rx) = start_test_server(config).await;
+
+        let mut ws = connect(addr).await;
+        hello(&mut ws, "caster", &["A"]).await;
+
+        let mut sent = Vec::new();
+        for content in ["1", "2", "3"] {
+            let cue = Message::kanpe_message(content.to_string(), vec!["A".to_string()], Default::default());
+            sent.push(cue.id().to_string());
+            server.broadcast_message(cue).await.unwrap();
+        }
+        // Commands are not cues and are not recorded
+        server.broadcast_message(Message::flash_command(vec!["A".to_string()])).await.unwrap();
+
+        let history: Vec<String> = server.message_history().await.iter().map(|m| m.id().to_string()).collect();
+        assert_eq!(history, sent[1..].to_vec());
+
+        let feedback = Message::feedback_message("了解".to_string(), "caster".to_string(), sent[2].clone(), Default::default());
+        send(&mut ws, &feedback).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let feedback_history = server.feedback_history().await;
+        assert_eq!(feedback_history.len(), 1);
+        assert_eq!(feedback_history[0].id(), feedback.id());
+
+        server.clear_history().await;
+        assert!(server.message_history().await.is_empty());
+        assert!(server.feedback_history().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_connection_log_records_connect_then_disconnect() {
+        use crate::connection_log::ConnectionEventKind;
+
+        let (server, addr, _event_rx) = start_test_server(ServerConfig::default()).await;
+
+        let mut ws = connect(addr).await;
+        let client_id = hello(&mut ws, "caster", &["A"]).await;
+        ws.close(None).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let log = server.connection_log().await;
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].client_id, client_id);
+        assert_eq!(log[0].event, ConnectionEventKind::Connected);
+        assert_eq!(log[0].reason, None);
+        assert_eq!(log[1].client_id, client_id);
+        assert_eq!(log[1].event, ConnectionEventKind::Disconnected);
+        assert_eq!(log[1].reason.as_deref(), Some("closed by client"));
+        assert!(log[0].timestamp <= log[1].timestamp);
+
+        // The same caster coming back is marked as a reconnect
+        let mut again = connect(addr).await;
+        hello(&mut again, "caster", &["A"]).await;
+        assert_eq!(server.connection_log().await[2].event, ConnectionEventKind::Reconnected);
+    }
+
+    #[tokio::test]
+    async fn test_pong_echoes_ping_id() {
+        let (_server, addr, _event_rx) = start_test_server(ServerConfig::default()).await;
+
+        let mut ws = connect(addr).await;
+        hello(&mut ws, "caster", &["A"]).await;
+        let ping = Message::ping();
+        send(&mut ws, &ping).await;
+
+        match recv(&mut ws).await {
+            Some(Message::Pong { payload, .. }) => assert_eq!(payload.ping_id, ping.id()),
+            other => panic!("expected Pong, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stop_notifies_clients_before_closing() {
+        let config = ServerConfig {
+            shutdown_grace: Duration::from_millis(200),
+            ..ServerConfig::default()
+        };
+        let (mut server, addr, _event_rx) = start_test_server(config).await;
+        let mut ws = connect(addr).await;
+        hello(&mut ws, "caster", &["A"]).await;
+
+        // This client ignores the notice, so it is closed once the grace period ends
+        let stop = tokio::spawn(async move {
+            server.stop().await.unwrap();
+        });
+        match recv(&mut ws).await {
+            Some(Message::ServerShutdown { payload, .. }) => assert_eq!(payload.grace_ms, 200),
+            other => panic!("expected ServerShutdown, got {:?}", other),
+        }
+        expect_closed(&mut ws).await;
+        stop.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stop_cancels_scheduled_messages() {
+        let (mut server, _addr, _event_rx) = start_test_server(ServerConfig::default()).await;
+
+        let cue = |content: &str| Message::kanpe_message(content.to_string(), vec!["ALL".to_string()], Default::default());
+        server.schedule_message(cue("soon"), Duration::from_millis(50));
+        server.schedule_message(cue("later"), Duration::from_millis(300));
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(server.message_history().await.len(), 1);
+        assert_eq!(server.pending_timers(), 1);
+
+        server.stop().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        assert_eq!(server.message_history().await.len(), 1);
+        assert_eq!(server.pending_timers(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_new_client_receives_current_air_status() {
+        let (server, addr, _event_rx) = start_test_server(ServerConfig::default()).await;
+        server.set_air_status(AirStatus::OnAir).await.unwrap();
+        let (_, since) = server.air_status().await.unwrap();
+
+        let mut ws = connect(addr).await;
+        hello(&mut ws, "late", &["A"]).await;
+
+        match recv(&mut ws).await {
+            Some(Message::AirStatus { payload, .. }) => {
+                assert_eq!(payload.status, AirStatus::OnAir);
+                assert_eq!(payload.since, since);
+            }
+            other => panic!("expected AirStatus, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_client_info_tracks_connected_at_and_last_pong() {
+        let (server, addr, _event_rx) = start_test_server(ServerConfig::default()).await;
+        let before = kanpe_core::types::timestamp();
+
+        let mut ws = connect(addr).await;
+        hello(&mut ws, "caster", &["A"]).await;
+        let info = server.get_connected_clients().await.remove(0);
+        assert!(info.connected_at >= before);
+        assert_eq!(info.last_pong_at, None);
+
+        send(&mut ws, &Message::pong_for("ping-1".to_string())).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let info = server.get_connected_clients().await.remove(0);
+        assert!(info.last_pong_at.is_some_and(|at| at >= info.connected_at));
+    }
+
+    #[tokio::test]
+    async fn test_is_client_connected_follows_connection() {
+        let (server, addr, _event_rx) = start_test_server(ServerConfig::default()).await;
+
+        let mut ws = connect(addr).await;
+        let client_id = hello(&mut ws, "caster", &["A"]).await;
+        assert!(server.is_client_connected(&client_id).await);
+        assert_eq!(server.last_disconnect_reason(&client_id).await, None);
+
+        ws.close(None).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        assert!(!server.is_client_connected(&client_id).await);
+        assert_eq!(
+            server.last_disconnect_reason(&client_id).await.as_deref(),
+            Some("closed by client")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_with_resume_token_keeps_client_id() {
+        let (_server, addr, mut event_rx) = start_test_server(ServerConfig::default()).await;
+
+        let mut ws = connect(addr).await;
+        send(&mut ws, &Message::client_hello("caster".to_string(), vec!["A".to_string()])).await;
+        let (client_id, token) = match recv(&mut ws).await {
+            Some(Message::ServerWelcome { payload, .. }) => (payload.assigned_client_id, payload.resume_token.unwrap()),
+            other => panic!("expected ServerWelcome, got {:?}", other),
+        };
+        ws.close(None).await.unwrap();
+
+        loop {
+            match tokio::time::timeout(Duration::from_secs(2), event_rx.recv()).await {
+                Ok(Some(ServerEvent::ClientDisconnected { .. })) => break,
+                Ok(Some(_)) => {}
+                other => panic!("expected ClientDisconnected, got {:?}", other),
+            }
+        }
+
+        let mut ws = connect(addr).await;
+        let resume = Message::client_hello_resuming("caster".to_string(), vec!["A".to_string()], Some(token.clone()));
+        send(&mut ws, &resume).await;
+        match recv(&mut ws).await {
+            Some(Message::ServerWelcome { payload, .. }) => {
+                assert_eq!(payload.assigned_client_id, client_id);
+                assert_ne!(payload.resume_token.as_deref(), Some(token.as_str()));
+            }
+            other => panic!("expected ServerWelcome, got {:?}", other),
+        }
+
+        loop {
+            match tokio::time::timeout(Duration::from_secs(2), event_rx.recv()).await {
+                Ok(Some(ServerEvent::ClientReconnected { client_id: id, .. })) => {
+                    assert_eq!(id, client_id);
+                    break;
+                }
+                Ok(Some(ServerEvent::ClientConnected { .. })) => panic!("resumed session reported as a new client"),
+                Ok(Some(_)) => {}
+                other => panic!("expected ClientReconnected, got {:?}", other),
+            }
+        }
+
+        // A spent token gives a fresh ID
+        let mut ws2 = connect(addr).await;
+        send(&mut ws2, &Message::client_hello_resuming("caster".to_string(), vec![], Some(token))).await;
+        match recv(&mut ws2).await {
+            Some(Message::ServerWelcome { payload, .. }) => assert_ne!(payload.assigned_client_id, client_id),
+            other => panic!("expected ServerWelcome, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resume_before_old_socket_is_noticed_keeps_name() {
+        let config = ServerConfig {
+            duplicate_names: DuplicateNamePolicy::Reject,
+            max_clients: Some(1),
+            ..ServerConfig::default()
+        };
+        let (server, addr, _event_rx) = start_test_server(config).await;
+        let mut old = connect(addr).await;
+        send(&mut old, &Message::client_hello("caster".to_string(), vec!["A".to_string()])).await;
+        let (client_id, token) = match recv(&mut old).await {
+            Some(Message::ServerWelcome { payload, .. }) => (payload.assigned_client_id, payload.resume_token.unwrap()),
+            other => panic!("expected ServerWelcome, got {:?}", other),
+        };
+        // The token is redeemable while the old entry is still registered
+        server.resume_tokens.disconnected(&client_id, std::time::Instant::now()).await;
+
+        let mut ws = connect(addr).await;
+        send(&mut ws, &Message::client_hello_resuming("caster".to_string(), vec!["A".to_string()], Some(token))).await;
+        match recv(&mut ws).await {
+            Some(Message::ServerWelcome { payload, .. }) => {
+                assert_eq!(payload.assigned_client_id, client_id);
+                assert_eq!(payload.assigned_client_name, None);
+            }
+            other => panic!("expected ServerWelcome, got {:?}", other),
+        }
+        assert_eq!(server.get_connected_clients().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_count_broadcasts_and_clients() {
+        let (server, addr, _event_rx) = start_test_server(ServerConfig::default()).await;
+
+        let mut first = connect(addr).await;
+        hello(&mut first, "first", &["A"]).await;
+        let mut second = connect(addr).await;
+        hello(&mut second, "second", &["B"]).await;
+        for content in ["1", "2", "3"] {
+            server
+                .broadcast_message(Message::kanpe_message(content.to_string(), vec!["ALL".to_string()], Default::default()))
+                .await
+                .unwrap();
+        }
+
+        let metrics = server.metrics().await;
+        assert!(metrics.started_at.is_some());
+        assert_eq!(metrics.messages_broadcast, 3);
+        assert_eq!(metrics.feedback_received, 0);
+        assert_eq!(metrics.current_clients, 2);
+        assert_eq!(metrics.peak_clients, 2);
+
+        // Each connection gets an immediate ping when its ping task starts
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(server.metrics().await.pings_sent, 2);
+
+        // Peak survives a client leaving
+        server.disconnect_client(&server.get_connected_clients().await[0].client_id).await.unwrap();
+        let metrics = server.metrics().await;
+        assert_eq!(metrics.current_clients, 1);
+        assert_eq!(metrics.peak_clients, 2);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_reflects_activity() {
+        let (server, addr, _event_rx) = start_test_server(ServerConfig::default()).await;
+        server.add_monitor("Foyer".to_string(), None, None, None).await.unwrap();
+        let mut first = connect(addr).await;
+        hello(&mut first, "first", &["A"]).await;
+        let mut second = connect(addr).await;
+        hello(&mut second, "second", &["M1"]).await;
+        server
+            .broadcast_message(Message::kanpe_message("Go".to_string(), vec!["A".to_string()], Default::default()))
+            .await
+            .unwrap();
+        server.set_ping_enabled(false);
+
+        let snapshot = server.snapshot().await;
+        let monitor_ids: Vec<&str> = snapshot.monitors.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(monitor_ids, vec!["A", "B", "C", "D", "M1"]);
+        assert_eq!(snapshot.clients.len(), 2);
+        assert_eq!(snapshot.metrics.current_clients, 2);
+        assert_eq!(snapshot.recent_messages.len(), 1);
+        assert!(snapshot.pings_paused);
+        assert!(serde_json::to_value(&snapshot).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_max_cues_evicts_oldest_cue() {
+        let (server, addr, _event_rx) = start_test_server(ServerConfig::default()).await;
+        let mut monitor = server.get_monitors().await.into_iter().find(|m| m.id == "A").unwrap();
+        monitor.max_cues = Some(1);
+        server.update_monitor(monitor).await.unwrap();
+
+        let mut ws = connect(addr).await;
+        hello(&mut ws, "caster", &["A"]).await;
+
+        let first = Message::kanpe_message("first".to_string(), vec!["A".to_string()], Default::default());
+        let second = Message::kanpe_message("second".to_string(), vec!["A".to_string()], Default::default());
+        server.broadcast_message(first.clone()).await.unwrap();
+        server.broadcast_message(second.clone()).await.unwrap();
+
+        assert_eq!(recv(&mut ws).await.unwrap().id(), first.id());
+        match recv(&mut ws).await {
+            Some(Message::MessageRecalled { payload, .. }) => {
+                assert_eq!(payload.message_id, first.id());
+                assert_eq!(payload.target_monitor_ids, vec!["A".to_string()]);
+            }
+            other => panic!("expected MessageRecalled, got {:?}", other),
+        }
+        assert_eq!(recv(&mut ws).await.unwrap().id(), second.id());
+
+        let active: Vec<String> = server.active_cues().await.iter().map(|m| m.id().to_string()).collect();
+        assert_eq!(active, vec![second.id().to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_max_cues_reject_policy_refuses_new_cue() {
+        let config = ServerConfig {
+            cue_overflow: CueOverflow::Reject,
+            ..Default::default()
+        };
+        let (server, _addr, _event_rx) = start_test_server(config).await;
+        let mut monitor = server.get_monitors().await.into_iter().find(|m| m.id == "A").unwrap();
+        monitor.max_cues = Some(1);
+        server.update_monitor(monitor).await.unwrap();
+
+        let first = Message::kanpe_message("first".to_string(), vec!["A".to_string()], Default::default());
+        server.broadcast_message(first.clone()).await.unwrap();
+        let second = Message::kanpe_message("second".to_string(), vec!["A".to_string()], Default::default());
+        assert!(server.broadcast_message(second).await.is_err());
+
+        let active: Vec<String> = server.active_cues().await.iter().map(|m| m.id().to_string()).collect();
+        assert_eq!(active, vec![first.id().to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_cue_without_targets_is_rejected() {
+        let (server, _addr, _event_rx) = start_test_server(ServerConfig::default()).await;
+
+        let cue = Message::kanpe_message("nowhere".to_string(), vec![], Priority::Normal);
+        let err = server.broadcast_message(cue).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<KanpeError>(), Some(KanpeError::InvalidTarget(_))));
+        assert!(server.message_history().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_targets_warn_or_reject() {
+        let (server, _addr, mut event_rx) = start_test_server(ServerConfig::default()).await;
+        let targets = vec!["A".to_string(), "Z".to_string(), "ALL".to_string()];
+
+        let cue = Message::kanpe_message("stale".to_string(), targets.clone(), Priority::Normal);
+        server.broadcast_message(cue.clone()).await.unwrap();
+        match event_rx.try_recv() {
+            Ok(ServerEvent::UnknownTargets { message_id, monitor_ids }) => {
+                assert_eq!(message_id, cue.id());
+                assert_eq!(monitor_ids, vec!["Z".to_string()]);
+            }
+            other => panic!("expected UnknownTargets, got {:?}", other),
+        }
+
+        let config = ServerConfig {
+            unknown_targets: UnknownTargetPolicy::Reject,
+            ..ServerConfig::default()
+        };
+        let (server, _addr, _event_rx) = start_test_server(config).await;
+        let cue = Message::kanpe_message("stale".to_string(), targets, Priority::Normal);
+        let err = server.broadcast_message(cue).await.unwrap_err();
+        assert!(err.to_string().contains("Z"));
+    }
+
+    #[tokio::test]
+    async fn test_feedback_is_grouped_by_cue() {
+        let (server, addr, mut event_rx) = start_test_server(ServerConfig::default()).await;
+        let mut ws = connect(addr).await;
+        hello(&mut ws, "caster", &["A"]).await;
+
+        for (content, reply_to) in [("ok", "cue-1"), ("on it", "cue-1"), ("what?", "cue-2")] {
+            let feedback =
+                Message::feedback_message(content.to_string(), "caster".to_string(), reply_to.to_string(), FeedbackType::Ack);
+            send(&mut ws, &feedback).await;
+        }
+        let mut received = 0;
+        while received < 3 {
+            if let ServerEvent::FeedbackReceived { .. } = event_rx.recv().await.unwrap() {
+                received += 1;
+            }
+        }
+
+        let contents: Vec<String> = server.feedback_for("cue-1").await.into_iter().map(|f| f.content).collect();
+        assert_eq!(contents, vec!["ok".to_string(), "on it".to_string()]);
+        let all = server.all_feedback().await;
+        assert_eq!(all.len(), 2);
+        assert_eq!(all["cue-2"][0].content, "what?");
+        assert!(server.feedback_for("cue-3").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_typing_indicator_is_emitted_as_event() {
+        let (_server, addr, mut event_rx) = start_test_server(ServerConfig::default()).await;
+        let mut ws = connect(addr).await;
+        let client_id = hello(&mut ws, "Caster B", &["B"]).await;
+
+        send(&mut ws, &Message::feedback_typing("Caster B".to_string(), true)).await;
+        loop {
+            match tokio::time::timeout(Duration::from_secs(2), event_rx.recv()).await.unwrap() {
+                Some(ServerEvent::ClientTyping { client_id: id, client_name, is_typing }) => {
+                    assert_eq!(id, client_id);
+                    assert_eq!(client_name, "Caster B");
+                    assert!(is_typing);
+                    break;
+                }
+                Some(_) => {}
+                None => panic!("event channel closed"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reactions_are_relayed_and_counted() {
+        let (server, addr, mut event_rx) = start_test_server(ServerConfig::default()).await;
+        let mut ws = connect(addr).await;
+        hello(&mut ws, "caster", &["A"]).await;
+
+        for reaction in ["👍", "👍", "❓"] {
+            send(&mut ws, &Message::reaction("cue-1".to_string(), reaction.to_string(), "caster".to_string())).await;
+        }
+        send(&mut ws, &Message::reaction("cue-2".to_string(), "⚠️".to_string(), "caster".to_string())).await;
+        // Malformed reactions are dropped before they are counted or relayed
+        send(&mut ws, &Message::reaction("cue-1".to_string(), "x".repeat(200), "caster".to_string())).await;
+        send(&mut ws, &Message::reaction("cue-1".to_string(), String::new(), "caster".to_string())).await;
+        let mut received = 0;
+        while received < 4 {
+            if let ServerEvent::ReactionReceived { .. } = event_rx.recv().await.unwrap() {
+                received += 1;
+            }
+        }
+
+        let counts = server.reaction_counts("cue-1").await;
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts["👍"], 2);
+        assert_eq!(counts["❓"], 1);
+        // Reactions are not textual feedback
+        assert!(server.feedback_for("cue-1").await.is_empty());
+        assert!(server.feedback_history().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_seq_counts_per_connection() {
+        let (server, addr, _event_rx) = start_test_server(ServerConfig::default()).await;
+        let mut on_a = connect(addr).await;
+        hello(&mut on_a, "A", &["A"]).await;
+        let mut on_b = connect(addr).await;
+        hello(&mut on_b, "B", &["B"]).await;
+
+        for target in ["A", "A", "B"] {
+            let cue = Message::kanpe_message(format!("to {}", target), vec![target.to_string()], Priority::Normal);
+            server.broadcast_message(cue).await.unwrap();
+        }
+
+        // B skipped the cues for A without seeing a gap
+        for expected in [1, 2] {
+            assert_eq!(Message::parse_frame(&recv_text(&mut on_a).await.unwrap()).0, Some(expected));
+        }
+        assert_eq!(Message::parse_frame(&recv_text(&mut on_b).await.unwrap()).0, Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_disabled_monitor_gets_no_targeted_cues() {
+        let (server, addr, _event_rx) = start_test_server(ServerConfig::default()).await;
+        let mut on_b = connect(addr).await;
+        hello(&mut on_b, "caster-b", &["B"]).await;
+
+        server.set_monitor_enabled("B", false).await.unwrap();
+        match recv(&mut on_b).await {
+            Some(Message::MonitorUpdated { payload, .. }) => {
+                assert_eq!(payload.monitor.id, "B");
+                assert!(!payload.monitor.enabled);
+            }
+            other => panic!("expected MonitorUpdated, got {:?}", other),
+        }
+
+        let cue = Message::kanpe_message("hold".to_string(), vec!["B".to_string()], Priority::Normal);
+        server.broadcast_message(cue).await.unwrap();
+        let flash = Message::flash_command(vec!["B".to_string()]);
+        server.broadcast_message(flash).await.unwrap();
+
+        // Re-enabling proves nothing was queued in between
+        server.set_monitor_enabled("B", true).await.unwrap();
+        match recv(&mut on_b).await {
+            Some(Message::MonitorUpdated { payload, .. }) => assert!(payload.monitor.enabled),
+            other => panic!("expected MonitorUpdated, got {:?}", other),
+        }
+
+        let monitor = server.get_monitors().await.into_iter().find(|m| m.id == "B").unwrap();
+        assert_eq!(monitor.name, "Monitor B");
+        assert_eq!(monitor.color.as_deref(), Some("#10b981"));
+    }
+
+    #[tokio::test]
+    async fn test_group_target_reaches_only_members() {
+        let (server, addr, _event_rx) = start_test_server(ServerConfig::default()).await;
+        server
+            .create_monitor_group("Pit".to_string(), "Orchestra pit".to_string(), vec!["A".to_string(), "B".to_string()])
+            .await
+            .unwrap();
+
+        let mut casters = Vec::new();
+        for monitor_id in ["A", "B", "C"] {
+            let mut ws = connect(addr).await;
+            hello(&mut ws, monitor_id, &[monitor_id]).await;
+            match recv(&mut ws).await {
+                Some(Message::MonitorGroupSync { payload, .. }) => assert_eq!(payload.groups[0].id, "Pit"),
+                other => panic!("expected MonitorGroupSync, got {:?}", other),
+            }
+            casters.push(ws);
+        }
+
+        let cue = Message::kanpe_message("Pit, stand by".to_string(), vec!["group:Pit".to_string()], Priority::Normal);
+        server.broadcast_message(cue).await.unwrap();
+        for ws in &mut casters[..2] {
+            match recv(ws).await {
+                Some(Message::KanpeMessage { payload, .. }) => {
+                    assert_eq!(payload.target_monitor_ids, vec!["A".to_string(), "B".to_string()]);
+                }
+                other => panic!("expected KanpeMessage, got {:?}", other),
+            }
+        }
+
+        // C's next message is one addressed to it, so the group cue skipped it
+        let direct = Message::kanpe_message("C only".to_string(), vec!["C".to_string()], Priority::Normal);
+        server.broadcast_message(direct).await.unwrap();
+        match recv(&mut casters[2]).await {
+            Some(Message::KanpeMessage { payload, .. }) => assert_eq!(payload.content, "C only"),
+            other => panic!("expected KanpeMessage, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_inverted_cue_skips_listed_monitors() {
+        let (server, addr, _event_rx) = start_test_server(ServerConfig::default()).await;
+        let mut casters = Vec::new();
+        for monitor_id in ["A", "B", "C"] {
+            let mut ws = connect(addr).await;
+            hello(&mut ws, monitor_id, &[monitor_id]).await;
+            casters.push(ws);
+        }
+
+        let cue = Message::kanpe_message_except("Reset".to_string(), vec!["A".to_string()], Priority::Normal);
+        server.broadcast_message(cue).await.unwrap();
+        for ws in &mut casters[1..] {
+            match recv(ws).await {
+                Some(Message::KanpeMessage { payload, .. }) => {
+                    assert_eq!(payload.content, "Reset");
+                    assert!(payload.invert);
+                }
+                other => panic!("expected KanpeMessage, got {:?}", other),
+            }
+        }
+
+        // Flash and clear commands are inverted the same way
+        server.broadcast_message(Message::flash_command_except(vec!["A".to_string()])).await.unwrap();
+        server.broadcast_message(Message::clear_command_except(vec!["A".to_string()])).await.unwrap();
+        for ws in &mut casters[1..] {
+            match recv(ws).await {
+                Some(Message::FlashCommand { payload, .. }) => assert!(payload.invert),
+                other => panic!("expected FlashCommand, got {:?}", other),
+            }
+            match recv(ws).await {
+                Some(Message::ClearCommand { payload, .. }) => assert!(payload.invert),
+                other => panic!("expected ClearCommand, got {:?}", other),
+            }
+        }
+
+        // A's next message is one addressed to it, so the inverted cue and commands skipped it
+        let direct = Message::kanpe_message("A only".to_string(), vec!["A".to_string()], Priority::Normal);
+        server.broadcast_message(direct).await.unwrap();
+        match recv(&mut casters[0]).await {
+            Some(Message::KanpeMessage { payload, .. }) => assert_eq!(payload.content, "A only"),
+            other => panic!("expected KanpeMessage, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_all_reaches_every_client_whatever_it_displays() {
+        let (server, addr, _event_rx) = start_test_server(ServerConfig::default()).await;
+        let mut casters = Vec::new();
+        for monitor_ids in [&["A"][..], &["B", "C"], &[]] {
+            let mut ws = connect(addr).await;
+            hello(&mut ws, "Caster", monitor_ids).await;
+            casters.push(ws);
+        }
+
+        server.flash_all().await.unwrap();
+        server.clear_all().await.unwrap();
+        server
+            .broadcast_message(Message::kanpe_message("Everyone".to_string(), vec![ALL_MONITORS.to_string()], Priority::Normal))
+            .await
+            .unwrap();
+
+        for ws in &mut casters {
+            assert!(matches!(recv(ws).await, Some(Message::FlashCommand { payload, .. }) if payload.target_monitor_ids == [ALL_MONITORS]));
+            assert!(matches!(recv(ws).await, Some(Message::ClearCommand { payload, .. }) if payload.target_monitor_ids == [ALL_MONITORS]));
+            assert!(matches!(recv(ws).await, Some(Message::KanpeMessage { payload, .. }) if payload.content == "Everyone"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replace_monitors_sends_one_list_sync() {
+        let (server, addr, _event_rx) = start_test_server(ServerConfig::default()).await;
+        let mut ws = connect(addr).await;
+        hello(&mut ws, "Caster", &["S1"]).await;
+
+        let imported: Vec<kanpe_core::types::VirtualMonitor> = (1..=8)
+            .map(|n| kanpe_core::types::VirtualMonitor {
+                id: format!("S{}", n),
+                name: format!("Stage {}", n),
+                description: None,
+                color: None,
+                icon: None,
+                max_cues: None,
+                position: n,
+                enabled: true,
+            })
+            .collect();
+        let expected: Vec<String> = imported.iter().map(|m| m.id.clone()).collect();
+        server.replace_monitors(imported).await.unwrap();
+
+        let ids: Vec<String> = server.get_monitors().await.into_iter().map(|m| m.id).collect();
+        assert_eq!(ids, expected);
+        match recv(&mut ws).await {
+            Some(Message::MonitorListSync { payload, .. }) => {
+                let ids: Vec<String> = payload.monitors.into_iter().map(|m| m.id).collect();
+                assert_eq!(ids, expected);
+            }
+            other => panic!("expected MonitorListSync, got {:?}", other),
+        }
+
+        // The next frame is this cue, so no per-monitor add/remove messages were sent
+        let cue = Message::kanpe_message("Places".to_string(), vec!["S1".to_string()], Priority::Normal);
+        server.broadcast_message(cue).await.unwrap();
+        match recv(&mut ws).await {
+            Some(Message::KanpeMessage { payload, .. }) => assert_eq!(payload.content, "Places"),
+            other => panic!("expected KanpeMessage, got {:?}", other),
+        }
     }
 }
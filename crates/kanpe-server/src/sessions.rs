@@ -0,0 +1,99 @@
+//! Resume tokens that let a reconnecting client keep its client ID
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+struct Session {
+    client_id: String,
+    /// Set once the client disconnects; the token can only be redeemed after that
+    disconnected_at: Option<Instant>,
+}
+
+/// Issues a token per connected client and redeems it for the same ID shortly after a drop
+pub struct ResumeTokens {
+    grace: Duration,
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl ResumeTokens {
+    pub fn new(grace: Duration) -> Self {
+        Self {
+            grace,
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Create a fresh token for a newly registered client
+    pub async fn issue(&self, client_id: &str) -> String {
+        let token = kanpe_core::types::new_id();
+        self.sessions.lock().await.insert(
+            token.clone(),
+            Session {
+                client_id: client_id.to_string(),
+                disconnected_at: None,
+            },
+        );
+        token
+    }
+
+    /// Start the grace window for every token held by a client that has disconnected
+    pub async fn disconnected(&self, client_id: &str, now: Instant) {
+        let mut sessions = self.sessions.lock().await;
+        sessions.retain(|_, session| !session.expired(now, self.grace));
+        for session in sessions.values_mut() {
+            if session.client_id == client_id {
+                session.disconnected_at = Some(now);
+            }
+        }
+    }
+
+    /// Redeem a token, returning the client ID to reuse
+    ///
+    /// Tokens are single-use, and only redeemable within the grace window after
+    /// their client disconnected.
+    pub async fn resume(&self, token: &str, now: Instant) -> Option<String> {
+        let mut sessions = self.sessions.lock().await;
+        let session = sessions.get(token)?;
+        let disconnected_at = session.disconnected_at?;
+        let valid = now.duration_since(disconnected_at) <= self.grace;
+        let session = sessions.remove(token)?;
+        valid.then_some(session.client_id)
+    }
+}
+
+impl Session {
+    fn expired(&self, now: Instant, grace: Duration) -> bool {
+        self.disconnected_at
+            .is_some_and(|disconnected_at| now.duration_since(disconnected_at) > grace)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_token_is_redeemable_once_within_grace_after_disconnect() {
+        let tokens = ResumeTokens::new(Duration::from_secs(30));
+        let start = Instant::now();
+        let token = tokens.issue("client-1").await;
+
+        // Still connected: the token cannot be used to clone the session
+        assert_eq!(tokens.resume(&token, start).await, None);
+
+        tokens.disconnected("client-1", start).await;
+        assert_eq!(tokens.resume(&token, start + Duration::from_secs(10)).await.as_deref(), Some("client-1"));
+        assert_eq!(tokens.resume(&token, start + Duration::from_secs(10)).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_token_expires_after_grace() {
+        let tokens = ResumeTokens::new(Duration::from_secs(30));
+        let start = Instant::now();
+        let token = tokens.issue("client-1").await;
+        tokens.disconnected("client-1", start).await;
+
+        assert_eq!(tokens.resume(&token, start + Duration::from_secs(31)).await, None);
+    }
+}
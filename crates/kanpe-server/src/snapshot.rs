@@ -0,0 +1,20 @@
+//! Point-in-time view of the whole server for hydrating a UI
+
+use crate::client_manager::ClientInfo;
+use crate::metrics::ServerMetrics;
+use kanpe_core::types::VirtualMonitor;
+use kanpe_core::Message;
+use serde::Serialize;
+
+/// Everything a director window needs to render the server, captured at one instant
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerSnapshot {
+    /// Monitors in display order
+    pub monitors: Vec<VirtualMonitor>,
+    pub clients: Vec<ClientInfo>,
+    /// Message history, oldest first
+    pub recent_messages: Vec<Message>,
+    pub metrics: ServerMetrics,
+    /// Keepalive pings are paused (see `KanpeServer::set_ping_enabled`)
+    pub pings_paused: bool,
+}
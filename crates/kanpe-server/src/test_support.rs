@@ -0,0 +1,185 @@
+//! Helpers for exercising the server over a real WebSocket in tests
+
+use crate::config::ServerConfig;
+use crate::events::ServerEvent;
+use crate::server::KanpeServer;
+use futures_util::{SinkExt, StreamExt};
+use kanpe_core::Message;
+use std::net::{Ipv4Addr, SocketAddr};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::time::{timeout, Duration};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+pub type TestSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// How long a test waits for a frame before giving up
+pub const RECV_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Start a server on an ephemeral loopback port
+pub async fn start_test_server(
+    config: ServerConfig,
+) -> (KanpeServer, SocketAddr, mpsc::UnboundedReceiver<ServerEvent>) {
+    let (event_tx, event_rx) = mpsc::unbounded_channel();
+    let mut server = KanpeServer::with_config(event_tx, config);
+    let addr = server
+        .start_on(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)))
+        .await
+        .expect("failed to start test server");
+    (server, addr, event_rx)
+}
+
+/// Open a raw WebSocket connection to the server
+pub async fn connect(addr: SocketAddr) -> TestSocket {
+    let (ws, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws", addr))
+        .await
+        .expect("failed to connect to test server");
+    ws
+}
+
+/// Open a WebSocket connection presenting a bearer token
+pub async fn connect_with_token(addr: SocketAddr, token: &str) -> TestSocket {
+    let mut request = format!("ws://{}/ws", addr).into_client_request().unwrap();
+    request
+        .headers_mut()
+        .insert("Authorization", format!("Bearer {}", token).parse().unwrap());
+    let (ws, _) = tokio_tungstenite::connect_async(request)
+        .await
+        .expect("failed to connect to test server");
+    ws
+}
+
+/// Send a protocol message as a JSON text frame
+pub async fn send(ws: &mut TestSocket, message: &Message) {
+    let json = serde_json::to_string(message).unwrap();
+    ws.send(WsMessage::Text(json)).await.unwrap();
+}
+
+/// Receive the next protocol message, skipping pings; None if the socket closed
+pub async fn recv(ws: &mut TestSocket) -> Option<Message> {
+    let text = recv_text(ws).await?;
+    Some(serde_json::from_str(&text).unwrap())
+}
+
+/// Receive the next protocol frame as raw JSON text, skipping pings; None if the socket closed
+pub async fn recv_text(ws: &mut TestSocket) -> Option<String> {
+    loop {
+        let frame = timeout(RECV_TIMEOUT, ws.next())
+            .await
+            .expect("timed out waiting for a frame");
+        match frame {
+            Some(Ok(WsMessage::Text(text))) => {
+                let message: Message = serde_json::from_str(&text).unwrap();
+                if !matches!(message, Message::Ping { .. }) {
+                    return Some(text);
+                }
+            }
+            Some(Ok(WsMessage::Close(_))) | Some(Err(_)) | None => return None,
+            Some(Ok(_)) => {}
+        }
+    }
+}
+
+/// Receive the next MessagePack frame as its sequence number and message, skipping pings;
+/// None if the socket closed
+pub async fn recv_msgpack(ws: &mut TestSocket) -> Option<(Option<u64>, Message)> {
+    loop {
+        let frame = timeout(RECV_TIMEOUT, ws.next())
+            .await
+            .expect("timed out waiting for a frame");
+        match frame {
+            Some(Ok(WsMessage::Binary(bytes))) => {
+                let (seq, message) = Message::parse_msgpack_frame(&bytes);
+                let message = message.unwrap();
+                if !matches!(message, Message::Ping { .. }) {
+                    return Some((seq, message));
+                }
+            }
+            Some(Ok(WsMessage::Text(text))) => panic!("expected a binary frame, got {}", text),
+            Some(Ok(WsMessage::Close(_))) | Some(Err(_)) | None => return None,
+            Some(Ok(_)) => {}
+        }
+    }
+}
+
+/// Send ClientHello and consume the welcome and monitor sync, returning the assigned client ID
+pub async fn hello(ws: &mut TestSocket, name: &str, monitor_ids: &[&str]) -> String {
+    let ids = monitor_ids.iter().map(|id| id.to_string()).collect();
+    send(ws, &Message::client_hello(name.to_string(), ids)).await;
+
+    let client_id = match recv(ws).await {
+        Some(Message::ServerWelcome { payload, .. }) => payload.assigned_client_id,
+        other => panic!("expected ServerWelcome, got {:?}", other),
+    };
+    match recv(ws).await {
+        Some(Message::MonitorListSync { .. }) => {}
+        other => panic!("expected MonitorListSync, got {:?}", other),
+    }
+    client_id
+}
+
+/// Assert that the server closes the connection
+pub async fn expect_closed(ws: &mut TestSocket) {
+    if let Some(message) = recv(ws).await {
+        panic!("expected connection to close, got {:?}", message);
+    }
+}
+
+/// Send an HTTP POST with a JSON body, returning the status code and response body
+pub async fn post_json(addr: SocketAddr, path: &str, body: &str, token: Option<&str>) -> (u16, String) {
+    let auth = token
+        .map(|token| format!("Authorization: Bearer {}\r\n", token))
+        .unwrap_or_default();
+    let request = format!(
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n{}",
+        path,
+        addr,
+        body.len(),
+        auth,
+        body
+    );
+
+    send_http(addr, &request).await
+}
+
+/// Send an HTTP GET, returning the status code and response body
+pub async fn http_get(addr: SocketAddr, path: &str) -> (u16, String) {
+    let request = format!("GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n", path, addr);
+    send_http(addr, &request).await
+}
+
+/// Send a browser's CORS preflight for a cross-origin POST, returning the status
+/// code and the lowercased response headers
+pub async fn preflight(addr: SocketAddr, path: &str) -> (u16, String) {
+    let request = format!(
+        "OPTIONS {} HTTP/1.1\r\nHost: {}\r\nOrigin: http://evil.example\r\nAccess-Control-Request-Method: POST\r\nAccess-Control-Request-Headers: content-type\r\nConnection: close\r\n\r\n",
+        path, addr
+    );
+    let (status, head, _) = send_http_raw(addr, &request).await;
+    (status, head.to_lowercase())
+}
+
+/// Write a raw HTTP/1.1 request and split the response into status code and body
+async fn send_http(addr: SocketAddr, request: &str) -> (u16, String) {
+    let (status, _, body) = send_http_raw(addr, request).await;
+    (status, body)
+}
+
+/// Write a raw HTTP/1.1 request and split the response into status code, head and body
+async fn send_http_raw(addr: SocketAddr, request: &str) -> (u16, String, String) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    timeout(RECV_TIMEOUT, stream.read_to_string(&mut response))
+        .await
+        .expect("timed out waiting for a response")
+        .unwrap();
+
+    let (head, body) = response.split_once("\r\n\r\n").unwrap_or((&response, ""));
+    let status = head.split_whitespace().nth(1).and_then(|code| code.parse().ok()).unwrap_or(0);
+    (status, head.to_string(), body.to_string())
+}
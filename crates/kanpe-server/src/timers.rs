@@ -0,0 +1,57 @@
+//! Tracking of background timer tasks so they can be cancelled together
+
+use std::future::Future;
+use std::sync::Mutex;
+use tokio::task::JoinHandle;
+
+/// Timer tasks owned by the server, cancelled when it stops
+pub struct TimerSet {
+    handles: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl TimerSet {
+    pub fn new() -> Self {
+        Self {
+            handles: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Spawn a timer task, forgetting any earlier ones that already finished
+    pub fn spawn<F>(&self, timer: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let mut handles = self.handles.lock().unwrap();
+        handles.retain(|handle| !handle.is_finished());
+        handles.push(tokio::spawn(timer));
+    }
+
+    /// Abort every outstanding timer
+    pub fn cancel_all(&self) {
+        for handle in self.handles.lock().unwrap().drain(..) {
+            handle.abort();
+        }
+    }
+
+    /// Count timers that have neither fired nor been cancelled
+    pub fn active_count(&self) -> usize {
+        self.handles
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|handle| !handle.is_finished())
+            .count()
+    }
+}
+
+impl Default for TimerSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for TimerSet {
+    fn drop(&mut self) {
+        self.cancel_all();
+    }
+}
@@ -0,0 +1,82 @@
+//! Reading of schema-versioned JSON files, backing up ones from a newer version
+
+use kanpe_core::versioned::{versioned_parse, VersionParseError};
+use serde::de::DeserializeOwned;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Errors returned by [`load_versioned`]
+#[derive(Debug, Error)]
+pub(crate) enum VersionedFileError {
+    #[error("failed to read {0}: {1}")]
+    Io(PathBuf, #[source] std::io::Error),
+    #[error("failed to parse {0}: {1}")]
+    Parse(PathBuf, #[source] serde_json::Error),
+    #[error(
+        "{path} uses schema version {found}, but this version of Bi-Kanpe only supports up to {supported}; \
+         the file was left untouched and backed up to {backup}"
+    )]
+    TooNew {
+        path: PathBuf,
+        found: u64,
+        supported: u64,
+        backup: PathBuf,
+    },
+}
+
+/// Read a JSON file written with schema version `supported` or older
+///
+/// A file from a newer schema is copied to `<file>.v<found>.bak` and rejected.
+pub(crate) fn load_versioned<T: DeserializeOwned>(path: &Path, supported: u32) -> Result<T, VersionedFileError> {
+    let content = fs::read_to_string(path).map_err(|e| VersionedFileError::Io(path.to_path_buf(), e))?;
+    match versioned_parse(&content, supported) {
+        Ok(value) => Ok(value),
+        Err(VersionParseError::Parse(e)) => Err(VersionedFileError::Parse(path.to_path_buf(), e)),
+        Err(VersionParseError::TooNew { found, supported }) => {
+            let mut backup = OsString::from(path.as_os_str());
+            backup.push(format!(".v{}.bak", found));
+            let backup = PathBuf::from(backup);
+            fs::copy(path, &backup).map_err(|e| VersionedFileError::Io(backup.clone(), e))?;
+            Err(VersionedFileError::TooNew {
+                path: path.to_path_buf(),
+                found,
+                supported,
+                backup,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    struct Settings {
+        name: String,
+    }
+
+    #[test]
+    fn test_future_version_is_rejected_and_backed_up() {
+        let path = std::env::temp_dir().join(format!("kanpe-versioned-{}.json", kanpe_core::types::new_id()));
+        let future = r#"{ "schema_version": 7, "name": "from the future" }"#;
+        fs::write(&path, future).unwrap();
+
+        let err = load_versioned::<Settings>(&path, 1).unwrap_err();
+        let VersionedFileError::TooNew { found, supported, backup, .. } = err else {
+            panic!("expected TooNew, got {:?}", err);
+        };
+        assert_eq!((found, supported), (7, 1));
+        assert_eq!(fs::read_to_string(&path).unwrap(), future);
+        assert_eq!(fs::read_to_string(&backup).unwrap(), future);
+
+        fs::write(&path, r#"{ "name": "legacy" }"#).unwrap();
+        assert_eq!(load_versioned::<Settings>(&path, 1).unwrap().name, "legacy");
+
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&backup).unwrap();
+    }
+}
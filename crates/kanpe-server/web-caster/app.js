@@ -8,10 +8,15 @@ const state = {
     selectedMonitorIds: [],
     availableMonitors: [],
     currentMessage: null,
+    acknowledgedMessageId: null,
     fontSize: 4, // rem
     theme: 'light',
+    reconnectRequested: false,
 };
 
+// WebSocket subprotocol naming the Kanpe protocol version
+const KANPE_SUBPROTOCOL = 'kanpe.v1';
+
 // Default feedback templates (can be customized)
 const DEFAULT_TEMPLATES = [
     { id: '1', content: '了解しました', feedback_type: 'ack' },
@@ -50,6 +55,13 @@ const elements = {
     fontDecrease: document.getElementById('font-decrease'),
     fontSizeDisplay: document.getElementById('font-size-display'),
     themeToggle: document.getElementById('theme-toggle'),
+    airStatusBanner: document.getElementById('air-status-banner'),
+};
+
+const AIR_STATUS_LABELS = {
+    on_air: 'ON AIR',
+    standby: 'STANDBY',
+    off_air: 'OFF AIR',
 };
 
 // Utility: Generate UUID v4
@@ -101,6 +113,7 @@ function setupEventListeners() {
     elements.fontIncrease.addEventListener('click', () => adjustFontSize(0.5));
     elements.fontDecrease.addEventListener('click', () => adjustFontSize(-0.5));
     elements.themeToggle.addEventListener('click', toggleTheme);
+    elements.messageDisplay.addEventListener('click', acknowledgeCurrentMessage);
     
     elements.feedbackTabs.forEach(tab => {
         tab.addEventListener('click', () => switchTab(tab.dataset.tab));
@@ -163,10 +176,13 @@ function handleConnect() {
     
     // Connect to WebSocket
     const protocol = window.location.protocol === 'https:' ? 'wss:' : 'ws:';
-    const wsUrl = `${protocol}//${window.location.host}/ws`;
+    // Pass along the shared token from the page URL (e.g. http://host:port/?token=...)
+    const token = new URLSearchParams(window.location.search).get('token');
+    const query = token ? `?token=${encodeURIComponent(token)}` : '';
+    const wsUrl = `${protocol}//${window.location.host}/ws${query}`;
     
     try {
-        state.ws = new WebSocket(wsUrl);
+        state.ws = new WebSocket(wsUrl, KANPE_SUBPROTOCOL);
         
         state.ws.onopen = handleWebSocketOpen;
         state.ws.onmessage = handleWebSocketMessage;
@@ -220,14 +236,29 @@ function handleWebSocketMessage(event) {
             case 'clear_command':
                 handleClearCommand(message);
                 break;
+            case 'message_recalled':
+                handleMessageRecalled(message);
+                break;
+            case 'air_status':
+                handleAirStatus(message);
+                break;
             case 'monitor_added':
             case 'monitor_removed':
             case 'monitor_updated':
                 // Handle monitor updates if needed
                 break;
+            case 'server_shutdown':
+                alert('ディレクターがセッションを終了しました');
+                handleDisconnect();
+                break;
+            case 'reconnect_request':
+                // Drop the socket and reconnect from the close handler
+                state.reconnectRequested = true;
+                state.ws.close();
+                break;
             case 'ping':
                 // Respond with pong
-                sendPong();
+                sendPong(message.id);
                 break;
         }
     } catch (error) {
@@ -262,16 +293,29 @@ function handleMonitorListSync(message) {
     }
 }
 
+// Check whether a cue or command is addressed to the selected monitors
+// (inverted ones list the monitors to skip)
+function isForSelectedMonitors(payload) {
+    const targetIds = payload.target_monitor_ids;
+    if (payload.invert) {
+        return !targetIds.includes('ALL') &&
+               state.selectedMonitorIds.some(id => !targetIds.includes(id));
+    }
+    return targetIds.includes('ALL') || 
+           state.selectedMonitorIds.some(id => targetIds.includes(id));
+}
+
 // Handle KanpeMessage
 function handleKanpeMessage(message) {
     const payload = message.payload;
-    const targetIds = payload.target_monitor_ids;
     
-    // Filter message based on target_monitor_ids
-    const shouldDisplay = targetIds.includes('ALL') || 
-                         state.selectedMonitorIds.some(id => targetIds.includes(id));
+    // Ignore stale cues (e.g. replayed after a reconnect)
+    if (payload.expires_at && payload.expires_at <= Date.now()) {
+        return;
+    }
     
-    if (!shouldDisplay) {
+    // Filter message based on target_monitor_ids
+    if (!isForSelectedMonitors(payload)) {
         return;
     }
     
@@ -290,9 +334,12 @@ function handleKanpeMessage(message) {
         urgent: '🚨 緊急',
         high: '⚠ 重要',
         normal: '📝 通常',
+        low: '💬 参考',
     }[payload.priority] || '📝 通常';
     
-    elements.priorityBadge.textContent = priorityText;
+    elements.priorityBadge.textContent = needsAcknowledgement()
+        ? `${priorityText}（タップで確認）`
+        : priorityText;
     elements.priorityBadge.className = `priority-badge ${payload.priority}`;
     
     // Update background
@@ -312,11 +359,7 @@ function handleKanpeMessage(message) {
 
 // Handle FlashCommand
 function handleFlashCommand(message) {
-    const targetIds = message.payload.target_monitor_ids;
-    const shouldFlash = targetIds.includes('ALL') || 
-                       state.selectedMonitorIds.some(id => targetIds.includes(id));
-    
-    if (shouldFlash) {
+    if (isForSelectedMonitors(message.payload)) {
         elements.messageDisplay.classList.add('flash-animation');
         setTimeout(() => {
             elements.messageDisplay.classList.remove('flash-animation');
@@ -326,11 +369,23 @@ function handleFlashCommand(message) {
 
 // Handle ClearCommand
 function handleClearCommand(message) {
-    const targetIds = message.payload.target_monitor_ids;
-    const shouldClear = targetIds.includes('ALL') || 
-                       state.selectedMonitorIds.some(id => targetIds.includes(id));
-    
-    if (shouldClear) {
+    if (isForSelectedMonitors(message.payload)) {
+        state.currentMessage = null;
+        elements.messageContent.style.display = 'none';
+        elements.waitingState.style.display = 'block';
+        elements.messageDisplay.className = 'message-display';
+    }
+}
+
+// Handle MessageRecalled (a cue withdrawn by the server)
+function handleMessageRecalled(message) {
+    const payload = message.payload;
+    const targetIds = payload.target_monitor_ids;
+    const isShown = state.currentMessage && state.currentMessage.id === payload.message_id;
+    const affectsUs = targetIds.includes('ALL') ||
+                      state.selectedMonitorIds.some(id => targetIds.includes(id));
+
+    if (isShown && affectsUs) {
         state.currentMessage = null;
         elements.messageContent.style.display = 'none';
         elements.waitingState.style.display = 'block';
@@ -338,19 +393,56 @@ function handleClearCommand(message) {
     }
 }
 
+// Handle AirStatus
+function handleAirStatus(message) {
+    const status = message.payload.status;
+    elements.airStatusBanner.textContent = AIR_STATUS_LABELS[status] || status;
+    elements.airStatusBanner.className = `air-status-banner ${status}`;
+    elements.airStatusBanner.style.display = 'block';
+}
+
 // Send Pong
-function sendPong() {
+function sendPong(pingId) {
     if (!state.ws || state.ws.readyState !== WebSocket.OPEN) return;
     
     const pong = {
         type: 'pong',
         id: generateUUID(),
         timestamp: getTimestamp(),
+        payload: {
+            ping_id: pingId,
+        },
     };
     
     state.ws.send(JSON.stringify(pong));
 }
 
+// Check whether the current cue keeps flashing until it is acknowledged
+function needsAcknowledgement() {
+    const message = state.currentMessage;
+    return !!message &&
+           message.payload.repeat_flash_interval_ms != null &&
+           state.acknowledgedMessageId !== message.id;
+}
+
+// Acknowledge the current cue, stopping its repeated urgent flash
+function acknowledgeCurrentMessage() {
+    if (!needsAcknowledgement() || !state.ws || state.ws.readyState !== WebSocket.OPEN) return;
+    
+    const ack = {
+        type: 'message_ack',
+        id: generateUUID(),
+        timestamp: getTimestamp(),
+        payload: {
+            message_id: state.currentMessage.id,
+        },
+    };
+    
+    state.ws.send(JSON.stringify(ack));
+    state.acknowledgedMessageId = state.currentMessage.id;
+    elements.priorityBadge.textContent = elements.priorityBadge.textContent.replace('（タップで確認）', '');
+}
+
 // WebSocket error
 function handleWebSocketError(error) {
     console.error('WebSocket error:', error);
@@ -365,6 +457,12 @@ function handleWebSocketClose() {
     state.connected = false;
     state.ws = null;
     
+    if (state.reconnectRequested) {
+        state.reconnectRequested = false;
+        handleConnect();
+        return;
+    }
+    
     if (elements.displayScreen.style.display !== 'none') {
         // Was connected, show disconnection
         alert('サーバーから切断されました');
@@ -496,6 +594,11 @@ function sendFeedback(template, isReply) {
     
     state.ws.send(JSON.stringify(feedback));
     
+    // Replying to an urgent cue also acknowledges it
+    if (isReply) {
+        acknowledgeCurrentMessage();
+    }
+    
     // Show status
     elements.feedbackStatus.style.display = 'block';
     setTimeout(() => {
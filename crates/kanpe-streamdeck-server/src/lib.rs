@@ -2,4 +2,4 @@ pub mod protocol;
 pub mod server;
 
 pub use protocol::{StreamDeckRequest, StreamDeckResponse, LatestMessageInfo};
-pub use server::{StreamDeckEvent, StreamDeckEventReceiver, StreamDeckEventSender, StreamDeckServer};
+pub use server::{ConnectionId, StreamDeckEvent, StreamDeckEventReceiver, StreamDeckEventSender, StreamDeckServer};
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
-use kanpe_core::types::VirtualMonitor;
+use kanpe_core::message::KanpeMessagePayload;
+use kanpe_core::types::{ContentFormat, VirtualMonitor};
 
 /// Messages from StreamDeck plugin to the caster app
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,6 +17,21 @@ pub enum StreamDeckRequest {
     },
     /// Request current state
     GetState,
+    /// Flash monitors (server mode)
+    SendFlash {
+        target_monitor_ids: Vec<String>,
+    },
+    /// Clear monitors (server mode)
+    SendClear {
+        target_monitor_ids: Vec<String>,
+    },
+    /// Request the monitor list
+    GetMonitors,
+    /// Request recently received messages, at most `limit` if given
+    GetHistory {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        limit: Option<usize>,
+    },
 }
 
 /// Messages from caster app to StreamDeck plugin
@@ -35,6 +51,14 @@ pub enum StreamDeckResponse {
         latest_message: Option<LatestMessageInfo>,
         monitors: Vec<VirtualMonitor>,
     },
+    /// Answer to GetMonitors
+    Monitors {
+        monitors: Vec<VirtualMonitor>,
+    },
+    /// Answer to GetHistory, oldest first
+    History {
+        messages: Vec<LatestMessageInfo>,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -43,6 +67,22 @@ pub struct LatestMessageInfo {
     pub content: String,
     pub priority: String,
     pub target_monitor_ids: Vec<String>,
+    /// How `content` is formatted, so the deck can strip markup it cannot render
+    #[serde(default)]
+    pub content_format: ContentFormat,
+}
+
+impl LatestMessageInfo {
+    /// Summarize a received cue for the deck's display
+    pub fn from_payload(id: String, payload: KanpeMessagePayload) -> Self {
+        Self {
+            id,
+            content: payload.content,
+            priority: format!("{:?}", payload.priority).to_lowercase(),
+            target_monitor_ids: payload.target_monitor_ids,
+            content_format: payload.content_format,
+        }
+    }
 }
 
 impl StreamDeckResponse {
@@ -60,3 +100,82 @@ impl StreamDeckResponse {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_send_flash_round_trip() {
+        let json = r#"{"type":"send_flash","target_monitor_ids":["A","B"]}"#;
+        let request: StreamDeckRequest = serde_json::from_str(json).unwrap();
+        assert!(matches!(
+            &request,
+            StreamDeckRequest::SendFlash { target_monitor_ids } if target_monitor_ids == &["A", "B"]
+        ));
+        assert_eq!(serde_json::to_string(&request).unwrap(), json);
+    }
+
+    #[test]
+    fn test_send_clear_round_trip() {
+        let json = r#"{"type":"send_clear","target_monitor_ids":["ALL"]}"#;
+        let request: StreamDeckRequest = serde_json::from_str(json).unwrap();
+        assert!(matches!(
+            &request,
+            StreamDeckRequest::SendClear { target_monitor_ids } if target_monitor_ids == &["ALL"]
+        ));
+        assert_eq!(serde_json::to_string(&request).unwrap(), json);
+    }
+
+    #[test]
+    fn test_get_monitors_and_history_round_trip() {
+        let request: StreamDeckRequest = serde_json::from_str(r#"{"type":"get_monitors"}"#).unwrap();
+        assert!(matches!(request, StreamDeckRequest::GetMonitors));
+
+        let json = r#"{"type":"get_history","limit":5}"#;
+        let request: StreamDeckRequest = serde_json::from_str(json).unwrap();
+        assert!(matches!(request, StreamDeckRequest::GetHistory { limit: Some(5) }));
+        assert_eq!(serde_json::to_string(&request).unwrap(), json);
+        let request: StreamDeckRequest = serde_json::from_str(r#"{"type":"get_history"}"#).unwrap();
+        assert!(matches!(request, StreamDeckRequest::GetHistory { limit: None }));
+    }
+
+    #[test]
+    fn test_monitors_and_history_responses_round_trip() {
+        let monitors = StreamDeckResponse::Monitors {
+            monitors: vec![VirtualMonitor {
+                id: "A".to_string(),
+                name: "Monitor A".to_string(),
+                description: None,
+                color: None,
+                icon: None,
+                max_cues: None,
+                position: 0,
+                enabled: true,
+            }],
+        };
+        let json = serde_json::to_string(&monitors).unwrap();
+        assert!(json.starts_with(r#"{"type":"monitors","#));
+        let StreamDeckResponse::Monitors { monitors } = serde_json::from_str(&json).unwrap() else {
+            panic!("expected Monitors");
+        };
+        assert_eq!(monitors[0].id, "A");
+
+        let history = StreamDeckResponse::History {
+            messages: vec![LatestMessageInfo {
+                id: "m1".to_string(),
+                content: "押してます".to_string(),
+                priority: "normal".to_string(),
+                target_monitor_ids: vec!["ALL".to_string()],
+                content_format: ContentFormat::Markdown,
+            }],
+        };
+        let json = serde_json::to_string(&history).unwrap();
+        assert!(json.starts_with(r#"{"type":"history","#));
+        let StreamDeckResponse::History { messages } = serde_json::from_str(&json).unwrap() else {
+            panic!("expected History");
+        };
+        assert_eq!(messages[0].content, "押してます");
+        assert_eq!(messages[0].content_format, ContentFormat::Markdown);
+    }
+}
@@ -8,7 +8,10 @@ use axum::{
     routing::get,
     Router,
 };
-use futures_util::{stream::SplitSink, SinkExt, StreamExt};
+use futures_util::{future::join_all, stream::SplitSink, SinkExt, StreamExt};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::sync::{mpsc, RwLock};
 
@@ -17,53 +20,87 @@ use crate::protocol::{StreamDeckRequest, StreamDeckResponse};
 pub type StreamDeckEventSender = mpsc::UnboundedSender<StreamDeckEvent>;
 pub type StreamDeckEventReceiver = mpsc::UnboundedReceiver<StreamDeckEvent>;
 
+/// Identifies one connected StreamDeck for the lifetime of the server
+pub type ConnectionId = u64;
+
+type Connections = Arc<RwLock<HashMap<ConnectionId, SplitSink<WebSocket, WsMessage>>>>;
+
 /// Events emitted by the StreamDeck server
 #[derive(Debug, Clone)]
 pub enum StreamDeckEvent {
     /// A StreamDeck client connected
-    Connected,
+    Connected { connection_id: ConnectionId },
     /// A StreamDeck client disconnected
-    Disconnected,
+    Disconnected { connection_id: ConnectionId },
     /// Send feedback request received
     SendFeedback {
+        connection_id: ConnectionId,
         content: String,
         feedback_type: String,
     },
     /// React to latest message request received
     ReactToLatest {
+        connection_id: ConnectionId,
         feedback_type: String,
     },
     /// Get state request received
-    GetState,
+    GetState { connection_id: ConnectionId },
+    /// Flash request received
+    SendFlash {
+        connection_id: ConnectionId,
+        target_monitor_ids: Vec<String>,
+    },
+    /// Clear request received
+    SendClear {
+        connection_id: ConnectionId,
+        target_monitor_ids: Vec<String>,
+    },
+    /// Monitor list request received
+    GetMonitors { connection_id: ConnectionId },
+    /// Message history request received
+    GetHistory {
+        connection_id: ConnectionId,
+        limit: Option<usize>,
+    },
 }
 
 struct AppState {
     event_tx: StreamDeckEventSender,
-    ws_sender: Arc<RwLock<Option<SplitSink<WebSocket, WsMessage>>>>,
+    connections: Connections,
+    next_connection_id: AtomicU64,
 }
 
 pub struct StreamDeckServer {
     port: u16,
     shutdown_tx: mpsc::Sender<()>,
-    ws_sender: Arc<RwLock<Option<SplitSink<WebSocket, WsMessage>>>>,
+    connections: Connections,
 }
 
 impl StreamDeckServer {
+    /// Start the server on loopback, so only this machine can connect
+    ///
+    /// Pass port `0` to let the OS pick one; [`port`](Self::port) reports it.
     pub async fn new(port: u16, event_tx: StreamDeckEventSender) -> Result<Self> {
+        Self::new_on(IpAddr::V4(Ipv4Addr::LOCALHOST), port, event_tx).await
+    }
+
+    /// Start the server on a specific address, e.g. `0.0.0.0` to accept decks on other machines
+    pub async fn new_on(ip: IpAddr, port: u16, event_tx: StreamDeckEventSender) -> Result<Self> {
         let (shutdown_tx, mut shutdown_rx) = mpsc::channel::<()>(1);
-        let ws_sender = Arc::new(RwLock::new(None));
+        let connections = Connections::default();
 
         let app_state = Arc::new(AppState {
             event_tx: event_tx.clone(),
-            ws_sender: ws_sender.clone(),
+            connections: connections.clone(),
+            next_connection_id: AtomicU64::new(1),
         });
 
         let app = Router::new()
             .route("/ws", get(ws_handler))
             .with_state(app_state);
 
-        let addr = format!("127.0.0.1:{}", port);
-        let listener = tokio::net::TcpListener::bind(&addr).await?;
+        let listener = tokio::net::TcpListener::bind(SocketAddr::new(ip, port)).await?;
+        let port = listener.local_addr()?.port();
 
         tokio::spawn(async move {
             axum::serve(listener, app)
@@ -77,23 +114,48 @@ impl StreamDeckServer {
         Ok(Self {
             port,
             shutdown_tx,
-            ws_sender,
+            connections,
         })
     }
 
+    /// Port the server is listening on
     pub fn port(&self) -> u16 {
         self.port
     }
 
-    pub async fn send_response(&self, response: StreamDeckResponse) -> Result<()> {
-        let mut sender_lock = self.ws_sender.write().await;
-        if let Some(sender) = sender_lock.as_mut() {
+    /// Number of StreamDecks currently connected
+    pub async fn connection_count(&self) -> usize {
+        self.connections.read().await.len()
+    }
+
+    /// Send a response to the StreamDeck that made a request
+    pub async fn send_response(&self, connection_id: ConnectionId, response: StreamDeckResponse) -> Result<()> {
+        let mut connections = self.connections.write().await;
+        if let Some(sender) = connections.get_mut(&connection_id) {
             let json = serde_json::to_string(&response)?;
             sender.send(WsMessage::Text(json)).await?;
         }
         Ok(())
     }
 
+    /// Send a response (typically a `StateUpdate`) to every connected StreamDeck
+    pub async fn broadcast(&self, response: StreamDeckResponse) -> Result<()> {
+        let json = serde_json::to_string(&response)?;
+        let mut connections = self.connections.write().await;
+        // Send to every deck at once so one slow socket doesn't hold up the rest
+        let sends = connections.iter_mut().map(|(connection_id, sender)| {
+            let message = WsMessage::Text(json.clone());
+            async move { sender.send(message).await.err().map(|_| *connection_id) }
+        });
+        let dead: Vec<ConnectionId> = join_all(sends).await.into_iter().flatten().collect();
+        // Stop pushing to a socket whose deck went away; its connection task
+        // still reports the disconnect once the read side ends
+        for connection_id in dead {
+            connections.remove(&connection_id);
+        }
+        Ok(())
+    }
+
     pub async fn shutdown(self) -> Result<()> {
         self.shutdown_tx.send(()).await?;
         Ok(())
@@ -109,15 +171,13 @@ async fn ws_handler(
 
 async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
     let (sender, mut receiver) = socket.split();
-    
+    let connection_id = state.next_connection_id.fetch_add(1, Ordering::Relaxed);
+
     // Store the sender for sending responses
-    {
-        let mut ws_sender = state.ws_sender.write().await;
-        *ws_sender = Some(sender);
-    }
+    state.connections.write().await.insert(connection_id, sender);
 
     // Notify connection
-    let _ = state.event_tx.send(StreamDeckEvent::Connected);
+    let _ = state.event_tx.send(StreamDeckEvent::Connected { connection_id });
 
     // Handle incoming messages
     while let Some(Ok(msg)) = receiver.next().await {
@@ -128,13 +188,24 @@ async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
                         content,
                         feedback_type,
                     } => StreamDeckEvent::SendFeedback {
+                        connection_id,
                         content,
                         feedback_type,
                     },
                     StreamDeckRequest::ReactToLatest { feedback_type } => {
-                        StreamDeckEvent::ReactToLatest { feedback_type }
+                        StreamDeckEvent::ReactToLatest { connection_id, feedback_type }
                     }
-                    StreamDeckRequest::GetState => StreamDeckEvent::GetState,
+                    StreamDeckRequest::GetState => StreamDeckEvent::GetState { connection_id },
+                    StreamDeckRequest::SendFlash { target_monitor_ids } => StreamDeckEvent::SendFlash {
+                        connection_id,
+                        target_monitor_ids,
+                    },
+                    StreamDeckRequest::SendClear { target_monitor_ids } => StreamDeckEvent::SendClear {
+                        connection_id,
+                        target_monitor_ids,
+                    },
+                    StreamDeckRequest::GetMonitors => StreamDeckEvent::GetMonitors { connection_id },
+                    StreamDeckRequest::GetHistory { limit } => StreamDeckEvent::GetHistory { connection_id, limit },
                 };
 
                 let _ = state.event_tx.send(event);
@@ -144,12 +215,115 @@ async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
         }
     }
 
-    // Clear the sender on disconnect
-    {
-        let mut ws_sender = state.ws_sender.write().await;
-        *ws_sender = None;
+    // Close and drop the sender so a reconnecting deck starts from a clean slate
+    if let Some(mut sender) = state.connections.write().await.remove(&connection_id) {
+        let _ = sender.close().await;
     }
 
     // Notify disconnection
-    let _ = state.event_tx.send(StreamDeckEvent::Disconnected);
+    let _ = state.event_tx.send(StreamDeckEvent::Disconnected { connection_id });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_tungstenite::tungstenite::Message as TungsteniteMessage;
+
+    async fn next_response(
+        deck: &mut tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>,
+    ) -> StreamDeckResponse {
+        let frame = tokio::time::timeout(std::time::Duration::from_secs(2), deck.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        let TungsteniteMessage::Text(text) = frame else {
+            panic!("expected a text frame, got {:?}", frame);
+        };
+        serde_json::from_str(&text).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_port_zero_reports_bound_port() {
+        let (event_tx, mut event_rx) = mpsc::unbounded_channel();
+        let server = StreamDeckServer::new(0, event_tx).await.unwrap();
+        assert_ne!(server.port(), 0);
+
+        let url = format!("ws://127.0.0.1:{}/ws", server.port());
+        let _deck = tokio_tungstenite::connect_async(url.as_str()).await.unwrap();
+        assert!(matches!(event_rx.recv().await, Some(StreamDeckEvent::Connected { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_state_update_reaches_every_connection() {
+        let (event_tx, mut event_rx) = mpsc::unbounded_channel();
+        let server = StreamDeckServer::new(0, event_tx).await.unwrap();
+        let url = format!("ws://127.0.0.1:{}/ws", server.port());
+
+        let (mut first, _) = tokio_tungstenite::connect_async(url.as_str()).await.unwrap();
+        let (mut second, _) = tokio_tungstenite::connect_async(url.as_str()).await.unwrap();
+        let mut connection_ids = Vec::new();
+        while connection_ids.len() < 2 {
+            if let Some(StreamDeckEvent::Connected { connection_id }) = event_rx.recv().await {
+                connection_ids.push(connection_id);
+            }
+        }
+        assert_ne!(connection_ids[0], connection_ids[1]);
+        assert_eq!(server.connection_count().await, 2);
+
+        server
+            .broadcast(StreamDeckResponse::StateUpdate {
+                connected: true,
+                latest_message: None,
+                monitors: vec![],
+            })
+            .await
+            .unwrap();
+
+        for deck in [&mut first, &mut second] {
+            assert!(matches!(
+                next_response(deck).await,
+                StreamDeckResponse::StateUpdate { connected: true, .. }
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_state_update_reaches_reconnected_deck() {
+        let (event_tx, mut event_rx) = mpsc::unbounded_channel();
+        let server = StreamDeckServer::new(0, event_tx).await.unwrap();
+        let url = format!("ws://127.0.0.1:{}/ws", server.port());
+
+        // Stream Deck app restart: the plugin drops its socket and connects again
+        let (mut first, _) = tokio_tungstenite::connect_async(url.as_str()).await.unwrap();
+        let Some(StreamDeckEvent::Connected { connection_id: first_id }) = event_rx.recv().await else {
+            panic!("expected the first deck to connect");
+        };
+        first.close(None).await.unwrap();
+        assert!(matches!(
+            event_rx.recv().await,
+            Some(StreamDeckEvent::Disconnected { connection_id }) if connection_id == first_id
+        ));
+        assert_eq!(server.connection_count().await, 0);
+
+        let (mut second, _) = tokio_tungstenite::connect_async(url.as_str()).await.unwrap();
+        let Some(StreamDeckEvent::Connected { connection_id: second_id }) = event_rx.recv().await else {
+            panic!("expected the deck to reconnect");
+        };
+        assert_ne!(first_id, second_id);
+        assert_eq!(server.connection_count().await, 1);
+
+        server
+            .broadcast(StreamDeckResponse::StateUpdate {
+                connected: true,
+                latest_message: None,
+                monitors: vec![],
+            })
+            .await
+            .unwrap();
+        assert!(matches!(
+            next_response(&mut second).await,
+            StreamDeckResponse::StateUpdate { connected: true, .. }
+        ));
+    }
 }
@@ -36,6 +36,7 @@ export interface StreamDeckResponse {
     content: string;
     priority: string;
     target_monitor_ids: string[];
+    content_format?: 'plain' | 'markdown';
   };
   monitors?: Array<{
     id: string;